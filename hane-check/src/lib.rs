@@ -0,0 +1,377 @@
+use std::collections::HashSet;
+use std::iter::once;
+
+use hane_kernel::{
+    entry::{Binder, Entry},
+    global::{GEntryRef, GIndBody},
+    Evars, Global, Sort, Stack, Term, TermVariant, TypeError, TypeErrorVariant,
+};
+
+pub enum CheckError<M, B> {
+    NameAlreadyExists(String),
+    /// A shorthand stored for the named type or constructor differs from the one derived from its type.
+    InconsistentShorthand(String),
+    /// The named constructor does not apply its type to the parameters unchanged.
+    NonUniformParameters(String),
+    /// The named constructor takes an argument from a larger universe than its type.
+    UniverseInconsistency(String),
+    ConstructorFailsPositivityCondition(String),
+    ConstructorArgsContainsType(String),
+    TypeError(TypeError<M, B>),
+}
+
+/// An entry rejected by the checker, `meta` locates the cause of the rejection.
+pub struct Rejection<M, B> {
+    pub meta: M,
+    pub err: CheckError<M, B>,
+}
+
+type CheckResult<M, B> = Result<(), (M, CheckError<M, B>)>;
+
+/// Checks every entry of `global` against the entries before it, returning the rejected entries from oldest to newest.
+/// Only the core typing rules of the kernel are used, and the shorthands stored for inductive types are derived again.
+/// Unlike `Command::eval`, constructors must apply their type to its parameters unchanged,
+/// and may not take arguments from a larger universe than their type unless it is in `Prop`.
+pub fn check<M: Clone, B: Clone>(global: &Global<M, B>) -> Vec<Rejection<M, B>> {
+    let mut env = global.clone();
+    let mut rejections: Vec<_> = global
+        .entries()
+        .rev()
+        .filter_map(|(meta, entry)| {
+            // `env` contains `entry` and every entry before it
+            check_newest(&mut env, meta, entry)
+                .err()
+                .map(|(meta, err)| Rejection { meta, err })
+        })
+        .collect();
+    rejections.reverse();
+    rejections
+}
+
+/// Checks `entry`, which must be the newest entry of `env`, and removes it from `env`.
+fn check_newest<M: Clone, B: Clone>(
+    env: &mut Global<M, B>,
+    meta: &M,
+    entry: GEntryRef<M, B>,
+) -> CheckResult<M, B> {
+    let evars = Evars::new();
+    match entry {
+        GEntryRef::Definition(name, ttype, value) => {
+            env.truncate(env.len() - 1);
+            expect_fresh(env, meta, name)?;
+            let mut local = Stack::new();
+            let sort = ttype
+                .type_check(env, &evars, &mut local)
+                .map_err(type_error)?;
+            sort.expect_sort(env, &evars, &mut local)
+                .map_err(|err| (ttype.meta.clone(), CheckError::TypeError(err)))?;
+            let value_type = value
+                .type_check(env, &evars, &mut local)
+                .map_err(type_error)?;
+            value_type
+                .expect_subtype(ttype, env, &evars, &mut local)
+                .map_err(|err| (value.meta.clone(), CheckError::TypeError(err)))?;
+        }
+        GEntryRef::Axiom(name, ttype) => {
+            env.truncate(env.len() - 1);
+            expect_fresh(env, meta, name)?;
+            let mut local = Stack::new();
+            let sort = ttype
+                .type_check(env, &evars, &mut local)
+                .map_err(type_error)?;
+            sort.expect_sort(env, &evars, &mut local)
+                .map_err(|err| (ttype.meta.clone(), CheckError::TypeError(err)))?;
+        }
+        GEntryRef::Inductive(_, params, bodies)
+        | GEntryRef::InductiveConstructor(_, _, params, bodies) => {
+            // The constructors are checked with the entry itself in scope, so it is only removed afterwards
+            let checked = check_inductive(env, meta, params, bodies);
+            env.truncate(env.len() - 1);
+
+            let mut names = HashSet::new();
+            for body in bodies {
+                let constructors = body.constructors.iter().map(|c| &*c.name);
+                for name in once(&*body.name).chain(constructors) {
+                    expect_fresh(env, meta, name)?;
+                    if !names.insert(name) {
+                        return Err((meta.clone(), CheckError::NameAlreadyExists(name.to_owned())));
+                    }
+                }
+            }
+            checked?;
+        }
+    }
+    Ok(())
+}
+
+/// Checks a set of mutually inductive types, which must be the newest entry of `env`.
+fn check_inductive<M: Clone, B: Clone>(
+    env: &Global<M, B>,
+    meta: &M,
+    params: &[Binder<M, B>],
+    bodies: &[GIndBody<M, B>],
+) -> CheckResult<M, B> {
+    let is_type = |name: &str| bodies.iter().any(|body| body.name == name);
+    let is_constructor = |name: &str| {
+        bodies
+            .iter()
+            .any(|body| body.constructors.iter().any(|c| c.name == name))
+    };
+
+    let evars = Evars::new();
+    let mut local = Stack::new();
+    let mut local = local.slot();
+
+    // Only the types of the constructors may refer to the entry itself
+    for param in params {
+        expect_defined(&param.ttype, &local, |name| {
+            is_type(name) || is_constructor(name)
+        })?;
+        let sort = param
+            .ttype
+            .type_check(env, &evars, &mut local)
+            .map_err(type_error)?;
+        sort.expect_sort(env, &evars, &mut local)
+            .map_err(|err| (param.ttype.meta.clone(), CheckError::TypeError(err)))?;
+        local.push_onto(param.clone().into());
+    }
+
+    for body in bodies {
+        expect_defined(&body.arity_type, &local, |name| {
+            is_type(name) || is_constructor(name)
+        })?;
+        if body.full_type != close(params, &body.arity_type) {
+            return Err((
+                meta.clone(),
+                CheckError::InconsistentShorthand(body.name.clone()),
+            ));
+        }
+
+        body.arity_type
+            .type_check(env, &evars, &mut local)
+            .map_err(type_error)?;
+        let mut norm = body.arity_type.clone();
+        norm.normalize(env, &evars, &mut local)
+            .map_err(|err| (body.arity_type.meta.clone(), CheckError::TypeError(err)))?;
+        let (arity, norm) = norm.strip_products();
+        let sort = if let TermVariant::Sort(sort) = &*norm.variant {
+            sort
+        } else {
+            return Err((
+                body.arity_type.meta.clone(),
+                CheckError::TypeError(TypeError::new(&local, TypeErrorVariant::NotASort(norm))),
+            ));
+        };
+        if *sort != body.sort || !same_binders(&arity, &body.arity) {
+            return Err((
+                meta.clone(),
+                CheckError::InconsistentShorthand(body.name.clone()),
+            ));
+        }
+    }
+
+    for body in bodies {
+        for constructor in &body.constructors {
+            let ttype = &constructor.arity_type;
+            expect_defined(ttype, &local, is_constructor)?;
+            if constructor.full_type != close(params, ttype) {
+                return Err((
+                    meta.clone(),
+                    CheckError::InconsistentShorthand(constructor.name.clone()),
+                ));
+            }
+
+            let sort = ttype
+                .type_check(env, &evars, &mut local)
+                .map_err(type_error)?;
+            sort.expect_sort(env, &evars, &mut local)
+                .map_err(|err| (ttype.meta.clone(), CheckError::TypeError(err)))?;
+
+            // Ensure the constructor produces the correct type
+            let mut norm = ttype.clone();
+            norm.normalize(env, &evars, &mut local)
+                .map_err(|err| (ttype.meta.clone(), CheckError::TypeError(err)))?;
+            let (arity, norm) = norm.strip_products();
+            let (hd, args) = norm.strip_args();
+            if !hd.is_const(&body.name) {
+                return Err((
+                    ttype.meta.clone(),
+                    CheckError::TypeError(TypeError::new(
+                        &local,
+                        TypeErrorVariant::NotOfExpectedInducitve(body.name.clone(), ttype.clone()),
+                    )),
+                ));
+            }
+            if !same_binders(&arity, &constructor.arity) || args != constructor.args {
+                return Err((
+                    meta.clone(),
+                    CheckError::InconsistentShorthand(constructor.name.clone()),
+                ));
+            }
+
+            // The `k`th parameter is the `arity.len() + params.len() - 1 - k`th newest variable in the conclusion
+            let uniform = (0..params.len()).all(|k| {
+                args.get(k).is_some_and(|arg| {
+                    matches!(&*arg.variant, TermVariant::Var(n) if *n == arity.len() + params.len() - 1 - k)
+                })
+            });
+            if !uniform {
+                return Err((
+                    ttype.meta.clone(),
+                    CheckError::NonUniformParameters(constructor.name.clone()),
+                ));
+            }
+
+            let mut local = local.slot();
+            for binder in &arity {
+                if !binder.ttype.strict_positivity(env, is_type) {
+                    return Err((
+                        ttype.meta.clone(),
+                        CheckError::ConstructorFailsPositivityCondition(constructor.name.clone()),
+                    ));
+                }
+
+                // Prop is impredicative, so its constructors may take arguments from any universe
+                if body.sort != Sort::Prop {
+                    let sort = binder
+                        .ttype
+                        .type_check(env, &evars, &mut local)
+                        .map_err(type_error)?
+                        .expect_sort(env, &evars, &mut local)
+                        .map_err(|err| (ttype.meta.clone(), CheckError::TypeError(err)))?;
+                    if sort > body.sort {
+                        return Err((
+                            ttype.meta.clone(),
+                            CheckError::UniverseInconsistency(constructor.name.clone()),
+                        ));
+                    }
+                }
+                local.push_onto(binder.clone().into());
+            }
+
+            for arg in &args {
+                arg.validate_consts(|name| if is_type(name) { Err(()) } else { Ok(()) })
+                    .map_err(|(meta, ())| {
+                        (
+                            meta,
+                            CheckError::ConstructorArgsContainsType(constructor.name.clone()),
+                        )
+                    })?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn expect_fresh<M: Clone, B: Clone>(env: &Global<M, B>, meta: &M, name: &str) -> CheckResult<M, B> {
+    env.expect_fresh(name)
+        .map_err(|_| (meta.clone(), CheckError::NameAlreadyExists(name.to_owned())))
+}
+
+/// Ensures `term` contains no constants for which `undefined` returns true.
+fn expect_defined<M: Clone, B: Clone>(
+    term: &Term<M, B>,
+    local: &Stack<Entry<M, B>>,
+    mut undefined: impl FnMut(&str) -> bool,
+) -> CheckResult<M, B> {
+    term.validate_consts(|name| {
+        if undefined(name) {
+            Err(name.to_owned())
+        } else {
+            Ok(())
+        }
+    })
+    .map_err(|(meta, name)| {
+        (
+            meta,
+            CheckError::TypeError(TypeError::new(
+                local,
+                TypeErrorVariant::UndefinedConst(name),
+            )),
+        )
+    })
+}
+
+/// Builds `∀ params.., body`.
+fn close<M: Clone, B: Clone>(params: &[Binder<M, B>], body: &Term<M, B>) -> Term<M, B> {
+    params.iter().rev().fold(body.clone(), |body, param| Term {
+        meta: body.meta.clone(),
+        variant: Box::new(TermVariant::Product(
+            param.x.clone(),
+            param.ttype.clone(),
+            body,
+        )),
+    })
+}
+
+fn same_binders<M, B>(l: &[Binder<M, B>], r: &[Binder<M, B>]) -> bool {
+    l.len() == r.len() && l.iter().zip(r).all(|(l, r)| l.ttype == r.ttype)
+}
+
+fn type_error<M, B>((meta, err): (M, TypeError<M, B>)) -> (M, CheckError<M, B>) {
+    (meta, CheckError::TypeError(err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hane_kernel::export::read;
+    use hane_kernel::fixtures::{cnst, inductive, nat_global, succ, term};
+    use hane_kernel::{Command, CommandVariant};
+
+    #[test]
+    fn accepts_evaluated_environment() {
+        let mut global = nat_global();
+        let command = Command {
+            meta: (),
+            variant: CommandVariant::Definition("one".to_owned(), cnst("nat"), succ(cnst("O"))),
+        };
+        assert!(command.eval(&mut global, |_| {}).is_ok());
+
+        assert!(check(&global).is_empty());
+    }
+
+    #[test]
+    fn rejects_large_constructor_argument() {
+        // `Inductive U : Set := u : Set -> U.` is accepted by `Command::eval`
+        let mut global = Global::new();
+        let command = inductive(
+            "U",
+            Sort::Set,
+            vec![(
+                "u",
+                term(TermVariant::Product(
+                    (),
+                    term(TermVariant::Sort(Sort::Set)),
+                    cnst("U"),
+                )),
+            )],
+        );
+        assert!(command.eval(&mut global, |_| {}).is_ok());
+
+        let rejections = check(&global);
+        assert_eq!(rejections.len(), 1);
+        assert!(matches!(
+            &rejections[0].err,
+            CheckError::UniverseInconsistency(name) if name == "u"
+        ));
+    }
+
+    #[test]
+    fn rejects_written_entry_at_its_line() {
+        let written = "\
+(inductive () (body \"nat\" () set set set (constructor \"O\" () () (const \"nat\") (const \"nat\"))))
+(definition \"zero\" (const \"nat\") (const \"O\"))
+(definition \"bad\" (const \"nat\") (const \"nat\"))
+(admitted \"hole\" 0 (const \"nat\"))
+";
+        let global = read(written).ok().unwrap();
+        assert_eq!(global.obligations().count(), 1);
+
+        let rejections = check(&global);
+        assert_eq!(rejections.len(), 1);
+        assert_eq!(rejections[0].meta, 3);
+        assert!(matches!(&rejections[0].err, CheckError::TypeError(_)));
+    }
+}
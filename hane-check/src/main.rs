@@ -0,0 +1,177 @@
+use hane_check::{check, CheckError};
+use hane_kernel::{export::read, TypeErrorVariant};
+use std::{
+    env::args,
+    fmt::{self, Display, Formatter},
+    fs::read_to_string,
+    process::ExitCode,
+};
+
+struct Report(CheckError<usize, String>);
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            CheckError::NameAlreadyExists(name) => {
+                write!(f, "The name `{name}` has already been defined")
+            }
+            CheckError::InconsistentShorthand(name) => {
+                write!(f, "The stored form of `{name}` does not match its type")
+            }
+            CheckError::NonUniformParameters(name) => {
+                write!(
+                    f,
+                    "The constructor `{name}` does not use the parameters of its type uniformly"
+                )
+            }
+            CheckError::UniverseInconsistency(name) => {
+                write!(
+                    f,
+                    "The constructor `{name}` takes an argument from a larger universe than its type"
+                )
+            }
+            CheckError::ConstructorFailsPositivityCondition(name) => {
+                write!(f, "The constructor `{name}` fails the positivity condition")
+            }
+            CheckError::ConstructorArgsContainsType(name) => {
+                write!(f, "Types from the constructors type family is not allowed as arguments to the constructed type of `{name}`")
+            }
+            CheckError::TypeError(err) => match &err.variant {
+                TypeErrorVariant::NotSubtypeType(t1, t2) => {
+                    write!(f, "Invalid Subtype\nFound: {t1}\nExpected: {t2}")
+                }
+                TypeErrorVariant::IncompatibleTypes(t1, t2) => {
+                    write!(f, "Incompatible Types\nFound: {t1}\nExpected: {t2}")
+                }
+                TypeErrorVariant::NotAProduct(t) => write!(f, "Expected a product, found {t}"),
+                TypeErrorVariant::NotASort(t) => write!(f, "Expected a sort, found {t}"),
+                TypeErrorVariant::NotAnInductiveType(name) => {
+                    write!(f, "Expected an inductive type, Found {name}")
+                }
+                TypeErrorVariant::NotAConstructor(ind, name, _) => {
+                    write!(f, "{name} is not a constructor for {ind}")
+                }
+                TypeErrorVariant::IncorrectParameterCount(expected, found) => {
+                    write!(f, "Expected {expected} parameters, found {found}")
+                }
+                TypeErrorVariant::NotOfExpectedInducitve(ind, t) => {
+                    write!(f, "Expected a term of type {ind}, found {t}")
+                }
+                TypeErrorVariant::DisallowedEleminationSort(s1, s2) => {
+                    write!(f, "{s1} cannot elemintate into {s2}")
+                }
+                TypeErrorVariant::DupplicateConstructor(name) => {
+                    write!(f, "The constructor {name} was previously covered")
+                }
+                TypeErrorVariant::MissingConstructors(constructors) => {
+                    write!(f, "Missing the constructors: {}", constructors.join(", "))
+                }
+                TypeErrorVariant::DebruijnOutOfScope(n) => write!(
+                    f,
+                    "Debruijn index {n} out of scope. The local environment only contains {} names.",
+                    err.local.len()
+                ),
+                TypeErrorVariant::UndefinedConst(name) => write!(f, "Unknown constant {name}"),
+                TypeErrorVariant::UniverseOverflow => write!(f, "Universe level is too large"),
+                TypeErrorVariant::UndefinedEvar(id) => {
+                    write!(f, "The metavariable ?{id} is not allowed in a checked environment")
+                }
+                TypeErrorVariant::IncorrectSubstitutionLength(expected, found) => write!(
+                    f,
+                    "Expected a substitution of {expected} terms for the metavariable, found {found}"
+                ),
+                TypeErrorVariant::EvarAlreadySolved(id) => {
+                    write!(f, "The metavariable ?{id} has already been solved")
+                }
+                TypeErrorVariant::EvarOccursInSolution(id, t) => {
+                    write!(f, "The metavariable ?{id} occurs in its own solution {t}")
+                }
+                TypeErrorVariant::CannotUnify(t1, t2) => {
+                    write!(f, "Unable to unify {t1} with {t2}")
+                }
+                TypeErrorVariant::EvarScopeEscape(id, t) => write!(
+                    f,
+                    "The solution of the metavariable ?{id} refers to {t}, outside of its context"
+                ),
+                TypeErrorVariant::UnificationStuck(t1, t2) => write!(
+                    f,
+                    "Unable to unify {t1} with {t2} outside of the pattern fragment"
+                ),
+                TypeErrorVariant::UnsolvedHole(t) => {
+                    write!(f, "Unable to infer a hole of type {t}")
+                }
+                TypeErrorVariant::ScrutineeNotInductive(t) => {
+                    write!(f, "Expected a scrutinee of an inductive type, found {t}")
+                }
+                TypeErrorVariant::NotAnEquality(t) => {
+                    write!(f, "Expected an equality, found {t}")
+                }
+                TypeErrorVariant::NoInstance(t, _) => write!(f, "No instance found for {t}"),
+                TypeErrorVariant::TacticFailed(reason, t) => write!(f, "{reason}, on {t}"),
+                TypeErrorVariant::SearchFailed(t, depth, _) => write!(
+                    f,
+                    "Proof search for {t} gave up after searching to depth {depth}"
+                ),
+                TypeErrorVariant::NothingToRewrite(t1, t2) => {
+                    write!(f, "Nothing to rewrite, {t1} does not occur in {t2}")
+                }
+                TypeErrorVariant::UnelaboratedRewrite => {
+                    write!(f, "A rewrite must be elaborated before it is checked")
+                }
+                TypeErrorVariant::NoGoals => write!(f, "No goals are left to prove"),
+                TypeErrorVariant::UnfinishedProof(n, t) => write!(
+                    f,
+                    "The proof is unfinished, {n} goals remain, the first of type {t}"
+                ),
+            },
+        }
+    }
+}
+
+/// Re-checks every environment written by `hane-export` given as an argument,
+/// reporting any entry which is rejected and every admitted obligation.
+fn main() -> ExitCode {
+    let mut success = true;
+
+    for path in args().skip(1) {
+        let content = match read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) => {
+                eprintln!("{path}: {err}");
+                success = false;
+                continue;
+            }
+        };
+
+        let global = match read(&content) {
+            Ok(global) => global,
+            Err(err) => {
+                eprintln!("{path}: {err}");
+                success = false;
+                continue;
+            }
+        };
+
+        let rejections = check(&global);
+        let rejected = rejections.len();
+        for rejection in rejections {
+            eprintln!("{path}: line {}: {}", rejection.meta, Report(rejection.err));
+        }
+        let mut admitted = 0;
+        for (name, ttype, _) in global.obligations() {
+            eprintln!("{path}: `{name}` is an admitted obligation of type {ttype}");
+            admitted += 1;
+        }
+        println!(
+            "{path}: {} entries checked, {rejected} rejected, {admitted} admitted",
+            global.len()
+        );
+        success &= rejected == 0 && admitted == 0;
+    }
+
+    if success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
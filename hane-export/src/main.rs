@@ -0,0 +1,76 @@
+use hane_kernel::{export::write, Global};
+use hane_syntax::{
+    elab::elaborate, env::Env, eval::EvalError, parser::parse, Ident, Span, SpanError,
+};
+use std::{env::args, fs, path::Path, process::ExitCode};
+
+/// Evaluates every `.v` file given as an argument and writes the resulting environment next to it as a `.hk` file,
+/// to be checked by `hane-check`.
+fn main() -> ExitCode {
+    let mut success = true;
+
+    for path in args().skip(1) {
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) => {
+                eprintln!("{path}: {err}");
+                success = false;
+                continue;
+            }
+        };
+
+        let global = match eval(&path, &content) {
+            Ok(global) => global,
+            Err(err) => {
+                eprintln!("{err}");
+                success = false;
+                continue;
+            }
+        };
+
+        let out = Path::new(&path).with_extension("hk");
+        if let Err(err) = fs::write(&out, write(&global, |x| &x.name)) {
+            eprintln!("{}: {err}", out.display());
+            success = false;
+            continue;
+        }
+        println!(
+            "{path}: {} entries written to {}",
+            global.len(),
+            out.display()
+        );
+    }
+
+    if success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Parses, lowers, elaborates and evaluates the content of a file, returning the printed error if any stage fails.
+fn eval(path: &str, content: &str) -> Result<Global<Span, Ident>, String> {
+    let commands = parse(content).map_err(|err| err.print(Some(path), content))?;
+
+    let mut env = Env::new();
+    let commands = commands
+        .into_iter()
+        .map(|command| command.lower(&mut env))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| err.print(Some(path), content))?;
+
+    let mut global = Global::new();
+    for command in commands.into_iter().flatten() {
+        let print = |(span, err)| {
+            SpanError {
+                span,
+                err: EvalError(err, &env),
+            }
+            .print(Some(path), content)
+        };
+        for command in elaborate(command, &global, &env).map_err(print)? {
+            command.eval(&mut global, |_| {}).map_err(print)?;
+        }
+    }
+    Ok(global)
+}
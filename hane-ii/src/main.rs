@@ -1,5 +1,7 @@
 use hane_kernel::Global;
 use hane_syntax::{
+    elab::elaborate,
+    env::Env,
     eval::EvalError,
     lower::LoweringError,
     parser::{parse, ParseError},
@@ -7,16 +9,16 @@ use hane_syntax::{
     Ident, Span, SpanError,
 };
 use std::{
-    collections::HashSet,
     fmt::{self, Display, Formatter},
     io::stdin,
 };
 
+#[allow(clippy::enum_variant_names)]
 enum Error<'a> {
     ParseError(Option<&'a str>, &'a str, ParseError),
     SingleCommand,
     LoweringError(Option<&'a str>, &'a str, SpanError<LoweringError>),
-    EvalError(Option<&'a str>, &'a str, SpanError<EvalError>),
+    EvalError(Option<&'a str>, &'a str, SpanError<EvalError<'a>>),
 }
 
 impl<'a> From<(Option<&'a str>, &'a str, ParseError)> for Error<'a> {
@@ -31,8 +33,8 @@ impl<'a> From<(Option<&'a str>, &'a str, SpanError<LoweringError>)> for Error<'a
     }
 }
 
-impl<'a> From<(Option<&'a str>, &'a str, SpanError<EvalError>)> for Error<'a> {
-    fn from((input, path, err): (Option<&'a str>, &'a str, SpanError<EvalError>)) -> Error<'a> {
+impl<'a> From<(Option<&'a str>, &'a str, SpanError<EvalError<'a>>)> for Error<'a> {
+    fn from((input, path, err): (Option<&'a str>, &'a str, SpanError<EvalError<'a>>)) -> Error<'a> {
         Error::EvalError(input, path, err)
     }
 }
@@ -49,43 +51,57 @@ impl<'a> Display for Error<'a> {
 }
 
 fn main() {
-    let mut names = HashSet::new();
+    let mut env = Env::new();
     let mut global = Global::new();
 
     for line in stdin().lines() {
         let line = line.unwrap();
 
-        if let Err(err) = eval_line(&line, &mut names, &mut global) {
-            eprintln!("{err}")
+        // A command lowered to several, such as a record whose projections are ill-typed, is undone as a whole
+        let len = global.len();
+        if let Err(err) = eval_line(&line, &mut env, &mut global) {
+            eprintln!("{err}");
+            global.truncate(len);
         }
     }
 }
 
+#[allow(clippy::result_large_err)]
 fn eval_line<'a>(
     line: &'a str,
-    names: &mut HashSet<String>,
+    env: &'a mut Env,
     global: &mut Global<Span, Ident>,
 ) -> Result<(), Error<'a>> {
-    let mut commands = parse(&line).map_err(|err| (None, line, err))?;
+    let mut commands = parse(line).map_err(|err| (None, line, err))?;
 
     if commands.len() != 1 {
         return Err(Error::SingleCommand);
     }
 
     let command = commands.pop().unwrap();
-    let command = command.lower(names).map_err(|err| (None, line, err))?;
+    let commands = command.lower(env).map_err(|err| (None, line, err))?;
+    for warning in env.take_warnings() {
+        eprintln!("{}", warning.print(None, line));
+    }
+    let env = &*env;
 
-    command
-        .eval(global, |out| print!("{}", Print(out)))
-        .map_err(|(span, err)| {
-            (
-                None,
-                line,
-                SpanError {
-                    span,
-                    err: EvalError(err),
-                },
-            )
-        })?;
+    for command in commands {
+        elaborate(command, global, env)
+            .and_then(|commands| {
+                commands.into_iter().try_for_each(|command| {
+                    command.eval(global, |out| print!("{}", Print(env, out)))
+                })
+            })
+            .map_err(|(span, err)| {
+                (
+                    None,
+                    line,
+                    SpanError {
+                        span,
+                        err: EvalError(err, env),
+                    },
+                )
+            })?;
+    }
     Ok(())
 }
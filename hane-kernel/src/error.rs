@@ -4,6 +4,7 @@ pub enum CommandError<M, B> {
     NameAlreadyExists(String),
     ConstructorFailsPositivityCondition,
     ConstructorArgsContainsType,
+    UnsolvedEvar(usize),
     TypeError(TypeError<M, B>),
 }
 
@@ -27,6 +28,49 @@ pub enum TypeErrorVariant<M, B> {
     MissingConstructors(Vec<String>),
     DebruijnOutOfScope(usize),
     UndefinedConst(String),
+    UniverseOverflow,
+    UndefinedEvar(usize),
+    IncorrectSubstitutionLength(usize, usize),
+    EvarAlreadySolved(usize),
+    EvarOccursInSolution(usize, Term<M, B>),
+    CannotUnify(Term<M, B>, Term<M, B>),
+    /// The variable would escape the context of the metavariable if it was part of its solution.
+    EvarScopeEscape(usize, Term<M, B>),
+    /// A unification problem which is outside of the pattern fragment and could not be solved.
+    UnificationStuck(Term<M, B>, Term<M, B>),
+    /// A hole of the given type which was not solved during elaboration.
+    UnsolvedHole(Term<M, B>),
+    /// The type of a scrutinee matched without an `in` clause, which is not an inductive type.
+    ScrutineeNotInductive(Term<M, B>),
+    /// The type of the equality of a rewrite, which is not an inductive type with a single index and a constructor without arguments.
+    NotAnEquality(Term<M, B>),
+    /// A hole whose type is a class, for which no instance could be found, along with the steps of the search.
+    NoInstance(Term<M, B>, Vec<InstanceTrace<M, B>>),
+    /// A tactic which cannot be used on the goal of the given type, with the reason why.
+    TacticFailed(String, Term<M, B>),
+    /// A goal of the given type which proof search gave up on, along with the depth it searched to
+    /// and the subgoals no hint proved, which are generalized over the hypotheses the search introduced.
+    SearchFailed(Term<M, B>, usize, Vec<Term<M, B>>),
+    /// The side of an equality which a rewrite replaces, which does not occur in the given expected type.
+    NothingToRewrite(Term<M, B>, Term<M, B>),
+    /// A rewrite, which only the elaborator can turn into a term of the kernel.
+    UnelaboratedRewrite,
+    /// A tactic which is used after every goal has been proven.
+    NoGoals,
+    /// A proof ending with the given number of goals left to prove, along with the type of the first of them.
+    UnfinishedProof(usize, Term<M, B>),
+}
+
+/// A step of the search for an instance of a class, with the depth of the goal it is part of.
+pub enum InstanceTrace<M, B> {
+    /// A goal to find an instance of the given type for.
+    Goal(usize, Term<M, B>),
+    /// An instance whose type does not unify with the goal.
+    Mismatch(usize, String),
+    /// An instance whose type unifies with the goal, whose premises are searched for next.
+    Matched(usize, String),
+    /// A goal which was given up on, as it is too deep.
+    DepthLimit(usize),
 }
 
 impl<M: Clone, B: Clone> TypeError<M, B> {
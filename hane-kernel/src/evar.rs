@@ -0,0 +1,363 @@
+use crate::entry::Entry;
+use crate::{Sort, Stack, Term, TermVariant, TypeError, TypeErrorVariant};
+
+/// A metavariable, a hole standing for a yet unknown term of type `ttype` in the local context `context`.
+#[derive(Clone)]
+pub struct EvarDecl<M, B> {
+    pub meta: M,
+    pub context: Stack<Entry<M, B>>,
+    pub ttype: Term<M, B>,
+    pub value: Option<Term<M, B>>,
+    /// Whether `ttype` is a sort that was only guessed, which is raised when the metavariable is solved with a type of a larger sort
+    pub cumulative: bool,
+    /// The name of the hole this metavariable was written as, if it was written as `?x`
+    pub name: Option<String>,
+}
+
+/// The store of metavariables, indexed by the id used in `TermVariant::Evar`.
+///
+/// A term `Evar(id, subst)` stands for the value of `id` with `subst[i]` substituted for the `i`th oldest variable of its context.
+#[derive(Default, Clone)]
+pub struct Evars<M, B> {
+    decls: Vec<EvarDecl<M, B>>,
+    /// The ids of the solved metavariables, in the order they were solved
+    trail: Vec<usize>,
+    /// The ids of the metavariables whose sort was raised, along with their previous type
+    raised: Vec<(usize, Term<M, B>)>,
+}
+
+/// A point in the history of a store of metavariables, which the store can be rolled back to.
+#[derive(Clone, Copy)]
+pub struct Snapshot {
+    declared: usize,
+    solved: usize,
+    raised: usize,
+}
+
+impl<M: Clone, B: Clone> Evars<M, B> {
+    pub fn new() -> Self {
+        Evars {
+            decls: Vec::new(),
+            trail: Vec::new(),
+            raised: Vec::new(),
+        }
+    }
+
+    /// Returns the number of metavariables declared in the store.
+    pub fn len(&self) -> usize {
+        self.decls.len()
+    }
+
+    /// Returns true if no metavariables have been declared.
+    pub fn is_empty(&self) -> bool {
+        self.decls.is_empty()
+    }
+
+    /// Declares a new unsolved metavariable of type `ttype` in `context`, returning its id.
+    pub fn declare(&mut self, meta: M, context: Stack<Entry<M, B>>, ttype: Term<M, B>) -> usize {
+        self.decls.push(EvarDecl {
+            meta,
+            context,
+            ttype,
+            value: None,
+            cumulative: false,
+            name: None,
+        });
+        self.decls.len() - 1
+    }
+
+    /// Declares a new unsolved metavariable for the hole `name` of type `ttype` in `context`, returning its id.
+    pub fn declare_named(
+        &mut self,
+        meta: M,
+        context: Stack<Entry<M, B>>,
+        ttype: Term<M, B>,
+        name: String,
+    ) -> usize {
+        let id = self.declare(meta, context, ttype);
+        self.decls[id].name = Some(name);
+        id
+    }
+
+    /// Returns the ids of the metavariables declared for the hole `name`, oldest first.
+    pub fn named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = usize> + 'a {
+        self.decls
+            .iter()
+            .enumerate()
+            .filter(move |(_, decl)| decl.name.as_deref() == Some(name))
+            .map(|(id, _)| id)
+    }
+
+    /// Declares a new unsolved metavariable standing for a type in `context`, returning its id.
+    ///
+    /// Its sort is not known, so it is given `sort`, which `raise` may later replace with a larger one.
+    pub fn declare_type(&mut self, meta: M, context: Stack<Entry<M, B>>, sort: Sort) -> usize {
+        let ttype = Term {
+            meta: meta.clone(),
+            variant: Box::new(TermVariant::Sort(sort)),
+        };
+        let id = self.declare(meta, context, ttype);
+        self.decls[id].cumulative = true;
+        id
+    }
+
+    /// Replaces the sort of the metavariable `id`, declared with `declare_type`, with `sort`.
+    ///
+    /// This function returns an error if `id` is undefined or was not declared as standing for a type.
+    pub fn raise(&mut self, id: usize, sort: Term<M, B>) -> Result<(), TypeError<M, B>> {
+        let decl = self
+            .decls
+            .get_mut(id)
+            .ok_or_else(|| TypeError::new(&Stack::new(), TypeErrorVariant::UndefinedEvar(id)))?;
+        if !decl.cumulative || !matches!(&*sort.variant, TermVariant::Sort(_)) {
+            return Err(TypeError::new(
+                &decl.context,
+                TypeErrorVariant::NotASort(sort),
+            ));
+        }
+        let previous = std::mem::replace(&mut decl.ttype, sort);
+        self.raised.push((id, previous));
+        Ok(())
+    }
+
+    /// Returns the declaration of the metavariable `id`.
+    pub fn get(&self, id: usize) -> Option<&EvarDecl<M, B>> {
+        self.decls.get(id)
+    }
+
+    /// Returns the value of the metavariable `id`, if it has been solved.
+    pub fn value(&self, id: usize) -> Option<&Term<M, B>> {
+        self.decls.get(id)?.value.as_ref()
+    }
+
+    /// Returns the term `Evar(id, subst)` where `subst` maps every variable of the context of `id` to itself.
+    /// The result is only meaningful in the context the metavariable was declared in.
+    pub fn identity(&self, meta: M, id: usize) -> Option<Term<M, B>> {
+        let len = self.decls.get(id)?.context.len();
+        let subst = (0..len)
+            .rev()
+            .map(|n| Term {
+                meta: meta.clone(),
+                variant: Box::new(TermVariant::Var(n)),
+            })
+            .collect();
+        Some(Term {
+            meta,
+            variant: Box::new(TermVariant::Evar(id, subst)),
+        })
+    }
+
+    /// Solves the metavariable `id` with `value`, a term in its context.
+    ///
+    /// The value is not typechecked here, the kernel only trusts a term once it has been instantiated and checked on its own.
+    /// This function returns an error if `id` is undefined or already solved, or if `value` depends on `id` itself.
+    pub fn solve(&mut self, id: usize, value: Term<M, B>) -> Result<(), TypeError<M, B>> {
+        let occurs = value
+            .zonk(self)
+            .validate_evars(|x| if x == id { Err(()) } else { Ok(()) });
+        let decl = self
+            .decls
+            .get_mut(id)
+            .ok_or_else(|| TypeError::new(&Stack::new(), TypeErrorVariant::UndefinedEvar(id)))?;
+        if decl.value.is_some() {
+            return Err(TypeError::new(
+                &decl.context,
+                TypeErrorVariant::EvarAlreadySolved(id),
+            ));
+        }
+        if occurs.is_err() {
+            return Err(TypeError::new(
+                &decl.context,
+                TypeErrorVariant::EvarOccursInSolution(id, value),
+            ));
+        }
+        decl.value = Some(value);
+        self.trail.push(id);
+        Ok(())
+    }
+
+    /// Returns the current point in the history of the store, to be passed to `rollback`.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            declared: self.decls.len(),
+            solved: self.trail.len(),
+            raised: self.raised.len(),
+        }
+    }
+
+    /// Forgets every metavariable declared, every solution made and every sort raised since `snapshot` was taken.
+    pub fn rollback(&mut self, snapshot: Snapshot) {
+        for id in self.trail.drain(snapshot.solved..) {
+            self.decls[id].value = None;
+        }
+        for (id, ttype) in self.raised.drain(snapshot.raised..).rev() {
+            self.decls[id].ttype = ttype;
+        }
+        self.decls.truncate(snapshot.declared);
+    }
+
+    /// Returns an iterator over the ids of all unsolved metavariables, oldest first.
+    pub fn unsolved(&self) -> impl Iterator<Item = usize> + '_ {
+        self.decls
+            .iter()
+            .enumerate()
+            .filter(|(_, decl)| decl.value.is_none())
+            .map(|(id, _)| id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{cnst, nat_global, succ, term};
+    use crate::{Command, CommandError, CommandVariant};
+
+    #[test]
+    fn instantiate_solved_evar() {
+        let global = nat_global();
+        let mut evars = Evars::new();
+        let context: Stack<_> = [Entry::new((), cnst("nat"))].into_iter().collect();
+        let id = evars.declare((), context.clone(), cnst("nat"));
+
+        // `?id[x]` in its own context, then solved as `S x`
+        let hole = evars.identity((), id).unwrap();
+        assert!(
+            hole.type_check(&global, &evars, &mut context.clone())
+                .ok()
+                .unwrap()
+                == cnst("nat")
+        );
+        evars
+            .solve(id, succ(term(TermVariant::Var(0))))
+            .ok()
+            .unwrap();
+        assert!(evars.unsolved().next().is_none());
+
+        // `?id[O]` in the empty context
+        let t = term(TermVariant::Evar(id, vec![cnst("O")]));
+        let ttype = t
+            .type_check(&global, &evars, &mut Stack::new())
+            .ok()
+            .unwrap();
+        assert!(ttype == cnst("nat"));
+        assert!(t.zonk(&evars) == succ(cnst("O")));
+        let mut norm = t.clone();
+        norm.normalize(&global, &evars, &mut Stack::new())
+            .ok()
+            .unwrap();
+        assert!(norm == succ(cnst("O")));
+    }
+
+    #[test]
+    fn rollback_to_snapshot() {
+        let mut evars = Evars::<(), ()>::new();
+        let old = evars.declare((), Stack::new(), cnst("nat"));
+        let snapshot = evars.snapshot();
+        let new = evars.declare((), Stack::new(), cnst("nat"));
+        evars.solve(old, cnst("O")).ok().unwrap();
+        evars.solve(new, cnst("O")).ok().unwrap();
+
+        evars.rollback(snapshot);
+        assert_eq!(evars.len(), 1);
+        assert!(evars.value(old).is_none());
+    }
+
+    #[test]
+    fn ill_typed_substitution() {
+        let global = nat_global();
+        let mut evars = Evars::new();
+        let context = [Entry::new((), cnst("nat"))].into_iter().collect();
+        let id = evars.declare((), context, cnst("nat"));
+
+        let wrong_type = term(TermVariant::Evar(id, vec![cnst("nat")]));
+        assert!(wrong_type
+            .type_check(&global, &evars, &mut Stack::new())
+            .is_err());
+        let wrong_length = term(TermVariant::Evar(id, Vec::new()));
+        assert!(matches!(
+            wrong_length.type_check(&global, &evars, &mut Stack::new()),
+            Err((
+                _,
+                TypeError {
+                    variant: TypeErrorVariant::IncorrectSubstitutionLength(1, 0),
+                    ..
+                }
+            ))
+        ));
+        let undefined = term(TermVariant::Evar(id + 1, Vec::new()));
+        assert!(matches!(
+            undefined.type_check(&global, &evars, &mut Stack::new()),
+            Err((
+                _,
+                TypeError {
+                    variant: TypeErrorVariant::UndefinedEvar(_),
+                    ..
+                }
+            ))
+        ));
+    }
+
+    #[test]
+    fn solve_rejects_cycles() {
+        let mut evars = Evars::new();
+        let x = evars.declare((), Stack::new(), cnst("nat"));
+        let y = evars.declare((), Stack::new(), cnst("nat"));
+        evars
+            .solve(x, succ(term(TermVariant::Evar(y, Vec::new()))))
+            .ok()
+            .unwrap();
+        assert!(matches!(
+            evars.solve(y, succ(term(TermVariant::Evar(x, Vec::new())))),
+            Err(TypeError {
+                variant: TypeErrorVariant::EvarOccursInSolution(_, _),
+                ..
+            })
+        ));
+        assert!(matches!(
+            evars.solve(x, cnst("O")),
+            Err(TypeError {
+                variant: TypeErrorVariant::EvarAlreadySolved(_),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn global_refuses_evars() {
+        let mut global = nat_global();
+        let mut evars = Evars::new();
+        let id = evars.declare((), Stack::new(), cnst("nat"));
+        let command = Command {
+            meta: (),
+            variant: CommandVariant::Definition(
+                "one".to_owned(),
+                cnst("nat"),
+                succ(term(TermVariant::Evar(id, Vec::new()))),
+            ),
+        };
+        assert!(matches!(
+            command.eval(&mut global, |_| {}),
+            Err((_, CommandError::UnsolvedEvar(_)))
+        ));
+        assert_eq!(global.len(), 1);
+    }
+
+    #[test]
+    fn raise_is_rolled_back() {
+        let mut evars = Evars::new();
+        let fixed = evars.declare((), Stack::new(), cnst("nat"));
+        let id = evars.declare_type((), Stack::new(), Sort::Type(0));
+        let snapshot = evars.snapshot();
+        evars
+            .raise(id, term(TermVariant::Sort(Sort::Type(1))))
+            .ok()
+            .unwrap();
+        assert!(evars.get(id).unwrap().ttype == term(TermVariant::Sort(Sort::Type(1))));
+        assert!(evars
+            .raise(fixed, term(TermVariant::Sort(Sort::Type(1))))
+            .is_err());
+
+        evars.rollback(snapshot);
+        assert!(evars.get(id).unwrap().ttype == term(TermVariant::Sort(Sort::Type(0))));
+    }
+}
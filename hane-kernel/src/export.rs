@@ -0,0 +1,565 @@
+use std::fmt::{self, Display, Formatter, Write};
+
+use crate::entry::Binder;
+use crate::global::{GEntry, GEntryRef, GIndBody, GIndConstructor};
+use crate::term::{grow, MatchArm};
+use crate::{Global, Sort, Term, TermVariant};
+
+/// An error in an environment being read, located at the line it occurs on.
+pub struct ReadError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for ReadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Writes `global` as one s-expression per entry from oldest to newest, such as `(definition "name" TYPE VALUE)`,
+/// with terms mirroring `TermVariant` and binders named by `name`.
+pub fn write<M: Clone, B: Clone>(global: &Global<M, B>, name: impl Fn(&B) -> &str) -> String {
+    let mut out = String::new();
+    let admitted: Vec<_> = global
+        .obligations()
+        .map(|(x, _, context)| (x, context))
+        .collect();
+    let w = &mut Writer {
+        out: &mut out,
+        name: &name,
+    };
+    for (_, entry) in global.entries() {
+        match entry {
+            GEntryRef::Definition(x, ttype, value) => {
+                w.open("definition");
+                w.string(x);
+                w.term(ttype);
+                w.term(value);
+            }
+            GEntryRef::Axiom(x, ttype) => match admitted.iter().find(|(y, _)| *y == x) {
+                Some((_, context)) => {
+                    w.open("admitted");
+                    w.string(x);
+                    w.number(*context);
+                    w.term(ttype);
+                }
+                None => {
+                    w.open("axiom");
+                    w.string(x);
+                    w.term(ttype);
+                }
+            },
+            GEntryRef::Inductive(_, params, bodies)
+            | GEntryRef::InductiveConstructor(_, _, params, bodies) => {
+                w.open("inductive");
+                w.binders(params);
+                for body in bodies {
+                    w.open("body");
+                    w.string(&body.name);
+                    w.binders(&body.arity);
+                    w.sort(&body.sort);
+                    w.term(&body.arity_type);
+                    w.term(&body.full_type);
+                    for constructor in &body.constructors {
+                        w.open("constructor");
+                        w.string(&constructor.name);
+                        w.binders(&constructor.arity);
+                        w.list();
+                        for arg in &constructor.args {
+                            w.term(arg);
+                        }
+                        w.out.push(')');
+                        w.term(&constructor.arity_type);
+                        w.term(&constructor.full_type);
+                        w.out.push(')');
+                    }
+                    w.out.push(')');
+                }
+            }
+        }
+        w.out.push_str(")\n");
+    }
+    out
+}
+
+struct Writer<'a, F> {
+    out: &'a mut String,
+    name: &'a F,
+}
+
+impl<F> Writer<'_, F> {
+    /// Separates the next element from the one before it, unless it is the first of a list.
+    fn sep(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with(['\n', '(']) {
+            self.out.push(' ');
+        }
+    }
+
+    fn list(&mut self) {
+        self.sep();
+        self.out.push('(');
+    }
+
+    fn open(&mut self, keyword: &str) {
+        self.list();
+        self.out.push_str(keyword);
+    }
+
+    fn string(&mut self, s: &str) {
+        self.sep();
+        self.out.push('"');
+        for c in s.chars() {
+            if c == '"' || c == '\\' {
+                self.out.push('\\');
+            }
+            self.out.push(c);
+        }
+        self.out.push('"');
+    }
+
+    fn number(&mut self, n: usize) {
+        self.sep();
+        write!(self.out, "{n}").unwrap();
+    }
+
+    fn sort(&mut self, sort: &Sort) {
+        self.sep();
+        match sort {
+            Sort::Prop => self.out.push_str("prop"),
+            Sort::Set => self.out.push_str("set"),
+            Sort::Type(n) => write!(self.out, "(type {n})").unwrap(),
+        }
+    }
+}
+
+impl<F> Writer<'_, F> {
+    fn binders<M, B>(&mut self, binders: &[Binder<M, B>])
+    where
+        F: Fn(&B) -> &str,
+    {
+        self.list();
+        for binder in binders {
+            self.list();
+            self.string((self.name)(&binder.x));
+            self.term(&binder.ttype);
+            self.out.push(')');
+        }
+        self.out.push(')');
+    }
+
+    fn term<M, B>(&mut self, t: &Term<M, B>)
+    where
+        F: Fn(&B) -> &str,
+    {
+        grow(|| match &*t.variant {
+            TermVariant::Sort(sort) => self.sort(sort),
+            TermVariant::Var(n) => {
+                self.open("var");
+                self.number(*n);
+                self.out.push(')');
+            }
+            TermVariant::Const(x) => {
+                self.open("const");
+                self.string(x);
+                self.out.push(')');
+            }
+            TermVariant::App(t1, t2) => {
+                self.open("app");
+                self.term(t1);
+                self.term(t2);
+                self.out.push(')');
+            }
+            TermVariant::Product(x, t1, t2) | TermVariant::Abstract(x, t1, t2) => {
+                let keyword = if matches!(&*t.variant, TermVariant::Product(..)) {
+                    "forall"
+                } else {
+                    "fun"
+                };
+                self.open(keyword);
+                self.string((self.name)(x));
+                self.term(t1);
+                self.term(t2);
+                self.out.push(')');
+            }
+            TermVariant::Bind(x, t1, t2, t3) => {
+                self.open("let");
+                self.string((self.name)(x));
+                self.term(t1);
+                self.term(t2);
+                self.term(t3);
+                self.out.push(')');
+            }
+            TermVariant::Match(t, x, ret, arms) => {
+                self.open("match");
+                self.term(t);
+                self.string((self.name)(x));
+                self.arm(ret);
+                self.list();
+                for arm in arms {
+                    self.arm(arm);
+                }
+                self.out.push_str("))");
+            }
+            TermVariant::Evar(id, subst) => {
+                self.open("evar");
+                self.number(*id);
+                for t in subst {
+                    self.term(t);
+                }
+                self.out.push(')');
+            }
+            TermVariant::Rewrite(reverse, eq, t) => {
+                self.open("rewrite");
+                self.string(if *reverse { "<-" } else { "->" });
+                self.term(eq);
+                self.term(t);
+                self.out.push(')');
+            }
+        })
+    }
+
+    fn arm<M, B>(&mut self, arm: &MatchArm<M, B>)
+    where
+        F: Fn(&B) -> &str,
+    {
+        self.list();
+        self.string(&arm.constructor);
+        self.list();
+        for x in &arm.params {
+            self.string((self.name)(x));
+        }
+        self.out.push(')');
+        self.term(&arm.body);
+        self.out.push(')');
+    }
+}
+
+/// Reads an environment written by `write`, with every entry and term located at the line it starts on.
+/// Nothing is checked, every entry is stored as it is written.
+pub fn read(text: &str) -> Result<Global<usize, String>, ReadError> {
+    let mut reader = Reader {
+        tokens: tokenize(text)?,
+        pos: 0,
+        end: text.lines().count().max(1),
+    };
+    let mut global = Global::new();
+    while reader.pos < reader.tokens.len() {
+        let line = reader.open()?;
+        let entry = match &*reader.keyword()? {
+            "definition" => GEntry::Definition(reader.string()?, reader.term()?, reader.term()?),
+            "axiom" => GEntry::Axiom(reader.string()?, reader.term()?),
+            "admitted" => {
+                let name = reader.string()?;
+                global.push_obligation(name.clone(), reader.number()?);
+                GEntry::Axiom(name, reader.term()?)
+            }
+            "inductive" => {
+                let params = reader.binders()?;
+                let mut bodies = Vec::new();
+                while !reader.at_close() {
+                    bodies.push(reader.body()?);
+                }
+                GEntry::Inductive(params, bodies)
+            }
+            keyword => return Err(reader.error(format!("Unknown entry `{keyword}`"))),
+        };
+        reader.close()?;
+        global.push_unchecked(line, entry);
+    }
+    Ok(global)
+}
+
+#[derive(Clone)]
+enum Token {
+    Open,
+    Close,
+    Atom(String),
+    Str(String),
+}
+
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, ReadError> {
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() => {}
+            '(' => tokens.push((line, Token::Open)),
+            ')' => tokens.push((line, Token::Close)),
+            '"' => {
+                let start = line;
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => s.push(c),
+                            _ => {
+                                return Err(ReadError {
+                                    line,
+                                    message: "Invalid escape in string".to_owned(),
+                                })
+                            }
+                        },
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            s.push(c);
+                        }
+                        None => {
+                            return Err(ReadError {
+                                line: start,
+                                message: "Unterminated string".to_owned(),
+                            })
+                        }
+                    }
+                }
+                tokens.push((start, Token::Str(s)));
+            }
+            c => {
+                let mut atom = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '(' | ')' | '"') {
+                        break;
+                    }
+                    atom.push(c);
+                    chars.next();
+                }
+                tokens.push((line, Token::Atom(atom)));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Reader {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    /// The last line of the text, where an unexpected end is reported
+    end: usize,
+}
+
+impl Reader {
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map_or(self.end, |(line, _)| *line)
+    }
+
+    fn error(&self, message: String) -> ReadError {
+        ReadError {
+            line: self.line(),
+            message,
+        }
+    }
+
+    fn next(&mut self, expected: &str) -> Result<Token, ReadError> {
+        match self.tokens.get(self.pos) {
+            Some((_, token)) => {
+                self.pos += 1;
+                Ok(token.clone())
+            }
+            None => Err(self.error(format!("Expected {expected}, found the end of the input"))),
+        }
+    }
+
+    fn at_close(&self) -> bool {
+        matches!(self.tokens.get(self.pos), Some((_, Token::Close)))
+    }
+
+    fn unexpected<T>(&mut self, expected: &str) -> Result<T, ReadError> {
+        self.pos -= 1;
+        Err(self.error(format!("Expected {expected}")))
+    }
+
+    /// Reads `(`, returning the line it is on.
+    fn open(&mut self) -> Result<usize, ReadError> {
+        let line = self.line();
+        match self.next("`(`")? {
+            Token::Open => Ok(line),
+            _ => self.unexpected("`(`"),
+        }
+    }
+
+    fn close(&mut self) -> Result<(), ReadError> {
+        match self.next("`)`")? {
+            Token::Close => Ok(()),
+            _ => self.unexpected("`)`"),
+        }
+    }
+
+    fn keyword(&mut self) -> Result<String, ReadError> {
+        match self.next("a keyword")? {
+            Token::Atom(atom) => Ok(atom),
+            _ => self.unexpected("a keyword"),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ReadError> {
+        match self.next("a string")? {
+            Token::Str(s) => Ok(s),
+            _ => self.unexpected("a string"),
+        }
+    }
+
+    fn number(&mut self) -> Result<usize, ReadError> {
+        match self.next("a number")? {
+            Token::Atom(atom) => match atom.parse() {
+                Ok(n) => Ok(n),
+                Err(_) => self.unexpected("a number"),
+            },
+            _ => self.unexpected("a number"),
+        }
+    }
+
+    fn binders(&mut self) -> Result<Vec<Binder<usize, String>>, ReadError> {
+        self.open()?;
+        let mut binders = Vec::new();
+        while !self.at_close() {
+            self.open()?;
+            binders.push(Binder {
+                x: self.string()?,
+                ttype: self.term()?,
+            });
+            self.close()?;
+        }
+        self.close()?;
+        Ok(binders)
+    }
+
+    fn body(&mut self) -> Result<GIndBody<usize, String>, ReadError> {
+        self.open()?;
+        self.expect_keyword("body")?;
+        let name = self.string()?;
+        let arity = self.binders()?;
+        let line = self.line();
+        let sort = match self.term()?.into_variant() {
+            TermVariant::Sort(sort) => sort,
+            _ => {
+                return Err(ReadError {
+                    line,
+                    message: "Expected a sort".to_owned(),
+                })
+            }
+        };
+        let arity_type = self.term()?;
+        let full_type = self.term()?;
+        let mut constructors = Vec::new();
+        while !self.at_close() {
+            self.open()?;
+            self.expect_keyword("constructor")?;
+            let name = self.string()?;
+            let arity = self.binders()?;
+            self.open()?;
+            let mut args = Vec::new();
+            while !self.at_close() {
+                args.push(self.term()?);
+            }
+            self.close()?;
+            constructors.push(GIndConstructor {
+                name,
+                arity,
+                args,
+                arity_type: self.term()?,
+                full_type: self.term()?,
+            });
+            self.close()?;
+        }
+        self.close()?;
+        Ok(GIndBody {
+            name,
+            arity,
+            sort,
+            arity_type,
+            full_type,
+            constructors,
+        })
+    }
+
+    fn expect_keyword(&mut self, expected: &str) -> Result<(), ReadError> {
+        if self.keyword()? == expected {
+            Ok(())
+        } else {
+            self.unexpected(&format!("`{expected}`"))
+        }
+    }
+
+    fn term(&mut self) -> Result<Term<usize, String>, ReadError> {
+        let meta = self.line();
+        let variant = grow(|| match self.next("a term")? {
+            Token::Atom(atom) if atom == "prop" => Ok(TermVariant::Sort(Sort::Prop)),
+            Token::Atom(atom) if atom == "set" => Ok(TermVariant::Sort(Sort::Set)),
+            Token::Open => {
+                let variant = match &*self.keyword()? {
+                    "type" => TermVariant::Sort(Sort::Type(self.number()?)),
+                    "var" => TermVariant::Var(self.number()?),
+                    "const" => TermVariant::Const(self.string()?),
+                    "app" => TermVariant::App(self.term()?, self.term()?),
+                    "forall" => TermVariant::Product(self.string()?, self.term()?, self.term()?),
+                    "fun" => TermVariant::Abstract(self.string()?, self.term()?, self.term()?),
+                    "let" => {
+                        TermVariant::Bind(self.string()?, self.term()?, self.term()?, self.term()?)
+                    }
+                    "match" => {
+                        let t = self.term()?;
+                        let x = self.string()?;
+                        let ret = self.arm()?;
+                        self.open()?;
+                        let mut arms = Vec::new();
+                        while !self.at_close() {
+                            arms.push(self.arm()?);
+                        }
+                        self.close()?;
+                        TermVariant::Match(t, x, ret, arms)
+                    }
+                    "evar" => {
+                        let id = self.number()?;
+                        let mut subst = Vec::new();
+                        while !self.at_close() {
+                            subst.push(self.term()?);
+                        }
+                        TermVariant::Evar(id, subst)
+                    }
+                    "rewrite" => {
+                        let reverse = match &*self.string()? {
+                            "->" => false,
+                            "<-" => true,
+                            _ => return self.unexpected("a direction"),
+                        };
+                        TermVariant::Rewrite(reverse, self.term()?, self.term()?)
+                    }
+                    _ => return self.unexpected("a term"),
+                };
+                self.close()?;
+                Ok(variant)
+            }
+            _ => self.unexpected("a term"),
+        })?;
+        Ok(Term {
+            meta,
+            variant: Box::new(variant),
+        })
+    }
+
+    fn arm(&mut self) -> Result<MatchArm<usize, String>, ReadError> {
+        let meta = self.open()?;
+        let constructor = self.string()?;
+        self.open()?;
+        let mut params = Vec::new();
+        while !self.at_close() {
+            params.push(self.string()?);
+        }
+        self.close()?;
+        let body = self.term()?;
+        self.close()?;
+        Ok(MatchArm {
+            meta,
+            constructor,
+            params,
+            body,
+        })
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{Command, CommandVariant, Global, IndBody, IndConstructor, Sort, Term, TermVariant};
+
+pub fn term(variant: TermVariant<(), ()>) -> Term<(), ()> {
+    Term {
+        meta: (),
+        variant: Box::new(variant),
+    }
+}
+
+pub fn cnst(name: &str) -> Term<(), ()> {
+    term(TermVariant::Const(name.to_owned()))
+}
+
+pub fn var(n: usize) -> Term<(), ()> {
+    term(TermVariant::Var(n))
+}
+
+pub fn app(f: Term<(), ()>, v: Term<(), ()>) -> Term<(), ()> {
+    term(TermVariant::App(f, v))
+}
+
+pub fn succ(t: Term<(), ()>) -> Term<(), ()> {
+    app(cnst("S"), t)
+}
+
+/// `Inductive name : sort := constructors` without parameters.
+pub fn inductive(
+    name: &str,
+    sort: Sort,
+    constructors: Vec<(&str, Term<(), ()>)>,
+) -> Command<(), ()> {
+    Command {
+        meta: (),
+        variant: CommandVariant::Inductive(
+            Vec::new(),
+            vec![IndBody {
+                name: name.to_owned(),
+                ttype: term(TermVariant::Sort(sort)),
+                constructors: constructors
+                    .into_iter()
+                    .map(|(name, ttype)| IndConstructor {
+                        name: name.to_owned(),
+                        ttype,
+                    })
+                    .collect(),
+            }],
+        ),
+    }
+}
+
+/// The environment containing only `Inductive nat : Set := O : nat | S : nat -> nat`.
+pub fn nat_global() -> Global<(), ()> {
+    let mut global = Global::new();
+    inductive(
+        "nat",
+        Sort::Set,
+        vec![
+            ("O", cnst("nat")),
+            (
+                "S",
+                term(TermVariant::Product((), cnst("nat"), cnst("nat"))),
+            ),
+        ],
+    )
+    .eval(&mut global, |_| {})
+    .ok()
+    .unwrap();
+    global
+}
@@ -2,11 +2,14 @@ use std::collections::HashSet;
 use std::fmt::{self, Display, Formatter};
 
 use crate::entry::{Binder, EntryRef};
+use crate::evar::Evars;
 use crate::{CommandError, Sort, Stack, Term, TermVariant, TypeError, TypeErrorVariant};
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Global<M, B> {
     env: Vec<(M, GEntry<M, B>)>,
+    /// The axioms which are admitted obligations, along with the size of the context of each of them
+    obligations: Vec<(String, usize)>,
 }
 
 /// A reference to a name in the global environment.
@@ -17,13 +20,15 @@ pub enum GEntryRef<'a, M, B> {
     InductiveConstructor(usize, usize, &'a [Binder<M, B>], &'a [GIndBody<M, B>]),
 }
 
-enum GEntry<M, B> {
+#[derive(Clone)]
+pub(crate) enum GEntry<M, B> {
     Definition(String, Term<M, B>, Term<M, B>),
     Axiom(String, Term<M, B>),
     Inductive(Vec<Binder<M, B>>, Vec<GIndBody<M, B>>),
 }
 
 /// A single inductive type in a mutually defined set in the global environment.
+#[derive(Clone)]
 pub struct GIndBody<M, B> {
     pub name: String,
     pub arity: Vec<Binder<M, B>>,
@@ -36,6 +41,7 @@ pub struct GIndBody<M, B> {
 }
 
 /// A Constructor of an inductive type.
+#[derive(Clone)]
 pub struct GIndConstructor<M, B> {
     pub name: String,
     pub arity: Vec<Binder<M, B>>,
@@ -86,7 +92,64 @@ impl<M, B> Display for GEntry<M, B> {
 
 impl<M: Clone, B: Clone> Global<M, B> {
     pub fn new() -> Self {
-        Global { env: Vec::new() }
+        Global {
+            env: Vec::new(),
+            obligations: Vec::new(),
+        }
+    }
+
+    /// Returns the number of entries in the environment.
+    pub fn len(&self) -> usize {
+        self.env.len()
+    }
+
+    /// Returns true if the environment contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.env.is_empty()
+    }
+
+    /// Shortens the environment, keeping the `len` oldest entries and dropping the rest.
+    pub fn truncate(&mut self, len: usize) {
+        self.env.truncate(len);
+        let env = &self.env;
+        self.obligations.retain(|(name, _)| {
+            env.iter()
+                .any(|(_, entry)| matches!(entry, GEntry::Axiom(x, _) if x == name))
+        });
+    }
+
+    /// Returns an iterator over the admitted obligations from oldest to newest,
+    /// with the type of each of them and the amount of variables of its context which the type is abstracted over.
+    pub fn obligations(&self) -> impl Iterator<Item = (&str, &Term<M, B>, usize)> {
+        self.obligations.iter().map(|(name, context)| {
+            let Some(EntryRef { ttype, .. }) = self.get(name) else {
+                unreachable!()
+            };
+            (&**name, ttype, *context)
+        })
+    }
+
+    /// Returns an iterator over the entries of the environment from oldest to newest.
+    /// A set of mutually inductive types is yielded once, as `GEntryRef::Inductive` of its first type.
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = (&M, GEntryRef<'_, M, B>)> {
+        self.env.iter().map(|(meta, entry)| {
+            let entry = match entry {
+                GEntry::Definition(x, ttype, val) => GEntryRef::Definition(x, ttype, val),
+                GEntry::Axiom(x, ttype) => GEntryRef::Axiom(x, ttype),
+                GEntry::Inductive(params, bodies) => GEntryRef::Inductive(0, params, bodies),
+            };
+            (meta, entry)
+        })
+    }
+
+    /// Adds `entry` without checking it, which is left to whoever reads the environment.
+    pub(crate) fn push_unchecked(&mut self, meta: M, entry: GEntry<M, B>) {
+        self.env.push((meta, entry));
+    }
+
+    /// Marks the axiom `name` as an admitted obligation abstracted over `context` variables.
+    pub(crate) fn push_obligation(&mut self, name: String, context: usize) {
+        self.obligations.push((name, context));
     }
 
     /// Checks whether `name` is alrady used, returning an error if is.
@@ -106,7 +169,7 @@ impl<M: Clone, B: Clone> Global<M, B> {
     }
 
     /// Returns the type and value of the constant `name`.
-    pub fn get(&self, name: &str) -> Option<EntryRef<M, B>> {
+    pub fn get(&self, name: &str) -> Option<EntryRef<'_, M, B>> {
         self.env.iter().find_map(|(_, entry)| match entry {
             GEntry::Definition(x, ttype, value) => {
                 (x == name).then_some(EntryRef::with_value(value, ttype))
@@ -128,7 +191,7 @@ impl<M: Clone, B: Clone> Global<M, B> {
     }
 
     /// Returns a reference to the entry containing the constant `name` along with where inside the entry `name` was found.
-    pub(crate) fn get_entry(&self, name: &str) -> Option<GEntryRef<M, B>> {
+    pub fn get_entry(&self, name: &str) -> Option<GEntryRef<'_, M, B>> {
         self.env.iter().find_map(|(_, entry)| match entry {
             GEntry::Definition(x, ttype, val) => {
                 (x == name).then_some(GEntryRef::Definition(x, ttype, val))
@@ -169,6 +232,11 @@ pub enum CommandVariant<M, B> {
     Check(Term<M, B>),
     /// Computes the normal form of a term and prints it.
     Compute(Term<M, B>),
+    /// Creates a constant standing for a goal which is left to prove, tracked as an admitted obligation.
+    /// Its type is the goal abstracted over the given amount of variables of the context it was stated in.
+    Admit(String, Term<M, B>, usize),
+    /// Prints the admitted obligations.
+    ShowAdmitted,
 }
 
 /// A single type in a mutually defined inductive type set
@@ -209,6 +277,41 @@ impl<M, B> Display for Command<M, B> {
             CommandVariant::Print(name) => write!(f, "Print {name}."),
             CommandVariant::Check(term) => write!(f, "Check {term}."),
             CommandVariant::Compute(term) => write!(f, "Compute {term}."),
+            CommandVariant::Admit(name, ttype, _) => write!(f, "Admit {name} : {ttype}."),
+            CommandVariant::ShowAdmitted => write!(f, "Show Admitted."),
+        }
+    }
+}
+
+impl<M: Clone, B: Clone> Command<M, B> {
+    /// Calls `f` with the id of every metavariable in the terms of the command, returning the first error along with the meta of the metavariable.
+    pub fn validate_evars<E>(
+        &self,
+        mut f: impl FnMut(usize) -> Result<(), E>,
+    ) -> Result<(), (M, E)> {
+        match &self.variant {
+            CommandVariant::Definition(_, ttype, value) => {
+                ttype.validate_evars(&mut f)?;
+                value.validate_evars(f)
+            }
+            CommandVariant::Axiom(_, ttype) => ttype.validate_evars(f),
+            CommandVariant::Inductive(params, bodies) => {
+                for param in params {
+                    param.ttype.validate_evars(&mut f)?;
+                }
+                for body in bodies {
+                    body.ttype.validate_evars(&mut f)?;
+                    for constructor in &body.constructors {
+                        constructor.ttype.validate_evars(&mut f)?;
+                    }
+                }
+                Ok(())
+            }
+            CommandVariant::Print(_) => Ok(()),
+            CommandVariant::Check(term) => term.validate_evars(f),
+            CommandVariant::Compute(term) => term.validate_evars(f),
+            CommandVariant::Admit(_, ttype, _) => ttype.validate_evars(f),
+            CommandVariant::ShowAdmitted => Ok(()),
         }
     }
 }
@@ -216,6 +319,8 @@ impl<M, B> Display for Command<M, B> {
 pub enum CommandOut<'a, M, B> {
     Entry(GEntryRef<'a, M, B>),
     Term(&'a Term<M, B>),
+    /// An admitted obligation, with its type and the amount of variables of its context which the type is abstracted over.
+    Obligation(&'a str, &'a Term<M, B>, usize),
 }
 
 impl<M: Clone, B: Clone> Command<M, B> {
@@ -225,6 +330,10 @@ impl<M: Clone, B: Clone> Command<M, B> {
         global: &mut Global<M, B>,
         mut out: impl FnMut(CommandOut<M, B>),
     ) -> Result<(), (M, CommandError<M, B>)> {
+        // Commands are evaluated without any metavariables, so every metavariable they contain is unsolved
+        let evars = Evars::new();
+        self.validate_evars(|id| Err(CommandError::UnsolvedEvar(id)))?;
+
         match self.variant {
             CommandVariant::Definition(name, ttype, value) => {
                 global
@@ -232,31 +341,30 @@ impl<M: Clone, B: Clone> Command<M, B> {
                     .map_err(|err| (self.meta.clone(), err))?;
                 let mut local = Stack::new();
                 let sort = ttype
-                    .type_check(global, &mut local)
+                    .type_check(global, &evars, &mut local)
                     .map_err(|(meta, err)| (meta, CommandError::TypeError(err)))?;
-                sort.expect_sort(global, &mut local)
+                sort.expect_sort(global, &evars, &mut local)
                     .map_err(|err| (ttype.meta.clone(), CommandError::TypeError(err)))?;
                 let value_type = value
-                    .type_check(global, &mut local)
+                    .type_check(global, &evars, &mut local)
                     .map_err(|(meta, err)| (meta, CommandError::TypeError(err)))?;
                 value_type
-                    .expect_subtype(&ttype, global, &mut local)
+                    .expect_subtype(&ttype, global, &evars, &mut local)
                     .map_err(|err| (value.meta.clone(), CommandError::TypeError(err)))?;
                 global
                     .env
                     .push((self.meta, GEntry::Definition(name, ttype, value)));
             }
             CommandVariant::Axiom(name, ttype) => {
-                global
-                    .expect_fresh(&name)
-                    .map_err(|err| (self.meta.clone(), err))?;
-                let mut local = Stack::new();
-                let sort = ttype
-                    .type_check(global, &mut local)
-                    .map_err(|(meta, err)| (meta, CommandError::TypeError(err)))?;
-                sort.expect_sort(global, &mut local)
-                    .map_err(|err| (ttype.meta.clone(), CommandError::TypeError(err)))?;
-                global.env.push((self.meta, GEntry::Axiom(name, ttype)));
+                global.assume(self.meta, name, ttype)?;
+            }
+            CommandVariant::Admit(name, ttype, context) => {
+                global.assume(self.meta, name.clone(), ttype)?;
+                let Some((_, GEntry::Axiom(_, ttype))) = global.env.last() else {
+                    unreachable!()
+                };
+                out(CommandOut::Obligation(&name, ttype, context));
+                global.obligations.push((name, context));
             }
             CommandVariant::Inductive(params, bodies) => {
                 // Ensure all names are fresh
@@ -294,10 +402,10 @@ impl<M: Clone, B: Clone> Command<M, B> {
                 for param in &params {
                     let ttype = param
                         .ttype
-                        .type_check(global, &mut local)
+                        .type_check(global, &evars, &mut local)
                         .map_err(|(meta, err)| (meta, CommandError::TypeError(err)))?;
                     ttype
-                        .expect_sort(global, &mut local)
+                        .expect_sort(global, &evars, &mut local)
                         .map_err(|err| (param.ttype.meta.clone(), CommandError::TypeError(err)))?;
                     local.push_onto(param.clone().into());
                 }
@@ -307,18 +415,19 @@ impl<M: Clone, B: Clone> Command<M, B> {
                     constructors.push(body.constructors);
 
                     body.ttype
-                        .type_check(global, &mut local)
+                        .type_check(global, &evars, &mut local)
                         .map_err(|(meta, err)| (meta, CommandError::TypeError(err)))?;
 
                     let mut norm = body.ttype.clone();
-                    norm.normalize(global, &mut local);
+                    norm.normalize(global, &evars, &mut local)
+                        .map_err(|err| (body.ttype.meta.clone(), CommandError::TypeError(err)))?;
                     let (arity, norm) = norm.strip_products();
 
-                    let sort = if let TermVariant::Sort(sort) = *norm.variant {
-                        sort
+                    let sort = if let TermVariant::Sort(sort) = &*norm.variant {
+                        sort.clone()
                     } else {
                         return Err((
-                            body.ttype.meta,
+                            body.ttype.meta.clone(),
                             CommandError::TypeError(TypeError::new(
                                 &local,
                                 TypeErrorVariant::NotASort(norm),
@@ -355,58 +464,80 @@ impl<M: Clone, B: Clone> Command<M, B> {
                 }));
 
                 // Finally we typecheck the constructors and add them to the bodies of each type
-                for (body, constructors) in ind_bodies.iter_mut().zip(constructors) {
-                    body.constructors = constructors
-                        .into_iter()
-                        .map(|constructor| {
-                            let sort = constructor
-                                .ttype
-                                .type_check(global, &mut local)
-                                .map_err(|(meta, err)| (meta, CommandError::TypeError(err)))?;
-                            let _ = sort.expect_sort(global, &mut local).map_err(|err| {
-                                (constructor.ttype.meta.clone(), CommandError::TypeError(err))
-                            })?;
-
-                            // Ensure the constructor produces the correct type
-                            let mut norm = constructor.ttype.clone();
-                            norm.normalize(global, &mut local);
-                            let (arity, ttype) = norm.strip_products();
-                            let (hd, args) = ttype.strip_args();
-                            if !hd.is_const(&body.name) {
-                                return Err((
-                                    constructor.ttype.meta.clone(),
-                                    CommandError::TypeError(TypeError::new(
-                                        &local,
-                                        TypeErrorVariant::NotOfExpectedInducitve(
-                                            body.name.clone(),
-                                            constructor.ttype,
-                                        ),
-                                    )),
-                                ));
-                            }
-
-                            let full_type = params.iter().cloned().rev().fold(
-                                constructor.ttype.clone(),
-                                |body, binder| Term {
-                                    meta: body.meta.clone(),
-                                    variant: Box::new(TermVariant::Product(
-                                        binder.x,
-                                        binder.ttype,
-                                        body,
-                                    )),
-                                },
-                            );
-
-                            Ok(GIndConstructor {
-                                name: constructor.name,
-                                arity,
-                                args,
-                                arity_type: constructor.ttype,
-                                full_type,
-                            })
-                        })
-                        .collect::<Result<_, (M, CommandError<M, B>)>>()?;
-                }
+                let checked =
+                    ind_bodies
+                        .iter_mut()
+                        .zip(constructors)
+                        .try_for_each(|(body, constructors)| {
+                            body.constructors = constructors
+                                .into_iter()
+                                .map(|constructor| {
+                                    let sort = constructor
+                                        .ttype
+                                        .type_check(global, &evars, &mut local)
+                                        .map_err(|(meta, err)| {
+                                            (meta, CommandError::TypeError(err))
+                                        })?;
+                                    let _ = sort.expect_sort(global, &evars, &mut local).map_err(
+                                        |err| {
+                                            (
+                                                constructor.ttype.meta.clone(),
+                                                CommandError::TypeError(err),
+                                            )
+                                        },
+                                    )?;
+
+                                    // Ensure the constructor produces the correct type
+                                    let mut norm = constructor.ttype.clone();
+                                    norm.normalize(global, &evars, &mut local).map_err(|err| {
+                                        (
+                                            constructor.ttype.meta.clone(),
+                                            CommandError::TypeError(err),
+                                        )
+                                    })?;
+                                    let (arity, ttype) = norm.strip_products();
+                                    let (hd, args) = ttype.strip_args();
+                                    if !hd.is_const(&body.name) {
+                                        return Err((
+                                            constructor.ttype.meta.clone(),
+                                            CommandError::TypeError(TypeError::new(
+                                                &local,
+                                                TypeErrorVariant::NotOfExpectedInducitve(
+                                                    body.name.clone(),
+                                                    constructor.ttype,
+                                                ),
+                                            )),
+                                        ));
+                                    }
+
+                                    let full_type = params.iter().cloned().rev().fold(
+                                        constructor.ttype.clone(),
+                                        |body, binder| Term {
+                                            meta: body.meta.clone(),
+                                            variant: Box::new(TermVariant::Product(
+                                                binder.x,
+                                                binder.ttype,
+                                                body,
+                                            )),
+                                        },
+                                    );
+
+                                    Ok(GIndConstructor {
+                                        name: constructor.name,
+                                        arity,
+                                        args,
+                                        arity_type: constructor.ttype,
+                                        full_type,
+                                    })
+                                })
+                                .collect::<Result<_, (M, CommandError<M, B>)>>()?;
+                            Ok(())
+                        });
+
+                // With the constructors typechecked, we can now remove the new types, so that they can be properly instantiated as inductive types.
+                // This must also happen if the check failed, so that the environment is left untouched.
+                global.env.truncate(global.env.len() - ind_bodies.len());
+                checked?;
 
                 for body in &ind_bodies {
                     for constructor in &body.constructors {
@@ -433,9 +564,6 @@ impl<M: Clone, B: Clone> Command<M, B> {
                     }
                 }
 
-                // With the constructors typechecked, we can now remove the new types, so that they can be properly instantiated as inductive types
-                global.env.truncate(global.env.len() - ind_bodies.len());
-
                 global
                     .env
                     .push((self.meta, GEntry::Inductive(params, ind_bodies)));
@@ -457,18 +585,46 @@ impl<M: Clone, B: Clone> Command<M, B> {
             CommandVariant::Check(term) => {
                 let mut local = Stack::new();
                 let ttype = term
-                    .type_check(global, &mut local)
+                    .type_check(global, &evars, &mut local)
                     .map_err(|(meta, err)| (meta, CommandError::TypeError(err)))?;
                 out(CommandOut::Term(&ttype))
             }
             CommandVariant::Compute(mut term) => {
                 let mut local = Stack::new();
-                term.type_check(global, &mut local)
+                term.type_check(global, &evars, &mut local)
                     .map_err(|(meta, err)| (meta, CommandError::TypeError(err)))?;
-                term.normalize(global, &mut local);
+                term.normalize(global, &evars, &mut local)
+                    .map_err(|err| (term.meta.clone(), CommandError::TypeError(err)))?;
                 out(CommandOut::Term(&term))
             }
+            CommandVariant::ShowAdmitted => {
+                for (name, ttype, context) in global.obligations() {
+                    out(CommandOut::Obligation(name, ttype, context))
+                }
+            }
         }
         Ok(())
     }
 }
+
+impl<M: Clone, B: Clone> Global<M, B> {
+    /// Adds the axiom `name` of type `ttype`, after checking that `ttype` is a type.
+    fn assume(
+        &mut self,
+        meta: M,
+        name: String,
+        ttype: Term<M, B>,
+    ) -> Result<(), (M, CommandError<M, B>)> {
+        self.expect_fresh(&name)
+            .map_err(|err| (meta.clone(), err))?;
+        let evars = Evars::new();
+        let mut local = Stack::new();
+        let sort = ttype
+            .type_check(self, &evars, &mut local)
+            .map_err(|(meta, err)| (meta, CommandError::TypeError(err)))?;
+        sort.expect_sort(self, &evars, &mut local)
+            .map_err(|err| (ttype.meta.clone(), CommandError::TypeError(err)))?;
+        self.env.push((meta, GEntry::Axiom(name, ttype)));
+        Ok(())
+    }
+}
@@ -1,11 +1,18 @@
 pub mod entry;
 pub mod error;
+pub mod evar;
+pub mod export;
+// Terms and environments shared by the tests of this crate and of the crates checking its output
+#[cfg(any(test, feature = "fixtures"))]
+pub mod fixtures;
 pub mod global;
 pub mod sort;
 pub mod stack;
 pub mod term;
+pub mod unify;
 
 pub use error::{CommandError, TypeError, TypeErrorVariant};
+pub use evar::Evars;
 pub use global::{Command, CommandVariant, Global, IndBody, IndConstructor};
 pub use sort::Sort;
 pub use stack::Stack;
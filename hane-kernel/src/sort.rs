@@ -41,11 +41,12 @@ impl Ord for Sort {
 }
 
 impl Sort {
-    pub fn ttype(&self) -> Self {
+    /// Returns the sort of this sort, or `None` if its universe level would overflow.
+    pub fn ttype(&self) -> Option<Self> {
         match self {
-            Sort::Prop => Sort::Type(0),
-            Sort::Set => Sort::Type(0),
-            Sort::Type(n) => Sort::Type(n + 1),
+            Sort::Prop => Some(Sort::Type(0)),
+            Sort::Set => Some(Sort::Type(0)),
+            Sort::Type(n) => n.checked_add(1).map(Sort::Type),
         }
     }
 
@@ -59,7 +59,7 @@ impl<T> Stack<T> {
 
     /// Creates a new stack slot with ownership of all elements added trough it.
     /// These elements are removed from the stack when the slot is dropped.
-    pub fn slot(&mut self) -> StackSlot<T> {
+    pub fn slot(&mut self) -> StackSlot<'_, T> {
         StackSlot {
             slot: self.len(),
             stack: ManuallyDrop::new(self),
@@ -67,7 +67,7 @@ impl<T> Stack<T> {
     }
 
     /// Creates a new stack slot with a single element
-    pub fn push(&mut self, value: T) -> StackSlot<T> {
+    pub fn push(&mut self, value: T) -> StackSlot<'_, T> {
         let slot = self.0.len();
         self.0.push(value);
         StackSlot {
@@ -3,10 +3,23 @@ use std::convert::Infallible;
 use std::fmt::{self, Display, Formatter};
 
 use crate::entry::{Binder, Entry, EntryRef};
+use crate::evar::{EvarDecl, Evars};
 use crate::global::GEntryRef;
 use crate::{Global, Sort, Stack, TypeError, TypeErrorVariant};
 
-#[derive(Clone)]
+/// Minimum amount of native stack that must be left before a traversal moves onto a new segment.
+const RED_ZONE: usize = 64 * 1024;
+/// Size of the stack segments allocated when the current one runs low.
+const STACK_SEGMENT: usize = 4 * 1024 * 1024;
+
+/// Runs `f`, moving it onto a freshly allocated stack segment if the current one is about to run out.
+///
+/// Every recursive traversal of terms goes through this, so that deeply nested terms cannot overflow the native stack.
+#[inline]
+pub fn grow<R>(f: impl FnOnce() -> R) -> R {
+    stacker::maybe_grow(RED_ZONE, STACK_SEGMENT, f)
+}
+
 pub struct Term<M, B> {
     pub meta: M,
     pub variant: Box<TermVariant<M, B>>,
@@ -22,6 +35,11 @@ pub enum TermVariant<M, B> {
     Abstract(B, Term<M, B>, Term<M, B>),
     Bind(B, Term<M, B>, Term<M, B>, Term<M, B>),
     Match(Term<M, B>, B, MatchArm<M, B>, Vec<MatchArm<M, B>>),
+    /// A metavariable along with a substitution for the variables of its context, oldest first.
+    Evar(usize, Vec<Term<M, B>>),
+    /// Rewrites the expected type of the body with an equality, from right to left if the flag is set.
+    /// Only the elaborator can turn it into a match, so the kernel rejects it.
+    Rewrite(bool, Term<M, B>, Term<M, B>),
 }
 
 #[derive(Clone)]
@@ -32,9 +50,56 @@ pub struct MatchArm<M, B> {
     pub body: Term<M, B>,
 }
 
+impl<M: Clone, B: Clone> Clone for Term<M, B> {
+    fn clone(&self) -> Self {
+        grow(|| Term {
+            meta: self.meta.clone(),
+            variant: self.variant.clone(),
+        })
+    }
+}
+
+impl<M, B> Drop for Term<M, B> {
+    fn drop(&mut self) {
+        // The derived drop glue would recurse once per node, so the subterms are instead detached and dropped from a work list.
+        if self.variant.is_leaf() {
+            return;
+        }
+        let mut work = vec![self.take_variant()];
+        while let Some(variant) = work.pop() {
+            match variant {
+                TermVariant::Sort(_) | TermVariant::Var(_) | TermVariant::Const(_) => {}
+                TermVariant::App(mut t1, mut t2)
+                | TermVariant::Product(_, mut t1, mut t2)
+                | TermVariant::Abstract(_, mut t1, mut t2) => {
+                    work.push(t1.take_variant());
+                    work.push(t2.take_variant());
+                }
+                TermVariant::Bind(_, mut t1, mut t2, mut t3) => {
+                    work.push(t1.take_variant());
+                    work.push(t2.take_variant());
+                    work.push(t3.take_variant());
+                }
+                TermVariant::Match(mut t, _, mut ret, arms) => {
+                    work.push(t.take_variant());
+                    work.push(ret.body.take_variant());
+                    work.extend(arms.into_iter().map(|mut arm| arm.body.take_variant()));
+                }
+                TermVariant::Evar(_, subst) => {
+                    work.extend(subst.into_iter().map(|mut t| t.take_variant()));
+                }
+                TermVariant::Rewrite(_, mut eq, mut t) => {
+                    work.push(eq.take_variant());
+                    work.push(t.take_variant());
+                }
+            }
+        }
+    }
+}
+
 impl<M, B> PartialEq for Term<M, B> {
     fn eq(&self, other: &Self) -> bool {
-        self.variant == other.variant
+        grow(|| self.variant == other.variant)
     }
 }
 
@@ -53,6 +118,10 @@ impl<M, B> PartialEq for TermVariant<M, B> {
             (Self::Match(l0, _, l1, l2), Self::Match(r0, _, r1, r2)) => {
                 l0 == r0 && l1 == r1 && l2 == r2
             }
+            (Self::Evar(l0, l1), Self::Evar(r0, r1)) => l0 == r0 && l1 == r1,
+            (Self::Rewrite(l0, l1, l2), Self::Rewrite(r0, r1, r2)) => {
+                l0 == r0 && l1 == r1 && l2 == r2
+            }
             _ => false,
         }
     }
@@ -90,13 +159,48 @@ impl<M, B> Display for TermVariant<M, B> {
                 }
                 write!(f, " end")
             }
+            TermVariant::Evar(id, subst) => {
+                write!(f, "?{id}[")?;
+                let mut sep = "";
+                for t in subst {
+                    write!(f, "{sep}{t}")?;
+                    sep = "; ";
+                }
+                write!(f, "]")
+            }
+            TermVariant::Rewrite(reverse, eq, t) => {
+                let direction = if *reverse { "<-" } else { "->" };
+                write!(f, "rewrite[{direction}] ({eq}) ({t})")
+            }
         }
     }
 }
 
 impl<M, B> Display for Term<M, B> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.variant)
+        grow(|| write!(f, "{}", self.variant))
+    }
+}
+
+impl<M, B> TermVariant<M, B> {
+    /// Returns whether the variant has no subterms.
+    fn is_leaf(&self) -> bool {
+        matches!(
+            self,
+            TermVariant::Sort(_) | TermVariant::Var(_) | TermVariant::Const(_)
+        )
+    }
+}
+
+impl<M, B> Term<M, B> {
+    /// Moves the variant out of the term, leaving a placeholder leaf in its place.
+    fn take_variant(&mut self) -> TermVariant<M, B> {
+        std::mem::replace(&mut *self.variant, TermVariant::Var(0))
+    }
+
+    /// Consumes the term, returning its variant.
+    pub fn into_variant(mut self) -> TermVariant<M, B> {
+        self.take_variant()
     }
 }
 
@@ -139,53 +243,67 @@ impl<M: Clone, B: Clone> Term<M, B> {
         push: usize,
         f: &mut impl FnMut(&M, usize, usize) -> Result<Self, E>,
     ) -> Result<Self, E> {
-        let variant = match &*self.variant {
-            TermVariant::Sort(sort) => TermVariant::Sort(sort.clone()),
-            TermVariant::Var(n) => return f(&self.meta, *n, push),
-            TermVariant::Const(name) => TermVariant::Const(name.to_owned()),
-            TermVariant::App(t, v) => {
-                TermVariant::App(t.try_subst_inner(push, f)?, v.try_subst_inner(push, f)?)
-            }
-            TermVariant::Product(x, x_tp, t) => TermVariant::Product(
-                x.clone(),
-                x_tp.try_subst_inner(push, f)?,
-                t.try_subst_inner(push + 1, f)?,
-            ),
-            TermVariant::Abstract(x, x_tp, t) => TermVariant::Abstract(
-                x.clone(),
-                x_tp.try_subst_inner(push, f)?,
-                t.try_subst_inner(push + 1, f)?,
-            ),
-            TermVariant::Bind(x, x_tp, x_val, t) => TermVariant::Bind(
-                x.clone(),
-                x_tp.try_subst_inner(push, f)?,
-                x_val.try_subst_inner(push, f)?,
-                t.try_subst_inner(push + 1, f)?,
-            ),
-            TermVariant::Match(t, x, ret, arms) => TermVariant::Match(
-                t.try_subst_inner(push, f)?,
-                x.clone(),
-                MatchArm {
-                    meta: ret.meta.clone(),
-                    constructor: ret.constructor.clone(),
-                    params: ret.params.clone(),
-                    body: ret.body.try_subst_inner(push + ret.params.len() + 1, f)?,
-                },
-                arms.iter()
-                    .map(|arm| {
-                        Ok(MatchArm {
-                            meta: arm.meta.clone(),
-                            constructor: arm.constructor.clone(),
-                            params: arm.params.clone(),
-                            body: arm.body.try_subst_inner(push + arm.params.len(), f)?,
+        grow(|| {
+            let variant = match &*self.variant {
+                TermVariant::Sort(sort) => TermVariant::Sort(sort.clone()),
+                TermVariant::Var(n) => return f(&self.meta, *n, push),
+                TermVariant::Const(name) => TermVariant::Const(name.to_owned()),
+                TermVariant::App(t, v) => {
+                    TermVariant::App(t.try_subst_inner(push, f)?, v.try_subst_inner(push, f)?)
+                }
+                TermVariant::Product(x, x_tp, t) => TermVariant::Product(
+                    x.clone(),
+                    x_tp.try_subst_inner(push, f)?,
+                    t.try_subst_inner(push + 1, f)?,
+                ),
+                TermVariant::Abstract(x, x_tp, t) => TermVariant::Abstract(
+                    x.clone(),
+                    x_tp.try_subst_inner(push, f)?,
+                    t.try_subst_inner(push + 1, f)?,
+                ),
+                TermVariant::Bind(x, x_tp, x_val, t) => TermVariant::Bind(
+                    x.clone(),
+                    x_tp.try_subst_inner(push, f)?,
+                    x_val.try_subst_inner(push, f)?,
+                    t.try_subst_inner(push + 1, f)?,
+                ),
+                TermVariant::Match(t, x, ret, arms) => TermVariant::Match(
+                    t.try_subst_inner(push, f)?,
+                    x.clone(),
+                    MatchArm {
+                        meta: ret.meta.clone(),
+                        constructor: ret.constructor.clone(),
+                        params: ret.params.clone(),
+                        body: ret.body.try_subst_inner(push + ret.params.len() + 1, f)?,
+                    },
+                    arms.iter()
+                        .map(|arm| {
+                            Ok(MatchArm {
+                                meta: arm.meta.clone(),
+                                constructor: arm.constructor.clone(),
+                                params: arm.params.clone(),
+                                body: arm.body.try_subst_inner(push + arm.params.len(), f)?,
+                            })
                         })
-                    })
-                    .collect::<Result<_, E>>()?,
-            ),
-        };
-        Ok(Term {
-            meta: self.meta.clone(),
-            variant: Box::new(variant),
+                        .collect::<Result<_, E>>()?,
+                ),
+                TermVariant::Evar(id, subst) => TermVariant::Evar(
+                    *id,
+                    subst
+                        .iter()
+                        .map(|t| t.try_subst_inner(push, f))
+                        .collect::<Result<_, E>>()?,
+                ),
+                TermVariant::Rewrite(reverse, eq, t) => TermVariant::Rewrite(
+                    *reverse,
+                    eq.try_subst_inner(push, f)?,
+                    t.try_subst_inner(push, f)?,
+                ),
+            };
+            Ok(Term {
+                meta: self.meta.clone(),
+                variant: Box::new(variant),
+            })
         })
     }
 
@@ -200,7 +318,7 @@ impl<M: Clone, B: Clone> Term<M, B> {
         &self,
         f: &mut impl FnMut(&str) -> Result<(), E>,
     ) -> Result<(), (M, E)> {
-        match &*self.variant {
+        grow(|| match &*self.variant {
             TermVariant::Sort(_) => Ok(()),
             TermVariant::Var(_) => Ok(()),
             TermVariant::Const(name) => f(name).map_err(|e| (self.meta.clone(), e)),
@@ -227,22 +345,139 @@ impl<M: Clone, B: Clone> Term<M, B> {
                 arms.iter()
                     .try_for_each(|arm| arm.body.validate_consts_inner(f))
             }
-        }
+            TermVariant::Evar(_, subst) => {
+                subst.iter().try_for_each(|t| t.validate_consts_inner(f))
+            }
+            TermVariant::Rewrite(_, eq, t) => {
+                eq.validate_consts_inner(f)?;
+                t.validate_consts_inner(f)
+            }
+        })
+    }
+
+    /// Calls `f` with the id of every metavariable in the term, returning the first error along with the meta of the metavariable.
+    pub fn validate_evars<E>(
+        &self,
+        mut f: impl FnMut(usize) -> Result<(), E>,
+    ) -> Result<(), (M, E)> {
+        self.validate_evars_inner(&mut f)
+    }
+
+    fn validate_evars_inner<E>(
+        &self,
+        f: &mut impl FnMut(usize) -> Result<(), E>,
+    ) -> Result<(), (M, E)> {
+        grow(|| match &*self.variant {
+            TermVariant::Sort(_) => Ok(()),
+            TermVariant::Var(_) => Ok(()),
+            TermVariant::Const(_) => Ok(()),
+            TermVariant::App(t1, t2) => {
+                t1.validate_evars_inner(f)?;
+                t2.validate_evars_inner(f)
+            }
+            TermVariant::Product(_, t1, t2) => {
+                t1.validate_evars_inner(f)?;
+                t2.validate_evars_inner(f)
+            }
+            TermVariant::Abstract(_, t1, t2) => {
+                t1.validate_evars_inner(f)?;
+                t2.validate_evars_inner(f)
+            }
+            TermVariant::Bind(_, t1, t2, t3) => {
+                t1.validate_evars_inner(f)?;
+                t2.validate_evars_inner(f)?;
+                t3.validate_evars_inner(f)
+            }
+            TermVariant::Match(t, _, ret, arms) => {
+                t.validate_evars_inner(f)?;
+                ret.body.validate_evars_inner(f)?;
+                arms.iter()
+                    .try_for_each(|arm| arm.body.validate_evars_inner(f))
+            }
+            TermVariant::Evar(id, subst) => {
+                f(*id).map_err(|e| (self.meta.clone(), e))?;
+                subst.iter().try_for_each(|t| t.validate_evars_inner(f))
+            }
+            TermVariant::Rewrite(_, eq, t) => {
+                eq.validate_evars_inner(f)?;
+                t.validate_evars_inner(f)
+            }
+        })
+    }
+
+    /// Replaces every solved metavariable in the term with its instantiated value, recursively.
+    ///
+    /// Metavariables whose substitution does not match the length of their context are left untouched, `type_check` rejects them.
+    pub fn zonk(&self, evars: &Evars<M, B>) -> Self {
+        grow(|| {
+            let variant = match &*self.variant {
+                TermVariant::Sort(sort) => TermVariant::Sort(sort.clone()),
+                TermVariant::Var(n) => TermVariant::Var(*n),
+                TermVariant::Const(name) => TermVariant::Const(name.clone()),
+                TermVariant::App(t, v) => TermVariant::App(t.zonk(evars), v.zonk(evars)),
+                TermVariant::Product(x, x_tp, t) => {
+                    TermVariant::Product(x.clone(), x_tp.zonk(evars), t.zonk(evars))
+                }
+                TermVariant::Abstract(x, x_tp, t) => {
+                    TermVariant::Abstract(x.clone(), x_tp.zonk(evars), t.zonk(evars))
+                }
+                TermVariant::Bind(x, x_tp, x_val, t) => TermVariant::Bind(
+                    x.clone(),
+                    x_tp.zonk(evars),
+                    x_val.zonk(evars),
+                    t.zonk(evars),
+                ),
+                TermVariant::Match(t, x, ret, arms) => TermVariant::Match(
+                    t.zonk(evars),
+                    x.clone(),
+                    MatchArm {
+                        meta: ret.meta.clone(),
+                        constructor: ret.constructor.clone(),
+                        params: ret.params.clone(),
+                        body: ret.body.zonk(evars),
+                    },
+                    arms.iter()
+                        .map(|arm| MatchArm {
+                            meta: arm.meta.clone(),
+                            constructor: arm.constructor.clone(),
+                            params: arm.params.clone(),
+                            body: arm.body.zonk(evars),
+                        })
+                        .collect(),
+                ),
+                TermVariant::Evar(id, subst) => {
+                    let subst: Vec<_> = subst.iter().map(|t| t.zonk(evars)).collect();
+                    match evars.get(*id) {
+                        Some(EvarDecl {
+                            context,
+                            value: Some(value),
+                            ..
+                        }) if context.len() == subst.len() => {
+                            return value.zonk(evars).instantiate(&subst)
+                        }
+                        _ => TermVariant::Evar(*id, subst),
+                    }
+                }
+                TermVariant::Rewrite(reverse, eq, t) => {
+                    TermVariant::Rewrite(*reverse, eq.zonk(evars), t.zonk(evars))
+                }
+            };
+            Term {
+                meta: self.meta.clone(),
+                variant: Box::new(variant),
+            }
+        })
     }
 
     pub fn strict_positivity(
         &self,
-        global: &Global<M, B>,
+        _global: &Global<M, B>,
         mut f: impl FnMut(&str) -> bool,
     ) -> bool {
-        self.strict_positivity_inner(global, &mut f)
+        self.strict_positivity_inner(&mut f)
     }
 
-    fn strict_positivity_inner(
-        mut self: &Self,
-        global: &Global<M, B>,
-        f: &mut impl FnMut(&str) -> bool,
-    ) -> bool {
+    fn strict_positivity_inner(mut self: &Self, f: &mut impl FnMut(&str) -> bool) -> bool {
         while let TermVariant::Product(_, input, body) = &*self.variant {
             if input
                 .validate_consts(|name| (!f(name)).then_some(()).ok_or(()))
@@ -272,14 +507,9 @@ impl<M: Clone, B: Clone> Term<M, B> {
                 arg.validate_consts(|name| (!f(name)).then_some(()).ok_or(()))
                     .is_ok()
             })
-        } else if let Some(GEntryRef::Inductive(_, _params, bodies)) = global.get_entry(hd) {
-            if bodies.len() != 1 {
-                return false;
-            }
-            let _body = &bodies[1];
-            //TODO: [Nested Positivity](https://coq.inria.fr/distrib/current/refman/language/core/inductive.html#nested-positivity)
-            unimplemented!();
         } else {
+            //TODO: [Nested Positivity](https://coq.inria.fr/distrib/current/refman/language/core/inductive.html#nested-positivity)
+            // Until nested inductive types are supported, occurrences as arguments to other types are rejected.
             false
         }
     }
@@ -346,201 +576,421 @@ impl<M: Clone, B: Clone> Term<M, B> {
         })
     }
 
-    pub fn normalize(&mut self, global: &Global<M, B>, local: &mut Stack<Entry<M, B>>) {
-        loop {
-            match &mut *self.variant {
-                TermVariant::Sort(_) => break,
-                TermVariant::Var(n) => {
-                    // δ reduction
-                    if let Some(value) = &local.get(*n).unwrap().value {
-                        // To move the value into scope, it must first be pushed passed it self, then passed the other `n`
-                        *self = value.push(*n + 1);
-                        continue;
+    /// Substitutes `subst[i]` for the `i`th oldest variable of the term, which must only refer to `subst.len()` variables.
+    ///
+    /// This is how the value of a metavariable is moved into the scope of an occurrence `Evar(id, subst)`.
+    pub fn instantiate(&self, subst: &[Self]) -> Self {
+        self.subst_many(0, subst.len(), |i| &subst[i])
+    }
+
+    /// Computes the normal form of the term.
+    ///
+    /// This function returns an error if it encounters an ill-formed subterm while reducing,
+    /// such as an unbound variable or a match on something that is not an inductive type.
+    pub fn normalize(
+        &mut self,
+        global: &Global<M, B>,
+        evars: &Evars<M, B>,
+        local: &mut Stack<Entry<M, B>>,
+    ) -> Result<(), TypeError<M, B>> {
+        grow(|| {
+            loop {
+                match &mut *self.variant {
+                    TermVariant::Sort(_) => {}
+                    TermVariant::Var(n) => {
+                        // δ reduction
+                        let entry = local.get(*n).ok_or_else(|| {
+                            TypeError::new(local, TypeErrorVariant::DebruijnOutOfScope(*n))
+                        })?;
+                        if let Some(value) = &entry.value {
+                            // To move the value into scope, it must first be pushed passed it self, then passed the other `n`
+                            *self = value.push(*n + 1);
+                            continue;
+                        }
                     }
-                }
-                TermVariant::Const(name) => {
-                    // δ reduction
-                    if let Some(value) = global.get(name).unwrap().value {
-                        *self = value.push(local.len());
-                        continue;
+                    TermVariant::Const(name) => {
+                        // δ reduction
+                        let entry = global.get(name).ok_or_else(|| {
+                            TypeError::new(local, TypeErrorVariant::UndefinedConst(name.clone()))
+                        })?;
+                        if let Some(value) = entry.value {
+                            *self = value.push(local.len());
+                            continue;
+                        }
                     }
-                }
-                TermVariant::App(f, v) => {
-                    f.normalize(global, local);
-                    v.normalize(global, local);
+                    TermVariant::App(_, _) => {
+                        // Only the head is reduced before the arguments are normalized,
+                        // so that they are only normalized where the function uses them
+                        self.whnf(global, evars, local)?;
+                        let TermVariant::App(f, v) = &mut *self.variant else {
+                            continue;
+                        };
+                        f.normalize(global, evars, local)?;
+                        v.normalize(global, evars, local)?;
 
-                    // β reduction
-                    if let TermVariant::Abstract(_, _, t) = &*f.variant {
-                        *self = t.subst_single(0, v);
+                        // β reduction
+                        if let TermVariant::Abstract(_, _, t) = &*f.variant {
+                            *self = t.subst_single(0, v);
+                            continue;
+                        }
+                    }
+                    TermVariant::Product(x, input_type, output_type) => {
+                        input_type.normalize(global, evars, local)?;
+                        let mut local = local.push(Entry::new(x.clone(), input_type.clone()));
+                        output_type.normalize(global, evars, &mut local)?;
+                    }
+                    TermVariant::Abstract(x, input_type, body) => {
+                        input_type.normalize(global, evars, local)?;
+                        let mut local = local.push(Entry::new(x.clone(), input_type.clone()));
+                        body.normalize(global, evars, &mut local)?;
+                    }
+                    TermVariant::Bind(_name, _type, val, t) => {
+                        val.normalize(global, evars, local)?;
+                        // ζ reduction (Remove let binding)
+                        *self = t.subst_single(0, val);
                         continue;
                     }
-                }
-                TermVariant::Product(x, input_type, output_type) => {
-                    input_type.normalize(global, local);
-                    let mut local = local.push(Entry::new(x.clone(), input_type.clone()));
-                    output_type.normalize(global, &mut local);
-                }
-                TermVariant::Abstract(x, input_type, body) => {
-                    input_type.normalize(global, local);
-                    let mut local = local.push(Entry::new(x.clone(), input_type.clone()));
-                    body.normalize(global, &mut local);
-                }
-                TermVariant::Bind(_name, _type, val, t) => {
-                    val.normalize(global, local);
-                    // ζ reduction (Remove let binding)
-                    *self = t.subst_single(0, val);
-                    continue;
-                }
-                TermVariant::Match(t, name, ret, arms) => {
-                    t.normalize(global, local);
+                    TermVariant::Match(t, name, ret, arms) => {
+                        // The arguments of a constructor are only normalized where the arm uses them
+                        t.whnf(global, evars, local)?;
+                        let constructor = match &*t.app_head().variant {
+                            TermVariant::Const(c) => arms.iter().any(|arm| arm.constructor == *c),
+                            _ => false,
+                        };
+                        if !constructor {
+                            t.normalize(global, evars, local)?;
+                        }
+
+                        // ι reduction (Evaluate match expresions)
+                        if let TermVariant::Const(constructor) = &*t.app_head().variant {
+                            if let Some(arm) =
+                                arms.iter_mut().find(|arm| arm.constructor == *constructor)
+                            {
+                                let body = Term {
+                                    meta: arm.body.meta.clone(),
+                                    variant: Box::new(TermVariant::Sort(Sort::Prop)),
+                                };
+                                let body = std::mem::replace(&mut arm.body, body);
+                                let t_rep = Term {
+                                    meta: t.meta.clone(),
+                                    variant: Box::new(TermVariant::Sort(Sort::Prop)),
+                                };
+                                let t = std::mem::replace(t, t_rep);
+                                let (_, args) = t.strip_args();
+                                *self = body.subst_many(0, args.len(), |i| &args[i]);
+                                continue;
+                            }
+                        }
+
+                        let (i, params, bodies) = match global.get_entry(&ret.constructor) {
+                            Some(GEntryRef::Inductive(i, params, bodies)) => (i, params, bodies),
+                            Some(_) => {
+                                return Err(TypeError::new(
+                                    local,
+                                    TypeErrorVariant::NotAnInductiveType(ret.constructor.clone()),
+                                ))
+                            }
+                            None => {
+                                return Err(TypeError::new(
+                                    local,
+                                    TypeErrorVariant::UndefinedConst(ret.constructor.clone()),
+                                ))
+                            }
+                        };
+                        let body = &bodies[i];
+                        if ret.params.len() != params.len() + body.arity.len() {
+                            return Err(TypeError::new(
+                                local,
+                                TypeErrorVariant::IncorrectParameterCount(
+                                    params.len() + body.arity.len(),
+                                    ret.params.len(),
+                                ),
+                            ));
+                        }
+
+                        let t_type = t.type_check(global, evars, local).map_err(|(_, err)| err)?;
+                        let mut norm = t_type.clone();
+                        norm.normalize(global, evars, local)?;
+                        let (hd, mut args) = norm.strip_args();
+                        if !hd.is_const(&ret.constructor) || args.len() < params.len() {
+                            return Err(TypeError::new(
+                                local,
+                                TypeErrorVariant::NotOfExpectedInducitve(
+                                    ret.constructor.clone(),
+                                    t_type,
+                                ),
+                            ));
+                        }
+                        args.truncate(params.len());
 
-                    // ι reduction (Evaluate match expresions)
-                    if let TermVariant::Const(constructor) = &*t.app_head().variant {
-                        if let Some(arm) =
-                            arms.iter_mut().find(|arm| arm.constructor == *constructor)
                         {
-                            let body = Term {
-                                meta: arm.body.meta.clone(),
-                                variant: Box::new(TermVariant::Sort(Sort::Prop)),
-                            };
-                            let body = std::mem::replace(&mut arm.body, body);
-                            let t_rep = Term {
-                                meta: t.meta.clone(),
-                                variant: Box::new(TermVariant::Sort(Sort::Prop)),
+                            let mut local = local.slot();
+                            local.extend(ret.params.iter().zip(params).zip(&args).enumerate().map(
+                                |(i, ((x, param), value))| {
+                                    Entry::with_value(x.clone(), value.push(i), param.ttype.clone())
+                                },
+                            ));
+                            local.extend(
+                                ret.params[params.len()..]
+                                    .iter()
+                                    .zip(&body.arity)
+                                    .map(|(x, param)| Entry::new(x.clone(), param.ttype.clone())),
+                            );
+                            let ttype = (0..ret.params.len())
+                                .rev()
+                                .map(|n| Term {
+                                    meta: self.meta.clone(),
+                                    variant: Box::new(TermVariant::Var(n)),
+                                })
+                                .fold(
+                                    Term {
+                                        meta: self.meta.clone(),
+                                        variant: Box::new(TermVariant::Const(
+                                            ret.constructor.clone(),
+                                        )),
+                                    },
+                                    |f, v| Term {
+                                        meta: self.meta.clone(),
+                                        variant: Box::new(TermVariant::App(f, v)),
+                                    },
+                                );
+                            local.push_onto(Entry::new(name.clone(), ttype));
+                            ret.body.normalize(global, evars, &mut local)?;
+                        }
+
+                        for arm in arms {
+                            let constructor = if let Some(c) =
+                                body.constructors.iter().find(|c| c.name == arm.constructor)
+                            {
+                                c
+                            } else {
+                                return Err(TypeError::new(
+                                    local,
+                                    TypeErrorVariant::NotAConstructor(
+                                        body.name.clone(),
+                                        arm.constructor.clone(),
+                                        body.constructors
+                                            .iter()
+                                            .map(|constructor| constructor.name.clone())
+                                            .collect(),
+                                    ),
+                                ));
                             };
-                            let t = std::mem::replace(t, t_rep);
-                            let (_, args) = t.strip_args();
-                            *self = body.subst_many(0, args.len(), |i| &args[i]);
+                            if arm.params.len() != params.len() + constructor.arity.len() {
+                                return Err(TypeError::new(
+                                    local,
+                                    TypeErrorVariant::IncorrectParameterCount(
+                                        params.len() + constructor.arity.len(),
+                                        arm.params.len(),
+                                    ),
+                                ));
+                            }
+
+                            let mut local = local.slot();
+                            local.extend(arm.params.iter().zip(params).zip(&args).enumerate().map(
+                                |(i, ((x, param), value))| {
+                                    Entry::with_value(x.clone(), value.push(i), param.ttype.clone())
+                                },
+                            ));
+                            local.extend(
+                                arm.params[params.len()..]
+                                    .iter()
+                                    .zip(&constructor.arity)
+                                    .map(|(x, param)| Entry::new(x.clone(), param.ttype.clone())),
+                            );
+                            arm.body.normalize(global, evars, &mut local)?;
+                        }
+                    }
+                    TermVariant::Evar(id, subst) => {
+                        let decl = evars.get(*id).ok_or_else(|| {
+                            TypeError::new(local, TypeErrorVariant::UndefinedEvar(*id))
+                        })?;
+                        if subst.len() != decl.context.len() {
+                            return Err(TypeError::new(
+                                local,
+                                TypeErrorVariant::IncorrectSubstitutionLength(
+                                    decl.context.len(),
+                                    subst.len(),
+                                ),
+                            ));
+                        }
+                        // Instantiation of solved metavariables
+                        if let Some(value) = &decl.value {
+                            *self = value.instantiate(subst);
                             continue;
                         }
+                        for t in subst {
+                            t.normalize(global, evars, local)?;
+                        }
                     }
+                    TermVariant::Rewrite(_, _, _) => {
+                        return Err(TypeError::new(local, TypeErrorVariant::UnelaboratedRewrite))
+                    }
+                }
+                return Ok(());
+            }
+        })
+    }
 
-                    let (i, params, bodies) = match global.get_entry(&ret.constructor) {
-                        Some(GEntryRef::Inductive(i, params, bodies)) => (i, params, bodies),
-                        Some(_) => panic!("{} is not an inductive type", ret.constructor),
-                        None => panic!("{} is not defined", ret.constructor),
-                    };
-                    let body = &bodies[i];
-
-                    let mut t_type = t.type_check(global, local).ok().unwrap();
-                    t_type.normalize(global, local);
-                    let (hd, mut args) = t_type.strip_args();
-                    if !hd.is_const(&ret.constructor) {
-                        panic!("{i} is not the inductive type {}", ret.constructor)
+    /// Reduces the head of the term, leaving its arguments and the bodies of its binders as they are.
+    fn whnf(
+        &mut self,
+        global: &Global<M, B>,
+        evars: &Evars<M, B>,
+        local: &Stack<Entry<M, B>>,
+    ) -> Result<(), TypeError<M, B>> {
+        grow(|| {
+            loop {
+                match &mut *self.variant {
+                    TermVariant::Var(n) => {
+                        // δ reduction
+                        let entry = local.get(*n).ok_or_else(|| {
+                            TypeError::new(local, TypeErrorVariant::DebruijnOutOfScope(*n))
+                        })?;
+                        if let Some(value) = &entry.value {
+                            *self = value.push(*n + 1);
+                            continue;
+                        }
+                    }
+                    TermVariant::Const(name) => {
+                        // δ reduction
+                        let entry = global.get(name).ok_or_else(|| {
+                            TypeError::new(local, TypeErrorVariant::UndefinedConst(name.clone()))
+                        })?;
+                        if let Some(value) = entry.value {
+                            *self = value.push(local.len());
+                            continue;
+                        }
                     }
-                    args.truncate(params.len());
+                    TermVariant::App(_, _) => {
+                        let head = self.app_head_mut();
+                        head.whnf(global, evars, local)?;
 
-                    {
-                        let mut local = local.slot();
-                        local.extend(ret.params.iter().zip(params).zip(&args).enumerate().map(
-                            |(i, ((x, param), value))| {
-                                Entry::with_value(x.clone(), value.push(i), param.ttype.clone())
-                            },
-                        ));
-                        local.extend(
-                            ret.params[params.len()..]
-                                .iter()
-                                .zip(&body.arity)
-                                .map(|(x, param)| Entry::new(x.clone(), param.ttype.clone())),
-                        );
-                        let ttype = (0..ret.params.len())
-                            .rev()
-                            .map(|n| Term {
-                                meta: self.meta.clone(),
-                                variant: Box::new(TermVariant::Var(n)),
-                            })
-                            .fold(
+                        // β reduction of every argument the function takes at once,
+                        // so that its body is only copied once
+                        if let TermVariant::Abstract(_, _, _) = &*head.variant {
+                            let (f, mut args) = std::mem::replace(
+                                self,
                                 Term {
                                     meta: self.meta.clone(),
-                                    variant: Box::new(TermVariant::Const(ret.constructor.clone())),
-                                },
-                                |f, v| Term {
-                                    meta: self.meta.clone(),
-                                    variant: Box::new(TermVariant::App(f, v)),
+                                    variant: Box::new(TermVariant::Sort(Sort::Prop)),
                                 },
-                            );
-                        local.push_onto(Entry::new(name.clone(), ttype));
-                        ret.body.normalize(global, &mut local);
+                            )
+                            .strip_args();
+                            let mut body = &f;
+                            let mut n = 0;
+                            while let TermVariant::Abstract(_, _, t) = &*body.variant {
+                                if n == args.len() {
+                                    break;
+                                }
+                                body = t;
+                                n += 1;
+                            }
+                            let body = body.subst_many(0, n, |i| &args[i]);
+                            *self = args.drain(n..).fold(body, |f, v| Term {
+                                meta: f.meta.clone(),
+                                variant: Box::new(TermVariant::App(f, v)),
+                            });
+                            continue;
+                        }
                     }
+                    TermVariant::Bind(_name, _type, val, t) => {
+                        // ζ reduction
+                        *self = t.subst_single(0, val);
+                        continue;
+                    }
+                    TermVariant::Match(t, _name, _ret, arms) => {
+                        t.whnf(global, evars, local)?;
 
-                    for arm in arms {
-                        let constructor = if let Some(c) =
-                            body.constructors.iter().find(|c| c.name == arm.constructor)
-                        {
-                            c
-                        } else {
-                            panic!("{} is not a constructor of {}", arm.constructor, body.name)
-                        };
-
-                        let mut local = local.slot();
-                        local.extend(arm.params.iter().zip(params).zip(&args).enumerate().map(
-                            |(i, ((x, param), value))| {
-                                Entry::with_value(x.clone(), value.push(i), param.ttype.clone())
-                            },
-                        ));
-                        local.extend(
-                            arm.params[params.len()..]
-                                .iter()
-                                .zip(&constructor.arity)
-                                .map(|(x, param)| Entry::new(x.clone(), param.ttype.clone())),
-                        );
-                        arm.body.normalize(global, &mut local);
+                        // ι reduction
+                        if let TermVariant::Const(constructor) = &*t.app_head().variant {
+                            if let Some(arm) =
+                                arms.iter().find(|arm| arm.constructor == *constructor)
+                            {
+                                let (_, args) = t.strip_args_ref();
+                                *self = arm.body.subst_many(0, args.len(), |i| args[i]);
+                                continue;
+                            }
+                        }
                     }
+                    TermVariant::Evar(id, subst) => {
+                        if let Some(value) = evars.get(*id).and_then(|decl| decl.value.as_ref()) {
+                            *self = value.instantiate(subst);
+                            continue;
+                        }
+                    }
+                    _ => {}
                 }
+                return Ok(());
             }
-            break;
-        }
+        })
     }
 
     fn eta(&mut self) {
-        match &mut *self.variant {
-            TermVariant::Sort(_) => {}
-            TermVariant::Var(_) => {}
-            TermVariant::Const(_) => {}
-            TermVariant::App(f, v) => {
-                f.eta();
-                v.eta();
-            }
-            TermVariant::Product(_, input_type, output_type) => {
-                input_type.eta();
-                output_type.eta();
-            }
-            TermVariant::Abstract(_, input_type, body) => {
-                input_type.eta();
-                body.eta();
-            }
-            TermVariant::Bind(_, _, _, _) => unreachable!(),
-            TermVariant::Match(t, _, ret, arms) => {
-                t.eta();
-                ret.body.eta();
-                for arm in arms {
-                    arm.body.eta();
-                }
+        grow(|| {
+            match &mut *self.variant {
+                TermVariant::Sort(_) => {}
+                TermVariant::Var(_) => {}
+                TermVariant::Const(_) => {}
+                TermVariant::App(f, v) => {
+                    f.eta();
+                    v.eta();
+                }
+                TermVariant::Product(_, input_type, output_type) => {
+                    input_type.eta();
+                    output_type.eta();
+                }
+                TermVariant::Abstract(_, input_type, body) => {
+                    input_type.eta();
+                    body.eta();
+                }
+                TermVariant::Bind(_, x_tp, x_val, t) => {
+                    x_tp.eta();
+                    x_val.eta();
+                    t.eta();
+                }
+                TermVariant::Match(t, _, ret, arms) => {
+                    t.eta();
+                    ret.body.eta();
+                    for arm in arms {
+                        arm.body.eta();
+                    }
+                }
+                TermVariant::Evar(_, subst) => {
+                    for t in subst {
+                        t.eta();
+                    }
+                }
+                TermVariant::Rewrite(_, eq, t) => {
+                    eq.eta();
+                    t.eta();
+                }
             }
-        }
 
-        if let TermVariant::Abstract(_, _, body) = &*self.variant {
-            if let TermVariant::App(f, v) = &*body.variant {
-                if let TermVariant::Var(0) = &*v.variant {
-                    if let Some(f) = f.pop(0) {
-                        *self = f;
+            if let TermVariant::Abstract(_, _, body) = &*self.variant {
+                if let TermVariant::App(f, v) = &*body.variant {
+                    if let TermVariant::Var(0) = &*v.variant {
+                        if let Some(f) = f.pop(0) {
+                            *self = f;
+                        }
                     }
                 }
             }
-        }
+        })
     }
 
     pub fn expect_convertable(
         &self,
         other: &Self,
         global: &Global<M, B>,
+        evars: &Evars<M, B>,
         local: &mut Stack<Entry<M, B>>,
     ) -> Result<(), TypeError<M, B>> {
         let mut this = self.clone();
         let mut other = other.clone();
-        this.normalize(global, local);
+        this.normalize(global, evars, local)?;
         this.eta();
-        other.normalize(global, local);
+        other.normalize(global, evars, local)?;
         other.eta();
         if this == other {
             Ok(())
@@ -553,26 +1003,27 @@ impl<M: Clone, B: Clone> Term<M, B> {
     }
 
     fn subtype_inner(&self, other: &Self) -> bool {
-        match (&*self.variant, &*other.variant) {
+        grow(|| match (&*self.variant, &*other.variant) {
             (TermVariant::Sort(l), TermVariant::Sort(r)) => l <= r,
             (TermVariant::Product(_, l0, l1), TermVariant::Product(_, r0, r1)) => {
                 l0 == r0 && l1.subtype_inner(r1)
             }
             (l, r) => l == r,
-        }
+        })
     }
 
     pub fn expect_subtype(
         &self,
         other: &Self,
         global: &Global<M, B>,
+        evars: &Evars<M, B>,
         local: &mut Stack<Entry<M, B>>,
     ) -> Result<(), TypeError<M, B>> {
         let mut this = self.clone();
         let mut other0 = other.clone();
-        this.normalize(global, local);
+        this.normalize(global, evars, local)?;
         this.eta();
-        other0.normalize(global, local);
+        other0.normalize(global, evars, local)?;
         other0.eta();
         if this.subtype_inner(&other0) {
             Ok(())
@@ -588,23 +1039,37 @@ impl<M: Clone, B: Clone> Term<M, B> {
     /// If the input is not a product, it is returned unchanged.
     pub fn strip_products(mut self) -> (Vec<Binder<M, B>>, Self) {
         let mut arity = Vec::new();
-        while let TermVariant::Product(x, ttype, body) = *self.variant {
-            arity.push(Binder { x, ttype });
-            self = body
+        loop {
+            match self.take_variant() {
+                TermVariant::Product(x, ttype, body) => {
+                    arity.push(Binder { x, ttype });
+                    self = body
+                }
+                variant => {
+                    *self.variant = variant;
+                    return (arity, self);
+                }
+            }
         }
-        (arity, self)
     }
 
     /// Seperates terms of the form `f v1 .. vn` into `(f, [v1, .. , vn])`.
     /// If the input is not an application, it is returned unchanged.
     pub fn strip_args(mut self) -> (Self, Vec<Self>) {
         let mut args = Vec::new();
-        while let TermVariant::App(fun, arg) = *self.variant {
-            args.push(arg);
-            self = fun
+        loop {
+            match self.take_variant() {
+                TermVariant::App(fun, arg) => {
+                    args.push(arg);
+                    self = fun
+                }
+                variant => {
+                    *self.variant = variant;
+                    args.reverse();
+                    return (self, args);
+                }
+            }
         }
-        args.reverse();
-        (self, args)
     }
 
     /// Seperates terms of the form `forall (x1 : T1) .. (xn : Tn), t` into `([(x1 : T1), .. , (xn : Tn)], t)`.
@@ -619,6 +1084,17 @@ impl<M: Clone, B: Clone> Term<M, B> {
         (self, args)
     }
 
+    /// Finds the application head of a term, so that it can be reduced in place.
+    fn app_head_mut(&mut self) -> &mut Self {
+        if !matches!(*self.variant, TermVariant::App(_, _)) {
+            return self;
+        }
+        let TermVariant::App(f, _) = &mut *self.variant else {
+            unreachable!()
+        };
+        f.app_head_mut()
+    }
+
     /// Finds the application head of a term.
     /// If the input is not an application, it is returned unchanged.
     pub fn app_head(mut self: &Self) -> &Self {
@@ -631,11 +1107,12 @@ impl<M: Clone, B: Clone> Term<M, B> {
     pub fn expect_sort(
         &self,
         global: &Global<M, B>,
+        evars: &Evars<M, B>,
         local: &mut Stack<Entry<M, B>>,
     ) -> Result<Sort, TypeError<M, B>> {
         let mut t = self.clone();
-        t.normalize(global, local);
-        if let TermVariant::Sort(sort) = *t.variant {
+        t.normalize(global, evars, local)?;
+        if let TermVariant::Sort(sort) = t.into_variant() {
             Ok(sort)
         } else {
             Err(TypeError::new(
@@ -648,13 +1125,16 @@ impl<M: Clone, B: Clone> Term<M, B> {
     pub fn expect_product(
         mut self,
         global: &Global<M, B>,
+        evars: &Evars<M, B>,
         local: &mut Stack<Entry<M, B>>,
     ) -> Result<(Self, Self), TypeError<M, B>> {
-        self.normalize(global, local);
-        if let TermVariant::Product(_, input_type, output_type) = *self.variant {
-            Ok((input_type, output_type))
-        } else {
-            Err(TypeError::new(local, TypeErrorVariant::NotAProduct(self)))
+        self.normalize(global, evars, local)?;
+        match self.take_variant() {
+            TermVariant::Product(_, input_type, output_type) => Ok((input_type, output_type)),
+            variant => {
+                *self.variant = variant;
+                Err(TypeError::new(local, TypeErrorVariant::NotAProduct(self)))
+            }
         }
     }
 
@@ -670,316 +1150,669 @@ impl<M: Clone, B: Clone> Term<M, B> {
     pub fn type_check(
         &self,
         global: &Global<M, B>,
+        evars: &Evars<M, B>,
         local: &mut Stack<Entry<M, B>>,
     ) -> Result<Self, (M, TypeError<M, B>)> {
-        Ok(match &*self.variant {
-            TermVariant::Sort(sort) => Term {
-                meta: self.meta.clone(),
-                variant: Box::new(TermVariant::Sort(sort.ttype())),
-            },
-            TermVariant::Var(n) => {
-                // To move the type into scope, it must first be pushed passed it self, then passed the other `n`
-                return local.get(*n).map(|e| e.ttype.push(*n + 1)).ok_or_else(|| {
-                    (
-                        self.meta.clone(),
-                        TypeError::new(local, TypeErrorVariant::DebruijnOutOfScope(*n)),
-                    )
-                });
-            }
-            TermVariant::Const(name) => {
-                return global
-                    .get(name)
-                    .map(|EntryRef { ttype, .. }| ttype.push(local.len()))
-                    .ok_or_else(|| {
+        grow(|| {
+            Ok(match &*self.variant {
+                TermVariant::Sort(sort) => Term {
+                    meta: self.meta.clone(),
+                    variant: Box::new(TermVariant::Sort(sort.ttype().ok_or_else(|| {
                         (
                             self.meta.clone(),
-                            TypeError::new(local, TypeErrorVariant::UndefinedConst(name.clone())),
+                            TypeError::new(local, TypeErrorVariant::UniverseOverflow),
                         )
-                    })
-            }
-            TermVariant::App(f, v) => {
-                let f_tp = f.type_check(global, local)?;
-                let (input_type, output_type) = f_tp
-                    .expect_product(global, local)
-                    .map_err(|err| (f.meta.clone(), err))?;
-                let v_tp = v.type_check(global, local)?;
-                v_tp.expect_subtype(&input_type, global, local)
-                    .map_err(|err| (self.meta.clone(), err))?;
-                output_type.subst_single(0, v)
-            }
-            TermVariant::Product(x, x_tp, t) => {
-                let x_sort = x_tp.type_check(global, local)?;
-                let x_sort = x_sort
-                    .expect_sort(global, local)
-                    .map_err(|err| (x_tp.meta.clone(), err))?;
-                let mut local = local.push(Entry::new(x.clone(), x_tp.clone()));
-                let t_tp = t.type_check(global, &mut local)?;
-                let t_sort = t_tp
-                    .expect_sort(global, &mut local)
-                    .map_err(|err| (t.meta.clone(), err))?;
-                Term {
-                    meta: self.meta.clone(),
-                    variant: Box::new(TermVariant::Sort(x_sort.product(t_sort))),
+                    })?)),
+                },
+                TermVariant::Var(n) => {
+                    // To move the type into scope, it must first be pushed passed it self, then passed the other `n`
+                    return local.get(*n).map(|e| e.ttype.push(*n + 1)).ok_or_else(|| {
+                        (
+                            self.meta.clone(),
+                            TypeError::new(local, TypeErrorVariant::DebruijnOutOfScope(*n)),
+                        )
+                    });
                 }
-            }
-            TermVariant::Abstract(x, x_tp, t) => {
-                let x_sort = x_tp.type_check(global, local)?;
-                x_sort
-                    .expect_sort(global, local)
-                    .map_err(|err| (x_tp.meta.clone(), err))?;
-                let mut local = local.push(Entry::new(x.clone(), x_tp.clone()));
-                let t_tp = t.type_check(global, &mut local)?;
-                Term {
-                    meta: self.meta.clone(),
-                    variant: Box::new(TermVariant::Product(x.clone(), x_tp.clone(), t_tp)),
-                }
-            }
-            TermVariant::Bind(x, x_tp, x_val, t) => {
-                let x_sort = x_tp.type_check(global, local)?;
-                x_sort
-                    .expect_sort(global, local)
-                    .map_err(|err| (x_tp.meta.clone(), err))?;
-                let x_val_tp = x_val.type_check(global, local)?;
-                x_val_tp
-                    .expect_subtype(x_tp, global, local)
-                    .map_err(|err| (x_val.meta.clone(), err))?;
-                let t_subst = t.subst_single(0, x_val);
-                let mut local = local.push(Entry::new(x.clone(), x_tp.clone()));
-                t_subst.type_check(global, &mut local)?
-            }
-            TermVariant::Match(t, name, ret, arms) => {
-                let (params, body) = match global.get_entry(&ret.constructor) {
-                    Some(GEntryRef::Inductive(i, params, bodies)) => (params, &bodies[i]),
-                    Some(_) => {
+                TermVariant::Const(name) => {
+                    return global
+                        .get(name)
+                        .map(|EntryRef { ttype, .. }| ttype.push(local.len()))
+                        .ok_or_else(|| {
+                            (
+                                self.meta.clone(),
+                                TypeError::new(
+                                    local,
+                                    TypeErrorVariant::UndefinedConst(name.clone()),
+                                ),
+                            )
+                        })
+                }
+                TermVariant::App(f, v) => {
+                    let f_tp = f.type_check(global, evars, local)?;
+                    let (input_type, output_type) = f_tp
+                        .expect_product(global, evars, local)
+                        .map_err(|err| (f.meta.clone(), err))?;
+                    let v_tp = v.type_check(global, evars, local)?;
+                    v_tp.expect_subtype(&input_type, global, evars, local)
+                        .map_err(|err| (self.meta.clone(), err))?;
+                    output_type.subst_single(0, v)
+                }
+                TermVariant::Product(x, x_tp, t) => {
+                    let x_sort = x_tp.type_check(global, evars, local)?;
+                    let x_sort = x_sort
+                        .expect_sort(global, evars, local)
+                        .map_err(|err| (x_tp.meta.clone(), err))?;
+                    let mut local = local.push(Entry::new(x.clone(), x_tp.clone()));
+                    let t_tp = t.type_check(global, evars, &mut local)?;
+                    let t_sort = t_tp
+                        .expect_sort(global, evars, &mut local)
+                        .map_err(|err| (t.meta.clone(), err))?;
+                    Term {
+                        meta: self.meta.clone(),
+                        variant: Box::new(TermVariant::Sort(x_sort.product(t_sort))),
+                    }
+                }
+                TermVariant::Abstract(x, x_tp, t) => {
+                    let x_sort = x_tp.type_check(global, evars, local)?;
+                    x_sort
+                        .expect_sort(global, evars, local)
+                        .map_err(|err| (x_tp.meta.clone(), err))?;
+                    let mut local = local.push(Entry::new(x.clone(), x_tp.clone()));
+                    let t_tp = t.type_check(global, evars, &mut local)?;
+                    Term {
+                        meta: self.meta.clone(),
+                        variant: Box::new(TermVariant::Product(x.clone(), x_tp.clone(), t_tp)),
+                    }
+                }
+                TermVariant::Bind(_, x_tp, x_val, t) => {
+                    let x_sort = x_tp.type_check(global, evars, local)?;
+                    x_sort
+                        .expect_sort(global, evars, local)
+                        .map_err(|err| (x_tp.meta.clone(), err))?;
+                    let x_val_tp = x_val.type_check(global, evars, local)?;
+                    x_val_tp
+                        .expect_subtype(x_tp, global, evars, local)
+                        .map_err(|err| (x_val.meta.clone(), err))?;
+                    // With the value substituted in, the body no longer refers to `x`, so it is checked in the outer context
+                    let t_subst = t.subst_single(0, x_val);
+                    t_subst.type_check(global, evars, local)?
+                }
+                TermVariant::Match(t, name, ret, arms) => {
+                    let (params, body) = match global.get_entry(&ret.constructor) {
+                        Some(GEntryRef::Inductive(i, params, bodies)) => (params, &bodies[i]),
+                        Some(_) => {
+                            return Err((
+                                ret.meta.clone(),
+                                TypeError::new(
+                                    local,
+                                    TypeErrorVariant::NotAnInductiveType(ret.constructor.clone()),
+                                ),
+                            ))
+                        }
+                        None => {
+                            return Err((
+                                ret.meta.clone(),
+                                TypeError::new(
+                                    local,
+                                    TypeErrorVariant::UndefinedConst(ret.constructor.clone()),
+                                ),
+                            ))
+                        }
+                    };
+                    // Insure the parameter count on the return pattern is correct
+                    if ret.params.len() != params.len() + body.arity.len() {
                         return Err((
                             ret.meta.clone(),
                             TypeError::new(
                                 local,
-                                TypeErrorVariant::NotAnInductiveType(ret.constructor.clone()),
+                                TypeErrorVariant::IncorrectParameterCount(
+                                    params.len() + body.arity.len(),
+                                    ret.params.len(),
+                                ),
                             ),
-                        ))
+                        ));
                     }
-                    None => {
+                    let t_type = t.type_check(global, evars, local)?;
+                    let mut norm = t_type.clone();
+                    norm.normalize(global, evars, local)
+                        .map_err(|err| (t.meta.clone(), err))?;
+                    let (hd, mut args) = norm.strip_args();
+                    // Ensure the type of `t` is of the same inductive type that we want to match on
+                    if !hd.is_const(&ret.constructor) || args.len() < params.len() {
                         return Err((
-                            ret.meta.clone(),
+                            t.meta.clone(),
                             TypeError::new(
                                 local,
-                                TypeErrorVariant::UndefinedConst(ret.constructor.clone()),
-                            ),
-                        ))
-                    }
-                };
-                // Insure the parameter count on the return pattern is correct
-                if ret.params.len() != params.len() + body.arity.len() {
-                    return Err((
-                        ret.meta.clone(),
-                        TypeError::new(
-                            local,
-                            TypeErrorVariant::IncorrectParameterCount(
-                                params.len() + body.arity.len(),
-                                ret.params.len(),
-                            ),
-                        ),
-                    ));
-                }
-                let t_type = t.type_check(global, local)?;
-                let mut norm = t_type.clone();
-                norm.normalize(global, local);
-                let (hd, mut args) = norm.strip_args();
-                // Ensure the type of `t` is of the same inductive type that we want to match on
-                if !hd.is_const(&ret.constructor) {
-                    return Err((
-                        t.meta.clone(),
-                        TypeError::new(
-                            local,
-                            TypeErrorVariant::NotOfExpectedInducitve(
-                                ret.constructor.clone(),
-                                t_type,
+                                TypeErrorVariant::NotOfExpectedInducitve(
+                                    ret.constructor.clone(),
+                                    t_type,
+                                ),
                             ),
-                        ),
-                    ));
-                };
-                let arity_args = args.drain(params.len()..).collect::<Vec<_>>();
-
-                {
-                    // To typecheck the return type, we first need to put the type parameters into scope as well as a opaque version of the match argument.
-                    let mut local = local.slot();
-                    local.extend(ret.params.iter().zip(params).zip(&args).enumerate().map(
-                        |(i, ((x, param), value))| {
-                            Entry::with_value(x.clone(), value.push(i), param.ttype.clone())
-                        },
-                    ));
-                    local.extend(
-                        ret.params[params.len()..]
-                            .iter()
-                            .zip(&body.arity)
-                            .map(|(x, param)| Entry::new(x.clone(), param.ttype.clone())),
-                    );
-                    let ttype = (0..ret.params.len())
-                        .rev()
-                        .map(|n| Term {
-                            meta: self.meta.clone(),
-                            variant: Box::new(TermVariant::Var(n)),
-                        })
-                        .fold(
-                            Term {
-                                meta: self.meta.clone(),
-                                variant: Box::new(TermVariant::Const(ret.constructor.clone())),
-                            },
-                            |f, v| Term {
-                                meta: self.meta.clone(),
-                                variant: Box::new(TermVariant::App(f, v)),
+                        ));
+                    };
+                    let arity_args = args.drain(params.len()..).collect::<Vec<_>>();
+
+                    {
+                        // To typecheck the return type, we first need to put the type parameters into scope as well as a opaque version of the match argument.
+                        let mut local = local.slot();
+                        local.extend(ret.params.iter().zip(params).zip(&args).enumerate().map(
+                            |(i, ((x, param), value))| {
+                                Entry::with_value(x.clone(), value.push(i), param.ttype.clone())
                             },
+                        ));
+                        local.extend(
+                            ret.params[params.len()..]
+                                .iter()
+                                .zip(&body.arity)
+                                .map(|(x, param)| Entry::new(x.clone(), param.ttype.clone())),
                         );
-                    local.push_onto(Entry::new(name.clone(), ttype));
-                    let ret_sort = ret.body.type_check(global, &mut local)?;
-                    let sort = ret_sort
-                        .expect_sort(global, &mut local)
-                        .map_err(|err| (self.meta.clone(), err))?;
+                        let ttype = (0..ret.params.len())
+                            .rev()
+                            .map(|n| Term {
+                                meta: self.meta.clone(),
+                                variant: Box::new(TermVariant::Var(n)),
+                            })
+                            .fold(
+                                Term {
+                                    meta: self.meta.clone(),
+                                    variant: Box::new(TermVariant::Const(ret.constructor.clone())),
+                                },
+                                |f, v| Term {
+                                    meta: self.meta.clone(),
+                                    variant: Box::new(TermVariant::App(f, v)),
+                                },
+                            );
+                        local.push_onto(Entry::new(name.clone(), ttype));
+                        let ret_sort = ret.body.type_check(global, evars, &mut local)?;
+                        let sort = ret_sort
+                            .expect_sort(global, evars, &mut local)
+                            .map_err(|err| (self.meta.clone(), err))?;
 
-                    if body.sort == Sort::Prop
-                        && sort != Sort::Prop
-                        && !body.constructors.is_empty()
-                    {
-                        return Err((
-                            ret.meta.clone(),
-                            TypeError::new(
-                                &local,
-                                TypeErrorVariant::DisallowedEleminationSort(
-                                    body.sort.clone(),
-                                    sort,
+                        if body.sort == Sort::Prop
+                            && sort != Sort::Prop
+                            && !body.constructors.is_empty()
+                        {
+                            return Err((
+                                ret.meta.clone(),
+                                TypeError::new(
+                                    &local,
+                                    TypeErrorVariant::DisallowedEleminationSort(
+                                        body.sort.clone(),
+                                        sort,
+                                    ),
                                 ),
-                            ),
-                        ));
-                    }
-                };
+                            ));
+                        }
+                    };
 
-                let mut constrs = vec![false; body.constructors.len()];
-                for arm in arms {
-                    let constructor = if let Some((i, constructor)) = body
-                        .constructors
-                        .iter()
-                        .enumerate()
-                        .find(|(_, c)| c.name == arm.constructor)
-                    {
-                        if constrs[i] {
+                    let mut constrs = vec![false; body.constructors.len()];
+                    for arm in arms {
+                        let constructor = if let Some((i, constructor)) = body
+                            .constructors
+                            .iter()
+                            .enumerate()
+                            .find(|(_, c)| c.name == arm.constructor)
+                        {
+                            if constrs[i] {
+                                return Err((
+                                    arm.meta.clone(),
+                                    TypeError::new(
+                                        local,
+                                        TypeErrorVariant::DupplicateConstructor(
+                                            arm.constructor.clone(),
+                                        ),
+                                    ),
+                                ));
+                            }
+                            constrs[i] = true;
+                            constructor
+                        } else {
                             return Err((
                                 arm.meta.clone(),
                                 TypeError::new(
                                     local,
-                                    TypeErrorVariant::DupplicateConstructor(
+                                    TypeErrorVariant::NotAConstructor(
+                                        body.name.clone(),
                                         arm.constructor.clone(),
+                                        body.constructors
+                                            .iter()
+                                            .map(|constructor| constructor.name.clone())
+                                            .collect(),
+                                    ),
+                                ),
+                            ));
+                        };
+
+                        if arm.params.len() != params.len() + constructor.arity.len() {
+                            return Err((
+                                arm.meta.clone(),
+                                TypeError::new(
+                                    local,
+                                    TypeErrorVariant::IncorrectParameterCount(
+                                        params.len() + constructor.arity.len(),
+                                        arm.params.len(),
                                     ),
                                 ),
                             ));
                         }
-                        constrs[i] = true;
-                        constructor
-                    } else {
+                        let mut local = local.slot();
+                        local.extend(arm.params.iter().zip(params).zip(&args).enumerate().map(
+                            |(i, ((x, param), value))| {
+                                Entry::with_value(x.clone(), value.push(i), param.ttype.clone())
+                            },
+                        ));
+                        local.extend(
+                            arm.params[params.len()..]
+                                .iter()
+                                .zip(&constructor.arity)
+                                .map(|(x, param)| Entry::new(x.clone(), param.ttype.clone())),
+                        );
+                        let arm_type = arm.body.type_check(global, evars, &mut local)?;
+                        // `this` is the constructor applied to the pattern paramters
+                        // It looks like `arm.constructor 'n '(n-1) ... '1 '0`
+                        let this = (0..arm.params.len())
+                            .rev()
+                            .map(|n| Term {
+                                meta: self.meta.clone(),
+                                variant: Box::new(TermVariant::Var(n)),
+                            })
+                            .fold(
+                                Term {
+                                    meta: self.meta.clone(),
+                                    variant: Box::new(TermVariant::Const(arm.constructor.clone())),
+                                },
+                                |f, v| Term {
+                                    meta: self.meta.clone(),
+                                    variant: Box::new(TermVariant::App(f, v)),
+                                },
+                            );
+                        // The expected return type is moved into scope, then all the type arguments of the constructor are substituted into it.
+                        let exp_type = ret.body.push(arm.params.len()).subst_many(
+                            arm.params.len(),
+                            constructor.args.len() + 1,
+                            |i| {
+                                if i < constructor.args.len() {
+                                    &constructor.args[i]
+                                } else {
+                                    &this
+                                }
+                            },
+                        );
+                        arm_type
+                            .expect_subtype(&exp_type, global, evars, &mut local)
+                            .map_err(|err| (arm.body.meta.clone(), err))?;
+                    }
+
+                    if !constrs.iter().all(|b| *b) {
                         return Err((
-                            arm.meta.clone(),
+                            self.meta.clone(),
                             TypeError::new(
                                 local,
-                                TypeErrorVariant::NotAConstructor(
-                                    body.name.clone(),
-                                    arm.constructor.clone(),
+                                TypeErrorVariant::MissingConstructors(
                                     body.constructors
                                         .iter()
-                                        .map(|constructor| constructor.name.clone())
+                                        .zip(constrs)
+                                        .filter(|(_, b)| !b)
+                                        .map(|(constructor, _)| constructor.name.clone())
                                         .collect(),
                                 ),
                             ),
                         ));
-                    };
+                    }
 
-                    if arm.params.len() != params.len() + constructor.arity.len() {
+                    ret.body
+                        .subst_many(0, args.len() + arity_args.len() + 1, |i| {
+                            if i < args.len() {
+                                &args[i]
+                            } else if i < args.len() + arity_args.len() {
+                                &arity_args[i - args.len()]
+                            } else {
+                                t
+                            }
+                        })
+                }
+                TermVariant::Evar(id, subst) => {
+                    let decl = evars.get(*id).ok_or_else(|| {
+                        (
+                            self.meta.clone(),
+                            TypeError::new(local, TypeErrorVariant::UndefinedEvar(*id)),
+                        )
+                    })?;
+                    if subst.len() != decl.context.len() {
                         return Err((
-                            arm.meta.clone(),
+                            self.meta.clone(),
                             TypeError::new(
                                 local,
-                                TypeErrorVariant::IncorrectParameterCount(
-                                    params.len() + constructor.arity.len(),
-                                    arm.params.len(),
+                                TypeErrorVariant::IncorrectSubstitutionLength(
+                                    decl.context.len(),
+                                    subst.len(),
                                 ),
                             ),
                         ));
                     }
-                    let mut local = local.slot();
-                    local.extend(arm.params.iter().zip(params).zip(&args).enumerate().map(
-                        |(i, ((x, param), value))| {
-                            Entry::with_value(x.clone(), value.push(i), param.ttype.clone())
-                        },
-                    ));
-                    local.extend(
-                        arm.params[params.len()..]
-                            .iter()
-                            .zip(&constructor.arity)
-                            .map(|(x, param)| Entry::new(x.clone(), param.ttype.clone())),
-                    );
-                    let arm_type = arm.body.type_check(global, &mut local)?;
-                    // `this` is the constructor applied to the pattern paramters
-                    // It looks like `arm.constructor 'n '(n-1) ... '1 '0`
-                    let this = (0..arm.params.len())
-                        .rev()
-                        .map(|n| Term {
-                            meta: self.meta.clone(),
-                            variant: Box::new(TermVariant::Var(n)),
-                        })
-                        .fold(
-                            Term {
-                                meta: self.meta.clone(),
-                                variant: Box::new(TermVariant::Const(arm.constructor.clone())),
-                            },
-                            |f, v| Term {
-                                meta: self.meta.clone(),
-                                variant: Box::new(TermVariant::App(f, v)),
-                            },
-                        );
-                    // The expected return type is moved into scope, then all the type arguments of the constructor are substituted into it.
-                    let exp_type = ret.body.push(arm.params.len()).subst_many(
-                        arm.params.len(),
-                        constructor.args.len() + 1,
-                        |i| {
-                            if i < constructor.args.len() {
-                                &constructor.args[i]
-                            } else {
-                                &this
-                            }
-                        },
-                    );
-                    arm_type
-                        .expect_subtype(&exp_type, global, &mut local)
-                        .map_err(|err| (arm.body.meta.clone(), err))?;
+                    // Each term of the substitution must have the type of its variable, with the earlier terms substituted in
+                    for (i, (entry, t)) in decl.context.iter().rev().zip(subst).enumerate() {
+                        let ttype = entry.ttype.instantiate(&subst[..i]);
+                        t.type_check(global, evars, local)?
+                            .expect_subtype(&ttype, global, evars, local)
+                            .map_err(|err| (t.meta.clone(), err))?;
+                        if let Some(value) = &entry.value {
+                            value
+                                .instantiate(&subst[..i])
+                                .expect_convertable(t, global, evars, local)
+                                .map_err(|err| (t.meta.clone(), err))?;
+                        }
+                    }
+                    decl.ttype.instantiate(subst)
                 }
-
-                if !constrs.iter().all(|b| *b) {
+                TermVariant::Rewrite(_, _, _) => {
                     return Err((
                         self.meta.clone(),
-                        TypeError::new(
-                            local,
-                            TypeErrorVariant::MissingConstructors(
-                                body.constructors
-                                    .iter()
-                                    .zip(constrs)
-                                    .filter(|(_, b)| !b)
-                                    .map(|(constructor, _)| constructor.name.clone())
-                                    .collect(),
-                            ),
-                        ),
-                    ));
+                        TypeError::new(local, TypeErrorVariant::UnelaboratedRewrite),
+                    ))
                 }
+            })
+        })
+    }
+}
 
-                ret.body
-                    .subst_many(0, args.len() + arity_args.len() + 1, |i| {
-                        if i < args.len() {
-                            &args[i]
-                        } else if i < args.len() + arity_args.len() {
-                            &arity_args[i - args.len()]
-                        } else {
-                            t
-                        }
-                    })
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{app, cnst, nat_global, succ, term, var};
+    use crate::{Command, CommandVariant, IndBody, IndConstructor};
+
+    /// Deep enough to overflow the default native stack with a naive recursive traversal.
+    const DEPTH: usize = 200_000;
+
+    #[test]
+    fn deep_products() {
+        let global = Global::new();
+        let evars = Evars::new();
+        let mut local = Stack::new();
+        let t = (0..DEPTH).fold(term(TermVariant::Sort(Sort::Prop)), |t, _| {
+            term(TermVariant::Product(
+                (),
+                term(TermVariant::Sort(Sort::Prop)),
+                t,
+            ))
+        });
+
+        let ttype = t.type_check(&global, &evars, &mut local).ok().unwrap();
+        assert!(ttype == term(TermVariant::Sort(Sort::Type(0))));
+
+        let mut norm = t.clone();
+        norm.normalize(&global, &evars, &mut local).ok().unwrap();
+        assert!(norm == t);
+        assert!(t.push(1) == t);
+        assert!(t.validate_consts(|_| Err(())).is_ok());
+        assert!(t.to_string().len() > DEPTH);
+    }
+
+    #[test]
+    fn deep_applications() {
+        let global = nat_global();
+        let evars = Evars::new();
+        let mut local = Stack::new();
+        let t = (0..DEPTH).fold(term(TermVariant::Const("O".to_owned())), |t, _| {
+            term(TermVariant::App(
+                term(TermVariant::Const("S".to_owned())),
+                t,
+            ))
+        });
+
+        let ttype = t.type_check(&global, &evars, &mut local).ok().unwrap();
+        assert!(ttype.is_const("nat"));
+
+        let mut norm = t.clone();
+        norm.normalize(&global, &evars, &mut local).ok().unwrap();
+        assert!(norm == t);
+        assert!(t
+            .validate_consts(|name| (name != "nat").then_some(()).ok_or(()))
+            .is_ok());
+        ttype
+            .expect_convertable(&ttype, &global, &evars, &mut local)
+            .ok()
+            .unwrap();
+    }
+
+    /// A xorshift generator, so that the property tests are reproducible and need no extra dependencies.
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+    }
+
+    const CONSTS: [&str; 7] = ["nat", "O", "S", "eq", "eq_refl", "pred", "undefined"];
+
+    fn random_sort(rng: &mut Rng) -> Sort {
+        match rng.below(5) {
+            0 => Sort::Prop,
+            1 => Sort::Set,
+            2 => Sort::Type(usize::MAX),
+            _ => Sort::Type(rng.below(3)),
+        }
+    }
+
+    fn random_arm(rng: &mut Rng, depth: usize, lambdas: bool) -> MatchArm<(), ()> {
+        MatchArm {
+            meta: (),
+            constructor: CONSTS[rng.below(CONSTS.len())].to_owned(),
+            params: vec![(); rng.below(4)],
+            body: random_term(rng, depth, lambdas),
+        }
+    }
+
+    /// Generates an arbitrary, most likely ill-typed, term.
+    /// Without `lambdas` the term contains no abstractions, which makes normalization terminate even if it is ill-typed.
+    fn random_term(rng: &mut Rng, depth: usize, lambdas: bool) -> Term<(), ()> {
+        let depth = depth.saturating_sub(1);
+        let variant = match if depth == 0 {
+            rng.below(3)
+        } else {
+            rng.below(9)
+        } {
+            0 => TermVariant::Sort(random_sort(rng)),
+            1 => TermVariant::Var(rng.below(4)),
+            2 => TermVariant::Const(CONSTS[rng.below(CONSTS.len())].to_owned()),
+            3 => TermVariant::App(
+                random_term(rng, depth, lambdas),
+                random_term(rng, depth, lambdas),
+            ),
+            4 => TermVariant::Product(
+                (),
+                random_term(rng, depth, lambdas),
+                random_term(rng, depth, lambdas),
+            ),
+            5 if lambdas => TermVariant::Abstract(
+                (),
+                random_term(rng, depth, lambdas),
+                random_term(rng, depth, lambdas),
+            ),
+            5 | 6 => TermVariant::Bind(
+                (),
+                random_term(rng, depth, lambdas),
+                random_term(rng, depth, lambdas),
+                random_term(rng, depth, lambdas),
+            ),
+            7 => TermVariant::Evar(
+                rng.below(4),
+                (0..rng.below(3))
+                    .map(|_| random_term(rng, depth, lambdas))
+                    .collect(),
+            ),
+            _ => TermVariant::Match(
+                random_term(rng, depth, lambdas),
+                (),
+                random_arm(rng, depth, lambdas),
+                (0..rng.below(3))
+                    .map(|_| random_arm(rng, depth, lambdas))
+                    .collect(),
+            ),
+        };
+        term(variant)
+    }
+
+    fn random_local(rng: &mut Rng) -> Stack<Entry<(), ()>> {
+        (0..rng.below(4))
+            .map(|_| Entry {
+                x: (),
+                value: (rng.below(2) == 0).then(|| random_term(rng, 3, false)),
+                ttype: random_term(rng, 3, false),
+            })
+            .collect()
+    }
+
+    /// `nat` extended with `eq` and a `pred` function, so that random terms can hit every kind of reduction.
+    fn test_global() -> Global<(), ()> {
+        let mut global = nat_global();
+        let commands = [
+            CommandVariant::Inductive(
+                vec![
+                    Binder {
+                        x: (),
+                        ttype: term(TermVariant::Sort(Sort::Set)),
+                    },
+                    Binder {
+                        x: (),
+                        ttype: var(0),
+                    },
+                ],
+                vec![IndBody {
+                    name: "eq".to_owned(),
+                    ttype: term(TermVariant::Product(
+                        (),
+                        var(1),
+                        term(TermVariant::Sort(Sort::Prop)),
+                    )),
+                    constructors: vec![IndConstructor {
+                        name: "eq_refl".to_owned(),
+                        ttype: app(app(app(cnst("eq"), var(1)), var(0)), var(0)),
+                    }],
+                }],
+            ),
+            CommandVariant::Definition(
+                "pred".to_owned(),
+                term(TermVariant::Product((), cnst("nat"), cnst("nat"))),
+                term(TermVariant::Abstract(
+                    (),
+                    cnst("nat"),
+                    term(TermVariant::Match(
+                        var(0),
+                        (),
+                        MatchArm {
+                            meta: (),
+                            constructor: "nat".to_owned(),
+                            params: Vec::new(),
+                            body: cnst("nat"),
+                        },
+                        vec![
+                            MatchArm {
+                                meta: (),
+                                constructor: "O".to_owned(),
+                                params: Vec::new(),
+                                body: cnst("O"),
+                            },
+                            MatchArm {
+                                meta: (),
+                                constructor: "S".to_owned(),
+                                params: vec![()],
+                                body: var(0),
+                            },
+                        ],
+                    )),
+                )),
+            ),
+        ];
+        for variant in commands {
+            Command { meta: (), variant }
+                .eval(&mut global, |_| {})
+                .ok()
+                .unwrap();
+        }
+        global
+    }
+
+    /// An unsolved and two solved metavariables, one of them with a variable in its context.
+    fn test_evars() -> Evars<(), ()> {
+        let nat = || cnst("nat");
+        let mut evars = Evars::new();
+        evars.declare((), Stack::new(), nat());
+        let one = evars.declare((), Stack::new(), nat());
+        evars.solve(one, succ(cnst("O"))).ok().unwrap();
+        let context = [Entry::new((), nat())].into_iter().collect();
+        let succ_x = evars.declare((), context, nat());
+        evars.solve(succ_x, succ(var(0))).ok().unwrap();
+        evars
+    }
+
+    #[test]
+    fn random_terms_do_not_panic() {
+        let global = test_global();
+        let evars = test_evars();
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..20_000 {
+            let t = random_term(&mut rng, 5, true);
+            let mut local = random_local(&mut rng);
+            let _ = t.type_check(&global, &evars, &mut local);
+
+            let mut local = Stack::new();
+            if let Ok(mut ttype) = t.type_check(&global, &evars, &mut local) {
+                let _ = ttype.expect_sort(&global, &evars, &mut local);
+                let _ = ttype.expect_subtype(&ttype, &global, &evars, &mut local);
+                let _ = t.expect_convertable(&t, &global, &evars, &mut local);
+                let _ = ttype.normalize(&global, &evars, &mut local);
+                let mut norm = t.clone();
+                let _ = norm.normalize(&global, &evars, &mut local);
             }
-        })
+
+            let mut t = random_term(&mut rng, 5, false);
+            let mut local = random_local(&mut rng);
+            let _ = t.clone().expect_product(&global, &evars, &mut local);
+            let _ = t.normalize(&global, &evars, &mut local);
+            let _ = t.strict_positivity(&global, |name| name == "nat");
+            let _ = t.zonk(&evars);
+        }
+    }
+
+    #[test]
+    fn random_commands_do_not_panic() {
+        let mut global = test_global();
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        for i in 0..5_000 {
+            let variant = match rng.below(4) {
+                0 => CommandVariant::Definition(
+                    format!("c{i}"),
+                    random_term(&mut rng, 4, true),
+                    random_term(&mut rng, 4, true),
+                ),
+                1 => CommandVariant::Axiom(format!("c{i}"), random_term(&mut rng, 4, true)),
+                2 => CommandVariant::Compute(random_term(&mut rng, 4, true)),
+                _ => CommandVariant::Inductive(
+                    (0..rng.below(3))
+                        .map(|_| Binder {
+                            x: (),
+                            ttype: random_term(&mut rng, 3, true),
+                        })
+                        .collect(),
+                    vec![IndBody {
+                        name: format!("c{i}"),
+                        ttype: random_term(&mut rng, 3, true),
+                        constructors: (0..rng.below(3))
+                            .map(|j| IndConstructor {
+                                name: format!("c{i}_{j}"),
+                                ttype: random_term(&mut rng, 4, true),
+                            })
+                            .collect(),
+                    }],
+                ),
+            };
+            let _ = Command { meta: (), variant }.eval(&mut global, |_| {});
+        }
     }
 }
@@ -0,0 +1,637 @@
+use std::mem;
+
+use crate::entry::Entry;
+use crate::evar::Evars;
+use crate::term::grow;
+use crate::{Global, Sort, Stack, Term, TermVariant, TypeError, TypeErrorVariant};
+
+/// A unification problem `lhs = rhs` in the local context `local`.
+#[derive(Clone)]
+pub struct Constraint<M, B> {
+    pub local: Stack<Entry<M, B>>,
+    pub lhs: Term<M, B>,
+    pub rhs: Term<M, B>,
+}
+
+/// Solves metavariables such that terms become convertible.
+///
+/// Terms are compared by their normal forms, so δ, β, ι and ζ reduction all happen through `normalize`.
+/// A metavariable is solved when it appears as a Miller pattern, `?x[y1; ..; yn] z1 .. zm` where all `y`s and `z`s are distinct variables.
+/// Problems outside of this fragment are postponed, and retried whenever another metavariable has been solved.
+/// A solution is checked against the type of its metavariable when it is assigned.
+#[derive(Clone)]
+pub struct Unifier<M, B> {
+    postponed: Vec<Constraint<M, B>>,
+}
+
+impl<M, B> Default for Unifier<M, B> {
+    fn default() -> Self {
+        Unifier {
+            postponed: Vec::new(),
+        }
+    }
+}
+
+/// A metavariable applied to distinct variables, given as indices into the local context.
+/// Positions of the substitution for let bound variables of the metavariable's context are `None`, as they are determined by their value.
+struct Pattern {
+    id: usize,
+    subst: Vec<Option<usize>>,
+    args: Vec<usize>,
+}
+
+impl<M: Clone, B: Clone> Unifier<M, B> {
+    pub fn new() -> Self {
+        Unifier {
+            postponed: Vec::new(),
+        }
+    }
+
+    /// Returns the constraints which have been postponed and are still unsolved.
+    pub fn postponed(&self) -> &[Constraint<M, B>] {
+        &self.postponed
+    }
+
+    /// Unifies `lhs` and `rhs`, solving metavariables in `evars` as needed.
+    ///
+    /// Constraints which cannot be solved yet are postponed, so success does not mean that the terms are convertible until `finish` succeeds.
+    pub fn unify(
+        &mut self,
+        global: &Global<M, B>,
+        evars: &mut Evars<M, B>,
+        local: &mut Stack<Entry<M, B>>,
+        lhs: &Term<M, B>,
+        rhs: &Term<M, B>,
+    ) -> Result<(), TypeError<M, B>> {
+        let unsolved = evars.unsolved().count();
+        self.unify_inner(global, evars, local, lhs.clone(), rhs.clone())?;
+        if evars.unsolved().count() != unsolved {
+            self.wake(global, evars)?;
+        }
+        Ok(())
+    }
+
+    /// Retries all postponed constraints, returning an error for the first one which still cannot be solved.
+    pub fn finish(
+        &mut self,
+        global: &Global<M, B>,
+        evars: &mut Evars<M, B>,
+    ) -> Result<(), TypeError<M, B>> {
+        self.wake(global, evars)?;
+        match self.postponed.pop() {
+            Some(Constraint { local, lhs, rhs }) => {
+                self.postponed.clear();
+                Err(TypeError::new(
+                    &local,
+                    TypeErrorVariant::UnificationStuck(lhs.zonk(evars), rhs.zonk(evars)),
+                ))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Retries the postponed constraints until no more metavariables get solved.
+    fn wake(
+        &mut self,
+        global: &Global<M, B>,
+        evars: &mut Evars<M, B>,
+    ) -> Result<(), TypeError<M, B>> {
+        loop {
+            let unsolved = evars.unsolved().count();
+            for Constraint {
+                mut local,
+                lhs,
+                rhs,
+            } in mem::take(&mut self.postponed)
+            {
+                self.unify_inner(global, evars, &mut local, lhs, rhs)?;
+            }
+            if evars.unsolved().count() == unsolved {
+                return Ok(());
+            }
+        }
+    }
+
+    fn unify_inner(
+        &mut self,
+        global: &Global<M, B>,
+        evars: &mut Evars<M, B>,
+        local: &mut Stack<Entry<M, B>>,
+        mut lhs: Term<M, B>,
+        mut rhs: Term<M, B>,
+    ) -> Result<(), TypeError<M, B>> {
+        if self.unify_as_written(global, evars, local, &lhs, &rhs) {
+            return Ok(());
+        }
+        lhs.normalize(global, evars, local)?;
+        rhs.normalize(global, evars, local)?;
+        self.unify_normal(global, evars, local, lhs, rhs)
+    }
+
+    /// Tries to unify the terms without reducing them first, so that solutions keep the constants they were written with.
+    ///
+    /// Only patterns and applications of the same constant are attempted.
+    /// Returns false, with `evars` left untouched, if this did not solve the problem outright.
+    fn unify_as_written(
+        &mut self,
+        global: &Global<M, B>,
+        evars: &mut Evars<M, B>,
+        local: &mut Stack<Entry<M, B>>,
+        lhs: &Term<M, B>,
+        rhs: &Term<M, B>,
+    ) -> bool {
+        let saved = (evars.snapshot(), self.postponed.len());
+        let (l_hd, l_args) = lhs.strip_args_ref();
+        let (r_hd, r_args) = rhs.strip_args_ref();
+        let result = if let Some(pattern) = pattern(lhs, evars) {
+            self.assign(global, evars, local, pattern, lhs.clone(), rhs.clone())
+        } else if let Some(pattern) = pattern(rhs, evars) {
+            self.assign(global, evars, local, pattern, rhs.clone(), lhs.clone())
+        } else if matches!(&*l_hd.variant, TermVariant::Const(_))
+            && l_hd == r_hd
+            && l_args.len() == r_args.len()
+            && !l_args.is_empty()
+        {
+            l_args
+                .into_iter()
+                .zip(r_args)
+                .try_for_each(|(l, r)| self.unify_inner(global, evars, local, l.clone(), r.clone()))
+        } else {
+            return false;
+        };
+        if result.is_ok() && self.postponed.len() == saved.1 {
+            true
+        } else {
+            evars.rollback(saved.0);
+            self.postponed.truncate(saved.1);
+            false
+        }
+    }
+
+    /// Unifies two terms in normal form, though metavariables solved since they were normalized may still occur in them.
+    fn unify_normal(
+        &mut self,
+        global: &Global<M, B>,
+        evars: &mut Evars<M, B>,
+        local: &mut Stack<Entry<M, B>>,
+        lhs: Term<M, B>,
+        rhs: Term<M, B>,
+    ) -> Result<(), TypeError<M, B>> {
+        grow(|| {
+            // A metavariable solved by an earlier part of the problem needs to be instantiated and reduced
+            if is_solved_head(&lhs, evars) || is_solved_head(&rhs, evars) {
+                return self.unify_inner(global, evars, local, lhs, rhs);
+            }
+            if lhs == rhs {
+                return Ok(());
+            }
+
+            if let Some(pattern) = pattern(&lhs, evars) {
+                return self.assign(global, evars, local, pattern, lhs, rhs);
+            }
+            if let Some(pattern) = pattern(&rhs, evars) {
+                return self.assign(global, evars, local, pattern, rhs, lhs);
+            }
+            if is_flex(&lhs, evars) || is_flex(&rhs, evars) {
+                self.postpone(local, lhs, rhs);
+                return Ok(());
+            }
+
+            match (&*lhs.variant, &*rhs.variant) {
+                (TermVariant::Product(x, l0, l1), TermVariant::Product(_, r0, r1))
+                | (TermVariant::Abstract(x, l0, l1), TermVariant::Abstract(_, r0, r1)) => {
+                    self.unify_normal(global, evars, local, l0.clone(), r0.clone())?;
+                    let mut local = local.push(Entry::new(x.clone(), l0.clone()));
+                    self.unify_normal(global, evars, &mut local, l1.clone(), r1.clone())
+                }
+                // η expansion, `fun x => t = f` holds if `t = f x`
+                (TermVariant::Abstract(x, x_tp, body), _) => {
+                    let mut local = local.push(Entry::new(x.clone(), x_tp.clone()));
+                    let app = eta_app(&rhs);
+                    self.unify_normal(global, evars, &mut local, body.clone(), app)
+                }
+                (_, TermVariant::Abstract(x, x_tp, body)) => {
+                    let mut local = local.push(Entry::new(x.clone(), x_tp.clone()));
+                    let app = eta_app(&lhs);
+                    self.unify_normal(global, evars, &mut local, app, body.clone())
+                }
+                (TermVariant::App(_, _), TermVariant::App(_, _)) => {
+                    let (l_hd, l_args) = lhs.strip_args_ref();
+                    let (r_hd, r_args) = rhs.strip_args_ref();
+                    if l_args.len() != r_args.len() {
+                        return Err(mismatch(local, &lhs, &rhs));
+                    }
+                    self.unify_normal(global, evars, local, l_hd.clone(), r_hd.clone())?;
+                    l_args.into_iter().zip(r_args).try_for_each(|(l, r)| {
+                        self.unify_normal(global, evars, local, l.clone(), r.clone())
+                    })
+                }
+                (
+                    TermVariant::Match(l, name, l_ret, l_arms),
+                    TermVariant::Match(r, _, r_ret, r_arms),
+                ) if l_ret.constructor == r_ret.constructor
+                    && l_ret.params.len() == r_ret.params.len()
+                    && l_arms.len() == r_arms.len()
+                    && l_arms.iter().zip(r_arms).all(|(l, r)| {
+                        l.constructor == r.constructor && l.params.len() == r.params.len()
+                    }) =>
+                {
+                    self.unify_normal(global, evars, local, l.clone(), r.clone())?;
+                    {
+                        // Only the values of local entries are used while unifying, and the binders of a normal match have none
+                        let mut local = local.slot();
+                        local.extend(
+                            l_ret
+                                .params
+                                .iter()
+                                .chain([name])
+                                .map(|x| opaque(x, &l_ret.body)),
+                        );
+                        self.unify_normal(
+                            global,
+                            evars,
+                            &mut local,
+                            l_ret.body.clone(),
+                            r_ret.body.clone(),
+                        )?;
+                    }
+                    l_arms.iter().zip(r_arms).try_for_each(|(l, r)| {
+                        let mut local = local.slot();
+                        local.extend(l.params.iter().map(|x| opaque(x, &l.body)));
+                        self.unify_normal(global, evars, &mut local, l.body.clone(), r.body.clone())
+                    })
+                }
+                _ => Err(mismatch(local, &lhs, &rhs)),
+            }
+        })
+    }
+
+    /// Solves the metavariable of `pattern`, the head of `flex`, such that `flex` becomes `other`.
+    fn assign(
+        &mut self,
+        global: &Global<M, B>,
+        evars: &mut Evars<M, B>,
+        local: &mut Stack<Entry<M, B>>,
+        pattern: Pattern,
+        flex: Term<M, B>,
+        other: Term<M, B>,
+    ) -> Result<(), TypeError<M, B>> {
+        let Pattern { id, subst, args } = pattern;
+        let decl = evars
+            .get(id)
+            .ok_or_else(|| TypeError::new(local, TypeErrorVariant::UndefinedEvar(id)))?;
+
+        // The solution abstracts over the arguments, so their types are taken from the type of the metavariable
+        let mut context = decl.context.clone();
+        let expected = decl.ttype.clone();
+        let cumulative = decl.cumulative;
+        let mut ttype = expected.clone();
+        ttype.normalize(global, evars, &mut context)?;
+        let (mut binders, _) = ttype.strip_products();
+        if binders.len() < args.len() {
+            return Err(mismatch(local, &flex, &other));
+        }
+        binders.truncate(args.len());
+
+        // Every variable of `other` must be renamed to the argument or context variable it is equal to
+        let body = other.try_subst(|meta, x, push| {
+            if x < push {
+                return Ok(Term {
+                    meta: meta.clone(),
+                    variant: Box::new(TermVariant::Var(x)),
+                });
+            }
+            let n = if let Some(j) = args.iter().position(|y| *y == x - push) {
+                push + args.len() - 1 - j
+            } else if let Some(i) = subst.iter().position(|y| *y == Some(x - push)) {
+                push + args.len() + subst.len() - 1 - i
+            } else {
+                return Err(x - push);
+            };
+            Ok(Term {
+                meta: meta.clone(),
+                variant: Box::new(TermVariant::Var(n)),
+            })
+        });
+        let body = match body {
+            Ok(body) => body,
+            // The variable might only occur in the substitution of another metavariable, which could still be solved without it
+            Err(_) if is_flex_anywhere(&other, evars) => {
+                self.postpone(local, flex, other);
+                return Ok(());
+            }
+            Err(x) => {
+                return Err(TypeError::new(
+                    local,
+                    TypeErrorVariant::EvarScopeEscape(
+                        id,
+                        Term {
+                            meta: other.meta.clone(),
+                            variant: Box::new(TermVariant::Var(x)),
+                        },
+                    ),
+                ))
+            }
+        };
+
+        let value = binders.into_iter().rev().fold(body, |body, binder| Term {
+            meta: body.meta.clone(),
+            variant: Box::new(TermVariant::Abstract(binder.x, binder.ttype, body)),
+        });
+        evars
+            .solve(id, value.clone())
+            .map_err(|err| match err.variant {
+                TypeErrorVariant::EvarOccursInSolution(id, _) => TypeError::new(
+                    local,
+                    TypeErrorVariant::EvarOccursInSolution(id, other.clone()),
+                ),
+                _ => err,
+            })?;
+
+        // The solution must have the type of the metavariable, up to cumulativity,
+        // which may in turn solve metavariables occuring in either of the types
+        let actual = value
+            .type_check(global, evars, &mut context)
+            .map_err(|(_, err)| err)?;
+        if actual
+            .expect_subtype(&expected, global, evars, &mut context)
+            .is_err()
+        {
+            // A metavariable standing for a type of unknown sort takes the sort of its solution
+            let mut sort = actual.clone();
+            sort.normalize(global, evars, &mut context)?;
+            if cumulative && matches!(&*sort.variant, TermVariant::Sort(_)) {
+                return evars.raise(id, sort);
+            }
+            self.unify_inner(global, evars, &mut context, actual, expected)?;
+        }
+        Ok(())
+    }
+
+    fn postpone(&mut self, local: &Stack<Entry<M, B>>, lhs: Term<M, B>, rhs: Term<M, B>) {
+        self.postponed.push(Constraint {
+            local: local.clone(),
+            lhs,
+            rhs,
+        });
+    }
+}
+
+/// Unifies `lhs` and `rhs`, failing if any part of the problem had to be postponed and never became solvable.
+pub fn unify<M: Clone, B: Clone>(
+    global: &Global<M, B>,
+    evars: &mut Evars<M, B>,
+    local: &mut Stack<Entry<M, B>>,
+    lhs: &Term<M, B>,
+    rhs: &Term<M, B>,
+) -> Result<(), TypeError<M, B>> {
+    let mut unifier = Unifier::new();
+    unifier.unify(global, evars, local, lhs, rhs)?;
+    unifier.finish(global, evars)
+}
+
+fn mismatch<M: Clone, B: Clone>(
+    local: &Stack<Entry<M, B>>,
+    lhs: &Term<M, B>,
+    rhs: &Term<M, B>,
+) -> TypeError<M, B> {
+    TypeError::new(
+        local,
+        TypeErrorVariant::CannotUnify(lhs.clone(), rhs.clone()),
+    )
+}
+
+/// Returns whether the application head of `t` is a solved metavariable.
+fn is_solved_head<M: Clone, B: Clone>(t: &Term<M, B>, evars: &Evars<M, B>) -> bool {
+    matches!(&*t.app_head().variant, TermVariant::Evar(id, _) if evars.value(*id).is_some())
+}
+
+/// Returns whether the application head of `t` is an unsolved metavariable.
+fn is_flex<M: Clone, B: Clone>(t: &Term<M, B>, evars: &Evars<M, B>) -> bool {
+    matches!(&*t.app_head().variant, TermVariant::Evar(id, _) if evars.value(*id).is_none())
+}
+
+/// Returns whether `t` contains an unsolved metavariable.
+fn is_flex_anywhere<M: Clone, B: Clone>(t: &Term<M, B>, evars: &Evars<M, B>) -> bool {
+    t.validate_evars(|id| match evars.value(id) {
+        Some(_) => Ok(()),
+        None => Err(()),
+    })
+    .is_err()
+}
+
+/// Returns the pattern `t` is an instance of, if it is an unsolved metavariable applied to distinct variables.
+fn pattern<M: Clone, B: Clone>(t: &Term<M, B>, evars: &Evars<M, B>) -> Option<Pattern> {
+    let (hd, args) = t.strip_args_ref();
+    let (id, subst) = match &*hd.variant {
+        TermVariant::Evar(id, subst) if evars.value(*id).is_none() => (*id, subst),
+        _ => return None,
+    };
+    let var = |t: &Term<M, B>| match &*t.variant {
+        TermVariant::Var(n) => Some(*n),
+        _ => None,
+    };
+    // Let bound variables get replaced by their values when normalizing, so the substitution does not need to keep them as variables
+    let context = &evars.get(id)?.context;
+    let subst = context
+        .iter()
+        .rev()
+        .zip(subst)
+        .map(|(entry, t)| match entry.value {
+            Some(_) => Some(None),
+            None => var(t).map(Some),
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let args = args.into_iter().map(var).collect::<Option<Vec<_>>>()?;
+    let mut vars: Vec<_> = subst.iter().flatten().chain(&args).collect();
+    let len = vars.len();
+    vars.sort();
+    vars.dedup();
+    (vars.len() == len).then_some(Pattern { id, subst, args })
+}
+
+/// Builds `f x` for a new variable `x`, with `f` moved into its scope.
+fn eta_app<M: Clone, B: Clone>(f: &Term<M, B>) -> Term<M, B> {
+    Term {
+        meta: f.meta.clone(),
+        variant: Box::new(TermVariant::App(
+            f.push(1),
+            Term {
+                meta: f.meta.clone(),
+                variant: Box::new(TermVariant::Var(0)),
+            },
+        )),
+    }
+}
+
+/// A local entry without a value, for binders whose type is irrelevant.
+fn opaque<M: Clone, B: Clone>(x: &B, t: &Term<M, B>) -> Entry<M, B> {
+    Entry::new(
+        x.clone(),
+        Term {
+            meta: t.meta.clone(),
+            variant: Box::new(TermVariant::Sort(Sort::Prop)),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{app, cnst, nat_global, term, var};
+
+    fn evar(id: usize, subst: Vec<Term<(), ()>>) -> Term<(), ()> {
+        term(TermVariant::Evar(id, subst))
+    }
+
+    fn nat_local() -> Stack<Entry<(), ()>> {
+        [Entry::new((), cnst("nat"))].into_iter().collect()
+    }
+
+    fn variant(result: Result<(), TypeError<(), ()>>) -> TypeErrorVariant<(), ()> {
+        match result {
+            Ok(()) => panic!("expected unification to fail"),
+            Err(err) => err.variant,
+        }
+    }
+
+    #[test]
+    fn first_order() {
+        let global = nat_global();
+        let mut evars = Evars::new();
+        let id = evars.declare((), Stack::new(), cnst("nat"));
+
+        let lhs = app(cnst("S"), evar(id, Vec::new()));
+        let rhs = app(cnst("S"), app(cnst("S"), cnst("O")));
+        unify(&global, &mut evars, &mut Stack::new(), &lhs, &rhs)
+            .ok()
+            .unwrap();
+        assert!(lhs.zonk(&evars) == rhs);
+    }
+
+    #[test]
+    fn ill_typed_solution() {
+        let global = nat_global();
+        let mut evars = Evars::new();
+        let id = evars.declare((), Stack::new(), cnst("nat"));
+
+        // `?x : nat` cannot be solved by `nat`, which is a type rather than a number
+        let result = unify(
+            &global,
+            &mut evars,
+            &mut Stack::new(),
+            &evar(id, Vec::new()),
+            &cnst("nat"),
+        );
+        assert!(matches!(
+            variant(result),
+            TypeErrorVariant::CannotUnify(_, _)
+        ));
+    }
+
+    #[test]
+    fn miller_pattern() {
+        let global = nat_global();
+        let mut evars = Evars::new();
+        let nat_to_nat = term(TermVariant::Product((), cnst("nat"), cnst("nat")));
+        let f = evars.declare((), Stack::new(), nat_to_nat);
+
+        // `?f x = S x` is solved by `?f := fun x => S x`
+        let lhs = app(evar(f, Vec::new()), var(0));
+        let rhs = app(cnst("S"), var(0));
+        unify(&global, &mut evars, &mut nat_local(), &lhs, &rhs)
+            .ok()
+            .unwrap();
+
+        let mut t = app(evar(f, Vec::new()), cnst("O"));
+        t.normalize(&global, &evars, &mut Stack::new())
+            .ok()
+            .unwrap();
+        assert!(t == app(cnst("S"), cnst("O")));
+    }
+
+    #[test]
+    fn postponed_until_pattern() {
+        let global = nat_global();
+        let mut evars = Evars::new();
+        let g = evars.declare((), nat_local(), cnst("nat"));
+        let h = evars.declare((), nat_local(), cnst("nat"));
+        let mut local = nat_local();
+        let mut unifier = Unifier::new();
+
+        // `?g[?h[x]] = S x` is outside of the pattern fragment until `?h` is known
+        let lhs = evar(g, vec![evar(h, vec![var(0)])]);
+        let rhs = app(cnst("S"), var(0));
+        unifier
+            .unify(&global, &mut evars, &mut local, &lhs, &rhs)
+            .ok()
+            .unwrap();
+        assert_eq!(unifier.postponed().len(), 1);
+
+        unifier
+            .unify(
+                &global,
+                &mut evars,
+                &mut local,
+                &evar(h, vec![var(0)]),
+                &var(0),
+            )
+            .ok()
+            .unwrap();
+        assert!(unifier.postponed().is_empty());
+        unifier.finish(&global, &mut evars).ok().unwrap();
+        assert!(lhs.zonk(&evars) == rhs);
+    }
+
+    #[test]
+    fn errors() {
+        let global = nat_global();
+        let mut evars = Evars::new();
+        let a = evar(evars.declare((), Stack::new(), cnst("nat")), Vec::new());
+
+        let mismatch = unify(
+            &global,
+            &mut evars,
+            &mut Stack::new(),
+            &app(cnst("S"), cnst("O")),
+            &cnst("O"),
+        );
+        assert!(matches!(
+            variant(mismatch),
+            TypeErrorVariant::CannotUnify(_, _)
+        ));
+
+        let occurs = unify(
+            &global,
+            &mut evars,
+            &mut Stack::new(),
+            &a,
+            &app(cnst("S"), a.clone()),
+        );
+        assert!(matches!(
+            variant(occurs),
+            TypeErrorVariant::EvarOccursInSolution(_, _)
+        ));
+
+        let escape = unify(&global, &mut evars, &mut nat_local(), &a, &var(0));
+        assert!(matches!(
+            variant(escape),
+            TypeErrorVariant::EvarScopeEscape(_, _)
+        ));
+
+        let g = evars.declare((), nat_local(), cnst("nat"));
+        let h = evars.declare((), nat_local(), cnst("nat"));
+        let stuck = unify(
+            &global,
+            &mut evars,
+            &mut nat_local(),
+            &evar(g, vec![evar(h, vec![var(0)])]),
+            &app(cnst("S"), var(0)),
+        );
+        assert!(matches!(
+            variant(stuck),
+            TypeErrorVariant::UnificationStuck(_, _)
+        ));
+        assert_eq!(evars.unsolved().count(), 3);
+    }
+}
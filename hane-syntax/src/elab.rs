@@ -0,0 +1,1675 @@
+// Errors carry a concrete span and local context, as in the golden test runner
+#![allow(clippy::result_large_err)]
+
+use std::collections::VecDeque;
+
+use hane_kernel::{
+    entry::{Binder, Entry},
+    error::InstanceTrace,
+    global::{GEntryRef, GIndConstructor},
+    term::grow,
+    unify::Unifier,
+    CommandError, Evars, Global, Sort, Stack, TypeError, TypeErrorVariant,
+};
+
+use crate::{
+    env::{CoercionClass, Env},
+    lower::lowered::{
+        Command, CommandVariant, IndBody, IndConstructor, MatchArm, Term, TermVariant,
+    },
+    Ident, Span,
+};
+
+mod auto;
+mod congruence;
+mod equality;
+mod lia;
+mod rewrite;
+mod ring;
+mod tactic;
+mod tauto;
+
+type Local = Stack<Entry<Span, Ident>>;
+type Error = (Span, TypeError<Span, Ident>);
+
+/// Elaborates a lowered command, solving its holes by unification.
+///
+/// The holes left by lowering are replaced by metavariables, which are solved while the terms are typechecked.
+/// The terms are returned with the solutions substituted in, and are still checked by the kernel when the command is evaluated.
+/// Holes whose type is a class are solved by instance resolution, using the instances declared in `env`.
+/// The proof of a theorem is a hole, which is solved by running the tactics of the proof on it.
+/// Commands without holes or inferred match clauses are returned unchanged.
+///
+/// The holes `?x` which are left unsolved are admitted as obligations by the commands returned before the elaborated command.
+pub fn elaborate(
+    command: Command,
+    global: &Global<Span, Ident>,
+    env: &Env,
+) -> Result<Vec<Command>, (Span, CommandError<Span, Ident>)> {
+    let omits = match &command.variant {
+        CommandVariant::Definition(_, ttype, value) => {
+            needs_elaboration(ttype) || needs_elaboration(value)
+        }
+        CommandVariant::Axiom(_, t)
+        | CommandVariant::Admit(_, t, _)
+        | CommandVariant::Check(t)
+        | CommandVariant::Compute(t) => needs_elaboration(t),
+        CommandVariant::Inductive(params, bodies) => {
+            params.iter().any(|param| needs_elaboration(&param.ttype))
+                || bodies.iter().any(|body| {
+                    needs_elaboration(&body.ttype)
+                        || body
+                            .constructors
+                            .iter()
+                            .any(|constructor| needs_elaboration(&constructor.ttype))
+                })
+        }
+        CommandVariant::Print(_) | CommandVariant::ShowAdmitted => false,
+    };
+    // Coercions are only inserted where the kernel finds a mismatch, which it cannot resolve by itself
+    if !omits
+        && command.validate_evars(|_| Err(())).is_ok()
+        && (!env.has_coercions() || well_typed(&command, global))
+    {
+        return Ok(vec![command]);
+    }
+
+    // The holes `?x` are admitted under the name of what the command defines, so each definition has its own
+    let owner = match &command.variant {
+        CommandVariant::Definition(name, _, _)
+        | CommandVariant::Axiom(name, _)
+        | CommandVariant::Admit(name, _, _) => name.clone(),
+        CommandVariant::Inductive(_, bodies) => bodies
+            .first()
+            .map_or_else(String::new, |body| body.name.clone()),
+        CommandVariant::Check(_) => "Check".to_owned(),
+        CommandVariant::Compute(_) => "Compute".to_owned(),
+        CommandVariant::Print(_) | CommandVariant::ShowAdmitted => String::new(),
+    };
+    let meta = command.meta;
+    let mut elab = Elaborator::new(global, env, owner.clone());
+    let mut local = Stack::new();
+    // The obligations of the constructors of inductive types, which are elaborated separately
+    let mut obligations = Vec::new();
+    let variant = match command.variant {
+        CommandVariant::Definition(name, ttype, value) if env.proof(&name).is_some() => {
+            let (ttype, _) = elab.infer_type(&ttype, &mut local).map_err(type_error)?;
+            // The goals are the holes of the value, which is the proof abstracted over the parameters of the theorem
+            let first = elab.evars.len();
+            let value = elab.check(&value, &ttype, &mut local).map_err(type_error)?;
+            let goals = elab.evars.unsolved().filter(|&id| id >= first).collect();
+            elab.prove(goals, env.proof(&name).unwrap())
+                .map_err(type_error)?;
+            elab.finish(&meta)?;
+            CommandVariant::Definition(name, elab.zonk(&ttype)?, elab.zonk(&value)?)
+        }
+        CommandVariant::Definition(name, ttype, value) => {
+            if is_hole(&ttype) && !has_evars(&value) && !needs_elaboration(&value) {
+                // Only the type is missing, which the kernel can infer by itself unless a coercion is needed
+                match value.type_check(global, &elab.evars, &mut local) {
+                    Ok(ttype) => {
+                        return Ok(vec![Command {
+                            meta,
+                            variant: CommandVariant::Definition(name, ttype, value),
+                        }])
+                    }
+                    Err(err) if !env.has_coercions() => return Err(type_error(err)),
+                    Err(_) => {}
+                }
+            }
+            let (ttype, value) = elab
+                .infer_annotated(&ttype, &value, &mut local)
+                .map_err(type_error)?;
+            elab.finish(&meta)?;
+            CommandVariant::Definition(name, elab.zonk(&ttype)?, elab.zonk(&value)?)
+        }
+        CommandVariant::Axiom(name, ttype) => {
+            let (ttype, _) = elab.infer_type(&ttype, &mut local).map_err(type_error)?;
+            elab.finish(&meta)?;
+            CommandVariant::Axiom(name, elab.zonk(&ttype)?)
+        }
+        CommandVariant::Admit(name, ttype, context) => {
+            let (ttype, _) = elab.infer_type(&ttype, &mut local).map_err(type_error)?;
+            elab.finish(&meta)?;
+            CommandVariant::Admit(name, elab.zonk(&ttype)?, context)
+        }
+        CommandVariant::Inductive(params, bodies) => {
+            let mut local = local.slot();
+            let mut elab_params = Vec::with_capacity(params.len());
+            for param in params {
+                let (ttype, _) = elab
+                    .infer_type(&param.ttype, &mut local)
+                    .map_err(type_error)?;
+                local.push_onto(Entry::new(param.x.clone(), ttype.clone()));
+                elab_params.push(Binder { x: param.x, ttype });
+            }
+            let mut arities = Vec::with_capacity(bodies.len());
+            for body in &bodies {
+                let (ttype, _) = elab
+                    .infer_type(&body.ttype, &mut local)
+                    .map_err(type_error)?;
+                arities.push(ttype);
+            }
+            elab.finish(&meta)?;
+            let params = elab_params
+                .into_iter()
+                .map(|param| {
+                    Ok(Binder {
+                        x: param.x,
+                        ttype: elab.zonk(&param.ttype)?,
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let arities = arities
+                .iter()
+                .map(|ttype| elab.zonk(ttype))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            // The constructors refer to the types being defined, so those are assumed while elaborating them
+            let mut global = global.clone();
+            for (body, arity) in bodies.iter().zip(&arities) {
+                let full_type = params
+                    .iter()
+                    .rev()
+                    .fold(arity.clone(), |ttype, param| Term {
+                        meta: ttype.meta.clone(),
+                        variant: Box::new(TermVariant::Product(
+                            param.x.clone(),
+                            param.ttype.clone(),
+                            ttype,
+                        )),
+                    });
+                Command {
+                    meta: meta.clone(),
+                    variant: CommandVariant::Axiom(body.name.clone(), full_type),
+                }
+                .eval(&mut global, |_| {})?;
+            }
+            let mut elab = Elaborator::new(&global, env, owner);
+            let mut local: Local = params
+                .iter()
+                .map(|param| Entry::new(param.x.clone(), param.ttype.clone()))
+                .collect();
+            let mut elab_bodies = Vec::with_capacity(bodies.len());
+            for (body, ttype) in bodies.into_iter().zip(arities) {
+                let mut constructors = Vec::with_capacity(body.constructors.len());
+                for constructor in body.constructors {
+                    let (ttype, _) = elab
+                        .infer_type(&constructor.ttype, &mut local)
+                        .map_err(type_error)?;
+                    constructors.push(IndConstructor {
+                        name: constructor.name,
+                        ttype,
+                    });
+                }
+                elab_bodies.push(IndBody {
+                    name: body.name,
+                    ttype,
+                    constructors,
+                });
+            }
+            elab.finish(&meta)?;
+            for body in &mut elab_bodies {
+                for constructor in &mut body.constructors {
+                    constructor.ttype = elab.zonk(&constructor.ttype)?;
+                }
+            }
+            obligations = elab.obligations;
+            CommandVariant::Inductive(params, elab_bodies)
+        }
+        CommandVariant::Print(name) => CommandVariant::Print(name),
+        CommandVariant::ShowAdmitted => CommandVariant::ShowAdmitted,
+        CommandVariant::Check(term) => {
+            let (term, _) = elab.infer(&term, &mut local).map_err(type_error)?;
+            elab.finish(&meta)?;
+            CommandVariant::Check(elab.zonk(&term)?)
+        }
+        CommandVariant::Compute(term) => {
+            let (term, _) = elab.infer(&term, &mut local).map_err(type_error)?;
+            elab.finish(&meta)?;
+            CommandVariant::Compute(elab.zonk(&term)?)
+        }
+    };
+    let mut commands = elab.obligations;
+    commands.extend(obligations);
+    commands.push(Command { meta, variant });
+    Ok(commands)
+}
+
+/// Returns whether the kernel accepts `command` as it is, in which case no coercion has to be inserted.
+/// Inductive types are always elaborated, as checking them takes more than typing their terms.
+fn well_typed(command: &Command, global: &Global<Span, Ident>) -> bool {
+    let evars = Evars::new();
+    let local = &mut Stack::new();
+    let is_type = |t: &Term, local: &mut Local| {
+        t.type_check(global, &evars, local)
+            .is_ok_and(|ttype| ttype.expect_sort(global, &evars, local).is_ok())
+    };
+    match &command.variant {
+        CommandVariant::Definition(_, ttype, value) => {
+            is_type(ttype, local)
+                && value
+                    .type_check(global, &evars, local)
+                    .is_ok_and(|actual| actual.expect_subtype(ttype, global, &evars, local).is_ok())
+        }
+        CommandVariant::Axiom(_, ttype) | CommandVariant::Admit(_, ttype, _) => {
+            is_type(ttype, local)
+        }
+        CommandVariant::Check(t) | CommandVariant::Compute(t) => {
+            t.type_check(global, &evars, local).is_ok()
+        }
+        CommandVariant::Inductive(_, _) => false,
+        CommandVariant::Print(_) | CommandVariant::ShowAdmitted => true,
+    }
+}
+
+fn type_error((span, err): Error) -> (Span, CommandError<Span, Ident>) {
+    (span, CommandError::TypeError(err))
+}
+
+fn term(meta: &Span, variant: TermVariant) -> Term {
+    Term {
+        meta: meta.clone(),
+        variant: Box::new(variant),
+    }
+}
+
+/// Returns whether `t` is a hole, such as the type of a binder which was written without one.
+fn is_hole(t: &Term) -> bool {
+    matches!(&*t.variant, TermVariant::Evar(_, _))
+}
+
+/// Returns whether `t` contains a match without an `in` clause, whose inductive type is left to elaboration,
+/// a rewrite, or a hole `?x`, which is left to elaboration or admitted.
+fn needs_elaboration(t: &Term) -> bool {
+    grow(|| match &*t.variant {
+        TermVariant::Sort(_) | TermVariant::Var(_) => false,
+        TermVariant::Const(x) => goal_name(x).is_some(),
+        TermVariant::App(t1, t2)
+        | TermVariant::Product(_, t1, t2)
+        | TermVariant::Abstract(_, t1, t2) => needs_elaboration(t1) || needs_elaboration(t2),
+        TermVariant::Bind(_, t1, t2, t3) => {
+            needs_elaboration(t1) || needs_elaboration(t2) || needs_elaboration(t3)
+        }
+        TermVariant::Match(t, _, ret, arms) => {
+            ret.constructor.is_empty()
+                || needs_elaboration(t)
+                || needs_elaboration(&ret.body)
+                || arms.iter().any(|arm| needs_elaboration(&arm.body))
+        }
+        TermVariant::Evar(_, subst) => subst.iter().any(needs_elaboration),
+        TermVariant::Rewrite(_, _, _) => true,
+    })
+}
+
+/// Returns the name of the hole `?x` which lowering turned into the constant `x`, if it is one.
+fn goal_name(x: &str) -> Option<&str> {
+    x.strip_prefix('?')
+}
+
+/// Returns whether `t` contains any metavariables.
+fn has_evars(t: &Term) -> bool {
+    t.validate_evars(|_| Err(())).is_err()
+}
+
+/// Turns `expected` into the body of a return clause binding `bound` variables,
+/// by abstracting over the indices and the scrutinee wherever they are variables.
+fn generalize(expected: &Term, scrutinee: &Term, indices: &[Term], bound: usize) -> Term {
+    let as_var = |t: &Term| match &*t.variant {
+        TermVariant::Var(n) => Some(*n),
+        _ => None,
+    };
+    let scrutinee = as_var(scrutinee);
+    let indices: Vec<_> = indices.iter().map(as_var).collect();
+    expected.subst(|meta, x, push| {
+        let x = if x < push {
+            x
+        } else if scrutinee == Some(x - push) {
+            push
+        } else if let Some(i) = indices.iter().rposition(|&y| y == Some(x - push)) {
+            push + indices.len() - i
+        } else {
+            x + bound
+        };
+        term(meta, TermVariant::Var(x))
+    })
+}
+
+/// The unit type `forall P : Prop, P -> P`, which is the return type of the impossible cases of a match found by `Elaborator::invert`.
+fn unit_type(meta: &Span) -> Term {
+    let ident = |name: &str| Ident {
+        span: meta.clone(),
+        name: name.to_owned(),
+    };
+    let p = term(meta, TermVariant::Var(0));
+    let arrow = term(
+        meta,
+        TermVariant::Product(ident("_"), p, term(meta, TermVariant::Var(1))),
+    );
+    term(
+        meta,
+        TermVariant::Product(ident("P"), term(meta, TermVariant::Sort(Sort::Prop)), arrow),
+    )
+}
+
+/// The inhabitant `fun (P : Prop) (p : P) => p` of `unit_type`.
+fn unit_proof(meta: &Span) -> Term {
+    let ident = |name: &str| Ident {
+        span: meta.clone(),
+        name: name.to_owned(),
+    };
+    let p = term(meta, TermVariant::Var(0));
+    let id = term(meta, TermVariant::Abstract(ident("p"), p.clone(), p));
+    term(
+        meta,
+        TermVariant::Abstract(ident("P"), term(meta, TermVariant::Sort(Sort::Prop)), id),
+    )
+}
+
+/// Moves the body of an arm binding `bound` variables under a binder for each of the generalized variables in `convoy`,
+/// such that it refers to those binders instead of the variables.
+fn convoy_body(body: &Term, convoy: &[(usize, Ident)], bound: usize) -> Term {
+    let len = convoy.len();
+    let body = body.subst(|meta, x, push| {
+        let x = if x < push {
+            x
+        } else if let Some(l) = convoy
+            .iter()
+            .position(|(y, _)| x >= push + bound && *y == x - push - bound)
+        {
+            push + len - 1 - l
+        } else {
+            x + len
+        };
+        term(meta, TermVariant::Var(x))
+    });
+    convoy.iter().rev().fold(body, |body, (_, name)| {
+        let meta = body.meta.clone();
+        let hole = term(&meta, TermVariant::Evar(0, Vec::new()));
+        term(&meta, TermVariant::Abstract(name.clone(), hole, body))
+    })
+}
+
+/// The shape of an index of a scrutinee, as far as it is built from constructors.
+enum Shape {
+    Var(usize),
+    Opaque,
+    /// A constructor of the named inductive type, with the shapes of its arguments besides the parameters
+    Constructor(String, String, Vec<Shape>),
+}
+
+impl Shape {
+    fn has_constructor(&self) -> bool {
+        matches!(self, Shape::Constructor(_, _, _))
+    }
+
+    /// Adds the variables of the shape to `vars`, from left to right.
+    fn vars(&self, vars: &mut Vec<usize>) {
+        match self {
+            Shape::Var(x) => vars.push(*x),
+            Shape::Opaque => {}
+            Shape::Constructor(_, _, shapes) => shapes.iter().for_each(|shape| shape.vars(vars)),
+        }
+    }
+}
+
+/// The return clause found by `Elaborator::invert`, along with the variables it generalizes.
+struct Inversion {
+    motive: Term,
+    /// The local variables whose types depend on the indices, oldest first.
+    /// The match is applied to them, and its arms bind them again.
+    convoy: Vec<(usize, Ident)>,
+    /// The shapes of the indices of the scrutinee, which the motive matches on
+    shapes: Vec<Shape>,
+}
+
+/// Whether a constructor can build the scrutinee of a match with the return clause found by `Elaborator::invert`.
+enum Case {
+    Possible,
+    /// The indices of the constructor clash with those of the scrutinee,
+    /// so the return type of its arm is the unit type, and the arm is trivial
+    Impossible,
+}
+
+/// Builds the return type of `Elaborator::invert` for a case where the constructors agree,
+/// from the terms the variables correspond to and the amount of variables bound by the matches.
+type Leaf<'a> = dyn Fn(&[(usize, Term)], usize) -> Term + 'a;
+
+/// Builds the return clause of `Elaborator::invert` by matching on the terms in `queue` as long as their shapes are constructors.
+///
+/// `map` holds the terms which the variables of the shapes correspond to,
+/// in the context of the return clause extended with the `extra` variables bound by the matches so far.
+/// The return type in the cases where the constructors agree is built by `leaf`, while the others get the unit type.
+fn motive(
+    global: &Global<Span, Ident>,
+    meta: &Span,
+    sort: &Sort,
+    queue: &[(Term, &Shape)],
+    mut map: Vec<(usize, Term)>,
+    extra: usize,
+    leaf: &Leaf,
+) -> Term {
+    let Some(((t, shape), rest)) = queue.split_first() else {
+        return leaf(&map, extra);
+    };
+    let (inductive, constructor, shapes) = match shape {
+        Shape::Var(x) => {
+            map.push((*x, t.clone()));
+            return motive(global, meta, sort, rest, map, extra, leaf);
+        }
+        Shape::Opaque => return motive(global, meta, sort, rest, map, extra, leaf),
+        Shape::Constructor(inductive, constructor, shapes) => (inductive, constructor, shapes),
+    };
+    let Some(GEntryRef::Inductive(i, params, bodies)) = global.get_entry(inductive) else {
+        unreachable!()
+    };
+    let body = &bodies[i];
+    let names = |arity: &[Binder<Span, Ident>]| {
+        params
+            .iter()
+            .chain(arity)
+            .map(|param| param.x.clone())
+            .collect()
+    };
+    let ret = MatchArm {
+        meta: meta.clone(),
+        constructor: inductive.clone(),
+        params: names(&body.arity),
+        body: term(meta, TermVariant::Sort(sort.clone())),
+    };
+    let arms = body
+        .constructors
+        .iter()
+        .map(|c| {
+            let body = if c.name == *constructor {
+                let len = params.len() + c.arity.len();
+                let queue: Vec<_> = shapes
+                    .iter()
+                    .enumerate()
+                    .map(|(j, shape)| (term(meta, TermVariant::Var(shapes.len() - 1 - j)), shape))
+                    .chain(rest.iter().map(|(t, shape)| (t.push(len), *shape)))
+                    .collect();
+                let map = map.iter().map(|(x, t)| (*x, t.push(len))).collect();
+                motive(global, meta, sort, &queue, map, extra + len, leaf)
+            } else {
+                unit_type(meta)
+            };
+            MatchArm {
+                meta: meta.clone(),
+                constructor: c.name.clone(),
+                params: names(&c.arity),
+                body,
+            }
+        })
+        .collect();
+    let name = Ident {
+        span: meta.clone(),
+        name: "_".to_owned(),
+    };
+    term(meta, TermVariant::Match(t.clone(), name, ret, arms))
+}
+
+/// The depth of the premises at which instance resolution gives up, which stops instances from applying themselves forever.
+const INSTANCE_DEPTH: usize = 16;
+
+/// Typechecks terms while solving the metavariables they contain.
+///
+/// Terms are elaborated by `infer` when nothing is known about their type, and by `check` when it is known.
+/// Both return the term with its holes replaced by metavariables.
+struct Elaborator<'a> {
+    global: &'a Global<Span, Ident>,
+    env: &'a Env,
+    evars: Evars<Span, Ident>,
+    unifier: Unifier<Span, Ident>,
+    /// The name of what is being elaborated, which the holes `?x` are admitted under as `owner.?x`
+    owner: String,
+    /// The commands admitting the holes `?x` which were left unsolved, to be evaluated before the elaborated command
+    obligations: Vec<Command>,
+}
+
+impl<'a> Elaborator<'a> {
+    fn new(global: &'a Global<Span, Ident>, env: &'a Env, owner: String) -> Self {
+        Elaborator {
+            global,
+            env,
+            evars: Evars::new(),
+            unifier: Unifier::new(),
+            owner,
+            obligations: Vec::new(),
+        }
+    }
+
+    /// Returns the metavariable of the hole `?x` along with its type, which is `expected` if it is known.
+    ///
+    /// Every occurrence of `?x` in the same context stands for the same metavariable,
+    /// which is only declared at the first one.
+    fn goal(
+        &mut self,
+        meta: &Span,
+        x: &str,
+        expected: Option<&Term>,
+        local: &mut Local,
+    ) -> Result<(Term, Term), Error> {
+        let same = self.evars.named(x).find(|&id| {
+            let context = &self.evars.get(id).unwrap().context;
+            context.len() == local.len()
+                && context
+                    .iter()
+                    .zip(local.iter())
+                    .all(|(l, r)| l.ttype == r.ttype && l.value == r.value)
+        });
+        if let Some(id) = same {
+            let ttype = self.evars.get(id).unwrap().ttype.clone();
+            if let Some(expected) = expected {
+                self.unify(meta, local, &ttype, expected)?;
+            }
+            return Ok((self.evars.identity(meta.clone(), id).unwrap(), ttype));
+        }
+        let ttype = match expected {
+            Some(expected) => expected.clone(),
+            None => self.fresh_type(meta, local),
+        };
+        let id = self
+            .evars
+            .declare_named(meta.clone(), local.clone(), ttype.clone(), x.to_owned());
+        Ok((self.evars.identity(meta.clone(), id).unwrap(), ttype))
+    }
+
+    /// Declares a new metavariable of type `ttype` in `local`, returning it with the identity substitution.
+    fn fresh(&mut self, meta: &Span, ttype: Term, local: &Local) -> Term {
+        let id = self.evars.declare(meta.clone(), local.clone(), ttype);
+        self.evars.identity(meta.clone(), id).unwrap()
+    }
+
+    /// Declares a new metavariable standing for a type.
+    ///
+    /// Its sort is not known, so it is given the smallest sort containing both `Set` and `Prop`,
+    /// which is raised if it is solved with a type of a larger sort.
+    fn fresh_type(&mut self, meta: &Span, local: &Local) -> Term {
+        let id = self
+            .evars
+            .declare_type(meta.clone(), local.clone(), Sort::Type(0));
+        self.evars.identity(meta.clone(), id).unwrap()
+    }
+
+    /// Solves the holes whose type is a class by instance resolution,
+    /// then retries the postponed unification problems, failing if any of them remain unsolved.
+    /// The holes `?x` which are still unsolved after that are admitted.
+    fn finish(&mut self, meta: &Span) -> Result<(), (Span, CommandError<Span, Ident>)> {
+        self.resolve_instances()
+            .map_err(|(span, err)| (span, CommandError::TypeError(err)))?;
+        self.unifier
+            .finish(self.global, &mut self.evars)
+            .map_err(|err| (meta.clone(), CommandError::TypeError(err)))?;
+        self.admit()
+    }
+
+    /// Solves every unsolved hole `?x` with the constant `owner.?x` applied to the variables of its context,
+    /// which is admitted as an obligation whose type is the type of the hole abstracted over its context.
+    fn admit(&mut self) -> Result<(), (Span, CommandError<Span, Ident>)> {
+        let goals: Vec<_> = self
+            .evars
+            .unsolved()
+            .filter(|&id| self.evars.get(id).unwrap().name.is_some())
+            .collect();
+        for id in goals {
+            let decl = self.evars.get(id).unwrap();
+            let (meta, context, ttype, x) = (
+                decl.meta.clone(),
+                decl.context.clone(),
+                decl.ttype.clone(),
+                decl.name.clone().unwrap(),
+            );
+            let mut ttype = self.zonk(&ttype)?;
+            let mut args = Vec::new();
+            for (n, entry) in context.iter().enumerate() {
+                let x_tp = self.zonk(&entry.ttype)?;
+                let variant = match &entry.value {
+                    Some(value) => {
+                        TermVariant::Bind(entry.x.clone(), x_tp, self.zonk(value)?, ttype)
+                    }
+                    None => {
+                        args.push(term(&meta, TermVariant::Var(n)));
+                        TermVariant::Product(entry.x.clone(), x_tp, ttype)
+                    }
+                };
+                ttype = term(&meta, variant);
+            }
+            // A command which defines nothing, such as `Check`, or a hole written in different contexts
+            // can have holes of the same name more than once
+            let mut name = format!("{}.{x}", self.owner);
+            let mut n = 0;
+            let taken = |name: &str| {
+                self.global.get(name).is_some()
+                    || self.obligations.iter().any(|command| {
+                        matches!(&command.variant, CommandVariant::Admit(x, _, _) if x == name)
+                    })
+            };
+            while taken(&name) {
+                name = format!("{}{n}.{x}", self.owner);
+                n += 1;
+            }
+            let value = args
+                .into_iter()
+                .rev()
+                .fold(term(&meta, TermVariant::Const(name.clone())), |f, v| {
+                    term(&meta, TermVariant::App(f, v))
+                });
+            self.evars
+                .solve(id, value)
+                .map_err(|err| (meta.clone(), CommandError::TypeError(err)))?;
+            self.obligations.push(Command {
+                meta,
+                variant: CommandVariant::Admit(name, ttype, context.len()),
+            });
+        }
+        Ok(())
+    }
+
+    /// Substitutes the solutions into `t`, failing on the first metavariable which has not been solved.
+    ///
+    /// The error is reported at the hole with its expected type, in the context the hole was written in.
+    fn zonk(&self, t: &Term) -> Result<Term, (Span, CommandError<Span, Ident>)> {
+        let t = t.zonk(&self.evars);
+        t.validate_evars(|id| {
+            let Some(decl) = self.evars.get(id) else {
+                return Err(CommandError::UnsolvedEvar(id));
+            };
+            Err(CommandError::TypeError(TypeError::new(
+                &decl.context,
+                TypeErrorVariant::UnsolvedHole(decl.ttype.zonk(&self.evars)),
+            )))
+        })?;
+        Ok(t)
+    }
+
+    /// Returns the class which `ttype` is an instance of, if it is a class applied to its parameters.
+    fn class_of(&self, ttype: &Term) -> Option<String> {
+        match &*ttype.zonk(&self.evars).app_head().variant {
+            TermVariant::Const(name) if self.env.is_class(name) => Some(name.clone()),
+            _ => None,
+        }
+    }
+
+    /// Solves every hole whose type is a class, failing on the first one for which no instance is found.
+    fn resolve_instances(&mut self) -> Result<(), Error> {
+        loop {
+            let Some(id) = self
+                .evars
+                .unsolved()
+                .find(|&id| self.class_of(&self.evars.get(id).unwrap().ttype).is_some())
+            else {
+                return Ok(());
+            };
+            let mut trace = Vec::new();
+            if !self.resolve(id, 0, &mut trace) {
+                let decl = self.evars.get(id).unwrap();
+                return Err((
+                    decl.meta.clone(),
+                    TypeError::new(
+                        &decl.context,
+                        TypeErrorVariant::NoInstance(decl.ttype.zonk(&self.evars), trace),
+                    ),
+                ));
+            }
+        }
+    }
+
+    /// Searches for an instance solving the hole `id`, at `depth` premises from the hole resolution started with.
+    ///
+    /// The variables of the context of the hole are tried first, the most recent first, and then the instances of its class.
+    /// An instance is applied to new holes for its premises, and applies if its conclusion unifies with the type of the hole
+    /// and the premises whose types are classes are solved by the same search in turn.
+    /// Whatever an instance which does not apply has solved is undone before trying the next one.
+    fn resolve(
+        &mut self,
+        id: usize,
+        depth: usize,
+        trace: &mut Vec<InstanceTrace<Span, Ident>>,
+    ) -> bool {
+        let decl = self.evars.get(id).unwrap().clone();
+        let goal = decl.ttype.zonk(&self.evars);
+        trace.push(InstanceTrace::Goal(depth, goal.clone()));
+        if depth >= INSTANCE_DEPTH {
+            trace.push(InstanceTrace::DepthLimit(depth));
+            return false;
+        }
+        let class = self.class_of(&goal).unwrap();
+        let meta = &decl.meta;
+        let mut candidates = Vec::new();
+        for x in 0..decl.context.len() {
+            let entry = decl.context.get(x).unwrap();
+            let ttype = entry.ttype.push(x + 1);
+            if self.class_of(&ttype).as_ref() == Some(&class) {
+                candidates.push((entry.x.name.clone(), term(meta, TermVariant::Var(x)), ttype));
+            }
+        }
+        for instance in self.env.instances(&class) {
+            if let Some(entry) = self.global.get(&instance.name) {
+                let value = term(meta, TermVariant::Const(instance.name.clone()));
+                candidates.push((instance.name.clone(), value, entry.ttype.clone()));
+            }
+        }
+
+        for (name, mut value, mut ttype) in candidates {
+            let evars = self.evars.clone();
+            let unifier = self.unifier.clone();
+            let mut premises = Vec::new();
+            while let TermVariant::Product(_, input, output) = &*ttype.variant {
+                let premise = self.fresh(meta, input.clone(), &decl.context);
+                let TermVariant::Evar(premise_id, _) = &*premise.variant else {
+                    unreachable!()
+                };
+                premises.push(*premise_id);
+                ttype = output.subst_single(0, &premise);
+                value = term(meta, TermVariant::App(value, premise));
+            }
+            let mut local = decl.context.clone();
+            if self.unify(meta, &mut local, &ttype, &goal).is_err() {
+                self.evars = evars;
+                self.unifier = unifier;
+                trace.push(InstanceTrace::Mismatch(depth, name));
+                continue;
+            }
+            trace.push(InstanceTrace::Matched(depth, name));
+            let mut applies = self.evars.solve(id, value).is_ok();
+            for premise in premises {
+                if !applies {
+                    break;
+                }
+                if self.evars.value(premise).is_none() {
+                    let ttype = self.evars.get(premise).unwrap().ttype.clone();
+                    applies =
+                        self.class_of(&ttype).is_some() && self.resolve(premise, depth + 1, trace);
+                }
+            }
+            if applies {
+                return true;
+            }
+            self.evars = evars;
+            self.unifier = unifier;
+        }
+        false
+    }
+
+    fn unify(
+        &mut self,
+        meta: &Span,
+        local: &mut Local,
+        lhs: &Term,
+        rhs: &Term,
+    ) -> Result<(), Error> {
+        self.unifier
+            .unify(self.global, &mut self.evars, local, lhs, rhs)
+            .map_err(|err| (meta.clone(), err))
+    }
+
+    fn normalize(&self, meta: &Span, t: &Term, local: &mut Local) -> Result<Term, Error> {
+        let mut t = t.zonk(&self.evars);
+        t.normalize(self.global, &self.evars, local)
+            .map_err(|err| (meta.clone(), err))?;
+        Ok(t)
+    }
+
+    /// Ensures that a term of type `actual` can be used where a term of type `expected` is expected.
+    ///
+    /// Sorts are cumulative, so the types are only unified once they have been reduced to something other than sorts and products.
+    fn subtype(
+        &mut self,
+        meta: &Span,
+        local: &mut Local,
+        actual: &Term,
+        expected: &Term,
+    ) -> Result<(), Error> {
+        grow(|| {
+            let zonked_actual = actual.zonk(&self.evars);
+            let zonked_expected = expected.zonk(&self.evars);
+            if !has_evars(&zonked_actual) && !has_evars(&zonked_expected) {
+                return zonked_actual
+                    .expect_subtype(&zonked_expected, self.global, &self.evars, local)
+                    .map_err(|err| (meta.clone(), err));
+            }
+
+            if let (TermVariant::Product(x, l0, l1), TermVariant::Product(_, r0, r1)) =
+                (&*zonked_actual.variant, &*zonked_expected.variant)
+            {
+                self.unify(meta, local, l0, r0)?;
+                let mut local = local.push(Entry::new(x.clone(), l0.clone()));
+                return self.subtype(meta, &mut local, l1, r1);
+            }
+            let actual = self.normalize(meta, &zonked_actual, local)?;
+            let expected = self.normalize(meta, &zonked_expected, local)?;
+            match (&*actual.variant, &*expected.variant) {
+                (TermVariant::Sort(l), TermVariant::Sort(r)) => {
+                    if l <= r {
+                        Ok(())
+                    } else {
+                        Err((
+                            meta.clone(),
+                            TypeError::new(
+                                local,
+                                TypeErrorVariant::NotSubtypeType(zonked_expected, zonked_actual),
+                            ),
+                        ))
+                    }
+                }
+                (TermVariant::Product(x, l0, l1), TermVariant::Product(_, r0, r1)) => {
+                    self.unify(meta, local, l0, r0)?;
+                    let mut local = local.push(Entry::new(x.clone(), l0.clone()));
+                    self.subtype(meta, &mut local, l1, r1)
+                }
+                // The unifier reduces the terms itself, after first trying to solve them as they are written
+                _ => self.unify(meta, local, &zonked_actual, &zonked_expected),
+            }
+        })
+    }
+
+    /// Reduces `ttype`, the type of a term at `meta`, to a product and returns its input and output types.
+    fn expect_product(
+        &mut self,
+        meta: &Span,
+        ttype: &Term,
+        local: &mut Local,
+    ) -> Result<(Term, Term), Error> {
+        let norm = self.normalize(meta, ttype, local)?;
+        match norm.into_variant() {
+            TermVariant::Product(_, input, output) => Ok((input, output)),
+            _ => Err((
+                meta.clone(),
+                TypeError::new(
+                    local,
+                    TypeErrorVariant::NotAProduct(ttype.zonk(&self.evars)),
+                ),
+            )),
+        }
+    }
+
+    /// Elaborates a term which must be a type, returning it along with its sort.
+    fn infer_type(&mut self, t: &Term, local: &mut Local) -> Result<(Term, Sort), Error> {
+        if is_hole(t) {
+            return Ok((self.fresh_type(&t.meta, local), Sort::Type(0)));
+        }
+        let (t, ttype) = self.infer(t, local)?;
+        let norm = self.normalize(&t.meta, &ttype, local)?;
+        if let TermVariant::Sort(sort) = norm.into_variant() {
+            return Ok((t, sort));
+        }
+        if let Some((t, coerced)) = self.coerce(&t, &ttype, &CoercionClass::Sort, local) {
+            if let TermVariant::Sort(sort) =
+                self.normalize(&t.meta, &coerced, local)?.into_variant()
+            {
+                return Ok((t, sort));
+            }
+        }
+        Err((
+            t.meta.clone(),
+            TypeError::new(local, TypeErrorVariant::NotASort(ttype.zonk(&self.evars))),
+        ))
+    }
+
+    /// Elaborates a value along with its type annotation, returning both.
+    ///
+    /// If the annotation is a hole, the type is inferred from the value instead of the value being checked against it.
+    fn infer_annotated(
+        &mut self,
+        ttype: &Term,
+        value: &Term,
+        local: &mut Local,
+    ) -> Result<(Term, Term), Error> {
+        if is_hole(ttype) {
+            let (value, ttype) = self.infer(value, local)?;
+            return Ok((ttype, value));
+        }
+        let (ttype, _) = self.infer_type(ttype, local)?;
+        let value = self.check(value, &ttype, local)?;
+        Ok((ttype, value))
+    }
+
+    /// Elaborates `t`, returning it along with its type.
+    fn infer(&mut self, t: &Term, local: &mut Local) -> Result<(Term, Term), Error> {
+        grow(|| match &*t.variant {
+            TermVariant::Const(x) if goal_name(x).is_some() => self.goal(&t.meta, x, None, local),
+            TermVariant::Sort(_) | TermVariant::Var(_) | TermVariant::Const(_) => {
+                let ttype = t.type_check(self.global, &self.evars, local)?;
+                Ok((t.clone(), ttype))
+            }
+            TermVariant::App(f, v) => {
+                let (mut f, f_tp) = self.infer(f, local)?;
+                let (input, output) = match self.expect_product(&f.meta, &f_tp, local) {
+                    Ok(product) => product,
+                    Err(err) => {
+                        let Some((g, g_tp)) = self.coerce(&f, &f_tp, &CoercionClass::Fun, local)
+                        else {
+                            return Err(err);
+                        };
+                        f = g;
+                        self.expect_product(&f.meta, &g_tp, local)?
+                    }
+                };
+                let v = self.check(v, &input, local)?;
+                let ttype = output.subst_single(0, &v);
+                Ok((term(&t.meta, TermVariant::App(f, v)), ttype))
+            }
+            TermVariant::Product(x, x_tp, body) => {
+                let (x_tp, x_sort) = self.infer_type(x_tp, local)?;
+                let mut local = local.push(Entry::new(x.clone(), x_tp.clone()));
+                let (body, body_sort) = self.infer_type(body, &mut local)?;
+                Ok((
+                    term(&t.meta, TermVariant::Product(x.clone(), x_tp, body)),
+                    term(&t.meta, TermVariant::Sort(x_sort.product(body_sort))),
+                ))
+            }
+            TermVariant::Abstract(x, x_tp, body) => {
+                let (x_tp, _) = self.infer_type(x_tp, local)?;
+                let (body, body_tp) = {
+                    let mut local = local.push(Entry::new(x.clone(), x_tp.clone()));
+                    self.infer(body, &mut local)?
+                };
+                Ok((
+                    term(
+                        &t.meta,
+                        TermVariant::Abstract(x.clone(), x_tp.clone(), body),
+                    ),
+                    term(&t.meta, TermVariant::Product(x.clone(), x_tp, body_tp)),
+                ))
+            }
+            TermVariant::Bind(x, x_tp, x_val, body) => {
+                let (x_tp, x_val) = self.infer_annotated(x_tp, x_val, local)?;
+                let (body, body_tp) = {
+                    let mut local =
+                        local.push(Entry::with_value(x.clone(), x_val.clone(), x_tp.clone()));
+                    self.infer(body, &mut local)?
+                };
+                let ttype = body_tp.subst_single(0, &x_val);
+                Ok((
+                    term(&t.meta, TermVariant::Bind(x.clone(), x_tp, x_val, body)),
+                    ttype,
+                ))
+            }
+            TermVariant::Match(_, _, _, _) => self.elab_match(t, None, local),
+            TermVariant::Rewrite(_, _, _) => self.rewrite(t, None, local),
+            TermVariant::Evar(_, _) => {
+                let ttype = self.fresh_type(&t.meta, local);
+                let hole = self.fresh(&t.meta, ttype.clone(), local);
+                Ok((hole, ttype))
+            }
+        })
+    }
+
+    /// Elaborates `t` such that it has the type `expected`.
+    fn check(&mut self, t: &Term, expected: &Term, local: &mut Local) -> Result<Term, Error> {
+        grow(|| match &*t.variant {
+            TermVariant::Evar(_, _) => Ok(self.fresh(&t.meta, expected.clone(), local)),
+            TermVariant::Const(x) if goal_name(x).is_some() => {
+                let (hole, _) = self.goal(&t.meta, x, Some(expected), local)?;
+                Ok(hole)
+            }
+            TermVariant::Abstract(x, x_tp, body) => {
+                let norm = self.normalize(&t.meta, expected, local)?;
+                let TermVariant::Product(_, input, output) = &*norm.variant else {
+                    return self.check_inferred(t, expected, local);
+                };
+                let (x_tp, _) = self.infer_type(x_tp, local)?;
+                self.unify(&x_tp.meta, local, &x_tp, input)?;
+                let body = {
+                    let mut local = local.push(Entry::new(x.clone(), x_tp.clone()));
+                    self.check(body, output, &mut local)?
+                };
+                Ok(term(&t.meta, TermVariant::Abstract(x.clone(), x_tp, body)))
+            }
+            TermVariant::Bind(x, x_tp, x_val, body) => {
+                let (x_tp, x_val) = self.infer_annotated(x_tp, x_val, local)?;
+                let body = {
+                    let mut local =
+                        local.push(Entry::with_value(x.clone(), x_val.clone(), x_tp.clone()));
+                    self.check(body, &expected.push(1), &mut local)?
+                };
+                Ok(term(
+                    &t.meta,
+                    TermVariant::Bind(x.clone(), x_tp, x_val, body),
+                ))
+            }
+            TermVariant::Match(_, _, _, _) => {
+                let (elab, ttype) = self.elab_match(t, Some(expected), local)?;
+                self.subtype(&t.meta, local, &ttype, expected)?;
+                Ok(elab)
+            }
+            TermVariant::Rewrite(_, _, _) => {
+                let (elab, ttype) = self.rewrite(t, Some(expected), local)?;
+                self.subtype(&t.meta, local, &ttype, expected)?;
+                Ok(elab)
+            }
+            TermVariant::App(_, _) if matches!(&*t.app_head().variant, TermVariant::Const(_)) => {
+                self.check_app(t, expected, local)
+            }
+            _ => self.check_inferred(t, expected, local),
+        })
+    }
+
+    /// Checks an application of a constant against `expected`, which is unified with the type of the application first,
+    /// such that the arguments are elaborated knowing what the expected type says about them.
+    ///
+    /// The arguments are stood in for by new metavariables until they are elaborated.
+    /// If the types do not unify this way, the application is checked by inferring its type instead.
+    fn check_app(&mut self, t: &Term, expected: &Term, local: &mut Local) -> Result<Term, Error> {
+        // The arguments along with the applications they are part of, from the first argument
+        let mut args = Vec::new();
+        let mut head = t;
+        while let TermVariant::App(f, v) = &*head.variant {
+            args.push((&head.meta, v));
+            head = f;
+        }
+        args.reverse();
+        let (f, mut f_tp) = self.infer(head, local)?;
+        let evars = self.evars.clone();
+        let unifier = self.unifier.clone();
+        let mut placeholders = Vec::with_capacity(args.len());
+        for (_, arg) in &args {
+            let Ok((input, output)) = self.expect_product(&arg.meta, &f_tp, local) else {
+                self.evars = evars;
+                self.unifier = unifier;
+                return self.check_inferred(t, expected, local);
+            };
+            let placeholder = self.fresh(&arg.meta, input.clone(), local);
+            f_tp = output.subst_single(0, &placeholder);
+            placeholders.push((placeholder, input));
+        }
+        if self.subtype(&t.meta, local, &f_tp, expected).is_err() {
+            self.evars = evars;
+            self.unifier = unifier;
+            return self.check_inferred(t, expected, local);
+        }
+        let mut elab = f;
+        for ((meta, arg), (placeholder, input)) in args.into_iter().zip(placeholders) {
+            let v = self.check(arg, &input, local)?;
+            self.unify(&arg.meta, local, &placeholder, &v)?;
+            elab = term(meta, TermVariant::App(elab, v));
+        }
+        Ok(elab)
+    }
+
+    /// Checks `t` against `expected` by inferring its type first.
+    fn check_inferred(
+        &mut self,
+        t: &Term,
+        expected: &Term,
+        local: &mut Local,
+    ) -> Result<Term, Error> {
+        let (elab, ttype) = self.infer(t, local)?;
+        let snapshot = self.evars.snapshot();
+        let unifier = self.unifier.clone();
+        let Err(err) = self.subtype(&t.meta, local, &ttype, expected) else {
+            return Ok(elab);
+        };
+        if !self.env.has_coercions() {
+            return Err(err);
+        }
+        self.evars.rollback(snapshot);
+        self.unifier = unifier;
+        let norm = self.normalize(&t.meta, expected, local)?;
+        let target = match &*norm.app_head().variant {
+            TermVariant::Sort(_) => CoercionClass::Sort,
+            TermVariant::Product(_, _, _) => CoercionClass::Fun,
+            TermVariant::Const(name) => CoercionClass::Const(name.clone()),
+            _ => return Err(err),
+        };
+        let snapshot = self.evars.snapshot();
+        let unifier = self.unifier.clone();
+        if let Some((elab, coerced)) = self.coerce(&elab, &ttype, &target, local) {
+            if self.subtype(&t.meta, local, &coerced, expected).is_ok() {
+                return Ok(elab);
+            }
+        }
+        self.evars.rollback(snapshot);
+        self.unifier = unifier;
+        Err(err)
+    }
+
+    /// Returns the class of `ttype` in the coercion graph, which is the constant at its head,
+    /// or the constant at the head of its normal form if it has no coercions of its own.
+    fn coercion_class(&self, ttype: &Term, local: &mut Local) -> Option<CoercionClass> {
+        let head = |t: &Term| match &*t.app_head().variant {
+            TermVariant::Const(name) => Some(CoercionClass::Const(name.clone())),
+            _ => None,
+        };
+        match head(&ttype.zonk(&self.evars)) {
+            Some(class) if self.env.coerces_from(&class) => Some(class),
+            _ => head(&self.normalize(&ttype.meta, ttype, local).ok()?),
+        }
+    }
+
+    /// Converts `t` of type `ttype` to a term of class `target` by inserting the shortest path of coercions,
+    /// returning it along with its type.
+    ///
+    /// Whatever has been solved is left as it is when this fails, which the caller has to undo.
+    fn coerce(
+        &mut self,
+        t: &Term,
+        ttype: &Term,
+        target: &CoercionClass,
+        local: &mut Local,
+    ) -> Option<(Term, Term)> {
+        let source = self.coercion_class(ttype, local)?;
+        let env = self.env;
+        let path = env.coercion_path(&source, target)?;
+        if path.is_empty() {
+            return None;
+        }
+        let mut t = t.clone();
+        let mut ttype = ttype.clone();
+        for coercion in path {
+            let meta = t.meta.clone();
+            let mut f = term(&meta, TermVariant::Const(coercion.name.clone()));
+            let mut f_tp = self.global.get(&coercion.name)?.ttype.clone();
+            // The parameters of the source are inferred, and the last argument is the term itself
+            for i in 0..coercion.arity {
+                let (input, output) = self.expect_product(&meta, &f_tp, local).ok()?;
+                let arg = if i + 1 < coercion.arity {
+                    self.fresh(&meta, input, local)
+                } else {
+                    self.subtype(&meta, local, &ttype, &input).ok()?;
+                    t.clone()
+                };
+                f_tp = output.subst_single(0, &arg);
+                f = term(&meta, TermVariant::App(f, arg));
+            }
+            t = f;
+            ttype = f_tp;
+        }
+        Some((t, ttype))
+    }
+
+    /// Returns the shape of `t`, an index of a scrutinee.
+    fn shape(&self, t: &Term) -> Shape {
+        grow(|| {
+            if let TermVariant::Var(x) = &*t.variant {
+                return Shape::Var(*x);
+            }
+            let (head, args) = t.strip_args_ref();
+            let TermVariant::Const(name) = &*head.variant else {
+                return Shape::Opaque;
+            };
+            match self.global.get_entry(name) {
+                Some(GEntryRef::InductiveConstructor(i, j, params, bodies))
+                    if args.len() == params.len() + bodies[i].constructors[j].arity.len() =>
+                {
+                    let shapes = args[params.len()..]
+                        .iter()
+                        .map(|arg| self.shape(arg))
+                        .collect();
+                    Shape::Constructor(bodies[i].name.clone(), name.clone(), shapes)
+                }
+                _ => Shape::Opaque,
+            }
+        })
+    }
+
+    /// Finds the return clause for a match of type `expected` on an indexed family, by inverting the indices of the scrutinee.
+    ///
+    /// The return clause matches on each index of the family which is built from constructors in the type of the scrutinee,
+    /// following those constructors. Where a constructor differs the return type is a unit type,
+    /// so an arm whose constructor has indices which clash with the scrutinee's is impossible, and has a trivial body.
+    /// Where they agree, the variables under the constructors are replaced by what the arm has in their place,
+    /// so that the arms learn how their indices relate to those of the scrutinee.
+    /// The local variables whose types mention those variables are generalized too, and are passed to the match as arguments.
+    ///
+    /// The return clause takes the place of the no-confusion and injectivity lemmas of the family, which are not generated:
+    /// the unit type where constructors clash is what no-confusion proves,
+    /// and the replaced variables are what injectivity would rewrite with.
+    /// Which arms are impossible is found by `Elaborator::case`.
+    ///
+    /// Returns `None` when neither is needed, leaving `generalize` to find the return clause.
+    #[allow(clippy::too_many_arguments)]
+    fn invert(
+        &mut self,
+        meta: &Span,
+        expected: &Term,
+        scrutinee: &Term,
+        params: &[Term],
+        indices: &[Term],
+        bound: usize,
+        local: &mut Local,
+    ) -> Result<Option<Inversion>, Error> {
+        let expected = expected.zonk(&self.evars);
+        let scrutinee = scrutinee.zonk(&self.evars);
+        let params: Vec<_> = params.iter().map(|t| t.zonk(&self.evars)).collect();
+        let indices: Vec<_> = indices.iter().map(|t| t.zonk(&self.evars)).collect();
+        let shapes: Vec<_> = indices.iter().map(|index| self.shape(index)).collect();
+        let mut vars = Vec::new();
+        shapes.iter().for_each(|shape| shape.vars(&mut vars));
+        let scrutinee_var = match &*scrutinee.variant {
+            TermVariant::Var(x) => Some(*x),
+            _ => None,
+        };
+
+        // The variables in the type of the scrutinee stay as they are
+        let fixed = |x: usize| {
+            params
+                .iter()
+                .chain(&indices)
+                .chain([&scrutinee])
+                .any(|t| t.pop(x).is_none())
+        };
+        let convoy: Vec<_> = (0..local.len())
+            .rev()
+            .filter_map(|x| {
+                let entry = local.get(x).unwrap();
+                if entry.value.is_some() || vars.contains(&x) || fixed(x) {
+                    return None;
+                }
+                let ttype = entry.ttype.push(x + 1).zonk(&self.evars);
+                vars.iter()
+                    .any(|&y| ttype.pop(y).is_none())
+                    .then(|| (x, entry.x.clone()))
+            })
+            .collect();
+        if convoy.is_empty() && !shapes.iter().any(Shape::has_constructor) {
+            return Ok(None);
+        }
+
+        let sort_of = |elab: &mut Self, t: &Term, local: &mut Local| {
+            let ttype = t.type_check(elab.global, &elab.evars, local)?;
+            match elab.normalize(meta, &ttype, local)?.into_variant() {
+                TermVariant::Sort(sort) => Ok(sort),
+                _ => Err((
+                    meta.clone(),
+                    TypeError::new(local, TypeErrorVariant::NotASort(ttype)),
+                )),
+            }
+        };
+        let mut sort = sort_of(self, &expected, local)?;
+        for (x, _) in convoy.iter().rev() {
+            let ttype = local.get(*x).unwrap().ttype.push(x + 1);
+            sort = sort_of(self, &ttype, local)?.product(sort);
+        }
+
+        let leaf = |map: &[(usize, Term)], extra: usize| {
+            // Moves a term from the context of the match to the return type, under `generalized` of the variables in `convoy`
+            let remap = |t: &Term, generalized: usize| {
+                t.subst(|meta, x, push| {
+                    if x < push {
+                        return term(meta, TermVariant::Var(x));
+                    }
+                    let x = x - push;
+                    let t = if let Some((_, t)) = map.iter().rev().find(|(y, _)| *y == x) {
+                        t.push(generalized)
+                    } else if scrutinee_var == Some(x) {
+                        term(meta, TermVariant::Var(extra + generalized))
+                    } else if let Some(l) = convoy[..generalized].iter().position(|(y, _)| *y == x)
+                    {
+                        term(meta, TermVariant::Var(generalized - 1 - l))
+                    } else {
+                        term(meta, TermVariant::Var(x + bound + extra + generalized))
+                    };
+                    t.push(push)
+                })
+            };
+            let goal = remap(&expected, convoy.len());
+            convoy
+                .iter()
+                .enumerate()
+                .rev()
+                .fold(goal, |goal, (l, (x, name))| {
+                    let ttype = remap(&local.get(*x).unwrap().ttype.push(x + 1), l);
+                    term(meta, TermVariant::Product(name.clone(), ttype, goal))
+                })
+        };
+        let queue: Vec<_> = shapes
+            .iter()
+            .enumerate()
+            .map(|(i, shape)| (term(meta, TermVariant::Var(shapes.len() - i)), shape))
+            .collect();
+        let motive = motive(self.global, meta, &sort, &queue, Vec::new(), 0, &leaf);
+        Ok(Some(Inversion {
+            motive,
+            convoy,
+            shapes,
+        }))
+    }
+
+    /// Finds whether an arm whose indices are `indices` is possible in a match with the return clause of `inversion`.
+    ///
+    /// The indices are followed along the matches of the return clause, which the arm reaches as long as they are constructors.
+    /// The arm is impossible if one of them is a constructor other than the one of the scrutinee,
+    /// and possible once the match on an index is stuck or every match agrees.
+    fn case(
+        &mut self,
+        meta: &Span,
+        inversion: &Inversion,
+        indices: &[Term],
+        local: &mut Local,
+    ) -> Result<Case, Error> {
+        let mut queue: VecDeque<_> = indices.iter().cloned().zip(&inversion.shapes).collect();
+        while let Some((index, shape)) = queue.pop_front() {
+            let Shape::Constructor(_, expected, shapes) = shape else {
+                continue;
+            };
+            let index = self.normalize(meta, &index, local)?;
+            let (head, args) = index.strip_args();
+            let TermVariant::Const(name) = &*head.variant else {
+                return Ok(Case::Possible);
+            };
+            let Some(GEntryRef::InductiveConstructor(_, _, params, _)) =
+                self.global.get_entry(name)
+            else {
+                return Ok(Case::Possible);
+            };
+            if name != expected {
+                return Ok(Case::Impossible);
+            }
+            // The arguments of the constructor are matched on before the rest of the indices
+            for pair in args.into_iter().skip(params.len()).zip(shapes).rev() {
+                queue.push_front(pair);
+            }
+        }
+        Ok(Case::Possible)
+    }
+
+    /// Elaborates a match, with the return clause and arms typed the same way as in the kernel.
+    ///
+    /// A left out `in` clause is inferred from the type of the scrutinee.
+    /// A left out `return` clause is found by generalizing `expected` over the indices and the scrutinee,
+    /// or is a new metavariable independent of them when there is no expected type.
+    fn elab_match(
+        &mut self,
+        t: &Term,
+        expected: Option<&Term>,
+        local: &mut Local,
+    ) -> Result<(Term, Term), Error> {
+        let TermVariant::Match(scrutinee, name, ret, arms) = &*t.variant else {
+            unreachable!()
+        };
+        let omitted_in = ret.constructor.is_empty();
+        let mut inferred = None;
+        let constructor = if omitted_in {
+            let (scrutinee, s_type) = self.infer(scrutinee, local)?;
+            let norm = self.normalize(&scrutinee.meta, &s_type, local)?;
+            let TermVariant::Const(constructor) = &*norm.app_head().variant else {
+                return Err((
+                    scrutinee.meta.clone(),
+                    TypeError::new(
+                        local,
+                        TypeErrorVariant::ScrutineeNotInductive(s_type.zonk(&self.evars)),
+                    ),
+                ));
+            };
+            let constructor = constructor.clone();
+            inferred = Some((scrutinee, s_type));
+            constructor
+        } else {
+            ret.constructor.clone()
+        };
+        let global = self.global;
+        let (params, body) = match global.get_entry(&constructor) {
+            Some(GEntryRef::Inductive(i, params, bodies)) => (params, &bodies[i]),
+            Some(_) => {
+                return Err((
+                    ret.meta.clone(),
+                    TypeError::new(local, TypeErrorVariant::NotAnInductiveType(constructor)),
+                ))
+            }
+            None => {
+                return Err((
+                    ret.meta.clone(),
+                    TypeError::new(local, TypeErrorVariant::UndefinedConst(constructor)),
+                ))
+            }
+        };
+        // The return clause binds the parameters and indices under the names they were declared with
+        let ret_params = if omitted_in {
+            params
+                .iter()
+                .chain(&body.arity)
+                .map(|param| param.x.clone())
+                .collect()
+        } else {
+            ret.params.clone()
+        };
+        if ret_params.len() != params.len() + body.arity.len() {
+            return Err((
+                ret.meta.clone(),
+                TypeError::new(
+                    local,
+                    TypeErrorVariant::IncorrectParameterCount(
+                        params.len() + body.arity.len(),
+                        ret_params.len(),
+                    ),
+                ),
+            ));
+        }
+
+        let (scrutinee, s_type) = match inferred {
+            Some(inferred) => inferred,
+            None => self.infer(scrutinee, local)?,
+        };
+        let norm = self.normalize(&scrutinee.meta, &s_type, local)?;
+        let (hd, mut args) = norm.strip_args();
+        if !hd.is_const(&constructor) || args.len() < params.len() {
+            return Err((
+                scrutinee.meta.clone(),
+                TypeError::new(
+                    local,
+                    TypeErrorVariant::NotOfExpectedInducitve(constructor, s_type.zonk(&self.evars)),
+                ),
+            ));
+        }
+        let arity_args = args.split_off(params.len());
+
+        // The parameters are bound to their values, while the indices and the scrutinee are opaque
+        let bind_params = |xs: &[Ident]| {
+            xs.iter()
+                .zip(params)
+                .zip(&args)
+                .enumerate()
+                .map(|(i, ((x, param), value))| {
+                    Entry::with_value(x.clone(), value.push(i), param.ttype.clone())
+                })
+                .collect::<Vec<_>>()
+        };
+        let applied = |head: &str, len: usize| {
+            (0..len)
+                .rev()
+                .map(|n| term(&t.meta, TermVariant::Var(n)))
+                .fold(
+                    term(&t.meta, TermVariant::Const(head.to_owned())),
+                    |f, v| term(&t.meta, TermVariant::App(f, v)),
+                )
+        };
+
+        let bound = ret_params.len() + 1;
+        let inversion = match expected {
+            Some(expected) if is_hole(&ret.body) => self.invert(
+                &t.meta,
+                expected,
+                &scrutinee,
+                &args,
+                &arity_args,
+                bound,
+                local,
+            )?,
+            _ => None,
+        };
+        let ret_body = if let Some(inversion) = &inversion {
+            inversion.motive.clone()
+        } else if is_hole(&ret.body) {
+            match expected {
+                Some(expected) => generalize(
+                    &expected.zonk(&self.evars),
+                    &scrutinee.zonk(&self.evars),
+                    &arity_args,
+                    bound,
+                ),
+                None => self.fresh_type(&t.meta, local).push(bound),
+            }
+        } else {
+            // Without an `in` clause the return clause was lowered with only the scrutinee bound
+            let ret_body = if omitted_in {
+                ret.body.subst(|meta, x, push| {
+                    let x = if x > push { x + ret_params.len() } else { x };
+                    term(meta, TermVariant::Var(x))
+                })
+            } else {
+                ret.body.clone()
+            };
+            let entries = bind_params(&ret_params);
+            let mut local = local.slot();
+            local.extend(entries);
+            local.extend(
+                ret_params[params.len()..]
+                    .iter()
+                    .zip(&body.arity)
+                    .map(|(x, param)| Entry::new(x.clone(), param.ttype.clone())),
+            );
+            local.push_onto(Entry::new(
+                name.clone(),
+                applied(&constructor, ret_params.len()),
+            ));
+            self.infer_type(&ret_body, &mut local)?.0
+        };
+
+        // The context of an arm for `constructor`, along with the type of its body
+        let arm_context = |constructor: &GIndConstructor<Span, Ident>, arm_params: &[Ident]| {
+            let mut entries = bind_params(arm_params);
+            entries.extend(
+                arm_params[params.len()..]
+                    .iter()
+                    .zip(&constructor.arity)
+                    .map(|(x, param)| Entry::new(x.clone(), param.ttype.clone())),
+            );
+            let this = applied(&constructor.name, arm_params.len());
+            let expected = ret_body.push(arm_params.len()).subst_many(
+                arm_params.len(),
+                constructor.args.len() + 1,
+                |i| {
+                    if i < constructor.args.len() {
+                        &constructor.args[i]
+                    } else {
+                        &this
+                    }
+                },
+            );
+            (entries, expected)
+        };
+
+        let mut elab_arms = Vec::with_capacity(body.constructors.len());
+        for arm in arms {
+            let Some(constructor) = body.constructors.iter().find(|c| c.name == arm.constructor)
+            else {
+                return Err((
+                    arm.meta.clone(),
+                    TypeError::new(
+                        local,
+                        TypeErrorVariant::NotAConstructor(
+                            body.name.clone(),
+                            arm.constructor.clone(),
+                            body.constructors.iter().map(|c| c.name.clone()).collect(),
+                        ),
+                    ),
+                ));
+            };
+            if arm.params.len() != params.len() + constructor.arity.len() {
+                return Err((
+                    arm.meta.clone(),
+                    TypeError::new(
+                        local,
+                        TypeErrorVariant::IncorrectParameterCount(
+                            params.len() + constructor.arity.len(),
+                            arm.params.len(),
+                        ),
+                    ),
+                ));
+            }
+            let (entries, expected) = arm_context(constructor, &arm.params);
+            let mut local = local.slot();
+            local.extend(entries);
+            let body = match &inversion {
+                Some(inversion) => {
+                    let indices = &constructor.args[params.len()..];
+                    match self.case(&arm.meta, inversion, indices, &mut local)? {
+                        // An arm for an impossible case is unreachable, so its body is replaced
+                        Case::Impossible => unit_proof(&arm.meta),
+                        Case::Possible => {
+                            let expected = self.normalize(&arm.meta, &expected, &mut local)?;
+                            let body = convoy_body(&arm.body, &inversion.convoy, arm.params.len());
+                            self.check(&body, &expected, &mut local)?
+                        }
+                    }
+                }
+                None => self.check(&arm.body, &expected, &mut local)?,
+            };
+            elab_arms.push(MatchArm {
+                meta: arm.meta.clone(),
+                constructor: arm.constructor.clone(),
+                params: arm.params.clone(),
+                body,
+            });
+        }
+
+        // The impossible cases may be left out, as their arms are found here
+        if let Some(inversion) = &inversion {
+            let mut missing = Vec::new();
+            for constructor in &body.constructors {
+                if arms.iter().any(|arm| arm.constructor == constructor.name) {
+                    continue;
+                }
+                let arm_params: Vec<_> = params
+                    .iter()
+                    .chain(&constructor.arity)
+                    .map(|param| param.x.clone())
+                    .collect();
+                let (entries, _) = arm_context(constructor, &arm_params);
+                let mut local = local.slot();
+                local.extend(entries);
+                let indices = &constructor.args[params.len()..];
+                match self.case(&t.meta, inversion, indices, &mut local)? {
+                    Case::Impossible => elab_arms.push(MatchArm {
+                        meta: t.meta.clone(),
+                        constructor: constructor.name.clone(),
+                        params: arm_params,
+                        body: unit_proof(&t.meta),
+                    }),
+                    Case::Possible => missing.push(constructor.name.clone()),
+                }
+            }
+            if !missing.is_empty() {
+                return Err((
+                    t.meta.clone(),
+                    TypeError::new(local, TypeErrorVariant::MissingConstructors(missing)),
+                ));
+            }
+        }
+
+        let ttype = ret_body.subst_many(0, args.len() + arity_args.len() + 1, |i| {
+            if i < args.len() {
+                &args[i]
+            } else if i < args.len() + arity_args.len() {
+                &arity_args[i - args.len()]
+            } else {
+                &scrutinee
+            }
+        });
+        let ret = MatchArm {
+            meta: ret.meta.clone(),
+            constructor,
+            params: ret_params,
+            body: ret_body,
+        };
+        let elab = term(
+            &t.meta,
+            TermVariant::Match(scrutinee, name.clone(), ret, elab_arms),
+        );
+        match inversion {
+            // The motive was built such that the match, applied to the generalized variables, has the expected type
+            Some(inversion) => Ok((
+                inversion.convoy.iter().fold(elab, |f, (x, _)| {
+                    term(
+                        &t.meta,
+                        TermVariant::App(f, term(&t.meta, TermVariant::Var(*x))),
+                    )
+                }),
+                expected.unwrap().clone(),
+            )),
+            None => Ok((elab, ttype)),
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use std::cmp::max;
+
+use hane_kernel::{TypeError, TypeErrorVariant};
+
+use super::{has_evars, term, Elaborator, Error};
+use crate::{
+    lower::lowered::{Term, TermVariant},
+    Ident, Span,
+};
+
+/// The depth `auto` searches to unless it is given one.
+const AUTO_DEPTH: usize = 5;
+
+/// What the search knows about a goal it tries to prove.
+struct Search {
+    meta: Span,
+    hints: Vec<String>,
+    existential: bool,
+    /// The depth the search started with
+    limit: usize,
+    /// The length of the context of the goal the search started with
+    context: usize,
+}
+
+/// What the search has explored, which is reported when it gives up.
+#[derive(Default)]
+struct Explored {
+    /// The deepest the search went
+    depth: usize,
+    /// The goals which nothing applied to, or which were too deep, generalized over the hypotheses introduced by the search
+    unproven: Vec<Term>,
+}
+
+impl Elaborator<'_> {
+    /// Proves `goal` by searching to `depth` with the hints of `core` and `databases`,
+    /// applying hints whose arguments are not found by unification if `existential` is set.
+    /// The search is depth-first, trying the hypotheses from the most recent and then the hints in the order they were added.
+    pub(super) fn auto(
+        &mut self,
+        goal: usize,
+        meta: &Span,
+        depth: Option<usize>,
+        databases: &[Ident],
+        existential: bool,
+    ) -> Result<Vec<usize>, Error> {
+        let mut hints: Vec<String> = self.env.hints("core").unwrap_or_default().to_vec();
+        for database in databases {
+            let Some(found) = self.env.hints(&database.name) else {
+                return Err(self.failure(
+                    goal,
+                    meta,
+                    &format!("No hint database is named `{}`", database.name),
+                ));
+            };
+            for hint in found {
+                if !hints.contains(hint) {
+                    hints.push(hint.clone());
+                }
+            }
+        }
+        let decl = self.evars.get(goal).unwrap().clone();
+        let limit = depth.unwrap_or(AUTO_DEPTH);
+        let search = Search {
+            meta: meta.clone(),
+            hints,
+            existential,
+            limit,
+            context: decl.context.len(),
+        };
+
+        let evars = self.evars.clone();
+        let unifier = self.unifier.clone();
+        let mut explored = Explored::default();
+        if !self.search(vec![(goal, limit)], &search, &mut explored) {
+            self.evars = evars;
+            self.unifier = unifier;
+            return Err((
+                meta.clone(),
+                TypeError::new(
+                    &decl.context,
+                    TypeErrorVariant::SearchFailed(
+                        decl.ttype.zonk(&self.evars),
+                        explored.depth,
+                        explored.unproven,
+                    ),
+                ),
+            ));
+        }
+
+        let value = self.evars.value(goal).unwrap().zonk(&self.evars);
+        let mut local = decl.context.clone();
+        let checked = value
+            .type_check(self.global, &self.evars, &mut local)
+            .and_then(|ttype| self.subtype(meta, &mut local, &ttype, &decl.ttype));
+        if checked.is_err() {
+            self.evars = evars;
+            self.unifier = unifier;
+            return Err(self.failure(
+                goal,
+                meta,
+                "The proof found by auto is not accepted by the kernel",
+            ));
+        }
+        Ok(Vec::new())
+    }
+
+    /// Proves every goal of `goals`, each with the depth which is left for it, returning whether it succeeded.
+    ///
+    /// Whatever was solved is left as it is when it fails, for the caller to undo.
+    fn search(
+        &mut self,
+        mut goals: Vec<(usize, usize)>,
+        search: &Search,
+        explored: &mut Explored,
+    ) -> bool {
+        // Goals may have been solved by unification while proving the goals before them
+        goals.retain(|&(goal, _)| self.evars.value(goal).is_none());
+        if goals.is_empty() {
+            return true;
+        }
+        let (mut goal, depth) = goals.remove(0);
+        explored.depth = max(explored.depth, search.limit - depth);
+        loop {
+            let ttype = self.evars.get(goal).unwrap().ttype.zonk(&self.evars);
+            if self.product(&ttype).is_none() {
+                break;
+            }
+            match self.intro(goal, &search.meta, None) {
+                Ok(body) => goal = body,
+                Err(_) => return false,
+            }
+        }
+        if depth == 0 {
+            self.unproven(goal, search, explored);
+            return false;
+        }
+
+        let meta = &search.meta;
+        let context = self.evars.get(goal).unwrap().context.len();
+        let candidates = (0..context).map(|x| term(meta, TermVariant::Var(x))).chain(
+            search
+                .hints
+                .iter()
+                .map(|hint| term(meta, TermVariant::Const(hint.clone()))),
+        );
+        let mut applied = false;
+        for candidate in candidates {
+            let evars = self.evars.clone();
+            let unifier = self.unifier.clone();
+            let Ok(mut new) = self.apply_term(goal, meta, &candidate) else {
+                continue;
+            };
+            let types: Vec<_> = new
+                .iter()
+                .map(|&id| self.evars.get(id).unwrap().ttype.zonk(&self.evars))
+                .collect();
+            // The holes which occur in the other goals are solved by proving them, so they are left for last
+            let occurs = |id: usize| {
+                types.iter().any(|t| {
+                    t.validate_evars(|x| if x == id { Err(()) } else { Ok(()) })
+                        .is_err()
+                })
+            };
+            if types.iter().any(has_evars) && !search.existential {
+                self.evars = evars;
+                self.unifier = unifier;
+                continue;
+            }
+            applied = true;
+            new.sort_by_key(|&id| occurs(id));
+            let mut next: Vec<_> = new.into_iter().map(|id| (id, depth - 1)).collect();
+            next.extend(goals.iter().copied());
+            if self.search(next, search, explored) {
+                return true;
+            }
+            self.evars = evars;
+            self.unifier = unifier;
+        }
+        if !applied {
+            self.unproven(goal, search, explored);
+        }
+        false
+    }
+
+    /// Records `goal` as a goal the search could not prove.
+    fn unproven(&self, goal: usize, search: &Search, explored: &mut Explored) {
+        let decl = self.evars.get(goal).unwrap();
+        let introduced = decl.context.len() - search.context;
+        let generalized =
+            decl.context
+                .iter()
+                .take(introduced)
+                .fold(decl.ttype.zonk(&self.evars), |t, entry| {
+                    let ttype = entry.ttype.zonk(&self.evars);
+                    term(
+                        &search.meta,
+                        TermVariant::Product(entry.x.clone(), ttype, t),
+                    )
+                });
+        if !explored.unproven.contains(&generalized) {
+            explored.unproven.push(generalized);
+        }
+    }
+}
@@ -0,0 +1,468 @@
+use std::collections::VecDeque;
+
+use hane_kernel::{global::GEntryRef, Sort};
+
+use super::{equality::Equality, term, Elaborator, Error, Local};
+use crate::{
+    lower::lowered::{MatchArm, Term, TermVariant},
+    Ident, Span,
+};
+
+enum Reason {
+    /// A hypothesis, or a proof built from one, of the equality in the direction of the edge
+    Hyp(Term),
+    /// Two applications whose functions and arguments are equal
+    Congruence,
+    /// The arguments at the given position of two applications of the same constructor which are equal
+    Injection(usize, usize, usize),
+}
+
+/// A term which is part of the closure.
+struct Node {
+    term: Term,
+    /// The function and argument of an application, if its function is not dependent
+    app: Option<(usize, usize)>,
+    /// The inductive type and index of the constructor a term is a full application of, along with its arguments besides the parameters
+    constructor: Option<(String, usize, Vec<usize>)>,
+}
+
+struct Closure<'e, 'a> {
+    elab: &'e Elaborator<'a>,
+    eq: Equality,
+    meta: &'e Span,
+    local: Local,
+    nodes: Vec<Node>,
+    parent: Vec<usize>,
+    /// The merges, each between two nodes which were in distinct classes
+    edges: Vec<(usize, usize, Reason)>,
+}
+
+impl Closure<'_, '_> {
+    fn term(&self, variant: TermVariant) -> Term {
+        term(self.meta, variant)
+    }
+
+    fn underscores(&self, n: usize) -> Vec<Ident> {
+        let underscore = Ident {
+            span: self.meta.clone(),
+            name: "_".to_owned(),
+        };
+        vec![underscore; n]
+    }
+
+    fn type_of(&mut self, t: &Term) -> Result<Term, Error> {
+        t.type_check(self.elab.global, &self.elab.evars, &mut self.local)
+    }
+
+    /// Returns the node of `t`, adding it along with its subterms if it is not part of the closure yet.
+    fn node(&mut self, t: &Term) -> Result<usize, Error> {
+        if let Some(i) = self.nodes.iter().position(|node| node.term == *t) {
+            return Ok(i);
+        }
+        let app = match &*t.variant {
+            TermVariant::App(f, v) => {
+                let f_type = self.type_of(f)?;
+                let f_type = self.elab.normalize(self.meta, &f_type, &mut self.local)?;
+                let f = self.node(f)?;
+                let v = self.node(v)?;
+                match &*f_type.variant {
+                    TermVariant::Product(_, _, output) if output.pop(0).is_some() => Some((f, v)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        let (head, args) = t.strip_args_ref();
+        let constructor = match &*head.variant {
+            TermVariant::Const(c) => match self.elab.global.get_entry(c) {
+                Some(GEntryRef::InductiveConstructor(i, j, params, bodies))
+                    if bodies[i].arity.is_empty()
+                        && args.len() == params.len() + bodies[i].constructors[j].arity.len() =>
+                {
+                    let args = args[params.len()..]
+                        .iter()
+                        .map(|arg| self.node(arg))
+                        .collect::<Result<_, _>>()?;
+                    Some((bodies[i].name.clone(), j, args))
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        self.nodes.push(Node {
+            term: t.clone(),
+            app,
+            constructor,
+        });
+        self.parent.push(self.parent.len());
+        Ok(self.nodes.len() - 1)
+    }
+
+    fn find(&self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            i = self.parent[i];
+        }
+        i
+    }
+
+    /// Merges the classes of `a` and `b` for the given reason, returning false if they already were the same.
+    fn merge(&mut self, a: usize, b: usize, reason: Reason) -> bool {
+        let (root_a, root_b) = (self.find(a), self.find(b));
+        if root_a == root_b {
+            return false;
+        }
+        self.parent[root_a] = root_b;
+        self.edges.push((a, b, reason));
+        true
+    }
+
+    /// Merges terms by congruence and injectivity until nothing more is merged.
+    fn close(&mut self) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for i in 0..self.nodes.len() {
+                for j in i + 1..self.nodes.len() {
+                    if self.find(i) != self.find(j) {
+                        if let (Some((f1, v1)), Some((f2, v2))) =
+                            (self.nodes[i].app, self.nodes[j].app)
+                        {
+                            if self.find(f1) == self.find(f2) && self.find(v1) == self.find(v2) {
+                                changed |= self.merge(i, j, Reason::Congruence);
+                            }
+                        }
+                        continue;
+                    }
+                    let (Some((ind1, c1, args1)), Some((ind2, c2, args2))) =
+                        (&self.nodes[i].constructor, &self.nodes[j].constructor)
+                    else {
+                        continue;
+                    };
+                    if ind1 != ind2 || c1 != c2 {
+                        continue;
+                    }
+                    let pairs: Vec<_> = args1.iter().copied().zip(args2.iter().copied()).collect();
+                    for (k, (x, y)) in pairs.into_iter().enumerate() {
+                        changed |= self.merge(x, y, Reason::Injection(i, j, k));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the edges of the path from `a` to `b`, each with whether it is followed in the direction it was added in.
+    fn path(&self, a: usize, b: usize) -> Vec<(usize, bool)> {
+        let mut previous: Vec<Option<(usize, usize, bool)>> = vec![None; self.nodes.len()];
+        let mut queue = VecDeque::from([a]);
+        while let Some(n) = queue.pop_front() {
+            if n == b {
+                break;
+            }
+            for (e, (x, y, _)) in self.edges.iter().enumerate() {
+                let (next, forward) = match (*x == n, *y == n) {
+                    (true, _) => (*y, true),
+                    (_, true) => (*x, false),
+                    _ => continue,
+                };
+                if next != a && previous[next].is_none() {
+                    previous[next] = Some((n, e, forward));
+                    queue.push_back(next);
+                }
+            }
+        }
+        let mut path = Vec::new();
+        let mut n = b;
+        while n != a {
+            let (prev, e, forward) = previous[n].unwrap();
+            path.push((e, forward));
+            n = prev;
+        }
+        path.reverse();
+        path
+    }
+
+    /// Proves `f a = g b` of type `ttype` from `p : f = g` and `q : a = b`, or by reflexivity when the terms are the same.
+    fn congruence(
+        &self,
+        ttype: &Term,
+        (f, g, p): (&Term, &Term, Term),
+        (a, b, q): (&Term, &Term, Term),
+    ) -> Term {
+        let app = |f: Term, v: Term| self.term(TermVariant::App(f, v));
+        let fa = app(f.clone(), a.clone());
+        // `f a = f b`, followed by `f b = g b`
+        let arg = (a != b).then(|| self.eq.cong(ttype, f, &fa, q));
+        let fun = (f != g).then(|| {
+            let fb = app(f.clone(), b.clone());
+            let var = self.term(TermVariant::Var(1));
+            let motive = self.eq.eq(ttype.push(4), fb.push(4), app(var, b.push(4)));
+            self.eq
+                .rewrite(p, motive, self.eq.refl(ttype.push(2), fb.push(2)))
+        });
+        match (arg, fun) {
+            (Some(arg), Some(fun)) => self.eq.trans(ttype, &fa, arg, fun),
+            (Some(proof), None) | (None, Some(proof)) => proof,
+            (None, None) => self.eq.refl(ttype.clone(), fa),
+        }
+    }
+
+    /// Returns `match z with c .. => hit | _ => miss end`, a match on the variable `z` of the inductive type `ind`,
+    /// where `hit` is under the arguments of the `c`th constructor and `miss` is moved under the arguments of the others.
+    fn select(&self, ind: &str, c: usize, z: usize, ret: Term, hit: Term, miss: &Term) -> Term {
+        let Some(GEntryRef::Inductive(i, params, bodies)) = self.elab.global.get_entry(ind) else {
+            unreachable!()
+        };
+        let k = params.len();
+        let ret = MatchArm {
+            meta: self.meta.clone(),
+            constructor: ind.to_owned(),
+            params: self.underscores(k),
+            body: ret,
+        };
+        let arms = bodies[i]
+            .constructors
+            .iter()
+            .enumerate()
+            .map(|(j, constructor)| {
+                let arity = constructor.arity.len();
+                MatchArm {
+                    meta: self.meta.clone(),
+                    constructor: constructor.name.clone(),
+                    params: self.underscores(k + arity),
+                    body: if j == c {
+                        hit.clone()
+                    } else {
+                        miss.push(k + arity)
+                    },
+                }
+            })
+            .collect();
+        let name = self.underscores(1).pop().unwrap();
+        self.term(TermVariant::Match(
+            self.term(TermVariant::Var(z)),
+            name,
+            ret,
+            arms,
+        ))
+    }
+
+    /// Proves that the terms of the nodes `a` and `b` are equal.
+    fn explain(&mut self, a: usize, b: usize) -> Result<Term, Error> {
+        let ta = self.nodes[a].term.clone();
+        let ttype = self.type_of(&ta)?;
+        let mut proof = None;
+        for (e, forward) in self.path(a, b) {
+            let x = self.edges[e].0;
+            let mut step = self.edge(e)?;
+            if !forward {
+                let tx = self.nodes[x].term.clone();
+                let x_type = self.type_of(&tx)?;
+                step = self.eq.sym(&x_type, &tx, step);
+            }
+            proof = Some(match proof {
+                None => step,
+                Some(proof) => self.eq.trans(&ttype, &ta, proof, step),
+            });
+        }
+        Ok(proof.unwrap_or_else(|| self.eq.refl(ttype, ta)))
+    }
+
+    /// Proves the equality of the edge `e` in the direction it was added in.
+    fn edge(&mut self, e: usize) -> Result<Term, Error> {
+        let (x, y) = (self.edges[e].0, self.edges[e].1);
+        match &self.edges[e].2 {
+            Reason::Hyp(p) => Ok(p.clone()),
+            Reason::Congruence => {
+                let (f, a) = self.nodes[x].app.unwrap();
+                let (g, b) = self.nodes[y].app.unwrap();
+                let p = self.explain(f, g)?;
+                let q = self.explain(a, b)?;
+                let ttype = self.type_of(&self.nodes[x].term.clone())?;
+                let [tf, tg, ta, tb] = [f, g, a, b].map(|n| self.nodes[n].term.clone());
+                Ok(self.congruence(&ttype, (&tf, &tg, p), (&ta, &tb, q)))
+            }
+            &Reason::Injection(i, j, k) => {
+                let h = self.explain(i, j)?;
+                let (ind, c, _) = self.nodes[i].constructor.clone().unwrap();
+                let tx = self.nodes[x].term.clone();
+                let x_type = self.type_of(&tx)?;
+                let arity = self.nodes[i].constructor.as_ref().unwrap().2.len();
+                // Under the parameters, the index and the proof, the projection of the index is the `k`th argument
+                let projection = self.select(
+                    &ind,
+                    c,
+                    1,
+                    x_type.push(4 + 1 + self.params(&ind)),
+                    self.term(TermVariant::Var(arity - 1 - k)),
+                    &tx.push(4),
+                );
+                let motive = self.eq.eq(x_type.push(4), tx.push(4), projection);
+                Ok(self
+                    .eq
+                    .rewrite(h, motive, self.eq.refl(x_type.push(2), tx.push(2))))
+            }
+        }
+    }
+
+    fn params(&self, ind: &str) -> usize {
+        match self.elab.global.get_entry(ind) {
+            Some(GEntryRef::Inductive(_, params, _)) => params.len(),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Proves `goal`, whose sort is `Prop`, from the equality of the nodes `i` and `j`, which are applications of distinct constructors.
+    fn discriminate(&mut self, i: usize, j: usize, goal: &Term) -> Result<Term, Error> {
+        let h = self.explain(i, j)?;
+        let (ind, c, _) = self.nodes[i].constructor.clone().unwrap();
+        let k = self.params(&ind);
+        // The type of the arm is `goal -> goal` where the index is the constructor of `i`, and `goal` otherwise
+        let prop = self.term(TermVariant::Sort(Sort::Prop));
+        let arity = match self.elab.global.get_entry(&ind) {
+            Some(GEntryRef::Inductive(n, _, bodies)) => bodies[n].constructors[c].arity.len(),
+            _ => unreachable!(),
+        };
+        let depth = 4 + k + arity;
+        let hit = self.term(TermVariant::Product(
+            self.underscores(1).pop().unwrap(),
+            goal.push(depth),
+            goal.push(depth + 1),
+        ));
+        let motive = self.select(&ind, c, 1, prop, hit, &goal.push(4));
+        let x = Ident {
+            span: self.meta.clone(),
+            name: "H".to_owned(),
+        };
+        let identity = self.term(TermVariant::Abstract(
+            x,
+            goal.push(2),
+            self.term(TermVariant::Var(0)),
+        ));
+        Ok(self.eq.rewrite(h, motive, identity))
+    }
+}
+
+impl Elaborator<'_> {
+    /// Proves `goal` by congruence closure over the equalities among its hypotheses,
+    /// either as an equality or from a contradiction between them.
+    /// Distinct constructors contradict each other, and the proof of an equality chains the reasons of the merges between its sides.
+    pub(super) fn congruence(&mut self, goal: usize, meta: &Span) -> Result<Vec<usize>, Error> {
+        let Some(eq) = self.registered_equality(meta) else {
+            return Err(self.failure(goal, meta, "No equality is registered as `eq`"));
+        };
+        let decl = self.evars.get(goal).unwrap().clone();
+        let falsity = self.env.registered("False");
+        let mut closure = Closure {
+            elab: self,
+            eq,
+            meta,
+            local: decl.context.clone(),
+            nodes: Vec::new(),
+            parent: Vec::new(),
+            edges: Vec::new(),
+        };
+        // The hypotheses which are equalities are merged, and those which are disequalities are checked once the closure is done
+        let mut disequalities = Vec::new();
+        for (x, entry) in decl.context.iter().enumerate() {
+            let var = term(meta, TermVariant::Var(x));
+            let ttype = entry.ttype.push(x + 1).zonk(&self.evars);
+            let ttype = match closure.eq.sides(&ttype) {
+                Some(_) => ttype,
+                None => self.normalize(meta, &ttype, &mut closure.local)?,
+            };
+            if let Some((_, lhs, rhs)) = closure.eq.sides(&ttype) {
+                let (a, b) = (closure.node(&lhs)?, closure.node(&rhs)?);
+                closure.merge(a, b, Reason::Hyp(var));
+                continue;
+            }
+            let TermVariant::Product(_, input, output) = &*ttype.variant else {
+                continue;
+            };
+            let is_false = matches!(
+                (output.pop(0).as_ref().map(|t| &*t.variant), falsity),
+                (Some(TermVariant::Const(x)), Some(falsity)) if x == falsity
+            );
+            if let (true, Some((_, lhs, rhs))) = (is_false, closure.eq.sides(input)) {
+                disequalities.push((closure.node(&lhs)?, closure.node(&rhs)?, var));
+            }
+        }
+        let ttype = decl.ttype.zonk(&self.evars);
+        let ttype = match closure.eq.sides(&ttype) {
+            Some(_) => ttype,
+            None => self.normalize(meta, &ttype, &mut closure.local)?,
+        };
+        let target = match closure.eq.sides(&ttype) {
+            Some((_, lhs, rhs)) => Some((closure.node(&lhs)?, closure.node(&rhs)?)),
+            None => None,
+        };
+        closure.close();
+
+        let mut value = None;
+        if let Some((a, b)) = target {
+            if closure.find(a) == closure.find(b) {
+                value = Some(closure.explain(a, b)?);
+            }
+        }
+        for (a, b, d) in disequalities {
+            if value.is_some() || closure.find(a) != closure.find(b) {
+                continue;
+            }
+            let proof = closure.explain(a, b)?;
+            let contradiction = term(meta, TermVariant::App(d, proof));
+            let ret = MatchArm {
+                meta: meta.clone(),
+                constructor: falsity.unwrap().to_owned(),
+                params: Vec::new(),
+                body: ttype.push(1),
+            };
+            let name = closure.underscores(1).pop().unwrap();
+            value = Some(term(
+                meta,
+                TermVariant::Match(contradiction, name, ret, Vec::new()),
+            ));
+        }
+        let goal_sort = closure.type_of(&ttype)?;
+        let goal_sort = self.normalize(meta, &goal_sort, &mut closure.local)?;
+        if value.is_none() && matches!(&*goal_sort.variant, TermVariant::Sort(Sort::Prop)) {
+            let constructed: Vec<_> = (0..closure.nodes.len())
+                .filter(|&i| closure.nodes[i].constructor.is_some())
+                .collect();
+            'search: for &i in &constructed {
+                for &j in &constructed {
+                    let (Some((ind1, c1, _)), Some((ind2, c2, _))) =
+                        (&closure.nodes[i].constructor, &closure.nodes[j].constructor)
+                    else {
+                        unreachable!()
+                    };
+                    if ind1 == ind2 && c1 != c2 && closure.find(i) == closure.find(j) {
+                        value = Some(closure.discriminate(i, j, &ttype)?);
+                        break 'search;
+                    }
+                }
+            }
+        }
+        let Some(value) = value else {
+            return Err(self.failure(goal, meta, "congruence failed to prove the goal"));
+        };
+
+        let evars = self.evars.clone();
+        let unifier = self.unifier.clone();
+        let mut local = decl.context.clone();
+        let checked = value
+            .type_check(self.global, &self.evars, &mut local)
+            .and_then(|ttype| self.subtype(meta, &mut local, &ttype, &decl.ttype));
+        if checked.is_err() {
+            self.evars = evars;
+            self.unifier = unifier;
+            return Err(self.failure(
+                goal,
+                meta,
+                "The proof found by congruence is not accepted by the kernel",
+            ));
+        }
+        self.evars
+            .solve(goal, value)
+            .map_err(|err| (meta.clone(), err))?;
+        Ok(Vec::new())
+    }
+}
@@ -0,0 +1,114 @@
+use hane_kernel::global::GEntryRef;
+
+use super::{tactic::applied, term, Elaborator};
+use crate::{
+    lower::lowered::{MatchArm, Term, TermVariant},
+    Ident, Span,
+};
+
+/// The inductive type registered as `eq` along with its constructor, which builds terms at `meta`.
+/// It has to look like `eq (T : Set) (x : T) : T -> Prop` with a single constructor without arguments.
+pub(super) struct Equality {
+    pub(super) name: String,
+    refl: String,
+    meta: Span,
+}
+
+impl Equality {
+    fn term(&self, variant: TermVariant) -> Term {
+        term(&self.meta, variant)
+    }
+
+    fn underscores(&self, n: usize) -> Vec<Ident> {
+        let underscore = Ident {
+            span: self.meta.clone(),
+            name: "_".to_owned(),
+        };
+        vec![underscore; n]
+    }
+
+    /// Returns the type, the left-hand side and the right-hand side of `t` if it is an equality.
+    pub(super) fn sides(&self, t: &Term) -> Option<(Term, Term, Term)> {
+        let (head, args) = t.strip_args_ref();
+        match (&*head.variant, &args[..]) {
+            (TermVariant::Const(name), [ttype, lhs, rhs]) if *name == self.name => {
+                Some(((*ttype).clone(), (*lhs).clone(), (*rhs).clone()))
+            }
+            _ => None,
+        }
+    }
+
+    /// `match p in eq _ _ z return motive with eq_refl _ _ => body end`,
+    /// where `motive` is under the parameters, the index and the proof, and `body` is under the parameters.
+    pub(super) fn rewrite(&self, p: Term, motive: Term, body: Term) -> Term {
+        let ret = MatchArm {
+            meta: self.meta.clone(),
+            constructor: self.name.clone(),
+            params: self.underscores(3),
+            body: motive,
+        };
+        let arm = MatchArm {
+            meta: self.meta.clone(),
+            constructor: self.refl.clone(),
+            params: self.underscores(2),
+            body,
+        };
+        let name = self.underscores(1).pop().unwrap();
+        self.term(TermVariant::Match(p, name, ret, vec![arm]))
+    }
+
+    pub(super) fn eq(&self, ttype: Term, lhs: Term, rhs: Term) -> Term {
+        let eq = self.term(TermVariant::Const(self.name.clone()));
+        applied(eq, [ttype, lhs, rhs])
+    }
+
+    pub(super) fn refl(&self, ttype: Term, t: Term) -> Term {
+        let refl = self.term(TermVariant::Const(self.refl.clone()));
+        applied(refl, [ttype, t])
+    }
+
+    /// Proves `b = a` from `p : a = b`.
+    pub(super) fn sym(&self, ttype: &Term, a: &Term, p: Term) -> Term {
+        let motive = self.eq(ttype.push(4), self.term(TermVariant::Var(1)), a.push(4));
+        self.rewrite(p, motive, self.refl(ttype.push(2), a.push(2)))
+    }
+
+    /// Proves `a = c` from `p : a = b` and `q : b = c`.
+    pub(super) fn trans(&self, ttype: &Term, a: &Term, p: Term, q: Term) -> Term {
+        let motive = self.eq(ttype.push(4), a.push(4), self.term(TermVariant::Var(1)));
+        self.rewrite(q, motive, p.push(2))
+    }
+
+    /// Proves `fa = f b` from `p : a = b`, where `fa` is `f a` and `ttype` is its type.
+    pub(super) fn cong(&self, ttype: &Term, f: &Term, fa: &Term, p: Term) -> Term {
+        let fz = self.term(TermVariant::App(f.push(4), self.term(TermVariant::Var(1))));
+        let motive = self.eq(ttype.push(4), fa.push(4), fz);
+        self.rewrite(p, motive, self.refl(ttype.push(2), fa.push(2)))
+    }
+}
+
+impl Elaborator<'_> {
+    /// Returns the inductive type registered as `eq`, if it has the shape of an equality.
+    pub(super) fn registered_equality(&self, meta: &Span) -> Option<Equality> {
+        let name = self.env.registered("eq")?;
+        let Some(GEntryRef::Inductive(i, params, bodies)) = self.global.get_entry(name) else {
+            return None;
+        };
+        let var = |x| term(meta, TermVariant::Var(x));
+        match &bodies[i].constructors[..] {
+            [refl]
+                if params.len() == 2
+                    && bodies[i].arity.len() == 1
+                    && refl.arity.is_empty()
+                    && refl.args[2] == var(0) =>
+            {
+                Some(Equality {
+                    name: name.to_owned(),
+                    refl: refl.name.clone(),
+                    meta: meta.clone(),
+                })
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,782 @@
+use hane_kernel::global::GEntryRef;
+
+use super::{equality::Equality, tactic::applied, term, Elaborator, Error};
+use crate::{
+    lower::lowered::{MatchArm, Term, TermVariant},
+    Ident, Span,
+};
+
+/// The roles of the lemmas the proofs use, besides the natural numbers registered as `nat`, `plus`, `le` and `lt`.
+const LEMMAS: [&str; 11] = [
+    "plus_O_n",
+    "plus_Sn_m",
+    "plus_comm",
+    "plus_assoc",
+    "plus_le_compat",
+    "le_0_n",
+    "le_S_n",
+    "le_Sn_n",
+    "le_trans",
+    "le_or_lt",
+    "le_antisym",
+];
+
+/// The constants registered as the natural numbers and the relations on them.
+struct Nat {
+    nat: String,
+    zero: String,
+    succ: String,
+    plus: String,
+    le: String,
+    lt: Option<String>,
+    /// The constructors of `le`, proving `le n n` and `le n m -> le n (S m)`
+    le_n: String,
+    le_s: String,
+}
+
+/// A hypothesis `le a b` along with its proof.
+type Bound = (Term, Term, Term);
+
+/// A constraint `coefficients · atoms + constant >= 0`, along with how many times each bound is added up in it.
+#[derive(Clone)]
+struct Row {
+    coefficients: Vec<i64>,
+    constant: i64,
+    multipliers: Vec<i64>,
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Merges the sorted atoms `xs` and `ys`.
+fn merge(xs: &[usize], ys: &[usize]) -> Vec<usize> {
+    let mut merged = [xs, ys].concat();
+    merged.sort_unstable();
+    merged
+}
+
+/// The rows are given up on past this many, as each elimination may square their number.
+const MAX_ROWS: usize = 4096;
+
+/// Returns how many times each bound is added up in a combination of `rows` which has no atoms and a negative constant.
+/// Gives up when a coefficient of a combination does not fit in an `i64`.
+fn refute(mut rows: Vec<Row>, atoms: usize) -> Option<Vec<i64>> {
+    for atom in 0..atoms {
+        let (zero, rest): (Vec<_>, Vec<_>) = rows
+            .into_iter()
+            .partition(|row| row.coefficients[atom] == 0);
+        let (positive, negative): (Vec<_>, Vec<_>) =
+            rest.into_iter().partition(|row| row.coefficients[atom] > 0);
+        rows = zero;
+        for p in &positive {
+            for n in &negative {
+                let (a, b) = (-n.coefficients[atom], p.coefficients[atom]);
+                let linear = |x: i64, y: i64| a.checked_mul(x)?.checked_add(b.checked_mul(y)?);
+                let combine = |x: &[i64], y: &[i64]| -> Option<Vec<i64>> {
+                    x.iter().zip(y).map(|(&x, &y)| linear(x, y)).collect()
+                };
+                let mut row = Row {
+                    coefficients: combine(&p.coefficients, &n.coefficients)?,
+                    constant: linear(p.constant, n.constant)?,
+                    multipliers: combine(&p.multipliers, &n.multipliers)?,
+                };
+                let divisor = row.multipliers.iter().fold(0, |d, &m| gcd(d, m));
+                if divisor > 1 {
+                    row.coefficients.iter_mut().for_each(|c| *c /= divisor);
+                    row.constant /= divisor;
+                    row.multipliers.iter_mut().for_each(|m| *m /= divisor);
+                }
+                rows.push(row);
+            }
+        }
+        if rows.len() > MAX_ROWS {
+            return None;
+        }
+    }
+    rows.into_iter()
+        .find(|row| row.constant < 0)
+        .map(|row| row.multipliers)
+}
+
+/// Builds the proofs for a single refutation, whose terms are all in the same context.
+struct Lia<'n> {
+    nat: &'n Nat,
+    eq: &'n Equality,
+    meta: &'n Span,
+    lemmas: &'n dyn Fn(&str) -> Result<Term, Error>,
+    /// The constants registered as `False` and `or` along with its constructors, which proofs by cases need
+    falsity: Option<&'n str>,
+    or: Option<&'n (String, String, String)>,
+    atoms: Vec<Term>,
+}
+
+impl Lia<'_> {
+    fn term(&self, variant: TermVariant) -> Term {
+        term(self.meta, variant)
+    }
+
+    fn constant(&self, name: &str) -> Term {
+        self.term(TermVariant::Const(name.to_owned()))
+    }
+
+    fn nat(&self) -> Term {
+        self.constant(&self.nat.nat)
+    }
+
+    fn succ(&self, t: Term) -> Term {
+        applied(self.constant(&self.nat.succ), [t])
+    }
+
+    fn plus(&self, a: Term, b: Term) -> Term {
+        applied(self.constant(&self.nat.plus), [a, b])
+    }
+
+    fn le(&self, a: Term, b: Term) -> Term {
+        applied(self.constant(&self.nat.le), [a, b])
+    }
+
+    fn lemma(&self, name: &str, args: impl IntoIterator<Item = Term>) -> Result<Term, Error> {
+        Ok(applied((self.lemmas)(name)?, args))
+    }
+
+    fn atom(&mut self, t: &Term) -> usize {
+        match self.atoms.iter().position(|atom| atom == t) {
+            Some(i) => i,
+            None => {
+                self.atoms.push(t.clone());
+                self.atoms.len() - 1
+            }
+        }
+    }
+
+    /// The sum `atoms` nested to the right, or `O` if there are none.
+    fn sum(&self, atoms: &[usize]) -> Term {
+        match atoms {
+            [] => self.constant(&self.nat.zero),
+            [x] => self.atoms[*x].clone(),
+            [x, rest @ ..] => self.plus(self.atoms[*x].clone(), self.sum(rest)),
+        }
+    }
+
+    /// The normal form `S (.. (S sum))` with `n` successors.
+    fn normal(&self, n: usize, atoms: &[usize]) -> Term {
+        (0..n).fold(self.sum(atoms), |t, _| self.succ(t))
+    }
+
+    /// Returns the normal form of `t` as its number of successors and its sorted atoms, along with a proof that `t` is equal to it.
+    fn normalize(&mut self, t: &Term) -> Result<(usize, Vec<usize>, Term), Error> {
+        let (head, args) = t.strip_args_ref();
+        let nat = self.nat();
+        match (&*head.variant, &args[..]) {
+            (TermVariant::Const(x), []) if *x == self.nat.zero => {
+                Ok((0, Vec::new(), self.eq.refl(nat, t.clone())))
+            }
+            (TermVariant::Const(x), [u]) if *x == self.nat.succ => {
+                let (n, atoms, p) = self.normalize(u)?;
+                let succ = self.constant(&self.nat.succ);
+                Ok((n + 1, atoms, self.eq.cong(&nat, &succ, t, p)))
+            }
+            (TermVariant::Const(x), [u, v]) if *x == self.nat.plus => {
+                let (u, v) = ((*u).clone(), (*v).clone());
+                let (n1, xs, p1) = self.normalize(&u)?;
+                let (n2, ys, p2) = self.normalize(&v)?;
+                let u1 = self.normal(n1, &xs);
+                // `plus u v = plus u1 v`, `plus u1 v = plus u1 v1` and then the normal form of `plus u1 v1`,
+                // where `u1` and `v1` are the normal forms of `u` and `v`
+                let x = Ident {
+                    span: self.meta.clone(),
+                    name: "x".to_owned(),
+                };
+                let var = self.term(TermVariant::Var(0));
+                let left = self.term(TermVariant::Abstract(
+                    x,
+                    nat.clone(),
+                    self.plus(var, v.push(1)),
+                ));
+                let first = self.eq.cong(&nat, &left, t, p1);
+                let plus_u1 = applied(self.constant(&self.nat.plus), [u1.clone()]);
+                let second = self.eq.cong(&nat, &plus_u1, &self.plus(u1.clone(), v), p2);
+                let third = self.add(n1, &xs, n2, &ys)?;
+                let proof = self.eq.trans(&nat, t, first, second);
+                Ok((
+                    n1 + n2,
+                    merge(&xs, &ys),
+                    self.eq.trans(&nat, t, proof, third),
+                ))
+            }
+            _ => {
+                let x = self.atom(t);
+                Ok((0, vec![x], self.eq.refl(nat, t.clone())))
+            }
+        }
+    }
+
+    /// Proves `plus u v` equal to its normal form, where `u` and `v` are the normal forms with `n1` and `n2` successors.
+    fn add(&self, n1: usize, xs: &[usize], n2: usize, ys: &[usize]) -> Result<Term, Error> {
+        let nat = self.nat();
+        let (u, v) = (self.normal(n1, xs), self.normal(n2, ys));
+        let lhs = self.plus(u.clone(), v.clone());
+        let succ = self.constant(&self.nat.succ);
+        if n1 > 0 {
+            // `plus (S u') v = S (plus u' v)`
+            let u1 = self.normal(n1 - 1, xs);
+            let step = self.lemma("plus_Sn_m", [u1.clone(), v.clone()])?;
+            let rest = self.add(n1 - 1, xs, n2, ys)?;
+            let rest = self
+                .eq
+                .cong(&nat, &succ, &self.succ(self.plus(u1, v)), rest);
+            return Ok(self.eq.trans(&nat, &lhs, step, rest));
+        }
+        if n2 > 0 {
+            // `plus u (S v') = plus (S v') u = S (plus v' u) = S (plus u v')`
+            let v1 = self.normal(n2 - 1, ys);
+            let comm = self.lemma("plus_comm", [u.clone(), v.clone()])?;
+            let step = self.lemma("plus_Sn_m", [v1.clone(), u.clone()])?;
+            let back = self.lemma("plus_comm", [v1.clone(), u.clone()])?;
+            let back = self.eq.cong(
+                &nat,
+                &succ,
+                &self.succ(self.plus(v1.clone(), u.clone())),
+                back,
+            );
+            let rest = self.add(0, xs, n2 - 1, ys)?;
+            let rest = self
+                .eq
+                .cong(&nat, &succ, &self.succ(self.plus(u, v1)), rest);
+            let proof = [step, back, rest]
+                .into_iter()
+                .fold(comm, |proof, step| self.eq.trans(&nat, &lhs, proof, step));
+            return Ok(proof);
+        }
+        self.add_atoms(xs, ys)
+    }
+
+    /// Proves `plus (sum xs) (sum ys)` equal to the sum of the merged atoms.
+    fn add_atoms(&self, xs: &[usize], ys: &[usize]) -> Result<Term, Error> {
+        let nat = self.nat();
+        let (u, v) = (self.sum(xs), self.sum(ys));
+        let lhs = self.plus(u.clone(), v.clone());
+        match (xs, ys) {
+            ([], _) => self.lemma("plus_O_n", [v]),
+            (_, []) => {
+                let comm = self.lemma("plus_comm", [u.clone(), v.clone()])?;
+                let zero = self.lemma("plus_O_n", [u])?;
+                Ok(self.eq.trans(&nat, &lhs, comm, zero))
+            }
+            ([x, ..], [y, ..]) if y < x => {
+                let comm = self.lemma("plus_comm", [u, v])?;
+                let rest = self.add_atoms(ys, xs)?;
+                Ok(self.eq.trans(&nat, &lhs, comm, rest))
+            }
+            ([_], _) => Ok(self.eq.refl(nat, lhs)),
+            ([x, rest @ ..], _) => {
+                // `plus (plus x u') v = plus x (plus u' v)`, by `plus_assoc` read from right to left
+                let x = self.atoms[*x].clone();
+                let u1 = self.sum(rest);
+                let assoc = self.lemma("plus_assoc", [x.clone(), u1.clone(), v.clone()])?;
+                let inner = self.plus(x.clone(), self.plus(u1.clone(), v.clone()));
+                let assoc = self.eq.sym(&nat, &inner, assoc);
+                let plus_x = applied(self.constant(&self.nat.plus), [x]);
+                let rest = self.add_atoms(rest, ys)?;
+                let rest = self.eq.cong(&nat, &plus_x, &inner, rest);
+                Ok(self.eq.trans(&nat, &lhs, assoc, rest))
+            }
+        }
+    }
+
+    /// Returns `a` and `b` if `t` is `le a b`, or `S a` and `b` if it is `lt a b`.
+    fn bound(&self, t: &Term) -> Option<(Term, Term)> {
+        let (head, args) = t.strip_args_ref();
+        match (&*head.variant, &args[..]) {
+            (TermVariant::Const(x), [a, b]) if *x == self.nat.le => {
+                Some(((*a).clone(), (*b).clone()))
+            }
+            (TermVariant::Const(x), [a, b]) if Some(x) == self.nat.lt.as_ref() => {
+                Some((self.succ((*a).clone()), (*b).clone()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns `a` and `b` if `t` is `eq nat a b`.
+    fn equality(&self, t: &Term) -> Option<(Term, Term)> {
+        match self.eq.sides(t) {
+            Some((ttype, a, b)) if ttype == self.nat() => Some((a, b)),
+            _ => None,
+        }
+    }
+
+    /// Returns the bounds given by a hypothesis of type `t` with the proof `h`.
+    fn bounds(&self, t: &Term, h: Term) -> Vec<Bound> {
+        if let Some((a, b)) = self.bound(t) {
+            return vec![(a, b, h)];
+        }
+        let Some((a, b)) = self.equality(t) else {
+            return Vec::new();
+        };
+        // `le a a` is rewritten to `le a b` and `le b a` to `le b a`
+        let le_n = |a: &Term| applied(self.constant(&self.nat.le_n), [a.clone()]);
+        let symmetric = self.eq.sym(&self.nat(), &a, h.clone());
+        vec![
+            (
+                a.clone(),
+                b.clone(),
+                self.transport(le_n(&a), (&a, &a), h, true),
+            ),
+            (
+                b.clone(),
+                a.clone(),
+                self.transport(le_n(&b), (&b, &b), symmetric, true),
+            ),
+        ]
+    }
+
+    /// Turns `h : le a b` into a proof of `le c b` using `p : a = c`, or of `le a c` if `right` is set.
+    fn transport(&self, h: Term, (a, b): (&Term, &Term), p: Term, right: bool) -> Term {
+        let z = self.term(TermVariant::Var(1));
+        let motive = if right {
+            self.le(a.push(4), z)
+        } else {
+            self.le(z, b.push(4))
+        };
+        self.eq.rewrite(p, motive, h.push(2))
+    }
+
+    /// Proves `False` from the bounds, if a combination of them is contradictory.
+    fn contradiction(&mut self, bounds: Vec<Bound>) -> Result<Option<Term>, Error> {
+        let mut bounds = bounds;
+        let mut sides = Vec::new();
+        for (a, b, _) in &bounds {
+            let (n1, xs, _) = self.normalize(a)?;
+            let (n2, ys, _) = self.normalize(b)?;
+            sides.push(((n1, xs), (n2, ys)));
+        }
+        // Every atom is at least zero
+        for x in 0..self.atoms.len() {
+            let zero = self.constant(&self.nat.zero);
+            let atom = self.atoms[x].clone();
+            let proof = self.lemma("le_0_n", [atom])?;
+            bounds.push((zero, self.atoms[x].clone(), proof));
+            sides.push(((0, Vec::new()), (0, vec![x])));
+        }
+        let atoms = self.atoms.len();
+        let rows = sides
+            .iter()
+            .enumerate()
+            .map(|(i, ((n1, xs), (n2, ys)))| {
+                let mut coefficients = vec![0; atoms];
+                ys.iter().for_each(|&y| coefficients[y] += 1);
+                xs.iter().for_each(|&x| coefficients[x] -= 1);
+                let mut multipliers = vec![0; bounds.len()];
+                multipliers[i] = 1;
+                Row {
+                    coefficients,
+                    constant: *n2 as i64 - *n1 as i64,
+                    multipliers,
+                }
+            })
+            .collect();
+        let Some(multipliers) = refute(rows, atoms) else {
+            return Ok(None);
+        };
+
+        // The sum of the bounds, each added as many times as its multiplier
+        let mut added = bounds
+            .iter()
+            .zip(&multipliers)
+            .flat_map(|(bound, &m)| (0..m).map(move |_| bound))
+            .rev();
+        let (a, b, h) = added.next().unwrap().clone();
+        let (lhs, rhs, h) = added.try_fold((a, b, h), |(l, r, acc), (a, b, h)| {
+            let proof = self.lemma(
+                "plus_le_compat",
+                [a.clone(), b.clone(), l.clone(), r.clone(), h.clone(), acc],
+            )?;
+            Ok((self.plus(a.clone(), l), self.plus(b.clone(), r), proof))
+        })?;
+
+        let (n1, xs, p1) = self.normalize(&lhs)?;
+        let (n2, ys, p2) = self.normalize(&rhs)?;
+        debug_assert!(xs == ys && n1 > n2);
+        let l = self.normal(n1, &xs);
+        let h = self.transport(h, (&lhs, &rhs), p1, false);
+        let mut h = self.transport(h, (&l, &rhs), p2, true);
+        // `le (S n) (S m)` is peeled down to `le (S^k x) x`
+        for i in 1..=n2 {
+            let (l, r) = (self.normal(n1 - i, &xs), self.normal(n2 - i, &ys));
+            h = self.lemma("le_S_n", [l, r, h])?;
+        }
+        let k = n1 - n2;
+        let x = self.sum(&xs);
+        if k > 1 {
+            // `le (S x) (S^k x)` by the constructors of `le`, and then `le (S x) x` by transitivity
+            let sx = self.succ(x.clone());
+            let le_n = applied(self.constant(&self.nat.le_n), [sx.clone()]);
+            let le_s = (2..=k).fold(le_n, |p, i| {
+                let m = self.normal(i - 1, &xs);
+                applied(self.constant(&self.nat.le_s), [sx.clone(), m, p])
+            });
+            let sk = self.normal(k, &xs);
+            h = self.lemma("le_trans", [sx, sk, x.clone(), le_s, h])?;
+        }
+        Ok(Some(self.lemma("le_Sn_n", [x, h])?))
+    }
+
+    /// Returns the bound `le a b` rounded to the integers, if that makes it stronger.
+    ///
+    /// When the coefficients of the atoms of `b - a` have a common divisor `g`, so does `b - a`,
+    /// and its constant can be rounded down to a multiple of `g`.
+    fn tighten(&mut self, a: &Term, b: &Term) -> Result<Option<(Term, Term)>, Error> {
+        let (n1, xs, _) = self.normalize(a)?;
+        let (n2, ys, _) = self.normalize(b)?;
+        let mut coefficients = vec![0; self.atoms.len()];
+        ys.iter().for_each(|&y| coefficients[y] += 1);
+        xs.iter().for_each(|&x| coefficients[x] -= 1);
+        let divisor = coefficients.iter().fold(0, |d, &c| gcd(d, c));
+        let constant = n2 as i64 - n1 as i64;
+        if divisor <= 1 || constant.rem_euclid(divisor) == 0 {
+            return Ok(None);
+        }
+        let constant = constant.div_euclid(divisor);
+        // The atoms with a positive coefficient are on the right, and those with a negative one on the left
+        let side = |sign: i64| -> Vec<usize> {
+            coefficients
+                .iter()
+                .enumerate()
+                .flat_map(|(x, &c)| (0..(sign * c / divisor).max(0)).map(move |_| x))
+                .collect()
+        };
+        let a = self.normal((-constant).max(0) as usize, &side(-1));
+        let b = self.normal(constant.max(0) as usize, &side(1));
+        Ok(Some((a, b)))
+    }
+
+    /// Proves `False` from the bounds with atoms of its own.
+    /// If that fails and `tighten` is set, it is tried again along with the bounds rounded to the integers.
+    fn refute(&self, bounds: Vec<Bound>, tighten: bool) -> Result<Option<Term>, Error> {
+        let mut lia = Lia {
+            atoms: Vec::new(),
+            ..*self
+        };
+        if let Some(contradiction) = lia.contradiction(bounds.clone())? {
+            return Ok(Some(contradiction));
+        }
+        if !tighten {
+            return Ok(None);
+        }
+        let tightened = self.tightened(&bounds)?;
+        if tightened.is_empty() {
+            return Ok(None);
+        }
+        let mut lia = Lia {
+            atoms: Vec::new(),
+            ..*self
+        };
+        lia.contradiction([bounds, tightened].concat())
+    }
+
+    /// Returns the bounds rounded to the integers, each proven by cases from `bounds`.
+    fn tightened(&self, bounds: &[Bound]) -> Result<Vec<Bound>, Error> {
+        let mut tightened = Vec::new();
+        for (a, b, _) in bounds {
+            let mut lia = Lia {
+                atoms: Vec::new(),
+                ..*self
+            };
+            let Some((a, b)) = lia.tighten(a, b)? else {
+                continue;
+            };
+            if let Some(proof) = self.by_cases(bounds, &a, &b, false)? {
+                tightened.push((a, b, proof));
+            }
+        }
+        Ok(tightened)
+    }
+
+    /// Proves `le a b` from the refutation of `le (S b) a` along with `bounds`, which is the other case of `le_or_lt a b`.
+    fn by_cases(
+        &self,
+        bounds: &[Bound],
+        a: &Term,
+        b: &Term,
+        tighten: bool,
+    ) -> Result<Option<Term>, Error> {
+        let (Some(falsity), Some((or, inl, inr))) = (self.falsity, self.or) else {
+            return Ok(None);
+        };
+        let meta = self.meta;
+        let negation = self.le(self.succ(b.clone()), a.clone());
+        let mut extended: Vec<Bound> = bounds
+            .iter()
+            .map(|(a, b, p)| (a.push(1), b.push(1), p.push(1)))
+            .collect();
+        extended.push((
+            self.succ(b.push(1)),
+            a.push(1),
+            self.term(TermVariant::Var(0)),
+        ));
+        let Some(contradiction) = self.refute(extended, tighten)? else {
+            return Ok(None);
+        };
+        let ident = |name: &str| Ident {
+            span: meta.clone(),
+            name: name.to_owned(),
+        };
+        let refutation = self.term(TermVariant::Abstract(ident("H"), negation, contradiction));
+        let bound = self.le(a.clone(), b.clone());
+        let absurd = MatchArm {
+            meta: meta.clone(),
+            constructor: falsity.to_owned(),
+            params: Vec::new(),
+            body: bound.push(4),
+        };
+        let refuted = self.term(TermVariant::App(
+            refutation.push(3),
+            self.term(TermVariant::Var(0)),
+        ));
+        let ret = MatchArm {
+            meta: meta.clone(),
+            constructor: or.clone(),
+            params: vec![ident("_"); 2],
+            body: bound.push(3),
+        };
+        let arms = vec![
+            MatchArm {
+                meta: meta.clone(),
+                constructor: inl.clone(),
+                params: vec![ident("_"), ident("_"), ident("H")],
+                body: self.term(TermVariant::Var(0)),
+            },
+            MatchArm {
+                meta: meta.clone(),
+                constructor: inr.clone(),
+                params: vec![ident("_"), ident("_"), ident("H")],
+                body: self.term(TermVariant::Match(refuted, ident("_"), absurd, Vec::new())),
+            },
+        ];
+        let cases = self.lemma("le_or_lt", [a.clone(), b.clone()])?;
+        Ok(Some(self.term(TermVariant::Match(
+            cases,
+            ident("_"),
+            ret,
+            arms,
+        ))))
+    }
+}
+
+impl Elaborator<'_> {
+    /// Returns the natural numbers and the relations on them which are registered, if they have the expected shapes.
+    fn registered_nat(&self) -> Option<Nat> {
+        let inductive = |role: &str| match self.global.get_entry(self.env.registered(role)?) {
+            Some(GEntryRef::Inductive(i, params, bodies)) => Some((i, params, bodies)),
+            _ => None,
+        };
+        let (i, params, bodies) = inductive("nat")?;
+        let (zero, succ) = match &bodies[i].constructors[..] {
+            [zero, succ]
+                if params.is_empty()
+                    && bodies[i].arity.is_empty()
+                    && zero.arity.is_empty()
+                    && succ.arity.len() == 1 =>
+            {
+                (zero.name.clone(), succ.name.clone())
+            }
+            _ => return None,
+        };
+        let (i, params, bodies) = inductive("le")?;
+        let (le_n, le_s) = match &bodies[i].constructors[..] {
+            [le_n, le_s]
+                if params.len() == 1
+                    && bodies[i].arity.len() == 1
+                    && le_n.arity.is_empty()
+                    && le_s.arity.len() == 2 =>
+            {
+                (le_n.name.clone(), le_s.name.clone())
+            }
+            _ => return None,
+        };
+        Some(Nat {
+            nat: self.env.registered("nat")?.to_owned(),
+            zero,
+            succ,
+            plus: self.env.registered("plus")?.to_owned(),
+            le: self.env.registered("le")?.to_owned(),
+            lt: self.env.registered("lt").map(str::to_owned),
+            le_n,
+            le_s,
+        })
+    }
+
+    /// Proves `goal`, a `le`, `lt` or `eq nat`, by linear arithmetic over the bounds among its hypotheses,
+    /// using Fourier–Motzkin elimination to find a contradiction with the negation of the goal.
+    /// `lt n m` has to unfold to `le (S n) m`, as the proofs use them interchangeably.
+    pub(super) fn lia(&mut self, goal: usize, meta: &Span) -> Result<Vec<usize>, Error> {
+        let Some(nat) = self.registered_nat() else {
+            return Err(self.failure(
+                goal,
+                meta,
+                "No natural numbers are registered as `nat`, along with `plus` and `le`",
+            ));
+        };
+        let Some(eq) = self.registered_equality(meta) else {
+            return Err(self.failure(goal, meta, "No equality is registered as `eq`"));
+        };
+        let decl = self.evars.get(goal).unwrap().clone();
+        let falsity = self.env.registered("False");
+        let lemmas = |name: &str| match self.env.registered(name) {
+            Some(lemma) => Ok(term(meta, TermVariant::Const(lemma.to_owned()))),
+            None => Err(self.failure(
+                goal,
+                meta,
+                &format!(
+                    "lia needs a lemma registered as `{name}`, it uses the lemmas registered as {}",
+                    LEMMAS.map(|lemma| format!("`{lemma}`")).join(", ")
+                ),
+            )),
+        };
+        let or = self.registered_or();
+        let lia = Lia {
+            nat: &nat,
+            eq: &eq,
+            meta,
+            lemmas: &lemmas,
+            falsity,
+            or: or.as_ref(),
+            atoms: Vec::new(),
+        };
+
+        let mut local = decl.context.clone();
+        let mut bounds = Vec::new();
+        for (x, entry) in decl.context.iter().enumerate() {
+            let var = term(meta, TermVariant::Var(x));
+            let ttype = entry.ttype.push(x + 1).zonk(&self.evars);
+            let mut found = lia.bounds(&ttype, var.clone());
+            if found.is_empty() {
+                let ttype = self.normalize(meta, &ttype, &mut local)?;
+                found = lia.bounds(&ttype, var);
+            }
+            bounds.extend(found);
+        }
+
+        let ttype = decl.ttype.zonk(&self.evars);
+        let is_false =
+            |t: &Term| matches!((&*t.variant, falsity), (TermVariant::Const(x), Some(f)) if x == f);
+        let mut target = ttype.clone();
+        if !is_false(&target) && lia.bound(&target).is_none() && lia.equality(&target).is_none() {
+            target = self.normalize(meta, &ttype, &mut local)?;
+        }
+        let arithmetic = lia.bound(&target).is_some() || lia.equality(&target).is_some();
+        if arithmetic && (falsity.is_none() || or.is_none()) {
+            return Err(self.failure(
+                goal,
+                meta,
+                "lia needs `False` and `or` to be registered to prove a bound",
+            ));
+        }
+        let by_cases = |a: &Term, b: &Term| lia.by_cases(&bounds, a, b, true);
+
+        let value = if is_false(&target) {
+            lia.refute(bounds.clone(), true)?
+        } else if let Some((a, b)) = lia.bound(&target) {
+            by_cases(&a, &b)?
+        } else if let Some((a, b)) = lia.equality(&target) {
+            match (by_cases(&a, &b)?, by_cases(&b, &a)?) {
+                (Some(ab), Some(ba)) => Some(lia.lemma("le_antisym", [a, b, ab, ba])?),
+                _ => None,
+            }
+        } else {
+            // A goal which is not arithmetic is proven from contradictory hypotheses
+            match (lia.refute(bounds.clone(), true)?, falsity) {
+                (Some(contradiction), Some(falsity)) => {
+                    let ret = MatchArm {
+                        meta: meta.clone(),
+                        constructor: falsity.to_owned(),
+                        params: Vec::new(),
+                        body: ttype.push(1),
+                    };
+                    let underscore = Ident {
+                        span: meta.clone(),
+                        name: "_".to_owned(),
+                    };
+                    Some(term(
+                        meta,
+                        TermVariant::Match(contradiction, underscore, ret, Vec::new()),
+                    ))
+                }
+                _ => None,
+            }
+        };
+        let Some(value) = value else {
+            return Err(self.failure(
+                goal,
+                meta,
+                "lia failed to prove the goal, only the bounds among the hypotheses are rounded to the integers",
+            ));
+        };
+
+        let evars = self.evars.clone();
+        let unifier = self.unifier.clone();
+        let mut local = decl.context.clone();
+        let checked = value
+            .type_check(self.global, &self.evars, &mut local)
+            .and_then(|ttype| self.subtype(meta, &mut local, &ttype, &decl.ttype));
+        if checked.is_err() {
+            self.evars = evars;
+            self.unifier = unifier;
+            return Err(self.failure(
+                goal,
+                meta,
+                "The proof found by lia is not accepted by the kernel",
+            ));
+        }
+        self.evars
+            .solve(goal, value)
+            .map_err(|err| (meta.clone(), err))?;
+        Ok(Vec::new())
+    }
+
+    /// Returns the inductive type registered as `or` along with its left and right constructors.
+    fn registered_or(&self) -> Option<(String, String, String)> {
+        let name = self.env.registered("or")?;
+        let Some(GEntryRef::Inductive(i, _, bodies)) = self.global.get_entry(name) else {
+            return None;
+        };
+        match &bodies[i].constructors[..] {
+            [inl, inr] => Some((name.to_owned(), inl.name.clone(), inr.name.clone())),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(coefficient: i64, constant: i64, bound: usize) -> Row {
+        let mut multipliers = vec![0; 3];
+        multipliers[bound] = 1;
+        Row {
+            coefficients: vec![coefficient],
+            constant,
+            multipliers,
+        }
+    }
+
+    #[test]
+    fn large_coefficients() {
+        // `x <= 1` and `2x >= 3` combine into `0 <= -1`
+        assert_eq!(
+            refute(vec![row(-1, 1, 0), row(2, -3, 1)], 1),
+            Some(vec![2, 1, 0])
+        );
+        // Combining the same bounds scaled by a coefficient that large does not fit in an `i64`
+        let large = i64::MAX / 2;
+        let rows = vec![row(-large, large, 0), row(large - 1, -large, 1)];
+        assert_eq!(refute(rows, 1), None);
+    }
+}
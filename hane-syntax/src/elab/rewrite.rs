@@ -0,0 +1,199 @@
+use hane_kernel::{entry::Entry, global::GEntryRef, term::grow, TypeError, TypeErrorVariant};
+
+use super::{term, Elaborator, Error, Local};
+use crate::{
+    lower::lowered::{MatchArm, Term, TermVariant},
+    Ident,
+};
+
+/// Moves `t` under `bound` new binders, replacing the occurrences of `u` by the variable `var` among them.
+fn abstract_occurrences(t: &Term, u: &Term, var: usize, bound: usize) -> Term {
+    replace(&t.push(bound), &u.push(bound), var)
+}
+
+fn replace(t: &Term, u: &Term, var: usize) -> Term {
+    grow(|| {
+        if t == u {
+            return term(&t.meta, TermVariant::Var(var));
+        }
+        let under = |t: &Term, n: usize| replace(t, &u.push(n), var + n);
+        let variant = match &*t.variant {
+            TermVariant::Sort(_) | TermVariant::Var(_) | TermVariant::Const(_) => return t.clone(),
+            TermVariant::App(f, v) => TermVariant::App(replace(f, u, var), replace(v, u, var)),
+            TermVariant::Product(x, t1, t2) => {
+                TermVariant::Product(x.clone(), replace(t1, u, var), under(t2, 1))
+            }
+            TermVariant::Abstract(x, t1, t2) => {
+                TermVariant::Abstract(x.clone(), replace(t1, u, var), under(t2, 1))
+            }
+            TermVariant::Bind(x, t1, t2, t3) => TermVariant::Bind(
+                x.clone(),
+                replace(t1, u, var),
+                replace(t2, u, var),
+                under(t3, 1),
+            ),
+            TermVariant::Match(t, name, ret, arms) => {
+                let arm = |arm: &MatchArm, n: usize| MatchArm {
+                    body: under(&arm.body, n),
+                    ..arm.clone()
+                };
+                TermVariant::Match(
+                    replace(t, u, var),
+                    name.clone(),
+                    arm(ret, ret.params.len() + 1),
+                    arms.iter().map(|a| arm(a, a.params.len())).collect(),
+                )
+            }
+            TermVariant::Evar(id, subst) => {
+                TermVariant::Evar(*id, subst.iter().map(|t| replace(t, u, var)).collect())
+            }
+            TermVariant::Rewrite(reverse, eq, t) => {
+                TermVariant::Rewrite(*reverse, replace(eq, u, var), replace(t, u, var))
+            }
+        };
+        term(&t.meta, variant)
+    })
+}
+
+impl Elaborator<'_> {
+    /// Elaborates the rewrite `t` against `expected`, returning it along with its type.
+    /// The rewrite is a match on the equality, an inductive type like `eq` whose last parameter is the left-hand side
+    /// and whose index is the right-hand side, with a motive abstracting the rewritten side of `expected`.
+    ///
+    /// Without an expected type the motive cannot abstract anything, so the rewritten term gets a new type.
+    /// With one, the side of the equality which is rewritten has to occur in it.
+    pub(super) fn rewrite(
+        &mut self,
+        t: &Term,
+        expected: Option<&Term>,
+        local: &mut Local,
+    ) -> Result<(Term, Term), Error> {
+        let TermVariant::Rewrite(reverse, eq, inner) = &*t.variant else {
+            unreachable!()
+        };
+        let reverse = *reverse;
+        let name = Ident {
+            span: eq.meta.clone(),
+            name: "_".to_owned(),
+        };
+        let (eq, eq_type) = self.infer(eq, local)?;
+        let norm = self.normalize(&eq.meta, &eq_type, local)?;
+        let (hd, args) = norm.strip_args();
+        let global = self.global;
+        let inductive = match &*hd.variant {
+            TermVariant::Const(ind) => match global.get_entry(ind) {
+                Some(GEntryRef::Inductive(i, params, bodies)) => Some((ind, params, &bodies[i])),
+                _ => None,
+            },
+            _ => None,
+        };
+        let var = |x| term(&t.meta, TermVariant::Var(x));
+        // The only constructor has to make the index equal to the last parameter
+        let equality = inductive.and_then(|(ind, params, body)| match &body.constructors[..] {
+            [refl]
+                if !params.is_empty()
+                    && body.arity.len() == 1
+                    && args.len() == params.len() + 1
+                    && refl.arity.is_empty()
+                    && refl.args[params.len()] == var(0) =>
+            {
+                Some((ind, params, body, refl))
+            }
+            _ => None,
+        });
+        let Some((ind, params, body, refl)) = equality else {
+            return Err((
+                eq.meta.clone(),
+                TypeError::new(
+                    local,
+                    TypeErrorVariant::NotAnEquality(eq_type.zonk(&self.evars)),
+                ),
+            ));
+        };
+        let k = params.len();
+        let lhs = args[k - 1].zonk(&self.evars);
+        let rhs = args[k].zonk(&self.evars);
+
+        let expected = match expected {
+            Some(expected) => {
+                let expected = expected.zonk(&self.evars);
+                let side = if reverse { &rhs } else { &lhs };
+                if abstract_occurrences(&expected, side, 0, 1) == expected.push(1) {
+                    return Err((
+                        t.meta.clone(),
+                        TypeError::new(
+                            local,
+                            TypeErrorVariant::NothingToRewrite(side.clone(), expected),
+                        ),
+                    ));
+                }
+                expected
+            }
+            None => self.fresh_type(&t.meta, local),
+        };
+        // The return clause binds the parameters, the index and the equality, and the arm binds the parameters
+        let bound = k + 2;
+        let ret_params: Vec<Ident> = params
+            .iter()
+            .chain(&body.arity)
+            .map(|param| param.x.clone())
+            .collect();
+        let arm_params = ret_params[..k].to_vec();
+
+        let (ret_body, arm_body, rewritten) = if reverse {
+            let motive = abstract_occurrences(&expected, &rhs, 1, bound);
+            let arm_type = abstract_occurrences(&expected, &rhs, 0, 1).subst_single(0, &lhs);
+            let mut local = local.slot();
+            local.extend(
+                params
+                    .iter()
+                    .zip(&args)
+                    .enumerate()
+                    .map(|(i, (param, value))| {
+                        Entry::with_value(param.x.clone(), value.push(i), param.ttype.clone())
+                    }),
+            );
+            let arm_body = self.check(&inner.push(k), &arm_type.push(k), &mut local)?;
+            (motive, arm_body, None)
+        } else {
+            let motive = term(
+                &t.meta,
+                TermVariant::Product(
+                    name.clone(),
+                    abstract_occurrences(&expected, &lhs, 1, bound),
+                    expected.push(bound + 1),
+                ),
+            );
+            let x = Ident {
+                span: t.meta.clone(),
+                name: "H".to_owned(),
+            };
+            let identity = term(&t.meta, TermVariant::Abstract(x, expected.push(k), var(0)));
+            let t_type = abstract_occurrences(&expected, &lhs, 0, 1).subst_single(0, &rhs);
+            let rewritten = self.check(inner, &t_type, local)?;
+            (motive, identity, Some(rewritten))
+        };
+
+        let ret = MatchArm {
+            meta: t.meta.clone(),
+            constructor: ind.clone(),
+            params: ret_params,
+            body: ret_body,
+        };
+        let arm = MatchArm {
+            meta: inner.meta.clone(),
+            constructor: refl.name.clone(),
+            params: arm_params,
+            body: arm_body,
+        };
+        let elab = term(
+            &t.meta,
+            TermVariant::Match(eq, name.clone(), ret, vec![arm]),
+        );
+        let elab = match rewritten {
+            Some(rewritten) => term(&t.meta, TermVariant::App(elab, rewritten)),
+            None => elab,
+        };
+        Ok((elab, expected))
+    }
+}
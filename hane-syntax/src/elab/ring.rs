@@ -0,0 +1,262 @@
+use hane_kernel::Sort;
+
+use super::{tactic::applied, term, Elaborator, Error};
+use crate::{
+    env::Ring,
+    lower::lowered::{Term, TermVariant},
+    Ident, Span,
+};
+
+/// A product of atoms, given by their indices in increasing order.
+type Monomial = Vec<usize>;
+
+/// A sum of monomials in increasing order, in which a monomial is repeated instead of given a coefficient.
+type Polynomial = Vec<Monomial>;
+
+/// Reifies terms of the ring into expressions of its library, computing the normal forms the library finds for them
+/// along with the fuel it needs to find them.
+struct Reifier<'r> {
+    ring: &'r Ring,
+    meta: &'r Span,
+    /// The normal forms of zero and one, which replace them in goals when they are definitions
+    zero: Term,
+    one: Term,
+    /// The subterms which are not built from the operations of the ring, indexed by when they were first found
+    atoms: Vec<Term>,
+    /// The largest degree of a monomial, bounding the comparisons of monomials
+    degree: usize,
+    /// The deepest recursion of a normalization
+    depth: usize,
+}
+
+impl Reifier<'_> {
+    fn term(&self, variant: TermVariant) -> Term {
+        term(self.meta, variant)
+    }
+
+    fn constant(&self, name: &str) -> Term {
+        self.term(TermVariant::Const(name.to_owned()))
+    }
+
+    /// The definition `name_suffix` of the library.
+    fn library(&self, suffix: &str) -> Term {
+        self.constant(&format!("{}_{suffix}", self.ring.name))
+    }
+
+    /// The operation `name` of the expression being reified, applied to its type family and its operations.
+    fn operation(&self, name: &str) -> Term {
+        let (x, a) = (
+            self.term(TermVariant::Var(1)),
+            self.term(TermVariant::Var(0)),
+        );
+        applied(self.library(name), [x, a])
+    }
+
+    fn atom(&mut self, t: &Term) -> usize {
+        match self.atoms.iter().position(|atom| atom == t) {
+            Some(i) => i,
+            None => {
+                self.atoms.push(t.clone());
+                self.atoms.len() - 1
+            }
+        }
+    }
+
+    /// The fuel numeral allowing at least `n` recursions, as `fuel_double` applied to `fuel_one`.
+    fn fuel(&self, n: usize) -> Term {
+        let doublings = n.max(1).next_power_of_two().trailing_zeros();
+        (0..doublings).fold(self.library("fuel_one"), |fuel, _| {
+            applied(self.library("fuel_double"), [fuel])
+        })
+    }
+
+    /// Merges `p` and `q`, recursing once for each of their monomials.
+    fn add(&mut self, p: &[Monomial], q: &[Monomial]) -> Polynomial {
+        self.depth = self.depth.max(p.len() + q.len() + 1);
+        let mut r = [p, q].concat();
+        r.sort();
+        r
+    }
+
+    /// Multiplies `p` and `q`, recursing once for each monomial of `p`, then once for each monomial of `q`
+    /// to insert its product with the monomial of `p` into the products found so far,
+    /// where each product recurses once for each of its atoms.
+    fn mul(&mut self, p: &[Monomial], q: &[Monomial]) -> Polynomial {
+        let mut r: Polynomial = Vec::new();
+        let mut depth = p.len().max(q.len()) + 1;
+        for m in p {
+            for n in q {
+                depth = depth.max(m.len() + n.len() + 1).max(r.len() + 2);
+                let mut k = [&m[..], &n[..]].concat();
+                k.sort_unstable();
+                self.degree = self.degree.max(k.len());
+                r.push(k);
+            }
+        }
+        self.depth = self.depth.max(depth);
+        r.sort();
+        r
+    }
+
+    /// Reifies `t`, which is in the context of the goal, into the body of an expression under its type family `X`
+    /// and its operations `A`, returning its normal form as the difference of two polynomials.
+    /// The second polynomial is always empty in a semiring.
+    fn reify(&mut self, t: &Term) -> (Term, Polynomial, Polynomial) {
+        let (head, args) = t.strip_args_ref();
+        match (&*head.variant, &args[..]) {
+            (TermVariant::Const(c), []) if *c == self.ring.zero => {
+                (self.operation("zero"), Vec::new(), Vec::new())
+            }
+            _ if *t == self.zero => (self.operation("zero"), Vec::new(), Vec::new()),
+            _ if *t == self.one => (self.operation("one"), vec![Vec::new()], Vec::new()),
+            (TermVariant::Const(c), []) if *c == self.ring.one => {
+                (self.operation("one"), vec![Vec::new()], Vec::new())
+            }
+            (TermVariant::Const(c), [u, v]) if *c == self.ring.add || *c == self.ring.mul => {
+                let (eu, pu, nu) = self.reify(u);
+                let (ev, pv, nv) = self.reify(v);
+                let e = applied(
+                    self.operation(if *c == self.ring.add { "add" } else { "mul" }),
+                    [u.push(2), v.push(2), eu, ev],
+                );
+                if *c == self.ring.add {
+                    let (p, n) = (self.add(&pu, &pv), self.add(&nu, &nv));
+                    (e, p, n)
+                } else if self.ring.opp.is_some() {
+                    // `(pu - nu) * (pv - nv) = (pu * pv + nu * nv) - (pu * nv + nu * pv)`
+                    let (pp, nn) = (self.mul(&pu, &pv), self.mul(&nu, &nv));
+                    let (pn, np) = (self.mul(&pu, &nv), self.mul(&nu, &pv));
+                    let (p, n) = (self.add(&pp, &nn), self.add(&pn, &np));
+                    (e, p, n)
+                } else {
+                    (e, self.mul(&pu, &pv), Vec::new())
+                }
+            }
+            (TermVariant::Const(c), [u]) if Some(c) == self.ring.opp.as_ref() => {
+                let (eu, pu, nu) = self.reify(u);
+                (applied(self.operation("opp"), [u.push(2), eu]), nu, pu)
+            }
+            _ => {
+                let i = self.atom(t);
+                let index = (0..i).fold(self.library("idx_O"), |index, _| {
+                    applied(self.library("idx_S"), [index])
+                });
+                self.degree = self.degree.max(1);
+                (
+                    applied(self.operation("atom"), [index, t.push(2)]),
+                    vec![vec![i]],
+                    Vec::new(),
+                )
+            }
+        }
+    }
+
+    /// The expression `fun (X : carrier -> Type{0}) (A : alg X) => e` of the body `e` found by `reify`.
+    fn expression(&self, e: Term) -> Term {
+        let ident = |name: &str| Ident {
+            span: self.meta.clone(),
+            name: name.to_owned(),
+        };
+        let family = self.term(TermVariant::Product(
+            ident("x"),
+            self.constant(&self.ring.carrier),
+            self.term(TermVariant::Sort(Sort::Type(0))),
+        ));
+        let alg = applied(self.library("alg"), [self.term(TermVariant::Var(0))]);
+        let body = self.term(TermVariant::Abstract(ident("A"), alg, e));
+        self.term(TermVariant::Abstract(ident("X"), family, body))
+    }
+}
+
+impl Elaborator<'_> {
+    /// Proves an equality between polynomials over the ring declared by `Add Ring` by reflection.
+    /// Both sides are reified into expressions, which the library normalizes into terms `w1` and `w2` along with a proof
+    /// that `w1 = w2` implies the goal.
+    /// The correctness lemma applied to the proof of `w1 = w1` leaves the kernel to check that `w1` and `w2` are convertible,
+    /// so the proof only grows with the size of the goal.
+    ///
+    /// The normal forms are also computed here, to fail without a costly conversion when they differ,
+    /// and to find how much fuel the library needs for the comparisons of atoms and monomials and for the deepest recursion of a normalization.
+    pub(super) fn ring(&mut self, goal: usize, meta: &Span) -> Result<Vec<usize>, Error> {
+        let Some(ring) = self.env.ring() else {
+            return Err(self.failure(goal, meta, "No ring has been declared with `Add Ring`"));
+        };
+        let Some(eq) = self.registered_equality(meta) else {
+            return Err(self.failure(goal, meta, "No equality is registered as `eq`"));
+        };
+        let decl = self.evars.get(goal).unwrap().clone();
+        let mut local = decl.context.clone();
+        let mut normal = |name: &str| {
+            let t = term(meta, TermVariant::Const(name.to_owned()));
+            self.normalize(meta, &t, &mut local)
+        };
+        let (zero, one) = (normal(&ring.zero)?, normal(&ring.one)?);
+        let mut reifier = Reifier {
+            ring,
+            meta,
+            zero,
+            one,
+            atoms: Vec::new(),
+            degree: 0,
+            depth: 0,
+        };
+
+        let carrier = reifier.constant(&ring.carrier);
+        let sides = |t: &Term| match eq.sides(t) {
+            Some((ttype, a, b)) if ttype == carrier => Some((a, b)),
+            _ => None,
+        };
+        let ttype = decl.ttype.zonk(&self.evars);
+        let sides = match sides(&ttype) {
+            Some(sides) => Some(sides),
+            None => sides(&self.normalize(meta, &ttype, &mut local)?),
+        };
+        let Some((a, b)) = sides else {
+            return Err(self.failure(
+                goal,
+                meta,
+                "ring only proves equalities in the declared ring",
+            ));
+        };
+        let (ea, pa, na) = reifier.reify(&a);
+        let (eb, pb, nb) = reifier.reify(&b);
+        // `a + na = pa` and `b + nb = pb`, so the sides are equal when `pa + nb = pb + na`
+        if reifier.add(&pa, &nb) != reifier.add(&pb, &na) {
+            return Err(self.failure(goal, meta, "ring failed to prove the goal, both sides must have the same normal form as sums of monomials"));
+        }
+        let run = applied(
+            reifier.library("run"),
+            [
+                a.clone(),
+                b.clone(),
+                reifier.expression(ea),
+                reifier.expression(eb),
+                reifier.fuel(reifier.atoms.len() + 1),
+                reifier.fuel(reifier.degree + 1),
+                reifier.fuel(reifier.depth),
+            ],
+        );
+        let refl = applied(reifier.library("refl"), [a.clone(), b.clone(), run.clone()]);
+        let value = applied(reifier.constant(&ring.name), [a, b, run, refl]);
+
+        let evars = self.evars.clone();
+        let unifier = self.unifier.clone();
+        let mut local = decl.context.clone();
+        let checked = value
+            .type_check(self.global, &self.evars, &mut local)
+            .and_then(|ttype| self.subtype(meta, &mut local, &ttype, &decl.ttype));
+        if checked.is_err() {
+            self.evars = evars;
+            self.unifier = unifier;
+            return Err(self.failure(
+                goal,
+                meta,
+                "The proof found by ring is not accepted by the kernel",
+            ));
+        }
+        self.evars
+            .solve(goal, value)
+            .map_err(|err| (meta.clone(), err))?;
+        Ok(Vec::new())
+    }
+}
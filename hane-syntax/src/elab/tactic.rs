@@ -0,0 +1,719 @@
+use hane_kernel::{
+    entry::Entry, global::GEntryRef, term::grow, Sort, Stack, TypeError, TypeErrorVariant,
+};
+
+use super::{has_evars, term, Elaborator, Error, Local};
+use crate::{
+    lower::{
+        lowered::{MatchArm, Term, TermVariant},
+        State,
+    },
+    Expr, ExprVariant, Ident, Proof, Span, Tactic, TacticVariant,
+};
+
+/// An inductive type at the head of a type, with what the tactics need to know about it.
+struct Inductive {
+    name: String,
+    params: usize,
+    /// The constructors, with their amounts of arguments besides the parameters
+    constructors: Vec<(String, usize)>,
+    /// The arguments of the inductive type, which are its parameters followed by its indices
+    args: Vec<Term>,
+}
+
+/// Returns the id of the metavariable `t`.
+fn evar_id(t: &Term) -> usize {
+    let TermVariant::Evar(id, _) = &*t.variant else {
+        unreachable!()
+    };
+    *id
+}
+
+/// A hole of a term built by a tactic, which is elaborated to a new metavariable.
+fn hole(meta: &Span) -> Term {
+    term(meta, TermVariant::Evar(0, Vec::new()))
+}
+
+pub(super) fn applied(f: Term, args: impl IntoIterator<Item = Term>) -> Term {
+    args.into_iter().fold(f, |f, v| {
+        let meta = f.meta.clone();
+        term(&meta, TermVariant::App(f, v))
+    })
+}
+
+/// Reduces every application of an abstraction in `t`, leaving its constants and matches as they are.
+fn beta(t: &Term) -> Term {
+    grow(|| {
+        let variant = match &*t.variant {
+            TermVariant::Sort(_) | TermVariant::Var(_) | TermVariant::Const(_) => return t.clone(),
+            TermVariant::App(f, v) => {
+                let f = beta(f);
+                let v = beta(v);
+                if let TermVariant::Abstract(_, _, body) = &*f.variant {
+                    return beta(&body.subst_single(0, &v));
+                }
+                TermVariant::App(f, v)
+            }
+            TermVariant::Product(x, t1, t2) => TermVariant::Product(x.clone(), beta(t1), beta(t2)),
+            TermVariant::Abstract(x, t1, t2) => {
+                TermVariant::Abstract(x.clone(), beta(t1), beta(t2))
+            }
+            TermVariant::Bind(x, t1, t2, t3) => {
+                TermVariant::Bind(x.clone(), beta(t1), beta(t2), beta(t3))
+            }
+            TermVariant::Match(t, name, ret, arms) => {
+                let arm = |arm: &MatchArm| MatchArm {
+                    body: beta(&arm.body),
+                    ..arm.clone()
+                };
+                TermVariant::Match(
+                    beta(t),
+                    name.clone(),
+                    arm(ret),
+                    arms.iter().map(arm).collect(),
+                )
+            }
+            TermVariant::Evar(id, subst) => {
+                TermVariant::Evar(*id, subst.iter().map(beta).collect())
+            }
+            TermVariant::Rewrite(reverse, eq, t) => {
+                TermVariant::Rewrite(*reverse, beta(eq), beta(t))
+            }
+        };
+        term(&t.meta, variant)
+    })
+}
+
+/// Returns a name for a variable introduced in `local`, which is `base` unless a hypothesis is already named that.
+///
+/// Anonymous variables, such as those of arrows, are named `H`.
+fn fresh_name(base: &str, meta: &Span, local: &Local, taken: &[Ident]) -> Ident {
+    let base = if base == "_" { "H" } else { base };
+    let free = |name: &str| {
+        !local.iter().any(|entry| entry.x.name == name) && !taken.iter().any(|x| x.name == name)
+    };
+    let name = if free(base) {
+        base.to_owned()
+    } else {
+        (0..)
+            .map(|i| format!("{base}{i}"))
+            .find(|name| free(name))
+            .unwrap()
+    };
+    Ident {
+        span: meta.clone(),
+        name,
+    }
+}
+
+impl<'a> Elaborator<'a> {
+    /// Runs the tactics of `proof` on `goals`, failing if any goals remain once all of them have been run.
+    /// A goal is an unsolved metavariable, and each tactic replaces the first goal with the holes it leaves unsolved.
+    pub(super) fn prove(&mut self, mut goals: Vec<usize>, proof: &Proof) -> Result<(), Error> {
+        for tactic in &proof.tactics {
+            // Goals may have been solved by unification while proving the goals before them
+            goals.retain(|&goal| self.evars.value(goal).is_none());
+            let Some(&goal) = goals.first() else {
+                return Err((
+                    tactic.span.clone(),
+                    TypeError::new(&Stack::new(), TypeErrorVariant::NoGoals),
+                ));
+            };
+            let new = self.tactic(goal, tactic)?;
+            goals.splice(0..1, new);
+        }
+        goals.retain(|&goal| self.evars.value(goal).is_none());
+        if let Some(&goal) = goals.first() {
+            let decl = self.evars.get(goal).unwrap();
+            return Err((
+                proof.qed.clone(),
+                TypeError::new(
+                    &decl.context,
+                    TypeErrorVariant::UnfinishedProof(goals.len(), decl.ttype.zonk(&self.evars)),
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Runs `tactic` on `goal`, returning the goals which replace it.
+    fn tactic(&mut self, goal: usize, tactic: &Tactic) -> Result<Vec<usize>, Error> {
+        let meta = &tactic.span;
+        match &tactic.variant {
+            TacticVariant::Intro(x) => Ok(vec![self.intro(goal, meta, x.as_ref())?]),
+            TacticVariant::Intros(xs) if xs.is_empty() => {
+                let mut goal = goal;
+                loop {
+                    let ttype = self.evars.get(goal).unwrap().ttype.zonk(&self.evars);
+                    if self.product(&ttype).is_none() {
+                        return Ok(vec![goal]);
+                    }
+                    goal = self.intro(goal, meta, None)?;
+                }
+            }
+            TacticVariant::Intros(xs) => {
+                let mut goal = goal;
+                for x in xs {
+                    goal = self.intro(goal, meta, Some(x))?;
+                }
+                Ok(vec![goal])
+            }
+            TacticVariant::Exact(t) => {
+                let t = self.lower_in(goal, t)?;
+                let goals = self.refine(goal, &t)?;
+                if let Some(&hole) = goals.first() {
+                    let decl = self.evars.get(hole).unwrap();
+                    return Err((
+                        decl.meta.clone(),
+                        TypeError::new(
+                            &decl.context,
+                            TypeErrorVariant::UnsolvedHole(decl.ttype.zonk(&self.evars)),
+                        ),
+                    ));
+                }
+                Ok(goals)
+            }
+            TacticVariant::Apply(f) => self.apply(goal, meta, f),
+            TacticVariant::Destruct(t, names) => self.destruct(goal, meta, t, names.as_deref()),
+            TacticVariant::Induction(x) => self.induction(goal, meta, x),
+            TacticVariant::Reflexivity => {
+                let ind = self.goal_inductive(goal, meta)?;
+                // The constructors without arguments prove the goal if its indices are the same as their own
+                let mut err = None;
+                for (constructor, arity) in ind.constructors {
+                    if arity != 0 {
+                        continue;
+                    }
+                    let evars = self.evars.clone();
+                    let unifier = self.unifier.clone();
+                    let f = term(meta, TermVariant::Const(constructor));
+                    match self.refine(goal, &applied(f, (0..ind.params).map(|_| hole(meta)))) {
+                        Ok(goals) => return Ok(goals),
+                        Err(e) => err = Some(e),
+                    }
+                    self.evars = evars;
+                    self.unifier = unifier;
+                }
+                Err(err.unwrap_or_else(|| {
+                    self.failure(goal, meta, "The goal is not a reflexive relation")
+                }))
+            }
+            TacticVariant::Symmetry => self.symmetry(goal, meta),
+            TacticVariant::Assumption => {
+                let len = self.evars.get(goal).unwrap().context.len();
+                for x in 0..len {
+                    let evars = self.evars.clone();
+                    let unifier = self.unifier.clone();
+                    if let Ok(goals) = self.refine(goal, &term(meta, TermVariant::Var(x))) {
+                        return Ok(goals);
+                    }
+                    self.evars = evars;
+                    self.unifier = unifier;
+                }
+                Err(self.failure(goal, meta, "No hypothesis proves the goal"))
+            }
+            TacticVariant::Tauto => self.tauto(goal, meta),
+            TacticVariant::Congruence => self.congruence(goal, meta),
+            TacticVariant::Lia => self.lia(goal, meta),
+            TacticVariant::Ring => self.ring(goal, meta),
+            TacticVariant::Auto(depth, databases) => {
+                self.auto(goal, meta, *depth, databases, false)
+            }
+            TacticVariant::EAuto(depth, databases) => {
+                self.auto(goal, meta, *depth, databases, true)
+            }
+            TacticVariant::Split => {
+                let ind = self.goal_inductive(goal, meta)?;
+                let [(constructor, arity)] = &ind.constructors[..] else {
+                    return Err(self.failure(
+                        goal,
+                        meta,
+                        "Unable to split a goal whose type does not have exactly one constructor",
+                    ));
+                };
+                let f = term(meta, TermVariant::Const(constructor.clone()));
+                let args = (0..ind.params + arity).map(|_| hole(meta));
+                self.refine(goal, &applied(f, args))
+            }
+        }
+    }
+
+    /// Returns an error for `tactic` failing on `goal` for the given reason.
+    pub(super) fn failure(&self, goal: usize, meta: &Span, reason: &str) -> Error {
+        let decl = self.evars.get(goal).unwrap();
+        (
+            meta.clone(),
+            TypeError::new(
+                &decl.context,
+                TypeErrorVariant::TacticFailed(reason.to_owned(), decl.ttype.zonk(&self.evars)),
+            ),
+        )
+    }
+
+    /// Lowers `t` in the context of `goal`, where it refers to the hypotheses by name.
+    fn lower_in(&self, goal: usize, t: &Expr) -> Result<Term, Error> {
+        let decl = self.evars.get(goal).unwrap();
+        let mut names: Stack<Ident> = decl
+            .context
+            .iter()
+            .rev()
+            .map(|entry| entry.x.clone())
+            .collect();
+        t.clone()
+            .lower(self.env, &mut names, &mut State::default())
+            .map_err(|err| {
+                (
+                    err.span,
+                    TypeError::new(
+                        &decl.context,
+                        TypeErrorVariant::TacticFailed(
+                            err.err.to_string(),
+                            decl.ttype.zonk(&self.evars),
+                        ),
+                    ),
+                )
+            })
+    }
+
+    /// Solves `goal` with `t`, which is elaborated against the type of the goal,
+    /// and returns the holes of `t` which are left unsolved as the new goals.
+    ///
+    /// Holes whose type is a class are left to instance resolution instead of becoming goals.
+    fn refine(&mut self, goal: usize, t: &Term) -> Result<Vec<usize>, Error> {
+        let decl = self.evars.get(goal).unwrap().clone();
+        let first = self.evars.len();
+        let mut local = decl.context.clone();
+        let value = self.check(t, &decl.ttype, &mut local)?;
+        self.evars
+            .solve(goal, value)
+            .map_err(|err| (t.meta.clone(), err))?;
+        let goals: Vec<_> = self
+            .evars
+            .unsolved()
+            .filter(|&id| id >= first)
+            .filter(|&id| self.class_of(&self.evars.get(id).unwrap().ttype).is_none())
+            .collect();
+        Ok(goals.into_iter().map(|id| self.simplify(id)).collect())
+    }
+
+    /// Replaces `goal` by a goal whose type has no applications of abstractions, such as those left by instantiating a motive.
+    fn simplify(&mut self, goal: usize) -> usize {
+        let decl = self.evars.get(goal).unwrap().clone();
+        let ttype = decl.ttype.zonk(&self.evars);
+        let reduced = beta(&ttype);
+        if reduced == ttype {
+            return goal;
+        }
+        let new = self.fresh(&decl.meta, reduced, &decl.context);
+        let id = evar_id(&new);
+        // The new goal is distinct from the old one, which cannot occur in its own solution
+        let _ = self.evars.solve(goal, new);
+        id
+    }
+
+    /// Unfolds the definitions at the head of `ttype` until it is a product, returning its binder, input and output.
+    pub(super) fn product(&self, ttype: &Term) -> Option<(Ident, Term, Term)> {
+        let mut t = ttype.clone();
+        loop {
+            if let TermVariant::Product(x, input, output) = &*t.variant {
+                return Some((x.clone(), input.clone(), output.clone()));
+            }
+            let (head, args) = t.strip_args_ref();
+            let value = match &*head.variant {
+                TermVariant::Const(name) => self.global.get(name)?.value?.clone(),
+                TermVariant::Abstract(_, _, _) if !args.is_empty() => head.clone(),
+                _ => return None,
+            };
+            let unfolded = beta(&applied(value, args.into_iter().cloned()));
+            t = unfolded;
+        }
+    }
+
+    /// Introduces the variable of the product which is the type of `goal`, returning the goal for its body.
+    pub(super) fn intro(
+        &mut self,
+        goal: usize,
+        meta: &Span,
+        x: Option<&Ident>,
+    ) -> Result<usize, Error> {
+        let decl = self.evars.get(goal).unwrap().clone();
+        let ttype = decl.ttype.zonk(&self.evars);
+        let Some((y, input, output)) = self.product(&ttype) else {
+            return Err(self.failure(
+                goal,
+                meta,
+                "Unable to introduce a variable, the goal is not a product",
+            ));
+        };
+        let x = match x {
+            Some(x) => x.clone(),
+            None => fresh_name(&y.name, meta, &decl.context, &[]),
+        };
+        let mut local = decl.context.clone();
+        let body = {
+            let local = local.push(Entry::new(x.clone(), input.clone()));
+            self.fresh(meta, output, &local)
+        };
+        let id = evar_id(&body);
+        self.evars
+            .solve(goal, term(meta, TermVariant::Abstract(x, input, body)))
+            .map_err(|err| (meta.clone(), err))?;
+        Ok(id)
+    }
+
+    /// Returns the inductive type of `goal`.
+    fn goal_inductive(&mut self, goal: usize, meta: &Span) -> Result<Inductive, Error> {
+        let decl = self.evars.get(goal).unwrap().clone();
+        let mut local = decl.context.clone();
+        match self.inductive(meta, &decl.ttype, &mut local)? {
+            Some(found) => Ok(found),
+            None => Err(self.failure(goal, meta, "The goal is not an inductive type")),
+        }
+    }
+
+    /// Returns the inductive type at the head of `ttype`, which is only reduced if it is not one as it is written.
+    fn inductive(
+        &self,
+        meta: &Span,
+        ttype: &Term,
+        local: &mut Local,
+    ) -> Result<Option<Inductive>, Error> {
+        let as_inductive = |t: Term| {
+            let (head, args) = t.strip_args();
+            let TermVariant::Const(name) = &*head.variant else {
+                return None;
+            };
+            let Some(GEntryRef::Inductive(i, params, bodies)) = self.global.get_entry(name) else {
+                return None;
+            };
+            let constructors = bodies[i]
+                .constructors
+                .iter()
+                .map(|c| (c.name.clone(), c.arity.len()))
+                .collect();
+            Some(Inductive {
+                name: name.clone(),
+                params: params.len(),
+                constructors,
+                args,
+            })
+        };
+        let ttype = ttype.zonk(&self.evars);
+        if let Some(found) = as_inductive(ttype.clone()) {
+            return Ok(Some(found));
+        }
+        Ok(as_inductive(self.normalize(meta, &ttype, local)?))
+    }
+
+    fn apply(&mut self, goal: usize, meta: &Span, f: &Expr) -> Result<Vec<usize>, Error> {
+        let f = self.lower_in(goal, f)?;
+        self.apply_term(goal, meta, &f)
+    }
+
+    /// Applies `f` to as few new holes as it takes for its type to prove `goal`, returning the holes which are not solved by unification.
+    pub(super) fn apply_term(
+        &mut self,
+        goal: usize,
+        meta: &Span,
+        f: &Term,
+    ) -> Result<Vec<usize>, Error> {
+        let evars = self.evars.clone();
+        let unifier = self.unifier.clone();
+        let mut local = self.evars.get(goal).unwrap().context.clone();
+        let (_, mut ttype) = self.infer(f, &mut local)?;
+        let mut arity = 0;
+        while let Some((_, _, output)) = self.product(&ttype.zonk(&self.evars)) {
+            ttype = output;
+            arity += 1;
+        }
+        self.evars = evars;
+        self.unifier = unifier;
+
+        let mut err = None;
+        for args in 0..=arity {
+            let evars = self.evars.clone();
+            let unifier = self.unifier.clone();
+            match self.refine(goal, &applied(f.clone(), (0..args).map(|_| hole(meta)))) {
+                Ok(goals) => return Ok(goals),
+                Err(e) => err = Some(e),
+            }
+            self.evars = evars;
+            self.unifier = unifier;
+        }
+        Err(err.unwrap())
+    }
+
+    /// Matches on `t`, leaving a goal for each of its constructors with their arguments introduced,
+    /// named by `names` if they are given.
+    fn destruct(
+        &mut self,
+        goal: usize,
+        meta: &Span,
+        t: &Expr,
+        names: Option<&[Vec<Ident>]>,
+    ) -> Result<Vec<usize>, Error> {
+        let t = self.lower_in(goal, t)?;
+        let decl = self.evars.get(goal).unwrap().clone();
+        let evars = self.evars.clone();
+        let unifier = self.unifier.clone();
+        let mut local = decl.context.clone();
+        let (_, ttype) = self.infer(&t, &mut local)?;
+        let found = self.inductive(meta, &ttype, &mut local)?;
+        self.evars = evars;
+        self.unifier = unifier;
+        let Some(ind) = found else {
+            return Err(self.failure(
+                goal,
+                meta,
+                "Unable to destruct a term whose type is not an inductive type",
+            ));
+        };
+        let Some(GEntryRef::Inductive(i, params, bodies)) = self.global.get_entry(&ind.name) else {
+            unreachable!()
+        };
+        if let Some(names) = names {
+            if names.len() != ind.constructors.len()
+                || names
+                    .iter()
+                    .zip(&ind.constructors)
+                    .any(|(names, (_, arity))| names.len() != *arity)
+            {
+                return Err(self.failure(
+                    goal,
+                    meta,
+                    &format!(
+                        "The pattern does not name the arguments of the constructors of `{}`",
+                        ind.name
+                    ),
+                ));
+            }
+        }
+        let underscore = Ident {
+            span: meta.clone(),
+            name: "_".to_owned(),
+        };
+        // The parameters are bound by the arms too, which is where the `_` come from
+        let arms = bodies[i]
+            .constructors
+            .iter()
+            .enumerate()
+            .map(|(j, c)| {
+                let mut args: Vec<Ident> = Vec::with_capacity(c.arity.len());
+                for (k, binder) in c.arity.iter().enumerate() {
+                    let x = match names {
+                        Some(names) => names[j][k].clone(),
+                        None => fresh_name(
+                            &self.argument_name(&binder.x.name, &binder.ttype),
+                            meta,
+                            &decl.context,
+                            &args,
+                        ),
+                    };
+                    args.push(x);
+                }
+                MatchArm {
+                    meta: meta.clone(),
+                    constructor: c.name.clone(),
+                    params: params
+                        .iter()
+                        .map(|_| underscore.clone())
+                        .chain(args)
+                        .collect(),
+                    body: hole(meta),
+                }
+            })
+            .collect();
+        // Without an `in` clause the elaborator finds the return type by generalizing the goal
+        let ret = MatchArm {
+            meta: meta.clone(),
+            constructor: String::new(),
+            params: Vec::new(),
+            body: hole(meta),
+        };
+        let t = term(meta, TermVariant::Match(t, underscore, ret, arms));
+        self.refine(goal, &t)
+    }
+
+    /// Returns the name to give an argument of a constructor, which is `x` unless it is anonymous,
+    /// in which case it is the initial of its type if that is an inductive type which is not a proposition, such as `n` for `nat`.
+    fn argument_name(&self, x: &str, ttype: &Term) -> String {
+        if x != "_" {
+            return x.to_owned();
+        }
+        if let TermVariant::Const(name) = &*ttype.app_head().variant {
+            if let Some(GEntryRef::Inductive(i, _, bodies)) = self.global.get_entry(name) {
+                if bodies[i].sort != Sort::Prop {
+                    if let Some(initial) = name.chars().next() {
+                        return initial.to_lowercase().collect();
+                    }
+                }
+            }
+        }
+        "H".to_owned()
+    }
+
+    /// Proves `goal` by the induction principle `I_ind` of the inductive type `I` of the variable `x`,
+    /// leaving a goal for each case of the principle.
+    /// The principle is not generated, so it has to be declared along with the inductive type.
+    /// The hypotheses which depend on `x` are generalized first, so they are quantified in the induction hypotheses.
+    fn induction(&mut self, goal: usize, meta: &Span, x: &Ident) -> Result<Vec<usize>, Error> {
+        let var = Expr {
+            span: x.span.clone(),
+            variant: Box::new(ExprVariant::Var(x.name.clone())),
+        };
+        let n = match self.lower_in(goal, &var)?.into_variant() {
+            TermVariant::Var(n) => n,
+            _ => {
+                return Err(self.failure(
+                    goal,
+                    meta,
+                    &format!(
+                        "Unable to do induction on `{}`, which is not a hypothesis",
+                        x.name
+                    ),
+                ))
+            }
+        };
+        let decl = self.evars.get(goal).unwrap().clone();
+        let ttype = decl.context.get(n).unwrap().ttype.push(n + 1);
+        let mut local = decl.context.clone();
+        let Some(ind) = self.inductive(meta, &ttype, &mut local)? else {
+            return Err(self.failure(
+                goal,
+                meta,
+                &format!(
+                    "Unable to do induction on `{}`, whose type is not an inductive type",
+                    x.name
+                ),
+            ));
+        };
+        let principle = format!("{}_ind", ind.name);
+        let Some(entry) = self.global.get(&principle) else {
+            return Err(self.failure(
+                goal,
+                meta,
+                &format!(
+                    "No induction principle `{principle}` is defined for `{}`, an eliminator of that name must be provided",
+                    ind.name
+                ),
+            ));
+        };
+        let (products, _) = entry.ttype.clone().strip_products();
+        let goal_type = decl.ttype.zonk(&self.evars);
+        if products.len() < ind.params + 2 || has_evars(&goal_type) {
+            return Err(self.failure(
+                goal,
+                meta,
+                &format!("Unable to do induction with `{principle}` on this goal"),
+            ));
+        }
+        let cases = products.len() - ind.params - 2;
+
+        // The hypotheses introduced after the variable which depend on it, or on one which does, oldest first
+        let mut generalized = vec![n];
+        for y in (0..n).rev() {
+            let entry = decl.context.get(y).unwrap();
+            let ttype = entry.ttype.push(y + 1);
+            if entry.value.is_none() && generalized.iter().any(|&z| ttype.pop(z).is_none()) {
+                generalized.push(y);
+            }
+        }
+        // Moves a term into the context where the first `bound` generalized variables are bound last
+        let abstracted = |t: &Term, bound: usize| {
+            t.subst(|meta, y, push| {
+                let y = if y < push {
+                    y
+                } else {
+                    match generalized[..bound].iter().position(|&z| z == y - push) {
+                        Some(l) => bound - 1 - l + push,
+                        None => y + bound,
+                    }
+                };
+                term(meta, TermVariant::Var(y))
+            })
+        };
+
+        // The motive is the goal with the variable abstracted, quantified over the generalized hypotheses
+        let body = (1..generalized.len()).rev().fold(
+            abstracted(&goal_type, generalized.len()),
+            |body, l| {
+                let y = generalized[l];
+                let entry = decl.context.get(y).unwrap();
+                let ttype = abstracted(&entry.ttype.push(y + 1), l);
+                term(meta, TermVariant::Product(entry.x.clone(), ttype, body))
+            },
+        );
+        let motive = term(meta, TermVariant::Abstract(x.clone(), hole(meta), body));
+        let f = term(meta, TermVariant::Const(principle));
+        let args = (0..ind.params)
+            .map(|_| hole(meta))
+            .chain([motive])
+            .chain((0..cases).map(|_| hole(meta)))
+            .chain(generalized.iter().map(|&y| term(meta, TermVariant::Var(y))));
+        self.refine(goal, &applied(f, args))
+    }
+
+    /// Proves `goal`, a relation `I .. x y` between the last parameter and the index of an inductive type such as `eq`,
+    /// from a new goal `I .. y x`, by matching on the proof of that goal.
+    fn symmetry(&mut self, goal: usize, meta: &Span) -> Result<Vec<usize>, Error> {
+        let Inductive {
+            name: ind,
+            params: k,
+            constructors,
+            args,
+        } = self.goal_inductive(goal, meta)?;
+        // An inductive type with a single index has as many arguments as parameters besides it
+        let refl = constructors.iter().find(|(_, arity)| *arity == 0);
+        let (Some((refl, _)), true) = (refl, k >= 1 && args.len() == k + 1) else {
+            return Err(self.failure(goal, meta, "The goal is not a symmetric relation"));
+        };
+        let decl = self.evars.get(goal).unwrap().clone();
+        let evars = self.evars.clone();
+        let unifier = self.unifier.clone();
+
+        let ind_term = term(meta, TermVariant::Const(ind.clone()));
+        let mut swapped = args.clone();
+        swapped.swap(k - 1, k);
+        let proof = self.fresh(meta, applied(ind_term.clone(), swapped), &decl.context);
+        let underscore = Ident {
+            span: meta.clone(),
+            name: "_".to_owned(),
+        };
+        // The return clause binds the parameters, the index and the scrutinee,
+        // and swaps the last parameter with the index back
+        let var = |x| term(meta, TermVariant::Var(x));
+        let motive = applied(ind_term, (3..k + 2).rev().map(var).chain([var(1), var(2)]));
+        let ret = MatchArm {
+            meta: meta.clone(),
+            constructor: ind,
+            params: vec![underscore.clone(); k + 1],
+            body: motive,
+        };
+        let arm = MatchArm {
+            meta: meta.clone(),
+            constructor: refl.clone(),
+            params: vec![underscore.clone(); k],
+            body: applied(
+                term(meta, TermVariant::Const(refl.clone())),
+                (0..k).rev().map(var),
+            ),
+        };
+        let value = term(
+            meta,
+            TermVariant::Match(proof.clone(), underscore, ret, vec![arm]),
+        );
+        let mut local = decl.context.clone();
+        let checked = value
+            .type_check(self.global, &self.evars, &mut local)
+            .and_then(|ttype| self.subtype(meta, &mut local, &ttype, &decl.ttype));
+        if checked.is_err() {
+            self.evars = evars;
+            self.unifier = unifier;
+            return Err(self.failure(goal, meta, "The goal is not a symmetric relation"));
+        }
+        self.evars
+            .solve(goal, value)
+            .map_err(|err| (meta.clone(), err))?;
+        Ok(vec![evar_id(&proof)])
+    }
+}
@@ -0,0 +1,577 @@
+use hane_kernel::global::{GEntryRef, Global};
+
+use super::{tactic::applied, term, Elaborator, Error};
+use crate::{
+    env::Env,
+    lower::lowered::{MatchArm, Term, TermVariant},
+    Ident, Span,
+};
+
+#[derive(Clone, PartialEq)]
+enum Formula {
+    /// A type which is not built from the connectives, in the context of the goal
+    Atom(Term),
+    True,
+    False,
+    And(Box<Formula>, Box<Formula>),
+    Or(Box<Formula>, Box<Formula>),
+    Imp(Box<Formula>, Box<Formula>),
+}
+
+/// A proof of a formula, in which hypotheses introduced by the proof are referred to by an id.
+#[derive(Clone)]
+enum Proof {
+    /// A variable of the context of the goal
+    Var(usize),
+    Hyp(usize),
+    /// `fun h : A => p`
+    Intro(usize, Formula, Box<Proof>),
+    App(Box<Proof>, Box<Proof>),
+    /// `let h : A := v in p`
+    Let(usize, Formula, Box<Proof>, Box<Proof>),
+    /// A constructor applied to the formulas which are the parameters of its type and to proofs of its arguments
+    Construct(String, Vec<Formula>, Vec<Proof>),
+    /// A match on a proof of a formula proving another formula,
+    /// with an arm for each constructor binding its arguments as hypotheses
+    Case(
+        Box<Proof>,
+        Formula,
+        Formula,
+        Vec<(String, Vec<usize>, Proof)>,
+    ),
+}
+
+/// The constants registered as the connectives, which are only known if they have the expected shape.
+struct Connectives {
+    /// `True` and its constructor
+    truth: Option<(String, String)>,
+    falsity: Option<String>,
+    /// `and` and its constructor
+    and: Option<(String, String)>,
+    /// `or` and its left and right constructors
+    or: Option<(String, String, String)>,
+}
+
+/// Returns the constructors of the inductive type registered as `role` along with their amounts of arguments,
+/// if it has `params` parameters and no indices.
+fn registered(
+    global: &Global<Span, Ident>,
+    env: &Env,
+    role: &str,
+    params: usize,
+) -> Option<(String, Vec<(String, usize)>)> {
+    let name = env.registered(role)?;
+    let Some(GEntryRef::Inductive(i, ind_params, bodies)) = global.get_entry(name) else {
+        return None;
+    };
+    if ind_params.len() != params || !bodies[i].arity.is_empty() {
+        return None;
+    }
+    let constructors = bodies[i]
+        .constructors
+        .iter()
+        .map(|c| (c.name.clone(), c.arity.len()))
+        .collect();
+    Some((name.to_owned(), constructors))
+}
+
+impl Connectives {
+    fn new(global: &Global<Span, Ident>, env: &Env) -> Self {
+        let truth = registered(global, env, "True", 0).and_then(|(name, cs)| match &cs[..] {
+            [(c, 0)] => Some((name, c.clone())),
+            _ => None,
+        });
+        let falsity = registered(global, env, "False", 0)
+            .and_then(|(name, cs)| cs.is_empty().then_some(name));
+        let and = registered(global, env, "and", 2).and_then(|(name, cs)| match &cs[..] {
+            [(c, 2)] => Some((name, c.clone())),
+            _ => None,
+        });
+        let or = registered(global, env, "or", 2).and_then(|(name, cs)| match &cs[..] {
+            [(l, 1), (r, 1)] => Some((name, l.clone(), r.clone())),
+            _ => None,
+        });
+        Connectives {
+            truth,
+            falsity,
+            and,
+            or,
+        }
+    }
+
+    /// Reads the normal form `t` as a formula.
+    fn formula(&self, t: &Term) -> Formula {
+        let is = |head: &Term, name: Option<&String>| matches!((&*head.variant, name), (TermVariant::Const(x), Some(name)) if x == name);
+        if let TermVariant::Product(_, input, output) = &*t.variant {
+            // Only products whose output does not depend on their input are implications
+            if let Some(output) = output.pop(0) {
+                return Formula::Imp(
+                    Box::new(self.formula(input)),
+                    Box::new(self.formula(&output)),
+                );
+            }
+        }
+        let (head, args) = t.strip_args_ref();
+        match &args[..] {
+            [] if is(head, self.truth.as_ref().map(|(x, _)| x)) => Formula::True,
+            [] if is(head, self.falsity.as_ref()) => Formula::False,
+            [a, b] if is(head, self.and.as_ref().map(|(x, _)| x)) => {
+                Formula::And(Box::new(self.formula(a)), Box::new(self.formula(b)))
+            }
+            [a, b] if is(head, self.or.as_ref().map(|(x, _, _)| x)) => {
+                Formula::Or(Box::new(self.formula(a)), Box::new(self.formula(b)))
+            }
+            _ => Formula::Atom(t.clone()),
+        }
+    }
+}
+
+/// The search for a proof, which gives every hypothesis it introduces a new id.
+struct Search<'c> {
+    connectives: &'c Connectives,
+    next: usize,
+}
+
+/// Returns the hypotheses without the `i`th of them, along with the given new ones.
+fn replace(
+    hyps: &[(Formula, Proof)],
+    i: usize,
+    new: impl IntoIterator<Item = (Formula, Proof)>,
+) -> Vec<(Formula, Proof)> {
+    let mut hyps = hyps.to_vec();
+    hyps.remove(i);
+    hyps.extend(new);
+    hyps
+}
+
+fn app(f: Proof, v: Proof) -> Proof {
+    Proof::App(Box::new(f), Box::new(v))
+}
+
+fn imp(a: &Formula, b: &Formula) -> Formula {
+    Formula::Imp(Box::new(a.clone()), Box::new(b.clone()))
+}
+
+impl Search<'_> {
+    fn fresh(&mut self) -> usize {
+        self.next += 1;
+        self.next - 1
+    }
+
+    /// Defines new hypotheses with the given types and values, and proves `goal` with them added to `hyps`.
+    fn define(
+        &mut self,
+        hyps: Vec<(Formula, Proof)>,
+        new: Vec<(Formula, Proof)>,
+        goal: &Formula,
+    ) -> Option<Proof> {
+        let ids: Vec<_> = new.iter().map(|_| self.fresh()).collect();
+        let mut hyps = hyps;
+        hyps.extend(
+            new.iter()
+                .zip(&ids)
+                .map(|((f, _), &id)| (f.clone(), Proof::Hyp(id))),
+        );
+        let body = self.prove(&hyps, goal)?;
+        Some(
+            new.into_iter()
+                .zip(ids)
+                .rev()
+                .fold(body, |body, ((f, v), id)| {
+                    Proof::Let(id, f, Box::new(v), Box::new(body))
+                }),
+        )
+    }
+
+    /// Returns a proof of `goal` from `hyps`, if there is one.
+    fn prove(&mut self, hyps: &[(Formula, Proof)], goal: &Formula) -> Option<Proof> {
+        for (f, p) in hyps {
+            if f == goal {
+                return Some(p.clone());
+            }
+            if *f == Formula::False {
+                return Some(Proof::Case(
+                    Box::new(p.clone()),
+                    Formula::False,
+                    goal.clone(),
+                    Vec::new(),
+                ));
+            }
+        }
+
+        // The rules on the left which cannot fail where the goal could otherwise be proven
+        for (i, (f, p)) in hyps.iter().enumerate() {
+            let scrutinee = || Box::new(p.clone());
+            match f {
+                Formula::True => return self.prove(&replace(hyps, i, []), goal),
+                Formula::And(a, b) => {
+                    let (x, y) = (self.fresh(), self.fresh());
+                    let new = [
+                        ((**a).clone(), Proof::Hyp(x)),
+                        ((**b).clone(), Proof::Hyp(y)),
+                    ];
+                    let body = self.prove(&replace(hyps, i, new), goal)?;
+                    let conj = self.connectives.and.as_ref().unwrap().1.clone();
+                    return Some(Proof::Case(
+                        scrutinee(),
+                        f.clone(),
+                        goal.clone(),
+                        vec![(conj, vec![x, y], body)],
+                    ));
+                }
+                Formula::Or(a, b) => {
+                    let (x, y) = (self.fresh(), self.fresh());
+                    let left =
+                        self.prove(&replace(hyps, i, [((**a).clone(), Proof::Hyp(x))]), goal)?;
+                    let right =
+                        self.prove(&replace(hyps, i, [((**b).clone(), Proof::Hyp(y))]), goal)?;
+                    let (_, l, r) = self.connectives.or.clone().unwrap();
+                    return Some(Proof::Case(
+                        scrutinee(),
+                        f.clone(),
+                        goal.clone(),
+                        vec![(l, vec![x], left), (r, vec![y], right)],
+                    ));
+                }
+                Formula::Imp(a, b) => {
+                    let new = match &**a {
+                        Formula::True => {
+                            let i = self.connectives.truth.as_ref().unwrap().1.clone();
+                            vec![(
+                                (**b).clone(),
+                                app(p.clone(), Proof::Construct(i, vec![], vec![])),
+                            )]
+                        }
+                        Formula::False => Vec::new(),
+                        // `C ∧ D → B` is curried to `C → D → B`
+                        Formula::And(c, d) => {
+                            let (x, y) = (self.fresh(), self.fresh());
+                            let conj = self.connectives.and.as_ref().unwrap().1.clone();
+                            let pair = Proof::Construct(
+                                conj,
+                                vec![(**c).clone(), (**d).clone()],
+                                vec![Proof::Hyp(x), Proof::Hyp(y)],
+                            );
+                            let v = Proof::Intro(
+                                x,
+                                (**c).clone(),
+                                Box::new(Proof::Intro(
+                                    y,
+                                    (**d).clone(),
+                                    Box::new(app(p.clone(), pair)),
+                                )),
+                            );
+                            vec![(imp(c, &imp(d, b)), v)]
+                        }
+                        // `C ∨ D → B` is split into `C → B` and `D → B`
+                        Formula::Or(c, d) => {
+                            let (_, l, r) = self.connectives.or.clone().unwrap();
+                            let params = vec![(**c).clone(), (**d).clone()];
+                            let mut case = |constructor: String, e: &Formula| {
+                                let x = self.fresh();
+                                let inj = Proof::Construct(
+                                    constructor,
+                                    params.clone(),
+                                    vec![Proof::Hyp(x)],
+                                );
+                                (
+                                    imp(e, b),
+                                    Proof::Intro(x, e.clone(), Box::new(app(p.clone(), inj))),
+                                )
+                            };
+                            vec![case(l, c), case(r, d)]
+                        }
+                        Formula::Atom(_) => match hyps.iter().find(|(g, _)| g == &**a) {
+                            Some((_, q)) => vec![((**b).clone(), app(p.clone(), q.clone()))],
+                            None => continue,
+                        },
+                        Formula::Imp(_, _) => continue,
+                    };
+                    return self.define(replace(hyps, i, []), new, goal);
+                }
+                Formula::Atom(_) | Formula::False => {}
+            }
+        }
+
+        // The rules on the right which cannot fail
+        match goal {
+            Formula::True => {
+                let i = self.connectives.truth.as_ref().unwrap().1.clone();
+                return Some(Proof::Construct(i, Vec::new(), Vec::new()));
+            }
+            Formula::And(a, b) => {
+                let left = self.prove(hyps, a)?;
+                let right = self.prove(hyps, b)?;
+                let conj = self.connectives.and.as_ref().unwrap().1.clone();
+                return Some(Proof::Construct(
+                    conj,
+                    vec![(**a).clone(), (**b).clone()],
+                    vec![left, right],
+                ));
+            }
+            Formula::Imp(a, b) => {
+                let x = self.fresh();
+                let mut hyps = hyps.to_vec();
+                hyps.push(((**a).clone(), Proof::Hyp(x)));
+                let body = self.prove(&hyps, b)?;
+                return Some(Proof::Intro(x, (**a).clone(), Box::new(body)));
+            }
+            Formula::Or(a, b) => {
+                let (_, l, r) = self.connectives.or.clone().unwrap();
+                let params = vec![(**a).clone(), (**b).clone()];
+                if let Some(p) = self.prove(hyps, a) {
+                    return Some(Proof::Construct(l, params, vec![p]));
+                }
+                if let Some(p) = self.prove(hyps, b) {
+                    return Some(Proof::Construct(r, params, vec![p]));
+                }
+            }
+            Formula::Atom(_) | Formula::False => {}
+        }
+
+        // `(C → D) → B` gives `B` once `C → D` is proven, for which it can be weakened to `D → B`
+        for (i, (f, p)) in hyps.iter().enumerate() {
+            let Formula::Imp(a, b) = f else {
+                continue;
+            };
+            let Formula::Imp(c, d) = &**a else {
+                continue;
+            };
+            let (x, y, z) = (self.fresh(), self.fresh(), self.fresh());
+            let weakened = Proof::Intro(
+                y,
+                (**d).clone(),
+                Box::new(app(
+                    p.clone(),
+                    Proof::Intro(z, (**c).clone(), Box::new(Proof::Hyp(y))),
+                )),
+            );
+            let Some(body) = self.define(
+                replace(hyps, i, [((**c).clone(), Proof::Hyp(x))]),
+                vec![(imp(d, b), weakened)],
+                d,
+            ) else {
+                continue;
+            };
+            let implication = Proof::Intro(x, (**c).clone(), Box::new(body));
+            if let Some(proof) = self.define(
+                replace(hyps, i, []),
+                vec![((**b).clone(), app(p.clone(), implication))],
+                goal,
+            ) {
+                return Some(proof);
+            }
+        }
+        None
+    }
+}
+
+/// Writes proofs as terms, keeping track of the hypotheses bound by the binders around them.
+struct Emit<'c> {
+    connectives: &'c Connectives,
+    meta: &'c Span,
+    /// The binders around the term, the innermost last, with the id of the hypothesis they bind if any
+    binders: Vec<(Ident, Option<usize>)>,
+    /// The names of the variables of the context of the goal
+    context: Vec<String>,
+}
+
+impl Emit<'_> {
+    fn ident(&self, name: &str) -> Ident {
+        Ident {
+            span: self.meta.clone(),
+            name: name.to_owned(),
+        }
+    }
+
+    fn constant(&self, name: &str) -> Term {
+        term(self.meta, TermVariant::Const(name.to_owned()))
+    }
+
+    /// Returns a name for a new hypothesis which is distinct from the names of the hypotheses around it.
+    fn fresh(&self) -> Ident {
+        let taken = |name: &str| {
+            self.context.iter().any(|x| x == name)
+                || self.binders.iter().any(|(x, _)| x.name == name)
+        };
+        let name = (0..)
+            .map(|i| {
+                if i == 0 {
+                    "H".to_owned()
+                } else {
+                    format!("H{i}")
+                }
+            })
+            .find(|name| !taken(name))
+            .unwrap();
+        self.ident(&name)
+    }
+
+    fn formula(&self, f: &Formula) -> Term {
+        self.formula_at(f, self.binders.len())
+    }
+
+    fn formula_at(&self, f: &Formula, depth: usize) -> Term {
+        let c = self.connectives;
+        match f {
+            Formula::Atom(t) => t.push(depth),
+            Formula::True => self.constant(&c.truth.as_ref().unwrap().0),
+            Formula::False => self.constant(c.falsity.as_ref().unwrap()),
+            Formula::And(a, b) => applied(
+                self.constant(&c.and.as_ref().unwrap().0),
+                [self.formula_at(a, depth), self.formula_at(b, depth)],
+            ),
+            Formula::Or(a, b) => applied(
+                self.constant(&c.or.as_ref().unwrap().0),
+                [self.formula_at(a, depth), self.formula_at(b, depth)],
+            ),
+            Formula::Imp(a, b) => term(
+                self.meta,
+                TermVariant::Product(
+                    self.ident("_"),
+                    self.formula_at(a, depth),
+                    self.formula_at(b, depth + 1),
+                ),
+            ),
+        }
+    }
+
+    /// Writes `body` under a binder for the hypothesis `id`, returning the name of the binder along with the term.
+    fn under(&mut self, id: usize, body: &Proof) -> (Ident, Term) {
+        let x = self.fresh();
+        self.binders.push((x.clone(), Some(id)));
+        let body = self.proof(body);
+        self.binders.pop();
+        (x, body)
+    }
+
+    fn proof(&mut self, p: &Proof) -> Term {
+        let variant = match p {
+            Proof::Var(x) => TermVariant::Var(x + self.binders.len()),
+            Proof::Hyp(id) => {
+                let x = self
+                    .binders
+                    .iter()
+                    .rev()
+                    .position(|(_, bound)| *bound == Some(*id))
+                    .unwrap();
+                TermVariant::Var(x)
+            }
+            Proof::Intro(id, a, body) => {
+                let a = self.formula(a);
+                let (x, body) = self.under(*id, body);
+                TermVariant::Abstract(x, a, body)
+            }
+            Proof::App(f, v) => TermVariant::App(self.proof(f), self.proof(v)),
+            Proof::Let(id, a, v, body) => {
+                let a = self.formula(a);
+                let v = self.proof(v);
+                let (x, body) = self.under(*id, body);
+                TermVariant::Bind(x, a, v, body)
+            }
+            Proof::Construct(c, params, args) => {
+                let params: Vec<_> = params.iter().map(|f| self.formula(f)).collect();
+                let args: Vec<_> = args.iter().map(|p| self.proof(p)).collect();
+                return applied(self.constant(c), params.into_iter().chain(args));
+            }
+            Proof::Case(scrutinee, f, goal, arms) => {
+                let c = self.connectives;
+                let (ind, params) = match f {
+                    Formula::And(a, b) => (&c.and.as_ref().unwrap().0, vec![a, b]),
+                    Formula::Or(a, b) => (&c.or.as_ref().unwrap().0, vec![a, b]),
+                    Formula::False => (c.falsity.as_ref().unwrap(), Vec::new()),
+                    _ => unreachable!(),
+                };
+                let k = params.len();
+                let underscore = self.ident("_");
+                // The return clause binds the parameters and the scrutinee, and the arms bind the parameters
+                let ret = MatchArm {
+                    meta: self.meta.clone(),
+                    constructor: ind.clone(),
+                    params: vec![underscore.clone(); k],
+                    body: self.formula_at(goal, self.binders.len() + k + 1),
+                };
+                let scrutinee = self.proof(scrutinee);
+                let arms = arms
+                    .iter()
+                    .map(|(constructor, ids, body)| {
+                        let depth = self.binders.len();
+                        self.binders
+                            .extend((0..k).map(|_| (underscore.clone(), None)));
+                        let mut params = vec![underscore.clone(); k];
+                        for &id in ids {
+                            let x = self.fresh();
+                            params.push(x.clone());
+                            self.binders.push((x, Some(id)));
+                        }
+                        let body = self.proof(body);
+                        self.binders.truncate(depth);
+                        MatchArm {
+                            meta: self.meta.clone(),
+                            constructor: constructor.clone(),
+                            params,
+                            body,
+                        }
+                    })
+                    .collect();
+                TermVariant::Match(scrutinee, underscore, ret, arms)
+            }
+        };
+        term(self.meta, variant)
+    }
+}
+
+impl Elaborator<'_> {
+    /// Proves `goal` by the search for a proof in intuitionistic propositional logic,
+    /// with the types of the hypotheses and of the goal read as formulas once they are normalized.
+    /// The search is Dyckhoff's contraction-free sequent calculus LJT, so it always ends.
+    pub(super) fn tauto(&mut self, goal: usize, meta: &Span) -> Result<Vec<usize>, Error> {
+        let decl = self.evars.get(goal).unwrap().clone();
+        let connectives = Connectives::new(self.global, self.env);
+        let mut local = decl.context.clone();
+        let mut hyps = Vec::new();
+        for (x, entry) in decl.context.iter().enumerate() {
+            let ttype = self.normalize(meta, &entry.ttype.push(x + 1), &mut local)?;
+            hyps.push((connectives.formula(&ttype), Proof::Var(x)));
+        }
+        let ttype = self.normalize(meta, &decl.ttype, &mut local)?;
+        let target = connectives.formula(&ttype);
+        let mut search = Search {
+            connectives: &connectives,
+            next: 0,
+        };
+        let Some(proof) = search.prove(&hyps, &target) else {
+            return Err(self.failure(goal, meta, "tauto failed to prove the goal"));
+        };
+        let value = Emit {
+            connectives: &connectives,
+            meta,
+            binders: Vec::new(),
+            context: decl
+                .context
+                .iter()
+                .map(|entry| entry.x.name.clone())
+                .collect(),
+        }
+        .proof(&proof);
+
+        let evars = self.evars.clone();
+        let unifier = self.unifier.clone();
+        let checked = value
+            .type_check(self.global, &self.evars, &mut local)
+            .and_then(|ttype| self.subtype(meta, &mut local, &ttype, &decl.ttype));
+        if checked.is_err() {
+            self.evars = evars;
+            self.unifier = unifier;
+            return Err(self.failure(
+                goal,
+                meta,
+                "The proof found by tauto is not accepted by the kernel",
+            ));
+        }
+        self.evars
+            .solve(goal, value)
+            .map_err(|err| (meta.clone(), err))?;
+        Ok(Vec::new())
+    }
+}
@@ -0,0 +1,532 @@
+use std::collections::{HashMap, HashSet};
+
+use hane_kernel::{Term, TermVariant};
+
+use crate::{
+    lower::{lowered, LoweringWarning},
+    Assoc, Proof, Span, SpanError,
+};
+
+/// A named argument of a constant, along with whether it is inserted implicitly at application sites.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Arg {
+    pub name: String,
+    pub implicit: bool,
+}
+
+/// The constructors of an inductive type along with how many arguments they take besides the parameters.
+pub struct Inductive {
+    pub params: usize,
+    pub indices: usize,
+    pub constructors: Vec<(String, usize)>,
+}
+
+/// A record, an inductive type with a single constructor whose arguments are named fields.
+pub struct Record {
+    pub constructor: String,
+    /// The fields in the order they are declared, each with a projection of the same name
+    pub fields: Vec<String>,
+}
+
+/// An instance of a class declared by the `Instance` command.
+pub struct Instance {
+    pub name: String,
+    pub class: String,
+    /// Instances with a lower priority are tried first
+    pub priority: usize,
+}
+
+/// The kind of type a coercion converts from or to.
+#[derive(Clone, PartialEq, Eq)]
+pub enum CoercionClass {
+    /// An inductive type or another constant, applied to its parameters
+    Const(String),
+    /// Any sort, written `Sortclass`
+    Sort,
+    /// Any function type, written `Funclass`
+    Fun,
+}
+
+/// A function declared by the `Coercion` command, which elaboration inserts to convert a term from `source` to `target`.
+pub struct Coercion {
+    pub name: String,
+    pub source: CoercionClass,
+    pub target: CoercionClass,
+    /// The number of arguments the coercion takes, of which the last is the term it converts
+    pub arity: usize,
+}
+
+/// The library declared by the `Add Ring` command, along with the carrier and the operations of the ring it is declared for.
+pub struct Ring {
+    /// The correctness lemma of the library, which also prefixes the names of the rest of it
+    pub name: String,
+    pub carrier: String,
+    pub zero: String,
+    pub one: String,
+    pub add: String,
+    pub mul: String,
+    /// The negation, which a semiring does not have
+    pub opp: Option<String>,
+}
+
+/// The names under which the `Register` command can register a constant, each naming what automation expects of it.
+pub const REGISTRABLE: &[&str] = &[
+    "True",
+    "False",
+    "and",
+    "or",
+    "eq",
+    "nat",
+    "plus",
+    "le",
+    "lt",
+    "plus_O_n",
+    "plus_Sn_m",
+    "plus_comm",
+    "plus_assoc",
+    "plus_le_compat",
+    "le_0_n",
+    "le_S_n",
+    "le_Sn_n",
+    "le_trans",
+    "le_or_lt",
+    "le_antisym",
+    "ring_carrier",
+    "ring_0",
+    "ring_1",
+    "ring_add",
+    "ring_mul",
+    "ring_opp",
+    "ring_add_0_l",
+    "ring_add_comm",
+    "ring_add_assoc",
+    "ring_mul_1_l",
+    "ring_mul_0_l",
+    "ring_mul_comm",
+    "ring_mul_assoc",
+    "ring_distr_l",
+    "ring_opp_def",
+];
+
+/// A part of the string of a notation.
+#[derive(Clone, PartialEq, Eq)]
+pub enum Token {
+    Var(String),
+    Symbol(String),
+}
+
+/// A notation declared by the `Notation` command.
+pub struct Notation {
+    pub tokens: Vec<Token>,
+    pub level: usize,
+    pub assoc: Assoc,
+    pub scope: Option<String>,
+    /// The term the notation stands for, with a variable for each variable of the notation in the order they are written.
+    pub body: lowered::Term,
+    /// The number of holes in the body, which are numbered from zero
+    pub holes: usize,
+}
+
+impl Notation {
+    /// Returns the levels of the terms taking the place of the variables.
+    ///
+    /// A variable at either end of the notation is just below the level of the notation,
+    /// or at the level of the notation on the side of its associativity.
+    /// A variable between two symbols is delimited by them, and can be anything.
+    pub fn levels(&self) -> Vec<usize> {
+        let last = self.tokens.len() - 1;
+        self.tokens
+            .iter()
+            .enumerate()
+            .filter(|(_, token)| matches!(token, Token::Var(_)))
+            .map(|(i, _)| match i {
+                0 if self.assoc == Assoc::Left => self.level,
+                i if i == last && self.assoc == Assoc::Right => self.level,
+                i if i == 0 || i == last => self.level.saturating_sub(1),
+                _ => 200,
+            })
+            .collect()
+    }
+}
+
+/// The constructors numbers are written with by a number notation.
+pub enum Numeral {
+    /// Zero and the successor, as `nat`.
+    Unary { zero: String, succ: String },
+    /// One, doubling and doubling plus one, as `positive`, which can only write positive numbers.
+    Binary {
+        one: String,
+        double: String,
+        double_succ: String,
+    },
+}
+
+/// A number notation declared by the `Number Notation` command, writing the numbers of the inductive type `ind`.
+pub struct NumberNotation {
+    pub ind: String,
+    pub numeral: Numeral,
+    pub scope: Option<String>,
+}
+
+impl NumberNotation {
+    /// Returns the term writing `n`, or `None` if it cannot be written with the constructors.
+    pub fn term(&self, span: &Span, n: u64) -> Option<lowered::Term> {
+        let constant = |name: &str| lowered::Term {
+            meta: span.clone(),
+            variant: Box::new(lowered::TermVariant::Const(name.to_owned())),
+        };
+        let apply = |f: &str, t| lowered::Term {
+            meta: span.clone(),
+            variant: Box::new(lowered::TermVariant::App(constant(f), t)),
+        };
+        match &self.numeral {
+            Numeral::Unary { zero, succ } => {
+                Some((0..n).fold(constant(zero), |t, _| apply(succ, t)))
+            }
+            Numeral::Binary {
+                one,
+                double,
+                double_succ,
+            } => {
+                if n == 0 {
+                    return None;
+                }
+                // The bits after the leading one, from the most significant
+                let bits = 63 - n.leading_zeros();
+                Some((0..bits).rev().fold(constant(one), |t, i| {
+                    if n >> i & 1 == 1 {
+                        apply(double_succ, t)
+                    } else {
+                        apply(double, t)
+                    }
+                }))
+            }
+        }
+    }
+
+    /// Returns the number written by `term`, or `None` if it is not made of the constructors.
+    pub fn number<M, B>(&self, mut term: &Term<M, B>) -> Option<u64> {
+        // The constructors applied around the innermost one, from the outermost
+        let mut ops = Vec::new();
+        while let TermVariant::App(f, t) = &*term.variant {
+            let TermVariant::Const(f) = &*f.variant else {
+                return None;
+            };
+            ops.push(f.as_str());
+            term = t;
+        }
+        let TermVariant::Const(base) = &*term.variant else {
+            return None;
+        };
+        match &self.numeral {
+            Numeral::Unary { zero, succ } => {
+                (base == zero && ops.iter().all(|op| op == succ)).then_some(ops.len() as u64)
+            }
+            Numeral::Binary {
+                one,
+                double,
+                double_succ,
+            } => {
+                if base != one {
+                    return None;
+                }
+                ops.iter().rev().try_fold(1u64, |n, op| {
+                    let n = n.checked_mul(2)?;
+                    match *op {
+                        op if op == double => Some(n),
+                        op if op == double_succ => n.checked_add(1),
+                        _ => None,
+                    }
+                })
+            }
+        }
+    }
+}
+
+/// The syntactic information about the global environment, which the kernel does not need to know about.
+#[derive(Default)]
+pub struct Env {
+    names: HashSet<String>,
+    args: HashMap<String, Vec<Arg>>,
+    inductives: HashMap<String, Inductive>,
+    /// The inductive type of each constructor
+    constructors: HashMap<String, String>,
+    warnings: Vec<SpanError<LoweringWarning>>,
+    notations: Vec<Notation>,
+    number_notations: Vec<NumberNotation>,
+    /// The scopes which have been opened, the most recently opened last
+    scopes: Vec<String>,
+    records: HashMap<String, Record>,
+    /// The proofs of theorems, which are run when the theorems are elaborated
+    proofs: HashMap<String, Proof>,
+    classes: HashSet<String>,
+    instances: Vec<Instance>,
+    coercions: Vec<Coercion>,
+    /// The constants registered for automation, by the name they are registered as
+    registered: HashMap<String, String>,
+    /// The lemmas of each hint database, in the order they were added
+    hints: HashMap<String, Vec<String>>,
+    /// The ring `ring` proves equalities in, the one last declared by `Add Ring`
+    ring: Option<Ring>,
+    /// The declared type of each constant, as it was lowered
+    types: HashMap<String, lowered::Term>,
+}
+
+impl Env {
+    pub fn new() -> Self {
+        Env::default()
+    }
+
+    /// Returns whether a constant named `name` has been declared.
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.contains(name)
+    }
+
+    /// Declares the constant `name`, returning false if it was already declared.
+    pub fn insert(&mut self, name: String) -> bool {
+        self.names.insert(name)
+    }
+
+    /// Returns the leading arguments of the constant `name` if any of them are implicit.
+    pub fn args(&self, name: &str) -> Option<&[Arg]> {
+        self.args.get(name).map(Vec::as_slice)
+    }
+
+    pub fn inductive(&self, name: &str) -> Option<&Inductive> {
+        self.inductives.get(name)
+    }
+
+    /// Returns the name of the inductive type that `name` is a constructor of.
+    pub fn constructor(&self, name: &str) -> Option<&str> {
+        self.constructors.get(name).map(String::as_str)
+    }
+
+    pub fn add_inductive(&mut self, name: String, inductive: Inductive) {
+        for (constructor, _) in &inductive.constructors {
+            self.constructors.insert(constructor.clone(), name.clone());
+        }
+        self.inductives.insert(name, inductive);
+    }
+
+    pub fn record(&self, name: &str) -> Option<&Record> {
+        self.records.get(name)
+    }
+
+    pub fn add_record(&mut self, name: String, record: Record) {
+        self.records.insert(name, record);
+    }
+
+    pub fn proof(&self, theorem: &str) -> Option<&Proof> {
+        self.proofs.get(theorem)
+    }
+
+    pub fn add_proof(&mut self, theorem: String, proof: Proof) {
+        self.proofs.insert(theorem, proof);
+    }
+
+    /// Returns the record which has a field named `field`, along with its name.
+    pub fn record_of_field(&self, field: &str) -> Option<(&str, &Record)> {
+        self.records
+            .iter()
+            .find(|(_, record)| record.fields.iter().any(|x| x == field))
+            .map(|(name, record)| (name.as_str(), record))
+    }
+
+    /// Returns whether `name` is a class, a record whose instances are found by resolution.
+    pub fn is_class(&self, name: &str) -> bool {
+        self.classes.contains(name)
+    }
+
+    pub fn add_class(&mut self, name: String) {
+        self.classes.insert(name);
+    }
+
+    pub fn add_instance(&mut self, instance: Instance) {
+        self.instances.push(instance);
+    }
+
+    /// Returns the instances of `class` in the order they are tried,
+    /// by priority and then the most recently declared first.
+    pub fn instances<'a>(&'a self, class: &'a str) -> impl Iterator<Item = &'a Instance> {
+        let mut instances: Vec<_> = self
+            .instances
+            .iter()
+            .enumerate()
+            .filter(|(_, instance)| instance.class == class)
+            .collect();
+        instances.sort_by_key(|&(i, instance)| (instance.priority, std::cmp::Reverse(i)));
+        instances.into_iter().map(|(_, instance)| instance)
+    }
+
+    /// Returns the declared type of the constant `name`, which is a hole if it was left to be inferred.
+    pub fn ttype(&self, name: &str) -> Option<&lowered::Term> {
+        self.types.get(name)
+    }
+
+    pub fn set_type(&mut self, name: String, ttype: lowered::Term) {
+        self.types.insert(name, ttype);
+    }
+
+    pub fn add_coercion(&mut self, coercion: Coercion) {
+        self.coercions.push(coercion);
+    }
+
+    pub fn has_coercions(&self) -> bool {
+        !self.coercions.is_empty()
+    }
+
+    /// Returns the coercion named `name`, the most recently declared if it was declared more than once.
+    pub fn coercion(&self, name: &str) -> Option<&Coercion> {
+        self.coercions.iter().rev().find(|c| c.name == name)
+    }
+
+    /// Returns whether there is a coercion from `class`.
+    pub fn coerces_from(&self, class: &CoercionClass) -> bool {
+        self.coercions.iter().any(|c| c.source == *class)
+    }
+
+    /// Finds the shortest path of coercions from `source` to `target` by a breadth first search of the coercion graph.
+    /// Among paths of the same length, the one with the most recently declared coercions is found.
+    pub fn coercion_path(
+        &self,
+        source: &CoercionClass,
+        target: &CoercionClass,
+    ) -> Option<Vec<&Coercion>> {
+        // The coercion leading to each class which has been reached, and the class it comes from
+        let mut reached: Vec<(&CoercionClass, Option<usize>)> = vec![(source, None)];
+        let mut next = 0;
+        while next < reached.len() {
+            let (class, _) = reached[next];
+            if class == target {
+                let mut path = Vec::new();
+                let mut at = next;
+                while let (_, Some(i)) = reached[at] {
+                    let coercion = &self.coercions[i];
+                    path.push(coercion);
+                    at = reached
+                        .iter()
+                        .position(|(class, _)| **class == coercion.source)
+                        .unwrap();
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for (i, coercion) in self.coercions.iter().enumerate().rev() {
+                if coercion.source == *class && !reached.iter().any(|(c, _)| **c == coercion.target)
+                {
+                    reached.push((&coercion.target, Some(i)));
+                }
+            }
+            next += 1;
+        }
+        None
+    }
+
+    pub fn warn(&mut self, warnings: Vec<SpanError<LoweringWarning>>) {
+        self.warnings.extend(warnings);
+    }
+
+    /// Returns the warnings from lowering commands since the last call.
+    pub fn take_warnings(&mut self) -> Vec<SpanError<LoweringWarning>> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    pub fn add_notation(&mut self, notation: Notation) {
+        self.notations.push(notation);
+    }
+
+    /// Opens the scope `name`, giving its notations priority over those of the scopes opened before.
+    pub fn open_scope(&mut self, name: String) {
+        self.scopes.retain(|scope| *scope != name);
+        self.scopes.push(name);
+    }
+
+    /// Returns the priority of what is declared in `scope`, or `None` if the scope has not been opened.
+    ///
+    /// What is declared in an opened scope comes before what is declared outside of any scope.
+    fn priority(&self, scope: &Option<String>) -> Option<usize> {
+        match scope {
+            Some(scope) => Some(self.scopes.iter().position(|s| s == scope)? + 1),
+            None => Some(0),
+        }
+    }
+
+    /// Returns the notations which can be used, those with the highest priority first.
+    ///
+    /// Notations in an opened scope come before those outside of any scope, and later notations come before earlier ones.
+    pub fn notations(&self) -> impl Iterator<Item = &Notation> {
+        let mut notations: Vec<_> = self
+            .notations
+            .iter()
+            .enumerate()
+            .filter_map(|(i, notation)| Some((self.priority(&notation.scope)?, i, notation)))
+            .collect();
+        notations.sort_by_key(|&(priority, i, _)| std::cmp::Reverse((priority, i)));
+        notations.into_iter().map(|(_, _, notation)| notation)
+    }
+
+    pub fn add_number_notation(&mut self, notation: NumberNotation) {
+        self.number_notations.push(notation);
+    }
+
+    /// Returns the number notation which numbers are written with, the latest one with the highest priority.
+    pub fn number_notation(&self) -> Option<&NumberNotation> {
+        self.number_notations
+            .iter()
+            .enumerate()
+            .filter_map(|(i, notation)| Some(((self.priority(&notation.scope)?, i), notation)))
+            .max_by_key(|&(priority, _)| priority)
+            .map(|(_, notation)| notation)
+    }
+
+    /// Returns the usable notation with the highest priority that starts with `symbol`,
+    /// or with a variable followed by `symbol` if `infix`.
+    pub fn notation(&self, infix: bool, symbol: &str) -> Option<&Notation> {
+        self.notations().find(|notation| {
+            let mut tokens = notation.tokens.iter();
+            if infix && !matches!(tokens.next(), Some(Token::Var(_))) {
+                return false;
+            }
+            matches!(tokens.next(), Some(Token::Symbol(s)) if s == symbol)
+        })
+    }
+
+    /// Returns the constant registered as `role`, such as the one automation treats as conjunction when `role` is `and`.
+    pub fn registered(&self, role: &str) -> Option<&str> {
+        self.registered.get(role).map(String::as_str)
+    }
+
+    pub fn register(&mut self, name: String, role: String) {
+        self.registered.insert(role, name);
+    }
+
+    pub fn ring(&self) -> Option<&Ring> {
+        self.ring.as_ref()
+    }
+
+    pub fn set_ring(&mut self, ring: Ring) {
+        self.ring = Some(ring);
+    }
+
+    /// Returns the lemmas of the hint database `database`, if anything has been added to it.
+    pub fn hints(&self, database: &str) -> Option<&[String]> {
+        self.hints.get(database).map(Vec::as_slice)
+    }
+
+    pub fn add_hint(&mut self, database: String, name: String) {
+        let hints = self.hints.entry(database).or_default();
+        if !hints.contains(&name) {
+            hints.push(name);
+        }
+    }
+
+    /// Sets the leading arguments of the constant `name`.
+    /// Nothing is stored if none of them are implicit, as the constant is then applied like any other term.
+    pub fn set_args(&mut self, name: String, args: Vec<Arg>) {
+        if args.iter().any(|arg| arg.implicit) {
+            self.args.insert(name, args);
+        } else {
+            self.args.remove(&name);
+        }
+    }
+}
@@ -1,12 +1,16 @@
 use std::fmt::{self, Display, Formatter};
 
 use crate::{
+    env::Env,
     print::{write_local, write_term},
     Ident, Span,
 };
-use hane_kernel::{entry::Entry, CommandError, Stack, TypeError, TypeErrorVariant};
+use hane_kernel::{
+    entry::Entry, error::InstanceTrace, CommandError, Stack, TypeError, TypeErrorVariant,
+};
 
-pub struct EvalError(pub CommandError<Span, Ident>);
+/// An error from evaluating a command, displayed with the terms printed according to the `Env`.
+pub struct EvalError<'e>(pub CommandError<Span, Ident>, pub &'e Env);
 
 fn write_cause(
     err: &TypeError<Span, Ident>,
@@ -58,11 +62,57 @@ fn write_cause(
             local.len()
         ),
         TypeErrorVariant::UndefinedConst(name) => write!(f, "Unknown constant {name}"),
+        TypeErrorVariant::UniverseOverflow => write!(f, "Universe level is too large"),
+        TypeErrorVariant::UndefinedEvar(id) => write!(f, "Unknown metavariable ?{id}"),
+        TypeErrorVariant::IncorrectSubstitutionLength(expected, found) => write!(
+            f,
+            "Expected a substitution of {expected} terms for the metavariable, found {found}"
+        ),
+        TypeErrorVariant::EvarAlreadySolved(id) => {
+            write!(f, "The metavariable ?{id} has already been solved")
+        }
+        TypeErrorVariant::EvarOccursInSolution(id, _) => {
+            write!(f, "The metavariable ?{id} occurs in its own solution")
+        }
+        TypeErrorVariant::CannotUnify(_, _) => write!(f, "Unable to unify terms"),
+        TypeErrorVariant::EvarScopeEscape(id, _) => {
+            write!(f, "The solution of the metavariable ?{id} refers to a variable outside of its context")
+        }
+        TypeErrorVariant::UnificationStuck(_, _) => {
+            write!(
+                f,
+                "Unable to solve a unification problem outside of the pattern fragment"
+            )
+        }
+        TypeErrorVariant::UnsolvedHole(_) => write!(f, "Unable to infer this hole"),
+        TypeErrorVariant::ScrutineeNotInductive(_) => {
+            write!(f, "Unable to infer the inductive type of the scrutinee")
+        }
+        TypeErrorVariant::NotAnEquality(_) => {
+            write!(f, "Unable to rewrite with a term which is not an equality")
+        }
+        TypeErrorVariant::NothingToRewrite(_, _) => write!(f, "Nothing to rewrite"),
+        TypeErrorVariant::UnelaboratedRewrite => {
+            write!(f, "A rewrite must be elaborated before it is checked")
+        }
+        TypeErrorVariant::NoInstance(_, _) => write!(f, "No instance found for this hole"),
+        TypeErrorVariant::TacticFailed(reason, _) => write!(f, "{reason}"),
+        TypeErrorVariant::SearchFailed(_, depth, _) => {
+            write!(f, "Proof search gave up after searching to depth {depth}")
+        }
+        TypeErrorVariant::NoGoals => write!(f, "No goals are left to prove"),
+        TypeErrorVariant::UnfinishedProof(1, _) => {
+            write!(f, "The proof is unfinished, 1 goal remains")
+        }
+        TypeErrorVariant::UnfinishedProof(n, _) => {
+            write!(f, "The proof is unfinished, {n} goals remain")
+        }
     }
 }
 
-impl Display for EvalError {
+impl<'e> Display for EvalError<'e> {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let env = self.1;
         match &self.0 {
             CommandError::NameAlreadyExists(name) => {
                 write!(f, "The name `{name}` has already been defined")
@@ -73,47 +123,137 @@ impl Display for EvalError {
             CommandError::ConstructorArgsContainsType => {
                 write!(f, "Types from the constructors type family is not allowed as arguments to the constructed type")
             }
+            CommandError::UnsolvedEvar(id) => {
+                write!(f, "The metavariable ?{id} must be solved before it can be added to the environment")
+            }
             CommandError::TypeError(err) => {
                 write_cause(err, &err.local, f)?;
                 writeln!(f)?;
                 let mut names = Stack::new();
-                let mut names = write_local(f, &err.local, &mut names)?;
+                let mut names = write_local(f, env, &err.local, &mut names)?;
                 writeln!(f)?;
                 match &err.variant {
                     TypeErrorVariant::NotSubtypeType(expected, actual) => {
                         write!(f, "Expected: ")?;
-                        write_term(f, expected, &mut names, 200)?;
+                        write_term(f, env, expected, &mut names, 200)?;
                         writeln!(f)?;
                         write!(f, "Actual: ")?;
-                        write_term(f, actual, &mut names, 200)
+                        write_term(f, env, actual, &mut names, 200)
                     }
                     TypeErrorVariant::IncompatibleTypes(expected, actual) => {
                         write!(f, "Expected: ")?;
-                        write_term(f, expected, &mut names, 200)?;
+                        write_term(f, env, expected, &mut names, 200)?;
                         writeln!(f)?;
                         write!(f, "Actual: ")?;
-                        write_term(f, actual, &mut names, 200)
+                        write_term(f, env, actual, &mut names, 200)
                     }
                     TypeErrorVariant::NotAProduct(ttype) => {
                         write!(f, "Found: ")?;
-                        write_term(f, ttype, &mut names, 200)
+                        write_term(f, env, ttype, &mut names, 200)
                     }
                     TypeErrorVariant::NotASort(ttype) => {
                         write!(f, "Found: ")?;
-                        write_term(f, ttype, &mut names, 200)
+                        write_term(f, env, ttype, &mut names, 200)
                     }
                     TypeErrorVariant::NotAnInductiveType(_) => Ok(()),
                     TypeErrorVariant::NotAConstructor(_, _, _) => Ok(()),
                     TypeErrorVariant::IncorrectParameterCount(_, _) => Ok(()),
                     TypeErrorVariant::NotOfExpectedInducitve(_, ttype) => {
                         write!(f, "Found: ")?;
-                        write_term(f, ttype, &mut names, 200)
+                        write_term(f, env, ttype, &mut names, 200)
                     }
                     TypeErrorVariant::DisallowedEleminationSort(_, _) => Ok(()),
                     TypeErrorVariant::DupplicateConstructor(_) => Ok(()),
                     TypeErrorVariant::MissingConstructors(_) => Ok(()),
                     TypeErrorVariant::DebruijnOutOfScope(_) => Ok(()),
                     TypeErrorVariant::UndefinedConst(_) => Ok(()),
+                    TypeErrorVariant::UniverseOverflow => Ok(()),
+                    TypeErrorVariant::UndefinedEvar(_) => Ok(()),
+                    TypeErrorVariant::IncorrectSubstitutionLength(_, _) => Ok(()),
+                    TypeErrorVariant::EvarAlreadySolved(_) => Ok(()),
+                    TypeErrorVariant::EvarOccursInSolution(_, value) => {
+                        write!(f, "Found: ")?;
+                        write_term(f, env, value, &mut names, 200)
+                    }
+                    TypeErrorVariant::CannotUnify(lhs, rhs)
+                    | TypeErrorVariant::UnificationStuck(lhs, rhs) => {
+                        write!(f, "Left: ")?;
+                        write_term(f, env, lhs, &mut names, 200)?;
+                        writeln!(f)?;
+                        write!(f, "Right: ")?;
+                        write_term(f, env, rhs, &mut names, 200)
+                    }
+                    TypeErrorVariant::EvarScopeEscape(_, var) => {
+                        write!(f, "Variable: ")?;
+                        write_term(f, env, var, &mut names, 200)
+                    }
+                    // The type of the hole is only shown once it is known, as the holes in it are not named in the input
+                    TypeErrorVariant::UnsolvedHole(ttype)
+                        if ttype.validate_evars(|_| Err(())).is_err() =>
+                    {
+                        Ok(())
+                    }
+                    TypeErrorVariant::UnsolvedHole(ttype) => {
+                        write!(f, "Expected: ")?;
+                        write_term(f, env, ttype, &mut names, 200)
+                    }
+                    TypeErrorVariant::ScrutineeNotInductive(ttype)
+                    | TypeErrorVariant::NotAnEquality(ttype) => {
+                        write!(f, "Found: ")?;
+                        write_term(f, env, ttype, &mut names, 200)
+                    }
+                    TypeErrorVariant::NothingToRewrite(side, expected) => {
+                        write!(f, "Rewritten: ")?;
+                        write_term(f, env, side, &mut names, 200)?;
+                        writeln!(f)?;
+                        write!(f, "Expected: ")?;
+                        write_term(f, env, expected, &mut names, 200)
+                    }
+                    TypeErrorVariant::UnelaboratedRewrite => Ok(()),
+                    TypeErrorVariant::NoInstance(ttype, trace) => {
+                        write!(f, "Expected: ")?;
+                        write_term(f, env, ttype, &mut names, 200)?;
+                        writeln!(f)?;
+                        write!(f, "Search:")?;
+                        // The instances tried for a goal are listed under it, and their premises under them
+                        for step in trace {
+                            writeln!(f)?;
+                            match step {
+                                InstanceTrace::Goal(depth, goal) => {
+                                    write!(f, "{:1$}", "", 2 + 4 * depth)?;
+                                    write_term(f, env, goal, &mut names, 200)?;
+                                }
+                                InstanceTrace::Mismatch(depth, name) => {
+                                    write!(f, "{:1$}{name} does not apply", "", 4 + 4 * depth)?;
+                                }
+                                InstanceTrace::Matched(depth, name) => {
+                                    write!(f, "{:1$}{name} applies", "", 4 + 4 * depth)?;
+                                }
+                                InstanceTrace::DepthLimit(depth) => {
+                                    write!(f, "{:1$}too deep, giving up", "", 4 + 4 * depth)?;
+                                }
+                            }
+                        }
+                        Ok(())
+                    }
+                    TypeErrorVariant::TacticFailed(_, goal)
+                    | TypeErrorVariant::UnfinishedProof(_, goal) => {
+                        write!(f, "Goal: ")?;
+                        write_term(f, env, goal, &mut names, 200)
+                    }
+                    TypeErrorVariant::SearchFailed(goal, _, unproven) => {
+                        write!(f, "Goal: ")?;
+                        write_term(f, env, goal, &mut names, 200)?;
+                        writeln!(f)?;
+                        write!(f, "Unproven:")?;
+                        for subgoal in unproven {
+                            writeln!(f)?;
+                            write!(f, "  ")?;
+                            write_term(f, env, subgoal, &mut names, 200)?;
+                        }
+                        Ok(())
+                    }
+                    TypeErrorVariant::NoGoals => Ok(()),
                 }
             }
         }
@@ -1,7 +1,13 @@
+pub mod elab;
+pub mod env;
 pub mod eval;
 pub mod lower;
+mod notation;
 pub mod parser;
+mod pattern;
 pub mod print;
+mod record;
+mod ring;
 
 use std::fmt::{self, Display, Write};
 
@@ -57,12 +63,134 @@ pub struct Command {
 }
 
 pub enum CommandVariant {
-    Definition(Ident, Vec<Binder>, Expr, Expr),
+    Definition(Ident, Vec<Binder>, Option<Expr>, Expr),
     Axiom(Ident, Expr),
     Inductive(Vec<IndBody>),
     Print(Ident),
     Check(Expr),
     Compute(Expr),
+    Arguments(Ident, Vec<Argument>),
+    Notation(Notation),
+    /// `Open Scope s`, which makes the notations declared in the scope `s` usable.
+    OpenScope(Ident),
+    /// `Number Notation nat O S : s`, writing numbers with the constructors of an inductive type.
+    NumberNotation(Ident, Vec<Ident>, Option<Ident>),
+    /// `Class C (A : Set) := { f : A -> A }`, a record whose instances are found by resolution.
+    Class(Record),
+    Instance(Instance),
+    /// `Record R (A : Set) := mk { x : A; y : A }`, whose constructor is named `Build_R` when left out.
+    Record(Record),
+    /// `Coercion f : A >-> B`, where either class may also be `Sortclass` or `Funclass`.
+    Coercion(Ident, Ident, Ident),
+    /// `Theorem t (A : Prop) : A -> A. Proof. intro a. exact a. Qed.`, or the same with `Lemma`.
+    Theorem(Ident, Vec<Binder>, Expr, Proof),
+    /// `Show Holes`, which prints the holes `?x` that are admitted as obligations.
+    ShowHoles,
+    /// `Register c as and`, which makes `c` the connective or type automation knows as `and`.
+    Register(Ident, Ident),
+    /// `Hint Resolve l1 l2 : db`, which adds lemmas to the hint database `db`, or to `core` if it is left out.
+    HintResolve(Vec<Ident>, Option<Ident>),
+    /// `Hint Constructors I : db`, which adds the constructors of the inductive type `I` to the hint database `db`.
+    HintConstructors(Vec<Ident>, Option<Ident>),
+    /// `Add Ring r`, which declares the library `ring` proves equalities with, for the ring registered as `ring_carrier`.
+    /// Its correctness lemma is named `r` and the rest of it is prefixed with `r_`.
+    AddRing(Ident),
+}
+
+/// The tactics of a proof, which are run on the statement of the theorem when it is elaborated.
+#[derive(Clone)]
+pub struct Proof {
+    pub tactics: Vec<Tactic>,
+    /// The span of `Qed`, where the goals which remain at the end of the proof are reported
+    pub qed: Span,
+}
+
+#[derive(Clone)]
+pub struct Tactic {
+    pub span: Span,
+    pub variant: TacticVariant,
+}
+
+#[derive(Clone)]
+pub enum TacticVariant {
+    /// `intro x`, or `intro` to name the variable after the binder of the goal.
+    Intro(Option<Ident>),
+    /// `intros x y`, or `intros` to introduce a variable for every product of the goal.
+    Intros(Vec<Ident>),
+    Exact(Expr),
+    /// `apply f`, leaving a goal for each argument of `f` which is not found by unification.
+    Apply(Expr),
+    /// `destruct t as [x y | z]`, naming the arguments of each constructor.
+    Destruct(Expr, Option<Vec<Vec<Ident>>>),
+    /// `induction n`, by the induction principle `I_ind` of the inductive type `I` of `n`.
+    Induction(Ident),
+    Reflexivity,
+    Symmetry,
+    Assumption,
+    Split,
+    /// `tauto`, which proves goals of intuitionistic propositional logic.
+    Tauto,
+    /// `congruence`, which proves equalities following from the equalities among the hypotheses.
+    Congruence,
+    /// `lia`, which proves goals of linear arithmetic over the natural numbers.
+    Lia,
+    /// `ring`, which proves equalities between polynomials over a commutative (semi)ring.
+    Ring,
+    /// `auto n with db`, which searches to the depth `n` for a proof by the hypotheses and the hints of `core` and `db`.
+    Auto(Option<usize>, Vec<Ident>),
+    /// `eauto n with db`, which is `auto` but also applies hints whose arguments are not found by unification.
+    EAuto(Option<usize>, Vec<Ident>),
+}
+
+/// A record type with named fields, each of which may depend on the fields before it.
+pub struct Record {
+    pub name: Ident,
+    pub params: Vec<Binder>,
+    /// The sort of the record, which is `Type{0}` when left out
+    pub sort: Option<Expr>,
+    pub constructor: Option<Ident>,
+    pub fields: Vec<(Ident, Expr)>,
+}
+
+/// An instance `Instance i (A : Set) : C A | 1 := { f := v }` of a class, which is a definition found by instance resolution.
+pub struct Instance {
+    pub name: Ident,
+    pub params: Vec<Binder>,
+    pub ttype: Expr,
+    /// The priority of the instance, with lower priorities tried first, defaulting to 0
+    pub priority: Option<usize>,
+    pub body: InstanceBody,
+}
+
+pub enum InstanceBody {
+    /// A value for each field of the class
+    Fields(Vec<(Ident, Expr)>),
+    /// A term of the class
+    Term(Expr),
+}
+
+/// An argument listed by the `Arguments` command, `{x}` if it is implicit and `x` otherwise.
+pub struct Argument {
+    pub ident: Ident,
+    pub implicit: bool,
+}
+
+/// A notation `Notation "x /\ y" := (and x y) (at level 80, right associativity) : scope`.
+pub struct Notation {
+    /// The string of the notation, with the span of the string literal
+    pub pattern: (Span, String),
+    pub body: Expr,
+    pub level: Option<usize>,
+    pub assoc: Assoc,
+    pub scope: Option<Ident>,
+}
+
+/// The associativity of a notation, which decides how a notation next to itself is parsed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Assoc {
+    Left,
+    Right,
+    None,
 }
 
 /// A single type in a mutually defined inductive type set
@@ -79,13 +207,14 @@ pub struct IndConstructor {
     pub ttype: Expr,
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq)]
 pub enum Sort {
     Prop,
     Set,
     Type(usize),
 }
 
+#[derive(Clone)]
 pub struct Expr {
     pub span: Span,
     pub variant: Box<ExprVariant>,
@@ -98,33 +227,144 @@ impl PartialEq for Expr {
     }
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq)]
 pub struct Binder {
     pub ident: Ident,
-    pub ttype: Expr,
+    /// The type of the binder, or `None` if it should be inferred.
+    pub ttype: Option<Expr>,
+    /// Whether the binder was written `{x : A}` or `[C A]`, making the argument implicit at application sites.
+    pub implicit: bool,
+    /// Whether the binder has the type of the binder before it, as `y` in `(x y : A)`, in which case `ttype` is `None`.
+    pub shared: bool,
 }
 
-pub struct Pattern {
+/// The `in I x y` clause of a match, naming the parameters and indices of the inductive type.
+#[derive(Clone)]
+pub struct IndPattern {
     constructor: Ident,
     params: Vec<Ident>,
 }
 
+impl Eq for IndPattern {}
+impl PartialEq for IndPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.constructor == other.constructor && self.params == other.params
+    }
+}
+
+/// A part of an expression containing the symbols of notations, which is only parsed further once the notations in scope are known.
+#[derive(Clone)]
+pub enum ExprItem {
+    /// A term, applied to the terms right after it, along with its span including any parentheses.
+    Term(Span, Expr),
+    /// A named argument `(x := v)` of the application it is part of.
+    Named(Span, Ident, Expr),
+    /// A symbol such as `/\`.
+    Symbol(Ident),
+}
+
+impl ExprItem {
+    pub fn span(&self) -> &Span {
+        match self {
+            ExprItem::Term(span, _) | ExprItem::Named(span, _, _) => span,
+            ExprItem::Symbol(symbol) => &symbol.span,
+        }
+    }
+}
+
+impl Eq for ExprItem {}
+impl PartialEq for ExprItem {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (ExprItem::Term(_, l), ExprItem::Term(_, r)) => l == r,
+            (ExprItem::Named(_, lx, l), ExprItem::Named(_, rx, r)) => lx == rx && l == r,
+            (ExprItem::Symbol(l), ExprItem::Symbol(r)) => l == r,
+            _ => false,
+        }
+    }
+}
+
+/// A pattern in an arm of a match.
+#[derive(Clone)]
+pub struct Pattern {
+    pub span: Span,
+    pub variant: PatternVariant,
+}
+
 impl Eq for Pattern {}
 impl PartialEq for Pattern {
     fn eq(&self, other: &Self) -> bool {
-        self.constructor == other.constructor && self.params == other.params
+        self.variant == other.variant
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub enum PatternVariant {
+    /// `_`, which matches anything without binding it.
+    Wildcard,
+    /// A constructor without arguments if there is one with that name, and otherwise a variable.
+    Ident(Ident),
+    /// A constructor applied to patterns for its parameters and then its arguments.
+    Constructor(Ident, Vec<Pattern>),
+    /// Alternatives written `(p | q)`, matching anything one of them matches.
+    Or(Vec<Pattern>),
+}
+
+/// An arm of a match, with a row of patterns for each of its alternatives `| p, q | r, s => t`.
+#[derive(Clone)]
+pub struct MatchArm {
+    pub alternatives: Vec<(Span, Vec<Pattern>)>,
+    pub body: Expr,
+}
+
+impl Eq for MatchArm {}
+impl PartialEq for MatchArm {
+    fn eq(&self, other: &Self) -> bool {
+        self.body == other.body
+            && self.alternatives.len() == other.alternatives.len()
+            && self
+                .alternatives
+                .iter()
+                .zip(&other.alternatives)
+                .all(|((_, l), (_, r))| l == r)
     }
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq)]
 pub enum ExprVariant {
     Sort(Sort),
     Var(String),
     App(Expr, Expr),
+    /// An application to a named implicit argument, `f (x := v)`.
+    NamedApp(Expr, Ident, Expr),
+    /// A constant with all of its arguments given explicitly, `@f`.
+    Explicit(String),
+    /// A term left for elaboration to infer, `_`.
+    Hole,
+    /// A hole which is admitted as an obligation named `?x` when elaboration does not solve it, `?x`.
+    Goal(String),
+    /// A decimal number, written with the constructors given by the number notation in scope.
+    Number(String),
     Product(Vec<Binder>, Expr),
     Abstract(Vec<Binder>, Expr),
-    Bind(Ident, Expr, Expr, Expr),
-    Match(Expr, Ident, Pattern, Expr, Vec<(Pattern, Expr)>),
+    Bind(Ident, Option<Expr>, Expr, Expr),
+    /// A match on one or more scrutinees, with the `as`, `in` and `return` clauses which are inferred during elaboration when left out.
+    Match(
+        Vec<Expr>,
+        Option<Ident>,
+        Option<IndPattern>,
+        Option<Expr>,
+        Vec<MatchArm>,
+    ),
+    /// Terms and symbols of notations, which are parsed during lowering.
+    Notation(Vec<ExprItem>),
+    /// A record built from the values of its fields `{| x := a |}`, or from another record with some of them replaced `{| p with x := a |}`.
+    Record(Option<Expr>, Vec<(Ident, Expr)>),
+    /// A projection `p.(x)` of the field `x` of the record `p`.
+    Project(Expr, Ident),
+    /// A term whose expected type is rewritten with an equality, `rewrite H in t`,
+    /// or from right to left when the flag is set, `rewrite <- H in t`.
+    Rewrite(bool, Expr, Expr),
 }
 
 pub struct SpanError<E> {
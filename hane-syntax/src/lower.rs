@@ -1,9 +1,13 @@
-use std::{
-    collections::HashSet,
-    fmt::{self, Display, Formatter},
-};
+use std::fmt::{self, Display, Formatter};
 
-use crate::{Binder, Command, CommandVariant, Expr, ExprVariant, Ident, SpanError};
+use crate::{
+    env::{
+        Arg, Coercion, CoercionClass, Env, Inductive, Instance, Notation, NumberNotation, Numeral,
+        Token, REGISTRABLE,
+    },
+    notation, pattern, record, ring, Binder, Command, CommandVariant, Expr, ExprVariant, Ident,
+    InstanceBody, Span, SpanError,
+};
 use hane_kernel::{Sort, Stack};
 
 pub mod lowered {
@@ -23,6 +27,70 @@ pub enum LoweringError {
     NameNotFree(String),
     UnknownVariable(String),
     ParamsMustMatch,
+    UnknownImplicit(String),
+    NotAConstructor(String),
+    /// A constructor pattern with the wrong number of patterns for its parameters and arguments.
+    PatternArgCount(String, usize, usize),
+    ParameterPattern,
+    ConstructorMismatch(String, String),
+    PatternCount(usize, usize),
+    DuplicatePatternVariable(String),
+    /// A variable which is bound by some alternatives of an or-pattern but not all of them.
+    AlternativeVariable(String),
+    /// A pattern which is not matched by any of the arms.
+    NonExhaustive(String),
+    MatchClausesWithScrutinees,
+    /// A symbol which does not start a notation, or continue the notation before it.
+    UnexpectedSymbol(String),
+    /// A symbol of a notation which is missing from where it should be.
+    MissingSymbol(String),
+    ExpectedTerm,
+    /// A term following a notation which ends with a symbol.
+    UnexpectedTerm,
+    InvalidNotation(String),
+    InvalidNumberNotation(String),
+    NoNumberNotation(String),
+    /// A number which cannot be written with the constructors of the number notation in scope.
+    UnrepresentableNumber(String, String),
+    /// The type of an instance, which is not a class applied to its parameters.
+    NotAClass,
+    /// A field given a value, which the record does not have.
+    UnknownField(String, String),
+    DuplicateField(String),
+    MissingField(String),
+    /// A name used as a field, which is not a field of any record.
+    NotAField(String),
+    InvalidCoercion(String),
+    /// A name to register a constant as, which automation does not know.
+    UnknownRegistration(String),
+    NotAnInductive(String),
+    /// A ring declared by `Add Ring`, which is missing a registration or whose equality is not an inductive type.
+    InvalidRing(String),
+}
+
+pub enum LoweringWarning {
+    RedundantClause,
+}
+
+/// The state shared by everything lowered as part of a single command.
+#[derive(Default)]
+pub struct State {
+    /// Every hole in the command gets a distinct id, which elaboration replaces with a metavariable
+    holes: usize,
+    /// The warnings are handed to the `Env` once the whole command has been lowered
+    warnings: Vec<SpanError<LoweringWarning>>,
+}
+
+impl State {
+    pub fn warn(&mut self, span: Span, warning: LoweringWarning) {
+        self.warnings.push(SpanError { span, err: warning });
+    }
+
+    /// Reserves `count` hole ids, returning the first of them.
+    pub(crate) fn reserve_holes(&mut self, count: usize) -> usize {
+        self.holes += count;
+        self.holes - count
+    }
 }
 
 impl Display for LoweringError {
@@ -34,16 +102,206 @@ impl Display for LoweringError {
                 f,
                 "Parameters must be syntactically the same on all mutually defined types"
             ),
+            LoweringError::UnknownImplicit(x) => {
+                write!(f, "No implicit argument named `{x}`")
+            }
+            LoweringError::NotAConstructor(x) => write!(f, "`{x}` is not a constructor"),
+            LoweringError::PatternArgCount(x, expected, found) => write!(
+                f,
+                "The constructor `{x}` expects {expected} patterns for its parameters and arguments, found {found}"
+            ),
+            LoweringError::ParameterPattern => {
+                write!(f, "Parameters can only be bound by variables or `_`")
+            }
+            LoweringError::ConstructorMismatch(ind, x) => {
+                write!(f, "Expected a constructor of `{ind}`, found `{x}`")
+            }
+            LoweringError::PatternCount(expected, found) => {
+                write!(f, "Expected {expected} patterns, found {found}")
+            }
+            LoweringError::DuplicatePatternVariable(x) => {
+                write!(f, "The variable `{x}` is bound several times in this pattern")
+            }
+            LoweringError::AlternativeVariable(x) => {
+                write!(f, "The variable `{x}` must be bound by every alternative")
+            }
+            LoweringError::NonExhaustive(pattern) => {
+                write!(f, "Non exhaustive match, `{pattern}` is not matched")
+            }
+            LoweringError::MatchClausesWithScrutinees => write!(
+                f,
+                "The `as`, `in` and `return` clauses can only be given with a single scrutinee"
+            ),
+            LoweringError::UnexpectedSymbol(s) => write!(f, "Unexpected symbol `{s}`"),
+            LoweringError::MissingSymbol(s) => write!(f, "Expected the symbol `{s}`"),
+            LoweringError::ExpectedTerm => write!(f, "Expected a term"),
+            LoweringError::UnexpectedTerm => write!(f, "Unexpected term after a notation"),
+            LoweringError::InvalidNotation(reason) => write!(f, "Invalid notation, {reason}"),
+            LoweringError::InvalidNumberNotation(reason) => {
+                write!(f, "Invalid number notation, {reason}")
+            }
+            LoweringError::NoNumberNotation(n) => {
+                write!(f, "No number notation is in scope for writing `{n}`")
+            }
+            LoweringError::UnrepresentableNumber(n, ind) => {
+                write!(f, "`{n}` cannot be written with `{ind}`")
+            }
+            LoweringError::NotAClass => write!(f, "The type of an instance must be a class"),
+            LoweringError::UnknownField(record, x) => {
+                write!(f, "The record `{record}` has no field named `{x}`")
+            }
+            LoweringError::DuplicateField(x) => {
+                write!(f, "The field `{x}` is given several times")
+            }
+            LoweringError::MissingField(x) => write!(f, "Missing a value for the field `{x}`"),
+            LoweringError::NotAField(x) => write!(f, "`{x}` is not a field of any record"),
+            LoweringError::InvalidCoercion(reason) => write!(f, "Invalid coercion, {reason}"),
+            LoweringError::UnknownRegistration(role) => {
+                write!(f, "Nothing can be registered as `{role}`")
+            }
+            LoweringError::NotAnInductive(x) => write!(f, "`{x}` is not an inductive type"),
+            LoweringError::InvalidRing(reason) => write!(f, "Invalid ring, {reason}"),
+        }
+    }
+}
+
+impl Display for LoweringWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            LoweringWarning::RedundantClause => write!(f, "This clause is redundant"),
+        }
+    }
+}
+
+/// Returns the arguments named by `binders`.
+pub(crate) fn binder_args(binders: &[Binder]) -> impl Iterator<Item = Arg> + '_ {
+    binders.iter().map(|binder| Arg {
+        name: binder.ident.name.clone(),
+        implicit: binder.implicit,
+    })
+}
+
+/// Returns the arguments named by the leading products of `ttype`.
+pub(crate) fn product_args(mut ttype: &Expr) -> Vec<Arg> {
+    let mut args = Vec::new();
+    while let ExprVariant::Product(binders, body) = &*ttype.variant {
+        args.extend(binder_args(binders));
+        ttype = body;
+    }
+    args
+}
+
+/// Lowers a type annotation, leaving a hole at `span` if it was left out.
+fn lower_annotation(
+    ttype: Option<Expr>,
+    span: &Span,
+    global: &Env,
+    names: &mut Stack<Ident>,
+    state: &mut State,
+) -> Result<lowered::Term, SpanError<LoweringError>> {
+    match ttype {
+        Some(ttype) => ttype.lower(global, names, state),
+        None => Ok(hole(span.clone(), state)),
+    }
+}
+
+/// Returns a new hole at `span`, to be solved during elaboration.
+pub(crate) fn hole(span: Span, state: &mut State) -> lowered::Term {
+    state.holes += 1;
+    lowered::Term {
+        meta: span,
+        variant: Box::new(lowered::TermVariant::Evar(state.holes - 1, Vec::new())),
+    }
+}
+
+/// Remembers the declared types of the constants defined by `commands`.
+fn set_types(global: &mut Env, commands: &[lowered::Command]) {
+    let generalized = |params: &[lowered::Binder], ttype: &lowered::Term| {
+        params
+            .iter()
+            .rev()
+            .fold(ttype.clone(), |ttype, param| lowered::Term {
+                meta: ttype.meta.clone(),
+                variant: Box::new(lowered::TermVariant::Product(
+                    param.x.clone(),
+                    param.ttype.clone(),
+                    ttype,
+                )),
+            })
+    };
+    for command in commands {
+        match &command.variant {
+            lowered::CommandVariant::Definition(name, ttype, _)
+            | lowered::CommandVariant::Axiom(name, ttype) => {
+                global.set_type(name.clone(), ttype.clone());
+            }
+            lowered::CommandVariant::Inductive(params, bodies) => {
+                for body in bodies {
+                    global.set_type(body.name.clone(), generalized(params, &body.ttype));
+                    for constructor in &body.constructors {
+                        global.set_type(
+                            constructor.name.clone(),
+                            generalized(params, &constructor.ttype),
+                        );
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Checks that `ttype` is of the form `forall params, source params -> target`,
+/// where the arity counts the parameters along with the converted term.
+/// A type left to be inferred is not known yet, so it is accepted.
+fn coercion_type(
+    ttype: &lowered::Term,
+    source: &str,
+    target: &CoercionClass,
+    arity: usize,
+) -> bool {
+    let mut codomain = ttype;
+    let mut source_type = None;
+    for _ in 0..arity {
+        match &*codomain.variant {
+            lowered::TermVariant::Product(_, ttype, body) => {
+                source_type = Some(ttype);
+                codomain = body;
+            }
+            lowered::TermVariant::Evar(..) => return true,
+            _ => return false,
         }
     }
+    let Some(source_type) = source_type else {
+        return false;
+    };
+    // The converted term is the last argument, so the parameters before it are applied in order
+    let (head, args) = source_type.strip_args_ref();
+    let uniform = args.iter().enumerate().all(
+        |(i, arg)| matches!(&*arg.variant, lowered::TermVariant::Var(x) if *x == arity - 2 - i),
+    );
+    if !head.is_const(source) || args.len() != arity - 1 || !uniform {
+        return false;
+    }
+    match (target, &*codomain.app_head().variant) {
+        (_, lowered::TermVariant::Evar(..)) => true,
+        (CoercionClass::Sort, lowered::TermVariant::Sort(_)) => true,
+        (CoercionClass::Fun, lowered::TermVariant::Product(..)) => true,
+        (CoercionClass::Const(target), _) => codomain.app_head().is_const(target),
+        _ => false,
+    }
 }
 
 impl Command {
+    /// Lowers the command into kernel commands, which are none if it only changes the syntactic environment,
+    /// and several if it declares more than one thing, as a class along with its projections.
     pub fn lower(
         self,
-        global: &mut HashSet<String>,
-    ) -> Result<lowered::Command, SpanError<LoweringError>> {
+        global: &mut Env,
+    ) -> Result<Vec<lowered::Command>, SpanError<LoweringError>> {
         let mut names = Stack::new();
+        let mut state = State::default();
+        let state = &mut state;
         let variant = match self.variant {
             CommandVariant::Definition(ident, params, ttype, value) => {
                 if global.contains(&ident.name) {
@@ -52,27 +310,43 @@ impl Command {
                         err: LoweringError::NameNotFree(ident.name),
                     });
                 }
+                let args = binder_args(&params)
+                    .chain(ttype.iter().flat_map(product_args))
+                    .collect();
                 let mut names = names.slot();
                 let mut lowered_params = Vec::with_capacity(params.len());
                 for param in params {
                     let name = param.ident.clone();
-                    lowered_params.push(param.lower(global, &mut names)?);
+                    lowered_params.push(param.lower(
+                        lowered_params.last(),
+                        global,
+                        &mut names,
+                        state,
+                    )?);
                     names.push_onto(name);
                 }
-                let ttype = ttype.lower(global, &mut names)?;
-                let value = value.lower(global, &mut names)?;
-                let ttype = lowered_params
-                    .iter()
-                    .cloned()
-                    .rev()
-                    .fold(ttype, |ttype, binder| lowered::Term {
-                        meta: ttype.meta.clone(),
-                        variant: Box::new(lowered::TermVariant::Product(
-                            binder.x,
-                            binder.ttype,
-                            ttype,
-                        )),
-                    });
+                let ttype = ttype
+                    .map(|ttype| ttype.lower(global, &mut names, state))
+                    .transpose()?;
+                let value = value.lower(global, &mut names, state)?;
+                // Without an annotation the whole type is inferred from the value, parameters included
+                let ttype = match ttype {
+                    Some(ttype) => {
+                        lowered_params
+                            .iter()
+                            .cloned()
+                            .rev()
+                            .fold(ttype, |ttype, binder| lowered::Term {
+                                meta: ttype.meta.clone(),
+                                variant: Box::new(lowered::TermVariant::Product(
+                                    binder.x,
+                                    binder.ttype,
+                                    ttype,
+                                )),
+                            })
+                    }
+                    None => hole(ident.span.clone(), state),
+                };
                 let value = lowered_params
                     .into_iter()
                     .rev()
@@ -85,6 +359,7 @@ impl Command {
                         )),
                     });
                 global.insert(ident.name.clone());
+                global.set_args(ident.name.clone(), args);
                 lowered::CommandVariant::Definition(ident.name, ttype, value)
             }
             CommandVariant::Axiom(ident, ttype) => {
@@ -94,8 +369,10 @@ impl Command {
                         err: LoweringError::NameNotFree(ident.name),
                     });
                 }
-                let ttype = ttype.lower(global, &mut names)?;
+                let args = product_args(&ttype);
+                let ttype = ttype.lower(global, &mut names, state)?;
                 global.insert(ident.name.clone());
+                global.set_args(ident.name.clone(), args);
                 lowered::CommandVariant::Axiom(ident.name, ttype)
             }
             CommandVariant::Inductive(mut bodies) => {
@@ -118,12 +395,31 @@ impl Command {
                         }
                     })?;
 
+                // The types and constructors all take the parameters as their first arguments
+                let param_args = binder_args(&params).collect::<Vec<_>>();
+                let mut args = Vec::new();
+                for body in &bodies {
+                    let mut body_args = param_args.clone();
+                    body_args.extend(product_args(&body.ttype));
+                    args.push((body.name.name.clone(), body_args));
+                    for constructor in &body.constructors {
+                        let mut constructor_args = param_args.clone();
+                        constructor_args.extend(product_args(&constructor.ttype));
+                        args.push((constructor.name.name.clone(), constructor_args));
+                    }
+                }
+
                 // Next we lower all the parameters and push then into our local name scope
                 let mut lowered_params = Vec::with_capacity(params.len());
                 let mut names = names.slot();
                 for param in params {
                     let name = param.ident.clone();
-                    lowered_params.push(param.lower(global, &mut names)?);
+                    lowered_params.push(param.lower(
+                        lowered_params.last(),
+                        global,
+                        &mut names,
+                        state,
+                    )?);
                     names.push_onto(name);
                 }
 
@@ -140,7 +436,7 @@ impl Command {
                             },
                         )
                     })
-                    .map(|ttype| ttype.lower(global, &mut names))
+                    .map(|ttype| ttype.lower(global, &mut names, state))
                     .collect::<Result<Vec<_>, _>>()?;
 
                 // With the types sorts lowered we can put the type names into the global name set as they are needed to handle the constructors
@@ -167,7 +463,7 @@ impl Command {
                                 cspans.push(constructor.name.span);
                                 Ok(lowered::IndConstructor {
                                     name: constructor.name.name,
-                                    ttype: constructor.ttype.lower(global, &mut names)?,
+                                    ttype: constructor.ttype.lower(global, &mut names, state)?,
                                 })
                             })
                             .collect::<Result<_, SpanError<LoweringError>>>()?;
@@ -193,6 +489,39 @@ impl Command {
                                 err: LoweringError::NameNotFree(constructor.name.clone()),
                             })
                     })?;
+                for (name, args) in args {
+                    global.set_args(name, args);
+                }
+                // The constructors are remembered for telling them apart from variables in patterns
+                for body in &lowered_bodies {
+                    let constructors = body
+                        .constructors
+                        .iter()
+                        .map(|constructor| {
+                            let mut arity = 0;
+                            let mut ttype = &constructor.ttype;
+                            while let lowered::TermVariant::Product(_, _, body) = &*ttype.variant {
+                                arity += 1;
+                                ttype = body;
+                            }
+                            (constructor.name.clone(), arity)
+                        })
+                        .collect();
+                    let mut indices = 0;
+                    let mut ttype = &body.ttype;
+                    while let lowered::TermVariant::Product(_, _, body) = &*ttype.variant {
+                        indices += 1;
+                        ttype = body;
+                    }
+                    global.add_inductive(
+                        body.name.clone(),
+                        Inductive {
+                            params: lowered_params.len(),
+                            indices,
+                            constructors,
+                        },
+                    );
+                }
                 lowered::CommandVariant::Inductive(lowered_params, lowered_bodies)
             }
             CommandVariant::Print(ident) => {
@@ -204,29 +533,351 @@ impl Command {
                 }
                 lowered::CommandVariant::Print(ident.name)
             }
+            CommandVariant::ShowHoles => lowered::CommandVariant::ShowAdmitted,
+            CommandVariant::Register(ident, role) => {
+                if !global.contains(&ident.name) {
+                    return Err(SpanError {
+                        span: ident.span,
+                        err: LoweringError::UnknownVariable(ident.name),
+                    });
+                }
+                if !REGISTRABLE.contains(&role.name.as_str()) {
+                    return Err(SpanError {
+                        span: role.span,
+                        err: LoweringError::UnknownRegistration(role.name),
+                    });
+                }
+                global.register(ident.name, role.name);
+                return Ok(Vec::new());
+            }
+            CommandVariant::HintResolve(idents, database) => {
+                let database = database.map_or_else(|| "core".to_owned(), |db| db.name);
+                for ident in &idents {
+                    if !global.contains(&ident.name) {
+                        return Err(SpanError {
+                            span: ident.span.clone(),
+                            err: LoweringError::UnknownVariable(ident.name.clone()),
+                        });
+                    }
+                }
+                for ident in idents {
+                    global.add_hint(database.clone(), ident.name);
+                }
+                return Ok(Vec::new());
+            }
+            CommandVariant::HintConstructors(idents, database) => {
+                let database = database.map_or_else(|| "core".to_owned(), |db| db.name);
+                let mut constructors = Vec::new();
+                for ident in idents {
+                    let Some(inductive) = global.inductive(&ident.name) else {
+                        return Err(SpanError {
+                            span: ident.span,
+                            err: LoweringError::NotAnInductive(ident.name),
+                        });
+                    };
+                    constructors.extend(inductive.constructors.iter().map(|(c, _)| c.clone()));
+                }
+                for constructor in constructors {
+                    global.add_hint(database.clone(), constructor);
+                }
+                return Ok(Vec::new());
+            }
             CommandVariant::Check(expr) => {
-                let term = expr.lower(global, &mut names)?;
+                let term = expr.lower(global, &mut names, state)?;
                 lowered::CommandVariant::Check(term)
             }
             CommandVariant::Compute(expr) => {
-                let term = expr.lower(global, &mut names)?;
+                let term = expr.lower(global, &mut names, state)?;
                 lowered::CommandVariant::Compute(term)
             }
+            CommandVariant::Arguments(ident, args) => {
+                if !global.contains(&ident.name) {
+                    return Err(SpanError {
+                        span: ident.span,
+                        err: LoweringError::UnknownVariable(ident.name),
+                    });
+                }
+                let args = args
+                    .into_iter()
+                    .map(|arg| Arg {
+                        name: arg.ident.name,
+                        implicit: arg.implicit,
+                    })
+                    .collect();
+                global.set_args(ident.name, args);
+                return Ok(Vec::new());
+            }
+            CommandVariant::Notation(notation) => {
+                let (span, pattern) = notation.pattern;
+                let tokens = notation::tokens(&pattern).map_err(|reason| SpanError {
+                    span: span.clone(),
+                    err: LoweringError::InvalidNotation(reason),
+                })?;
+                // The body can only refer to the variables of the notation and to constants
+                let mut names = names.slot();
+                for token in &tokens {
+                    if let Token::Var(x) = token {
+                        names.push_onto(Ident {
+                            span: span.clone(),
+                            name: x.clone(),
+                        });
+                    }
+                }
+                let body = notation.body.lower(global, &mut names, state)?;
+                global.add_notation(Notation {
+                    tokens,
+                    level: notation.level.unwrap_or(0),
+                    assoc: notation.assoc,
+                    scope: notation.scope.map(|scope| scope.name),
+                    body,
+                    holes: state.holes,
+                });
+                return Ok(Vec::new());
+            }
+            CommandVariant::OpenScope(scope) => {
+                global.open_scope(scope.name);
+                return Ok(Vec::new());
+            }
+            CommandVariant::NumberNotation(ind, constructors, scope) => {
+                let invalid = |span: &Span, reason: String| SpanError {
+                    span: span.clone(),
+                    err: LoweringError::InvalidNumberNotation(reason),
+                };
+                let inductive = global.inductive(&ind.name).ok_or_else(|| {
+                    invalid(
+                        &ind.span,
+                        format!("`{}` is not an inductive type", ind.name),
+                    )
+                })?;
+                if inductive.params + inductive.indices > 0 {
+                    return Err(invalid(
+                        &ind.span,
+                        format!("`{}` has parameters or indices", ind.name),
+                    ));
+                }
+                // The first constructor is the smallest number, and the others build on a single number
+                for (i, constructor) in constructors.iter().enumerate() {
+                    let arity = usize::from(i > 0);
+                    if !inductive
+                        .constructors
+                        .contains(&(constructor.name.clone(), arity))
+                    {
+                        return Err(invalid(
+                            &constructor.span,
+                            format!(
+                                "expected a constructor of `{}` with {arity} arguments",
+                                ind.name
+                            ),
+                        ));
+                    }
+                }
+                let mut names = constructors.into_iter().map(|constructor| constructor.name);
+                let numeral = match names.len() {
+                    2 => Numeral::Unary {
+                        zero: names.next().unwrap(),
+                        succ: names.next().unwrap(),
+                    },
+                    3 => Numeral::Binary {
+                        one: names.next().unwrap(),
+                        double: names.next().unwrap(),
+                        double_succ: names.next().unwrap(),
+                    },
+                    _ => {
+                        return Err(invalid(
+                            &self.span,
+                            "expected either a zero and a successor, or a one, a doubling and a doubling plus one".to_owned(),
+                        ))
+                    }
+                };
+                global.add_number_notation(NumberNotation {
+                    ind: ind.name,
+                    numeral,
+                    scope: scope.map(|scope| scope.name),
+                });
+                return Ok(Vec::new());
+            }
+            CommandVariant::Class(class) => {
+                let commands = record::lower(self.span, class, true, global, state)?;
+                set_types(global, &commands);
+                global.warn(std::mem::take(&mut state.warnings));
+                return Ok(commands);
+            }
+            CommandVariant::Record(record) => {
+                let commands = record::lower(self.span, record, false, global, state)?;
+                set_types(global, &commands);
+                global.warn(std::mem::take(&mut state.warnings));
+                return Ok(commands);
+            }
+            CommandVariant::AddRing(name) => {
+                let commands = ring::lower(self.span, name, global)?;
+                set_types(global, &commands);
+                return Ok(commands);
+            }
+            CommandVariant::Instance(instance) => {
+                // The class is the head of the type, which the instance is a value of
+                let mut head = &instance.ttype;
+                while let ExprVariant::App(f, _) | ExprVariant::NamedApp(f, _, _) = &*head.variant {
+                    head = f;
+                }
+                let class = match &*head.variant {
+                    ExprVariant::Var(x) | ExprVariant::Explicit(x) if global.is_class(x) => {
+                        x.clone()
+                    }
+                    _ => {
+                        return Err(SpanError {
+                            span: instance.ttype.span,
+                            err: LoweringError::NotAClass,
+                        })
+                    }
+                };
+                let value = match instance.body {
+                    InstanceBody::Term(value) => value,
+                    InstanceBody::Fields(values) => {
+                        // The fields must be those of the class, as the record is otherwise found from the first of them
+                        let record = global.record(&class).unwrap();
+                        if let Some((x, _)) = values
+                            .iter()
+                            .find(|(x, _)| !record.fields.contains(&x.name))
+                        {
+                            return Err(SpanError {
+                                span: x.span.clone(),
+                                err: LoweringError::UnknownField(class, x.name.clone()),
+                            });
+                        }
+                        let expr = |variant| Expr {
+                            span: self.span.clone(),
+                            variant: Box::new(variant),
+                        };
+                        if values.is_empty() {
+                            // Without a field to find the record from, the constructor is applied to the parameters alone
+                            let params = global.inductive(&class).unwrap().params;
+                            (0..params).fold(
+                                expr(ExprVariant::Explicit(record.constructor.clone())),
+                                |value, _| expr(ExprVariant::App(value, expr(ExprVariant::Hole))),
+                            )
+                        } else {
+                            expr(ExprVariant::Record(None, values))
+                        }
+                    }
+                };
+                let name = instance.name.name.clone();
+                let commands = Command {
+                    span: self.span,
+                    variant: CommandVariant::Definition(
+                        instance.name,
+                        instance.params,
+                        Some(instance.ttype),
+                        value,
+                    ),
+                }
+                .lower(global)?;
+                global.add_instance(Instance {
+                    name,
+                    class,
+                    priority: instance.priority.unwrap_or(0),
+                });
+                return Ok(commands);
+            }
+            CommandVariant::Theorem(name, params, ttype, proof) => {
+                // The proof is a hole, which is filled by running the tactics when the theorem is elaborated
+                let hole = Expr {
+                    span: proof.qed.clone(),
+                    variant: Box::new(ExprVariant::Hole),
+                };
+                let theorem = name.name.clone();
+                let commands = Command {
+                    span: self.span,
+                    variant: CommandVariant::Definition(name, params, Some(ttype), hole),
+                }
+                .lower(global)?;
+                global.add_proof(theorem, proof);
+                return Ok(commands);
+            }
+            CommandVariant::Coercion(name, source, target) => {
+                let class = |class: Ident| match class.name.as_str() {
+                    "Sortclass" => Ok(CoercionClass::Sort),
+                    "Funclass" => Ok(CoercionClass::Fun),
+                    _ if global.contains(&class.name) => Ok(CoercionClass::Const(class.name)),
+                    _ => Err(SpanError {
+                        span: class.span,
+                        err: LoweringError::UnknownVariable(class.name),
+                    }),
+                };
+                if !global.contains(&name.name) {
+                    return Err(SpanError {
+                        span: name.span,
+                        err: LoweringError::UnknownVariable(name.name),
+                    });
+                }
+                let source_span = source.span.clone();
+                let source = class(source)?;
+                let target = class(target)?;
+                // The coercion takes the parameters and indices of the source before the term it converts
+                let (ind, arity) = match &source {
+                    CoercionClass::Const(ind) => (
+                        ind,
+                        global
+                            .inductive(ind)
+                            .map_or(0, |ind| ind.params + ind.indices),
+                    ),
+                    _ => {
+                        return Err(SpanError {
+                            span: source_span,
+                            err: LoweringError::InvalidCoercion(
+                                "the source must be a type constant".to_owned(),
+                            ),
+                        })
+                    }
+                };
+                let well_typed = global
+                    .ttype(&name.name)
+                    .is_none_or(|ttype| coercion_type(ttype, ind, &target, arity + 1));
+                if !well_typed {
+                    let target = match &target {
+                        CoercionClass::Sort => "Sortclass",
+                        CoercionClass::Fun => "Funclass",
+                        CoercionClass::Const(target) => target,
+                    };
+                    return Err(SpanError {
+                        span: name.span,
+                        err: LoweringError::InvalidCoercion(format!(
+                            "the type of `{}` must be of the form `forall params, {ind} params -> {target}`",
+                            name.name
+                        )),
+                    });
+                }
+                global.add_coercion(Coercion {
+                    name: name.name,
+                    source,
+                    target,
+                    arity: arity + 1,
+                });
+                return Ok(Vec::new());
+            }
         };
-        Ok(lowered::Command {
+        global.warn(std::mem::take(&mut state.warnings));
+        let commands = vec![lowered::Command {
             meta: self.span,
             variant,
-        })
+        }];
+        set_types(global, &commands);
+        Ok(commands)
     }
 }
 
 impl Binder {
+    /// Lowers the binder, given the binder right before it if there is one.
     pub fn lower(
         self,
-        global: &HashSet<String>,
+        previous: Option<&lowered::Binder>,
+        global: &Env,
         names: &mut Stack<Ident>,
+        state: &mut State,
     ) -> Result<lowered::Binder, SpanError<LoweringError>> {
-        let ttype = self.ttype.lower(global, names)?;
+        let ttype = match previous {
+            Some(previous) if self.shared => previous.ttype.push(1),
+            _ => lower_annotation(self.ttype, &self.ident.span, global, names, state)?,
+        };
         Ok(lowered::Binder {
             x: self.ident,
             ttype,
@@ -237,9 +888,16 @@ impl Binder {
 impl Expr {
     pub fn lower(
         self,
-        global: &HashSet<String>,
+        global: &Env,
         names: &mut Stack<Ident>,
+        state: &mut State,
     ) -> Result<lowered::Term, SpanError<LoweringError>> {
+        // A constant with implicit arguments gets them inserted even when it is not applied
+        if let ExprVariant::Var(x) = &*self.variant {
+            if global.args(x).is_some() && !names.iter().any(|y| *x == y.name) {
+                return self.lower_app(Vec::new(), global, names, state);
+            }
+        }
         let variant = match *self.variant {
             ExprVariant::Sort(sort) => lowered::TermVariant::Sort(sort.lower()),
             ExprVariant::Var(x) => {
@@ -254,19 +912,63 @@ impl Expr {
                     });
                 }
             }
+            ExprVariant::Explicit(x) => {
+                if global.contains(&x) {
+                    lowered::TermVariant::Const(x)
+                } else {
+                    return Err(SpanError {
+                        span: self.span.clone(),
+                        err: LoweringError::UnknownVariable(x.to_owned()),
+                    });
+                }
+            }
+            ExprVariant::Hole => return Ok(hole(self.span, state)),
+            // No other constant has a name starting with `?`, so elaboration knows to replace it with a metavariable
+            ExprVariant::Goal(x) => lowered::TermVariant::Const(format!("?{x}")),
+            ExprVariant::Record(base, values) => {
+                return record::construct(self.span, base, values, global, names, state)
+            }
+            ExprVariant::Project(t, x) => {
+                return record::project(self.span, t, x, global, names, state)
+            }
+            ExprVariant::Number(n) => {
+                let Some(notation) = global.number_notation() else {
+                    return Err(SpanError {
+                        span: self.span,
+                        err: LoweringError::NoNumberNotation(n),
+                    });
+                };
+                return match n.parse().ok().and_then(|n| notation.term(&self.span, n)) {
+                    Some(term) => Ok(term),
+                    None => Err(SpanError {
+                        span: self.span,
+                        err: LoweringError::UnrepresentableNumber(n, notation.ind.clone()),
+                    }),
+                };
+            }
+            ExprVariant::Notation(items) => {
+                return notation::lower(self.span, items, global, names, state)
+            }
             ExprVariant::App(f, v) => {
-                lowered::TermVariant::App(f.lower(global, names)?, v.lower(global, names)?)
+                return f.lower_app(vec![(self.span, None, v)], global, names, state)
+            }
+            ExprVariant::NamedApp(f, x, v) => {
+                return f.lower_app(vec![(self.span, Some(x), v)], global, names, state)
             }
             ExprVariant::Product(binders, t) => {
-                let mut type_stack = Vec::new();
+                let mut lowered_binders: Vec<lowered::Binder> = Vec::new();
                 let mut names = names.slot();
-                for Binder { ident, ttype } in binders {
-                    let lowered_type = ttype.lower(global, &mut names)?;
-                    type_stack.push(lowered_type);
+                for binder in binders {
+                    let ident = binder.ident.clone();
+                    let lowered =
+                        binder.lower(lowered_binders.last(), global, &mut names, state)?;
+                    lowered_binders.push(lowered);
                     names.push_onto(ident);
                 }
-                let t = t.lower(global, &mut names)?;
-                let mut iter = names.pop().zip(type_stack.into_iter().rev());
+                let t = t.lower(global, &mut names, state)?;
+                let mut iter = names
+                    .pop()
+                    .zip(lowered_binders.into_iter().rev().map(|binder| binder.ttype));
 
                 let make_term = |inner, (name, ttype)| lowered::Term {
                     meta: self.span.clone(),
@@ -276,15 +978,19 @@ impl Expr {
                 return Ok(iter.fold(make_term(t, inner), make_term));
             }
             ExprVariant::Abstract(binders, t) => {
-                let mut type_stack = Vec::new();
+                let mut lowered_binders: Vec<lowered::Binder> = Vec::new();
                 let mut names = names.slot();
-                for Binder { ident, ttype } in binders {
-                    let lowered_type = ttype.lower(global, &mut names)?;
-                    type_stack.push(lowered_type);
+                for binder in binders {
+                    let ident = binder.ident.clone();
+                    let lowered =
+                        binder.lower(lowered_binders.last(), global, &mut names, state)?;
+                    lowered_binders.push(lowered);
                     names.push_onto(ident);
                 }
-                let t = t.lower(global, &mut names)?;
-                let mut iter = names.pop().zip(type_stack.into_iter().rev());
+                let t = t.lower(global, &mut names, state)?;
+                let mut iter = names
+                    .pop()
+                    .zip(lowered_binders.into_iter().rev().map(|binder| binder.ttype));
 
                 let make_term = |inner, (name, ttype)| lowered::Term {
                     meta: self.span.clone(),
@@ -294,61 +1000,90 @@ impl Expr {
                 return Ok(iter.fold(make_term(t, inner), make_term));
             }
             ExprVariant::Bind(x, x_tp, x_val, t) => {
-                let x_tp = x_tp.lower(global, names)?;
-                let x_val = x_val.lower(global, names)?;
+                let x_tp = lower_annotation(x_tp, &x.span, global, names, state)?;
+                let x_val = x_val.lower(global, names, state)?;
                 let mut names = names.push(x);
-                let t = t.lower(global, &mut names);
+                let t = t.lower(global, &mut names, state);
                 let x = names.pop().next().unwrap();
                 let t = t?;
                 lowered::TermVariant::Bind(x, x_tp, x_val, t)
             }
-            ExprVariant::Match(t, mut name, pat, ret, arms) => {
-                let t = t.lower(global, names)?;
-                let ret = {
-                    if !global.contains(&pat.constructor.name) {
-                        return Err(SpanError {
-                            span: pat.constructor.span.clone(),
-                            err: LoweringError::UnknownVariable(pat.constructor.name),
-                        });
-                    }
-                    let mut names = names.slot();
-                    names.extend(pat.params);
-                    let body = {
-                        let mut names = names.push(name);
-                        let ret = ret.lower(global, &mut names)?;
-                        name = names.pop().next().unwrap();
-                        ret
-                    };
-                    let params = names.pop().rev().collect();
-                    lowered::MatchArm {
-                        meta: pat.constructor.span,
-                        constructor: pat.constructor.name,
-                        params,
-                        body,
-                    }
-                };
-                let arms = arms
+            ExprVariant::Match(scrutinees, name, pat, ret, arms) => {
+                if scrutinees.len() > 1 && (name.is_some() || pat.is_some() || ret.is_some()) {
+                    return Err(SpanError {
+                        span: self.span,
+                        err: LoweringError::MatchClausesWithScrutinees,
+                    });
+                }
+                // Without an `as` clause a variable is matched under its own name, like in Coq
+                let name = (scrutinees.len() == 1).then(|| {
+                    let t = &scrutinees[0];
+                    name.unwrap_or_else(|| match &*t.variant {
+                        ExprVariant::Var(x) => Ident {
+                            name: x.clone(),
+                            span: t.span.clone(),
+                        },
+                        _ => Ident {
+                            name: "_".to_owned(),
+                            span: t.span.clone(),
+                        },
+                    })
+                });
+                let scrutinees = scrutinees
                     .into_iter()
-                    .map(|(pat, body)| {
-                        if !global.contains(&pat.constructor.name) {
-                            return Err(SpanError {
-                                span: pat.constructor.span.clone(),
-                                err: LoweringError::UnknownVariable(pat.constructor.name),
-                            });
-                        }
+                    .map(|t| t.lower(global, names, state))
+                    .collect::<Result<Vec<_>, _>>()?;
+                // A left out `in` clause is marked by an empty inductive name, and a left out `return` clause by a hole.
+                // Both are inferred during elaboration.
+                let clauses = match name {
+                    Some(mut name) => {
+                        let (meta, constructor, params) = match pat {
+                            Some(pat) => {
+                                if !global.contains(&pat.constructor.name) {
+                                    return Err(SpanError {
+                                        span: pat.constructor.span.clone(),
+                                        err: LoweringError::UnknownVariable(pat.constructor.name),
+                                    });
+                                }
+                                (pat.constructor.span, pat.constructor.name, pat.params)
+                            }
+                            None => (self.span.clone(), String::new(), Vec::new()),
+                        };
                         let mut names = names.slot();
-                        names.extend(pat.params);
-                        let body = body.lower(global, &mut names)?;
+                        names.extend(params);
+                        let body = {
+                            let mut names = names.push(name);
+                            let ret = match ret {
+                                Some(ret) => ret.lower(global, &mut names, state),
+                                None => Ok(hole(self.span.clone(), state)),
+                            };
+                            name = names.pop().next().unwrap();
+                            ret?
+                        };
                         let params = names.pop().rev().collect();
-                        Ok(lowered::MatchArm {
-                            meta: pat.constructor.span,
-                            constructor: pat.constructor.name,
-                            params,
-                            body,
-                        })
-                    })
-                    .collect::<Result<_, SpanError<LoweringError>>>()?;
-                lowered::TermVariant::Match(t, name, ret, arms)
+                        Some((
+                            name,
+                            lowered::MatchArm {
+                                meta,
+                                constructor,
+                                params,
+                                body,
+                            },
+                        ))
+                    }
+                    None => None,
+                };
+                return pattern::compile(
+                    self.span, scrutinees, clauses, arms, global, names, state,
+                );
+            }
+            ExprVariant::Rewrite(reverse, eq, t) => {
+                // The motive is built from the expected type during elaboration
+                lowered::TermVariant::Rewrite(
+                    reverse,
+                    eq.lower(global, names, state)?,
+                    t.lower(global, names, state)?,
+                )
             }
         };
         Ok(lowered::Term {
@@ -358,6 +1093,115 @@ impl Expr {
     }
 }
 
+impl Expr {
+    /// Lowers the application of `self` to `args`, given oldest first along with the span of each application.
+    /// If `self` is a constant with implicit arguments, holes are inserted for those which are not given by name.
+    fn lower_app(
+        self,
+        mut args: Vec<(Span, Option<Ident>, Expr)>,
+        global: &Env,
+        names: &mut Stack<Ident>,
+        state: &mut State,
+    ) -> Result<lowered::Term, SpanError<LoweringError>> {
+        // First the whole application spine is collected
+        let mut f = self;
+        loop {
+            match *f.variant {
+                ExprVariant::App(g, v) => {
+                    args.push((f.span, None, v));
+                    f = g;
+                }
+                ExprVariant::NamedApp(g, x, v) => {
+                    args.push((f.span, Some(x), v));
+                    f = g;
+                }
+                variant => {
+                    f.variant = Box::new(variant);
+                    break;
+                }
+            }
+        }
+        args.reverse();
+
+        let implicits = match &*f.variant {
+            ExprVariant::Var(x) if !names.iter().any(|y| *x == y.name) => global.args(x),
+            _ => None,
+        };
+        let app = |f: lowered::Term, span: Span, v| lowered::Term {
+            meta: span,
+            variant: Box::new(lowered::TermVariant::App(f, v)),
+        };
+
+        let Some(implicits) = implicits else {
+            let mut t = f.lower(global, names, state)?;
+            for (span, x, v) in args {
+                if let Some(x) = x {
+                    return Err(SpanError {
+                        span: x.span,
+                        err: LoweringError::UnknownImplicit(x.name),
+                    });
+                }
+                t = app(t, span, v.lower(global, names, state)?);
+            }
+            return Ok(t);
+        };
+
+        let span = f.span.clone();
+        let name = match *f.variant {
+            ExprVariant::Var(x) => x,
+            _ => unreachable!(),
+        };
+        let mut t = lowered::Term {
+            meta: span.clone(),
+            variant: Box::new(lowered::TermVariant::Const(name)),
+        };
+        let (mut named, positional): (Vec<_>, Vec<_>) =
+            args.into_iter().partition(|(_, x, _)| x.is_some());
+        let mut positional = positional.into_iter().peekable();
+        let mut end = span.clone();
+        for arg in implicits {
+            if arg.implicit {
+                // An implicit argument is either given by name or left as a hole
+                let v = match named
+                    .iter()
+                    .position(|(_, x, _)| x.as_ref().is_some_and(|x| x.name == arg.name))
+                {
+                    Some(i) => {
+                        let (span, _, v) = named.swap_remove(i);
+                        end = span;
+                        v.lower(global, names, state)?
+                    }
+                    None => hole(span.clone(), state),
+                };
+                t = app(
+                    t,
+                    Span {
+                        start: span.start,
+                        end: end.end,
+                    },
+                    v,
+                );
+            } else if let Some((span, _, v)) = positional.next() {
+                end = span.clone();
+                t = app(t, span, v.lower(global, names, state)?);
+            } else {
+                // Implicit arguments are only inserted up to the first missing explicit argument
+                break;
+            }
+        }
+        if let Some((_, Some(x), _)) = named.pop() {
+            return Err(SpanError {
+                span: x.span,
+                err: LoweringError::UnknownImplicit(x.name),
+            });
+        }
+        for (span, _, v) in positional {
+            t = app(t, span, v.lower(global, names, state)?);
+        }
+        Ok(t)
+    }
+}
+
 impl crate::Sort {
     pub fn lower(self) -> Sort {
         match self {
@@ -0,0 +1,239 @@
+use std::{iter::Peekable, vec::IntoIter};
+
+use hane_kernel::Stack;
+
+use crate::{
+    env::{Env, Notation, Token},
+    lower::{lowered, LoweringError, State},
+    parser::application,
+    ExprItem, Ident, Span, SpanError,
+};
+
+type Result<T> = std::result::Result<T, SpanError<LoweringError>>;
+
+/// Returns whether `c` can be part of a symbol, matching `symbol_char` in the grammar.
+fn is_symbol_char(c: char) -> bool {
+    "!#$%&*+-/<=>?\\^~".contains(c)
+}
+
+/// Splits the string of a notation into its variables and symbols, which are separated by whitespace.
+pub fn tokens(pattern: &str) -> std::result::Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    for token in pattern.split_whitespace() {
+        let mut chars = token.chars();
+        let first = chars.next().unwrap();
+        if (first.is_alphabetic() || first == '_')
+            && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '\'')
+        {
+            if tokens.contains(&Token::Var(token.to_owned())) {
+                return Err(format!("the variable `{token}` is used twice"));
+            }
+            if matches!(tokens.last(), Some(Token::Var(_))) {
+                return Err(format!("the variable `{token}` follows another variable"));
+            }
+            tokens.push(Token::Var(token.to_owned()));
+        } else if token.chars().all(is_symbol_char) && token != "=>" {
+            tokens.push(Token::Symbol(token.to_owned()));
+        } else {
+            return Err(format!("`{token}` is neither a variable nor a symbol"));
+        }
+    }
+    if !tokens.iter().any(|token| matches!(token, Token::Symbol(_))) {
+        return Err("it has no symbols".to_owned());
+    }
+    Ok(tokens)
+}
+
+/// Parses and lowers the terms and symbols of an expression spanning `span`,
+/// by precedence climbing over the notations in scope. Applications bind tighter than any notation.
+pub fn lower(
+    span: Span,
+    items: Vec<ExprItem>,
+    global: &Env,
+    names: &mut Stack<Ident>,
+    state: &mut State,
+) -> Result<lowered::Term> {
+    let mut parser = Parser {
+        items: items.into_iter().peekable(),
+        end: span,
+        global,
+        names,
+        state,
+    };
+    let (term, _, _) = parser.parse(200)?;
+    match parser.items.next() {
+        None => Ok(term),
+        Some(ExprItem::Symbol(symbol)) => Err(SpanError {
+            span: symbol.span,
+            err: LoweringError::UnexpectedSymbol(symbol.name),
+        }),
+        Some(ExprItem::Term(span, _) | ExprItem::Named(span, _, _)) => Err(SpanError {
+            span,
+            err: LoweringError::UnexpectedTerm,
+        }),
+    }
+}
+
+struct Parser<'a> {
+    items: Peekable<IntoIter<ExprItem>>,
+    /// The span of the whole expression, whose end is reported when an item is missing
+    end: Span,
+    global: &'a Env,
+    names: &'a mut Stack<Ident>,
+    state: &'a mut State,
+}
+
+impl Parser<'_> {
+    /// Parses a term of at most level `max`, returning it with its level and span.
+    fn parse(&mut self, max: usize) -> Result<(lowered::Term, usize, Span)> {
+        let global = self.global;
+        let (mut lhs, mut level, mut span) = match self.items.peek() {
+            Some(ExprItem::Symbol(symbol)) => {
+                let Some(notation) = global.notation(false, &symbol.name) else {
+                    return Err(SpanError {
+                        span: symbol.span.clone(),
+                        err: LoweringError::UnexpectedSymbol(symbol.name.clone()),
+                    });
+                };
+                let start = symbol.span.clone();
+                self.items.next();
+                self.rest(notation, Vec::new(), start)?
+            }
+            Some(_) => self.application()?,
+            None => {
+                return Err(SpanError {
+                    span: self.missing(),
+                    err: LoweringError::ExpectedTerm,
+                })
+            }
+        };
+
+        while let Some(ExprItem::Symbol(symbol)) = self.items.peek() {
+            let Some(notation) = global.notation(true, &symbol.name) else {
+                break;
+            };
+            if notation.level > max || level > notation.levels()[0] {
+                break;
+            }
+            self.items.next();
+            (lhs, level, span) = self.rest(notation, vec![lhs], span)?;
+        }
+        Ok((lhs, level, span))
+    }
+
+    /// Parses the rest of `notation` after its first symbol, given the term for the variable before it if there is one.
+    fn rest(
+        &mut self,
+        notation: &Notation,
+        mut args: Vec<lowered::Term>,
+        start: Span,
+    ) -> Result<(lowered::Term, usize, Span)> {
+        let levels = notation.levels();
+        let mut end = start.clone();
+        let first = notation
+            .tokens
+            .iter()
+            .position(|token| matches!(token, Token::Symbol(_)))
+            .unwrap();
+        for token in &notation.tokens[first + 1..] {
+            match token {
+                Token::Var(_) => {
+                    let (arg, _, span) = self.parse(levels[args.len()])?;
+                    args.push(arg);
+                    end = span;
+                }
+                Token::Symbol(expected) => match self.items.next() {
+                    Some(ExprItem::Symbol(symbol)) if symbol.name == *expected => end = symbol.span,
+                    item => {
+                        return Err(SpanError {
+                            span: item
+                                .as_ref()
+                                .map_or_else(|| self.missing(), |item| item.span().clone()),
+                            err: LoweringError::MissingSymbol(expected.clone()),
+                        })
+                    }
+                },
+            }
+        }
+        let span = Span {
+            start: start.start,
+            end: end.end,
+        };
+        let offset = self.state.reserve_holes(notation.holes);
+        let mut term = fresh_holes(&notation.body, offset).instantiate(&args);
+        term.meta = span.clone();
+        Ok((term, notation.level, span))
+    }
+
+    /// Parses and lowers an application, which is made of the terms up to the next symbol.
+    fn application(&mut self) -> Result<(lowered::Term, usize, Span)> {
+        let mut items = Vec::new();
+        while let Some(ExprItem::Term(_, _) | ExprItem::Named(_, _, _)) = self.items.peek() {
+            items.push(self.items.next().unwrap());
+        }
+        if let Some(ExprItem::Named(span, _, _)) = items.first() {
+            return Err(SpanError {
+                span: span.clone(),
+                err: LoweringError::ExpectedTerm,
+            });
+        }
+        let span = Span {
+            start: items[0].span().start,
+            end: items[items.len() - 1].span().end,
+        };
+        let term = application(items).lower(self.global, self.names, self.state)?;
+        Ok((term, 0, span))
+    }
+
+    /// The span right after the last item, where a missing item is reported.
+    fn missing(&self) -> Span {
+        Span {
+            start: self.end.end,
+            end: self.end.end,
+        }
+    }
+}
+
+/// Adds `offset` to the id of every hole in `term`, so that the holes of a notation are distinct at every use.
+fn fresh_holes(term: &lowered::Term, offset: usize) -> lowered::Term {
+    use lowered::TermVariant;
+    let arm = |arm: &lowered::MatchArm| lowered::MatchArm {
+        meta: arm.meta.clone(),
+        constructor: arm.constructor.clone(),
+        params: arm.params.clone(),
+        body: fresh_holes(&arm.body, offset),
+    };
+    let variant = match &*term.variant {
+        TermVariant::Sort(_) | TermVariant::Var(_) | TermVariant::Const(_) => return term.clone(),
+        TermVariant::App(t, v) => TermVariant::App(fresh_holes(t, offset), fresh_holes(v, offset)),
+        TermVariant::Product(x, x_tp, t) => {
+            TermVariant::Product(x.clone(), fresh_holes(x_tp, offset), fresh_holes(t, offset))
+        }
+        TermVariant::Abstract(x, x_tp, t) => {
+            TermVariant::Abstract(x.clone(), fresh_holes(x_tp, offset), fresh_holes(t, offset))
+        }
+        TermVariant::Bind(x, x_tp, x_val, t) => TermVariant::Bind(
+            x.clone(),
+            fresh_holes(x_tp, offset),
+            fresh_holes(x_val, offset),
+            fresh_holes(t, offset),
+        ),
+        TermVariant::Match(t, x, ret, arms) => TermVariant::Match(
+            fresh_holes(t, offset),
+            x.clone(),
+            arm(ret),
+            arms.iter().map(arm).collect(),
+        ),
+        TermVariant::Evar(id, subst) => TermVariant::Evar(
+            id + offset,
+            subst.iter().map(|t| fresh_holes(t, offset)).collect(),
+        ),
+        TermVariant::Rewrite(reverse, eq, t) => {
+            TermVariant::Rewrite(*reverse, fresh_holes(eq, offset), fresh_holes(t, offset))
+        }
+    };
+    lowered::Term {
+        meta: term.meta.clone(),
+        variant: Box::new(variant),
+    }
+}
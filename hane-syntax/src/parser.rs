@@ -1,6 +1,7 @@
 use crate::{
-    Binder, Command, CommandVariant, Expr, ExprVariant, Ident, IndBody, IndConstructor, Pattern,
-    Sort, Span, SpanError,
+    Argument, Assoc, Binder, Command, CommandVariant, Expr, ExprItem, ExprVariant, Ident, IndBody,
+    IndConstructor, IndPattern, Instance, InstanceBody, MatchArm, Notation, Pattern,
+    PatternVariant, Proof, Record, Sort, Span, SpanError, Tactic, TacticVariant,
 };
 use pest::Parser;
 use pest_derive::Parser;
@@ -10,6 +11,7 @@ use pest_derive::Parser;
 struct HaneParser;
 
 type Pair<'i> = pest::iterators::Pair<'i, Rule>;
+type Pairs<'i> = pest::iterators::Pairs<'i, Rule>;
 
 pub type ParseError = SpanError<pest::error::ErrorVariant<Rule>>;
 
@@ -55,8 +57,31 @@ impl From<pest::error::Error<Rule>> for ParseError {
     }
 }
 
+/// The rules whose `number` is read as a `usize`, which the input could overflow.
+const BOUNDED: [Rule; 4] = [
+    Rule::notation_level,
+    Rule::tactic_auto,
+    Rule::tactic_eauto,
+    Rule::instance_priority,
+];
+
 pub fn parse(input: &str) -> Result<Vec<Command>, ParseError> {
     let pairs = HaneParser::parse(Rule::commands, input)?;
+    for pair in pairs.clone().flatten() {
+        if !BOUNDED.contains(&pair.as_rule()) {
+            continue;
+        }
+        for number in pair.into_inner() {
+            if number.as_rule() == Rule::number && number.as_str().parse::<usize>().is_err() {
+                let message = format!("The number {} is too large", number.as_str());
+                return Err(pest::error::Error::new_from_span(
+                    pest::error::ErrorVariant::CustomError { message },
+                    number.as_span(),
+                )
+                .into());
+            }
+        }
+    }
     Ok(pairs
         .take_while(|pair| pair.as_rule() != Rule::EOI)
         .map(parse_command)
@@ -72,7 +97,7 @@ pub fn parse_command(pair: Pair) -> Command {
             debug_assert_rule!(pairs, keyword_definition);
             let name = parse_ident(pairs.next().unwrap());
             let params = parse_binders(pairs.next().unwrap());
-            let ttype = parse_expr(pairs.next().unwrap());
+            let ttype = parse_type_annotation(&mut pairs);
             let value = parse_expr(pairs.next().unwrap());
             CommandVariant::Definition(name, params, ttype, value)
         }
@@ -86,6 +111,51 @@ pub fn parse_command(pair: Pair) -> Command {
             // Skips the `Inductive` keyword and steps over the `with` keywords.
             CommandVariant::Inductive(pairs.skip(1).step_by(2).map(parse_ind_body).collect())
         }
+        Rule::command_show_holes => CommandVariant::ShowHoles,
+        Rule::command_register => {
+            debug_assert_rule!(pairs, keyword_register);
+            let name = parse_ident(pairs.next().unwrap());
+            debug_assert_rule!(pairs, keyword_as);
+            let role = parse_ident(pairs.next().unwrap());
+            CommandVariant::Register(name, role)
+        }
+        Rule::command_hint => {
+            debug_assert_rule!(pairs, keyword_hint);
+            let kind = pairs.next().unwrap().as_rule();
+            let mut names = Vec::new();
+            let mut database = None;
+            for pair in pairs {
+                if pair.as_rule() == Rule::hint_database {
+                    database = Some(parse_ident(pair.into_inner().next().unwrap()));
+                } else {
+                    names.push(parse_ident(pair));
+                }
+            }
+            match kind {
+                Rule::keyword_resolve => CommandVariant::HintResolve(names, database),
+                _ => CommandVariant::HintConstructors(names, database),
+            }
+        }
+        Rule::command_add_ring => {
+            debug_assert_rule!(pairs, keyword_add);
+            debug_assert_rule!(pairs, keyword_ring_structure);
+            CommandVariant::AddRing(parse_ident(pairs.next().unwrap()))
+        }
+        Rule::command_definition_by => {
+            debug_assert_rule!(pairs, keyword_definition);
+            let name = parse_ident(pairs.next().unwrap());
+            let params = parse_binders(pairs.next().unwrap());
+            let ttype = parse_expr(pairs.next().unwrap());
+            debug_assert_rule!(pairs, keyword_by);
+            let tactic = parse_tactic(pairs.next().unwrap());
+            // The proof ends with the tactic, where any goals it leaves are reported
+            let qed = tactic.span.clone();
+            let proof = Proof {
+                tactics: vec![tactic],
+                qed,
+            };
+            CommandVariant::Theorem(name, params, ttype, proof)
+        }
         Rule::command_print => {
             debug_assert_rule!(pairs, keyword_print);
             let name = parse_ident(pairs.next().unwrap());
@@ -101,6 +171,160 @@ pub fn parse_command(pair: Pair) -> Command {
             let expr = parse_expr(pairs.next().unwrap());
             CommandVariant::Compute(expr)
         }
+        Rule::command_arguments => {
+            debug_assert_rule!(pairs, keyword_arguments);
+            let name = parse_ident(pairs.next().unwrap());
+            let args = pairs
+                .next()
+                .unwrap()
+                .into_inner()
+                .map(|pair| {
+                    let implicit = pair.as_rule() == Rule::argument_implicit;
+                    let ident = parse_ident(pair.into_inner().next().unwrap());
+                    Argument { ident, implicit }
+                })
+                .collect();
+            CommandVariant::Arguments(name, args)
+        }
+        Rule::command_notation => {
+            debug_assert_rule!(pairs, keyword_notation);
+            let string = pairs.next().unwrap();
+            let pattern = (
+                Span::from_pest(string.as_span()),
+                string.into_inner().next().unwrap().as_str().to_owned(),
+            );
+            let body = parse_expr(pairs.next().unwrap());
+            let mut level = None;
+            let mut assoc = Assoc::None;
+            if pairs
+                .peek()
+                .is_some_and(|pair| pair.as_rule() == Rule::notation_modifiers)
+            {
+                for pair in pairs.next().unwrap().into_inner() {
+                    match pair.as_rule() {
+                        Rule::notation_level => {
+                            let number = pair.into_inner().next().unwrap();
+                            level = Some(number.as_str().parse().unwrap());
+                        }
+                        Rule::notation_associativity => {
+                            assoc = match pair.into_inner().next().unwrap().as_rule() {
+                                Rule::associativity_left => Assoc::Left,
+                                Rule::associativity_right => Assoc::Right,
+                                _ => Assoc::None,
+                            };
+                        }
+                        r => unreachable!("{:?}", r),
+                    }
+                }
+            }
+            let scope = pairs.next().map(parse_ident);
+            CommandVariant::Notation(Notation {
+                pattern,
+                body,
+                level,
+                assoc,
+                scope,
+            })
+        }
+        Rule::command_open_scope => {
+            debug_assert_rule!(pairs, keyword_open);
+            debug_assert_rule!(pairs, keyword_scope);
+            CommandVariant::OpenScope(parse_ident(pairs.next().unwrap()))
+        }
+        Rule::command_number_notation => {
+            debug_assert_rule!(pairs, keyword_number);
+            debug_assert_rule!(pairs, keyword_notation);
+            let ind = parse_ident(pairs.next().unwrap());
+            let mut constructors = Vec::new();
+            let mut scope = None;
+            for pair in pairs {
+                if pair.as_rule() == Rule::number_scope {
+                    scope = Some(parse_ident(pair.into_inner().next().unwrap()));
+                } else {
+                    constructors.push(parse_ident(pair));
+                }
+            }
+            CommandVariant::NumberNotation(ind, constructors, scope)
+        }
+        Rule::command_class => {
+            debug_assert_rule!(pairs, keyword_class);
+            let name = parse_ident(pairs.next().unwrap());
+            let params = parse_binders(pairs.next().unwrap());
+            let sort = parse_type_annotation(&mut pairs);
+            let fields = parse_fields(pairs.next().unwrap());
+            CommandVariant::Class(Record {
+                name,
+                params,
+                sort,
+                constructor: None,
+                fields,
+            })
+        }
+        Rule::command_instance => {
+            debug_assert_rule!(pairs, keyword_instance);
+            let name = parse_ident(pairs.next().unwrap());
+            let params = parse_binders(pairs.next().unwrap());
+            let ttype = parse_expr(pairs.next().unwrap());
+            let mut pair = pairs.next().unwrap();
+            let mut priority = None;
+            if pair.as_rule() == Rule::instance_priority {
+                priority = Some(pair.into_inner().as_str().parse().unwrap());
+                pair = pairs.next().unwrap();
+            }
+            let body = match pair.as_rule() {
+                Rule::instance_fields => InstanceBody::Fields(parse_fields(pair)),
+                _ => InstanceBody::Term(parse_expr(pair)),
+            };
+            CommandVariant::Instance(Instance {
+                name,
+                params,
+                ttype,
+                priority,
+                body,
+            })
+        }
+        Rule::command_record => {
+            debug_assert_rule!(pairs, keyword_record);
+            let name = parse_ident(pairs.next().unwrap());
+            let params = parse_binders(pairs.next().unwrap());
+            let sort = parse_type_annotation(&mut pairs);
+            let mut pair = pairs.next().unwrap();
+            let mut constructor = None;
+            if pair.as_rule() == Rule::ident {
+                constructor = Some(parse_ident(pair));
+                pair = pairs.next().unwrap();
+            }
+            CommandVariant::Record(Record {
+                name,
+                params,
+                sort,
+                constructor,
+                fields: parse_fields(pair),
+            })
+        }
+        Rule::command_theorem => {
+            // Skips the `Theorem` or `Lemma` keyword
+            pairs.next();
+            let name = parse_ident(pairs.next().unwrap());
+            let params = parse_binders(pairs.next().unwrap());
+            let ttype = parse_expr(pairs.next().unwrap());
+            debug_assert_rule!(pairs, keyword_proof);
+            let mut tactics = Vec::new();
+            let mut pair = pairs.next().unwrap();
+            while pair.as_rule() != Rule::keyword_qed {
+                tactics.push(parse_tactic(pair));
+                pair = pairs.next().unwrap();
+            }
+            let qed = Span::from_pest(pair.as_span());
+            CommandVariant::Theorem(name, params, ttype, Proof { tactics, qed })
+        }
+        Rule::command_coercion => {
+            debug_assert_rule!(pairs, keyword_coercion);
+            let name = parse_ident(pairs.next().unwrap());
+            let source = parse_ident(pairs.next().unwrap());
+            let target = parse_ident(pairs.next().unwrap());
+            CommandVariant::Coercion(name, source, target)
+        }
         r => unreachable!("{:?}", r),
     };
     Command { span, variant }
@@ -133,31 +357,210 @@ fn parse_ind_body(pair: Pair) -> IndBody {
     }
 }
 
+fn parse_tactic(pair: Pair) -> Tactic {
+    let span = Span::from_pest(pair.as_span());
+    let rule = pair.as_rule();
+    // Skips the name of the tactic
+    let mut pairs = pair.into_inner().skip(1);
+    let variant = match rule {
+        Rule::tactic_intro => TacticVariant::Intro(pairs.next().map(parse_ident)),
+        Rule::tactic_intros => TacticVariant::Intros(pairs.map(parse_ident).collect()),
+        Rule::tactic_exact => TacticVariant::Exact(parse_expr(pairs.next().unwrap())),
+        Rule::tactic_apply => TacticVariant::Apply(parse_expr(pairs.next().unwrap())),
+        Rule::tactic_destruct => {
+            let t = parse_expr(pairs.next().unwrap());
+            // Skips the `as` keyword
+            let names = pairs.nth(1).map(|pair| {
+                pair.into_inner()
+                    .map(|names| names.into_inner().map(parse_ident).collect())
+                    .collect()
+            });
+            TacticVariant::Destruct(t, names)
+        }
+        Rule::tactic_induction => TacticVariant::Induction(parse_ident(pairs.next().unwrap())),
+        Rule::tactic_reflexivity => TacticVariant::Reflexivity,
+        Rule::tactic_symmetry => TacticVariant::Symmetry,
+        Rule::tactic_assumption => TacticVariant::Assumption,
+        Rule::tactic_split => TacticVariant::Split,
+        Rule::tactic_tauto => TacticVariant::Tauto,
+        Rule::tactic_congruence => TacticVariant::Congruence,
+        Rule::tactic_lia => TacticVariant::Lia,
+        Rule::tactic_ring => TacticVariant::Ring,
+        Rule::tactic_auto | Rule::tactic_eauto => {
+            let mut depth = None;
+            let mut databases = Vec::new();
+            for pair in pairs {
+                match pair.as_rule() {
+                    Rule::number => depth = Some(pair.as_str().parse().unwrap()),
+                    // Skips the `with` keyword
+                    _ => databases = pair.into_inner().skip(1).map(parse_ident).collect(),
+                }
+            }
+            if rule == Rule::tactic_auto {
+                TacticVariant::Auto(depth, databases)
+            } else {
+                TacticVariant::EAuto(depth, databases)
+            }
+        }
+        r => unreachable!("{:?}", r),
+    };
+    Tactic { span, variant }
+}
+
+/// Parses the fields of a record, or the values given to them, each of which is a name and an expression.
+fn parse_fields(pair: Pair) -> Vec<(Ident, Expr)> {
+    pair.into_inner()
+        .map(|pair| {
+            let mut pairs = pair.into_inner();
+            let name = parse_ident(pairs.next().unwrap());
+            (name, parse_expr(pairs.next().unwrap()))
+        })
+        .collect()
+}
+
 fn parse_expr(pair: Pair) -> Expr {
     debug_assert!(
         pair.as_rule() == Rule::expr,
         "Unexpected rule: {:?}",
         pair.as_rule()
     );
-    let mut exprs = pair.into_inner().map(parse_expr_inner);
-    let (f_span, f) = exprs.next().unwrap();
-    exprs.fold(f, |f, (v_span, v)| Expr {
-        span: Span {
-            start: f_span.start,
-            end: v_span.end,
+    let span = Span::from_pest(pair.as_span());
+    let mut pairs = pair.into_inner();
+    let mut items = Vec::new();
+    let mut codomain = None;
+    while let Some(pair) = pairs.next() {
+        let item = match pair.as_rule() {
+            Rule::expr_symbol => ExprItem::Symbol(Ident {
+                span: Span::from_pest(pair.as_span()),
+                name: pair.as_str().to_owned(),
+            }),
+            Rule::expr_named => {
+                let span = Span::from_pest(pair.as_span());
+                let mut pairs = pair.into_inner();
+                let x = parse_ident(pairs.next().unwrap());
+                let v = parse_expr(pairs.next().unwrap());
+                ExprItem::Named(span, x, v)
+            }
+            Rule::expr_projection => {
+                // A projection follows the term it projects, which is the previous item
+                let Some(ExprItem::Term(span, t)) = items.pop() else {
+                    unreachable!()
+                };
+                let span = Span {
+                    start: span.start,
+                    end: Span::from_pest(pair.as_span()).end,
+                };
+                let x = parse_ident(pair.into_inner().next().unwrap());
+                ExprItem::Term(
+                    span.clone(),
+                    Expr {
+                        span,
+                        variant: Box::new(ExprVariant::Project(t, x)),
+                    },
+                )
+            }
+            Rule::expr_arrow => {
+                codomain = Some((
+                    Span::from_pest(pair.as_span()),
+                    parse_expr(pairs.next().unwrap()),
+                ));
+                break;
+            }
+            _ => {
+                let (span, expr) = parse_expr_inner(pair);
+                ExprItem::Term(span, expr)
+            }
+        };
+        items.push(item);
+    }
+    let Some((arrow, codomain)) = codomain else {
+        return items_expr(span, items);
+    };
+    // `A -> B` is a product whose variable is never used
+    let domain = items_expr(
+        Span {
+            start: span.start,
+            end: items.last().unwrap().span().end,
+        },
+        items,
+    );
+    let binder = Binder {
+        ident: Ident {
+            span: arrow,
+            name: "_".to_owned(),
         },
-        variant: Box::new(ExprVariant::App(f, v)),
+        ttype: Some(domain),
+        implicit: false,
+        shared: false,
+    };
+    Expr {
+        span,
+        variant: Box::new(ExprVariant::Product(vec![binder], codomain)),
+    }
+}
+
+/// Builds the expression made of `items`, spanning `span`.
+fn items_expr(span: Span, items: Vec<ExprItem>) -> Expr {
+    // The symbols can only be parsed once the notations are known
+    if items.iter().any(|item| matches!(item, ExprItem::Symbol(_))) {
+        return Expr {
+            span,
+            variant: Box::new(ExprVariant::Notation(items)),
+        };
+    }
+    application(items)
+}
+
+/// Builds the application of the first term of `items` to the rest of them, which must not be symbols.
+pub(crate) fn application(items: Vec<ExprItem>) -> Expr {
+    let mut items = items.into_iter();
+    let Some(ExprItem::Term(f_span, f)) = items.next() else {
+        unreachable!()
+    };
+    items.fold(f, |f, item| {
+        let (v_span, variant) = match item {
+            ExprItem::Term(v_span, v) => (v_span, ExprVariant::App(f, v)),
+            ExprItem::Named(v_span, x, v) => (v_span, ExprVariant::NamedApp(f, x, v)),
+            ExprItem::Symbol(_) => unreachable!(),
+        };
+        Expr {
+            span: Span {
+                start: f_span.start,
+                end: v_span.end,
+            },
+            variant: Box::new(variant),
+        }
     })
 }
 
 fn parse_expr_inner(pair: Pair) -> (Span, Expr) {
     let span = Span::from_pest(pair.as_span());
     let rule = pair.as_rule();
+    let text = pair.as_str();
     let mut pairs = pair.into_inner();
     let variant = match rule {
         Rule::expr_paren => return (span, parse_expr(pairs.next().unwrap())),
         Rule::sort => ExprVariant::Sort(parse_sort(pairs.next().unwrap())),
+        Rule::expr_hole => ExprVariant::Hole,
+        Rule::expr_goal => ExprVariant::Goal(parse_ident(pairs.next().unwrap()).name),
+        Rule::expr_record => {
+            let base = (pairs.peek().unwrap().as_rule() == Rule::expr).then(|| {
+                let base = parse_expr(pairs.next().unwrap());
+                debug_assert_rule!(pairs, keyword_with);
+                base
+            });
+            let fields = pairs
+                .map(|pair| {
+                    let mut pairs = pair.into_inner();
+                    let name = parse_ident(pairs.next().unwrap());
+                    (name, parse_expr(pairs.next().unwrap()))
+                })
+                .collect();
+            ExprVariant::Record(base, fields)
+        }
+        Rule::expr_number => ExprVariant::Number(text.to_owned()),
         Rule::expr_var => ExprVariant::Var(parse_ident(pairs.next().unwrap()).name),
+        Rule::expr_explicit => ExprVariant::Explicit(parse_ident(pairs.next().unwrap()).name),
         Rule::expr_product => {
             debug_assert_rule!(pairs, keyword_forall); // Skip forall keyword
             let binders = parse_binders(pairs.next().unwrap()); // parse binders
@@ -173,7 +576,7 @@ fn parse_expr_inner(pair: Pair) -> (Span, Expr) {
         Rule::expr_let_bind => {
             debug_assert_rule!(pairs, keyword_let); // Skip let keyword
             let x = parse_ident(pairs.next().unwrap()); // parse var name
-            let x_tp = parse_expr(pairs.next().unwrap()); // parse type
+            let x_tp = parse_type_annotation(&mut pairs); // parse type if given
             let x_val = parse_expr(pairs.next().unwrap()); // parse value
             debug_assert_rule!(pairs, keyword_in); // Skip in keyword
             let t = parse_expr(pairs.next().unwrap()); // parse rest of body
@@ -181,17 +584,25 @@ fn parse_expr_inner(pair: Pair) -> (Span, Expr) {
         }
         Rule::expr_match => {
             debug_assert_rule!(pairs, keyword_match);
-            let t = parse_expr(pairs.next().unwrap());
-            debug_assert_rule!(pairs, keyword_as);
-            let name = parse_ident(pairs.next().unwrap());
-            debug_assert_rule!(pairs, keyword_in);
-            let pattern = parse_pattern(pairs.next().unwrap());
-            debug_assert_rule!(pairs, keyword_return);
-            let ret = parse_expr(pairs.next().unwrap());
+            let scrutinees = pairs.next().unwrap().into_inner().map(parse_expr).collect();
+            let name = skip_keyword(&mut pairs, Rule::keyword_as)
+                .then(|| parse_ident(pairs.next().unwrap()));
+            let pattern = skip_keyword(&mut pairs, Rule::keyword_in)
+                .then(|| parse_ind_pattern(pairs.next().unwrap()));
+            let ret = skip_keyword(&mut pairs, Rule::keyword_return)
+                .then(|| parse_expr(pairs.next().unwrap()));
             debug_assert_rule!(pairs, keyword_with);
             let arms = parse_match_arms(pairs.next().unwrap());
             debug_assert_rule!(pairs, keyword_end);
-            ExprVariant::Match(t, name, pattern, ret, arms)
+            ExprVariant::Match(scrutinees, name, pattern, ret, arms)
+        }
+        Rule::expr_rewrite => {
+            debug_assert_rule!(pairs, keyword_rewrite);
+            let reverse = skip_keyword(&mut pairs, Rule::rewrite_reverse);
+            let eq = parse_expr(pairs.next().unwrap());
+            debug_assert_rule!(pairs, keyword_in);
+            let t = parse_expr(pairs.next().unwrap());
+            ExprVariant::Rewrite(reverse, eq, t)
         }
         r => unreachable!("{:?}", r),
     };
@@ -231,40 +642,133 @@ fn parse_sort(pair: Pair) -> Sort {
 fn parse_binders(pair: Pair) -> Vec<Binder> {
     let rule = pair.as_rule();
     debug_assert!(
-        rule == Rule::binders || rule == Rule::open_binders,
+        rule == Rule::binders || rule == Rule::open_binders || rule == Rule::fun_binders,
         "{rule:?}"
     );
     pair.into_inner()
-        .map(|p| {
-            debug_assert_eq!(p.as_rule(), Rule::open_binder);
+        .flat_map(|p| {
+            if p.as_rule() == Rule::instance_binder {
+                let span = Span::from_pest(p.as_span());
+                let mut pairs = p.into_inner();
+                let pair = pairs.next().unwrap();
+                let (ident, ttype) = if pair.as_rule() == Rule::ident {
+                    (parse_ident(pair), parse_type_annotation(&mut pairs))
+                } else {
+                    let ident = Ident {
+                        span,
+                        name: "_".to_owned(),
+                    };
+                    (ident, Some(parse_expr(pair)))
+                };
+                return vec![Binder {
+                    ident,
+                    ttype,
+                    implicit: true,
+                    shared: false,
+                }];
+            }
+            let implicit = p.as_rule() == Rule::implicit_binder;
+            debug_assert!(
+                implicit || p.as_rule() == Rule::open_binder || p.as_rule() == Rule::untyped_binder
+            );
             let mut pairs = p.into_inner();
-            Binder {
-                ident: parse_ident(pairs.next().unwrap()),
-                ttype: parse_expr(pairs.next().unwrap()),
+            let mut idents = Vec::new();
+            while pairs
+                .peek()
+                .is_some_and(|pair| pair.as_rule() == Rule::ident)
+            {
+                idents.push(parse_ident(pairs.next().unwrap()));
             }
+            // The names of a group `(x y : A)` all have the type `A`, which is only written once
+            let mut ttype = parse_type_annotation(&mut pairs);
+            let shared = ttype.is_some();
+            idents
+                .into_iter()
+                .enumerate()
+                .map(move |(i, ident)| Binder {
+                    ident,
+                    ttype: ttype.take(),
+                    implicit,
+                    shared: shared && i > 0,
+                })
+                .collect::<Vec<_>>()
         })
         .collect()
 }
 
-fn parse_match_arms(pair: Pair) -> Vec<(Pattern, Expr)> {
+/// Skips the next pair if it is the keyword `rule`, returning whether it was there.
+fn skip_keyword(pairs: &mut Pairs, rule: Rule) -> bool {
+    if pairs.peek().is_some_and(|pair| pair.as_rule() == rule) {
+        pairs.next();
+        true
+    } else {
+        false
+    }
+}
+
+/// Parses the `: A` annotation if it is the next pair.
+fn parse_type_annotation(pairs: &mut Pairs) -> Option<Expr> {
+    if pairs.peek()?.as_rule() != Rule::type_annotation {
+        return None;
+    }
+    Some(parse_expr(pairs.next()?.into_inner().next().unwrap()))
+}
+
+fn parse_match_arms(pair: Pair) -> Vec<MatchArm> {
     debug_assert_eq!(pair.as_rule(), Rule::expr_match_arms);
     pair.into_inner()
         .map(|pair| {
             debug_assert_eq!(pair.as_rule(), Rule::expr_match_arm);
-            let mut pairs = pair.into_inner();
-            let pattern = parse_pattern(pairs.next().unwrap());
-            let expr = parse_expr(pairs.next().unwrap());
-            (pattern, expr)
+            let mut pairs = pair.into_inner().rev();
+            let body = parse_expr(pairs.next().unwrap());
+            let mut alternatives: Vec<_> = pairs
+                .map(|pair| {
+                    debug_assert_eq!(pair.as_rule(), Rule::pattern_row);
+                    let patterns: Vec<_> = pair.into_inner().map(parse_pattern).collect();
+                    // The span of the row itself would include the whitespace before `|` or `=>`
+                    let span = Span {
+                        start: patterns[0].span.start,
+                        end: patterns[patterns.len() - 1].span.end,
+                    };
+                    (span, patterns)
+                })
+                .collect();
+            alternatives.reverse();
+            MatchArm { alternatives, body }
         })
         .collect()
 }
 
 fn parse_pattern(pair: Pair) -> Pattern {
-    debug_assert_eq!(pair.as_rule(), Rule::pattern);
+    let span = Span::from_pest(pair.as_span());
+    let rule = pair.as_rule();
+    let mut pairs = pair.into_inner();
+    let variant = match rule {
+        Rule::pattern_wildcard => PatternVariant::Wildcard,
+        Rule::pattern_ident => PatternVariant::Ident(parse_ident(pairs.next().unwrap())),
+        Rule::pattern_constructor => {
+            let constructor = parse_ident(pairs.next().unwrap());
+            PatternVariant::Constructor(constructor, pairs.map(parse_pattern).collect())
+        }
+        Rule::pattern_or => {
+            let mut alternatives: Vec<_> = pairs.map(parse_pattern).collect();
+            // Parentheses around a single pattern only group it
+            if alternatives.len() == 1 {
+                return alternatives.pop().unwrap();
+            }
+            PatternVariant::Or(alternatives)
+        }
+        r => unreachable!("{:?}", r),
+    };
+    Pattern { span, variant }
+}
+
+fn parse_ind_pattern(pair: Pair) -> IndPattern {
+    debug_assert_eq!(pair.as_rule(), Rule::ind_pattern);
     let mut pairs = pair.into_inner();
     let constructor = parse_ident(pairs.next().unwrap());
     let params = pairs.map(parse_ident).collect();
-    Pattern {
+    IndPattern {
         constructor,
         params,
     }
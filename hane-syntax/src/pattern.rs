@@ -0,0 +1,531 @@
+use std::{
+    collections::HashSet,
+    fmt::{self, Display, Formatter},
+};
+
+use hane_kernel::Stack;
+
+use crate::{
+    env::Env,
+    lower::{lowered, LoweringError, LoweringWarning, State},
+    Ident, MatchArm, Pattern, PatternVariant, Span, SpanError,
+};
+
+/// A pattern where the constructors have been told apart from the variables.
+#[derive(Clone)]
+enum Pat {
+    Wildcard,
+    Var(Ident),
+    /// A constructor with the variables bound to its parameters, followed by the patterns for its arguments
+    Constructor(Span, String, Vec<Option<Ident>>, Vec<Pat>),
+    Or(Vec<Pat>),
+}
+
+/// A row of the pattern matrix, with the variables it has bound to terms so far.
+#[derive(Clone)]
+struct Row {
+    pats: Vec<Pat>,
+    bindings: Vec<(String, lowered::Term)>,
+    arm: usize,
+    alternative: usize,
+}
+
+/// A pattern with no arm matching it.
+#[derive(Clone)]
+enum Missing {
+    Any,
+    Constructor(String, usize, Vec<Missing>),
+}
+
+impl Display for Missing {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Missing::Any => write!(f, "_"),
+            Missing::Constructor(name, params, args) => {
+                write!(f, "{name}")?;
+                for _ in 0..*params {
+                    write!(f, " _")?;
+                }
+                for arg in args {
+                    match arg {
+                        Missing::Constructor(_, params, args) if params + args.len() > 0 => {
+                            write!(f, " ({arg})")?
+                        }
+                        _ => write!(f, " {arg}")?,
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Why compiling a pattern matrix failed.
+enum Failure {
+    Missing(Vec<Missing>),
+    Error(SpanError<LoweringError>),
+}
+
+struct Compiler<'a> {
+    global: &'a Env,
+    span: Span,
+    /// The variables bound by each arm, and its body lowered with those variables added to the context of the match
+    arms: Vec<(Vec<String>, lowered::Term)>,
+    /// The alternatives of the arms which have been selected for some case
+    used: HashSet<(usize, usize)>,
+    /// The name of the scrutinee and the return clause of the first match, which are given by the user
+    clauses: Option<(Ident, lowered::MatchArm)>,
+}
+
+/// Compiles a match on the lowered `scrutinees` into nested kernel matches.
+/// The first column of the pattern matrix where the first row has a constructor is matched on,
+/// until the first row only binds variables, which selects its arm.
+///
+/// `clauses` are used for the outermost match, while the ones nested inside it are left for elaboration to infer.
+/// A match without any arms is kept as a match on the first scrutinee with no arms.
+pub fn compile(
+    span: Span,
+    scrutinees: Vec<lowered::Term>,
+    clauses: Option<(Ident, lowered::MatchArm)>,
+    arms: Vec<MatchArm>,
+    global: &Env,
+    names: &mut Stack<Ident>,
+    state: &mut State,
+) -> Result<lowered::Term, SpanError<LoweringError>> {
+    let mut compiler = Compiler {
+        global,
+        span: span.clone(),
+        arms: Vec::with_capacity(arms.len()),
+        used: HashSet::new(),
+        clauses,
+    };
+
+    let mut rows = Vec::new();
+    let mut spans = Vec::with_capacity(arms.len());
+    for (arm, MatchArm { alternatives, body }) in arms.into_iter().enumerate() {
+        let mut arm_vars: Option<Vec<Ident>> = None;
+        let mut arm_spans = Vec::with_capacity(alternatives.len());
+        for (alternative, (row_span, pats)) in alternatives.into_iter().enumerate() {
+            if pats.len() != scrutinees.len() {
+                return Err(SpanError {
+                    span: row_span,
+                    err: LoweringError::PatternCount(scrutinees.len(), pats.len()),
+                });
+            }
+            let mut vars = Vec::new();
+            let pats = pats
+                .into_iter()
+                .map(|pat| compiler.resolve(pat, &mut vars))
+                .collect::<Result<_, _>>()?;
+            match &arm_vars {
+                Some(arm_vars) => same_vars(arm_vars, &vars, &row_span)?,
+                None => arm_vars = Some(vars),
+            }
+            rows.push(Row {
+                pats,
+                bindings: Vec::new(),
+                arm,
+                alternative,
+            });
+            arm_spans.push(row_span);
+        }
+        let vars = arm_vars.unwrap_or_default();
+        let mut names = names.slot();
+        names.extend(vars.iter().cloned());
+        let body = body.lower(global, &mut names, state)?;
+        compiler
+            .arms
+            .push((vars.into_iter().map(|x| x.name).collect(), body));
+        spans.push(arm_spans);
+    }
+
+    let term = if rows.is_empty() {
+        let scrutinee = scrutinees.into_iter().next().unwrap();
+        let (name, ret) = compiler.clauses(&scrutinee.meta, state);
+        lowered::Term {
+            meta: span,
+            variant: Box::new(lowered::TermVariant::Match(
+                scrutinee,
+                name,
+                ret,
+                Vec::new(),
+            )),
+        }
+    } else {
+        match compiler.compile(scrutinees, rows, 0, state) {
+            Ok(term) => term,
+            Err(Failure::Missing(missing)) => {
+                let missing = missing
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                return Err(SpanError {
+                    span,
+                    err: LoweringError::NonExhaustive(missing),
+                });
+            }
+            Err(Failure::Error(err)) => return Err(err),
+        }
+    };
+
+    for (arm, arm_spans) in spans.into_iter().enumerate() {
+        for (alternative, span) in arm_spans.into_iter().enumerate() {
+            if !compiler.used.contains(&(arm, alternative)) {
+                state.warn(span, LoweringWarning::RedundantClause);
+            }
+        }
+    }
+    Ok(term)
+}
+
+/// Checks that an alternative binds the same variables as the first alternative of its arm.
+fn same_vars(
+    expected: &[Ident],
+    found: &[Ident],
+    span: &Span,
+) -> Result<(), SpanError<LoweringError>> {
+    let missing = expected
+        .iter()
+        .find(|x| !found.contains(x))
+        .or_else(|| found.iter().find(|x| !expected.contains(x)));
+    match missing {
+        Some(x) => Err(SpanError {
+            span: span.clone(),
+            err: LoweringError::AlternativeVariable(x.name.clone()),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Replaces every row starting with an or-pattern in some column by a row for each of its alternatives.
+fn expand_or(rows: Vec<Row>) -> Vec<Row> {
+    let mut expanded = Vec::with_capacity(rows.len());
+    for row in rows {
+        let Some(i) = row.pats.iter().position(|pat| matches!(pat, Pat::Or(_))) else {
+            expanded.push(row);
+            continue;
+        };
+        let Pat::Or(alternatives) = &row.pats[i] else {
+            unreachable!()
+        };
+        let rows = alternatives
+            .iter()
+            .map(|pat| {
+                let mut row = row.clone();
+                row.pats[i] = pat.clone();
+                row
+            })
+            .collect();
+        expanded.extend(expand_or(rows));
+    }
+    expanded
+}
+
+fn var(meta: &Span, n: usize) -> lowered::Term {
+    lowered::Term {
+        meta: meta.clone(),
+        variant: Box::new(lowered::TermVariant::Var(n)),
+    }
+}
+
+impl Compiler<'_> {
+    /// Tells constructors apart from variables in `pattern`, adding the variables it binds to `vars`.
+    fn resolve(
+        &self,
+        pattern: Pattern,
+        vars: &mut Vec<Ident>,
+    ) -> Result<Pat, SpanError<LoweringError>> {
+        let (name, args) = match pattern.variant {
+            PatternVariant::Wildcard => return Ok(Pat::Wildcard),
+            PatternVariant::Ident(x) if self.global.constructor(&x.name).is_none() => {
+                if vars.contains(&x) {
+                    return Err(SpanError {
+                        span: x.span,
+                        err: LoweringError::DuplicatePatternVariable(x.name),
+                    });
+                }
+                vars.push(x.clone());
+                return Ok(Pat::Var(x));
+            }
+            PatternVariant::Ident(x) => (x, Vec::new()),
+            PatternVariant::Constructor(x, args) => (x, args),
+            PatternVariant::Or(alternatives) => {
+                let mut alt_vars: Option<Vec<Ident>> = None;
+                let mut pats = Vec::with_capacity(alternatives.len());
+                for alternative in alternatives {
+                    let span = alternative.span.clone();
+                    let mut found = Vec::new();
+                    pats.push(self.resolve(alternative, &mut found)?);
+                    match &alt_vars {
+                        Some(alt_vars) => same_vars(alt_vars, &found, &span)?,
+                        None => alt_vars = Some(found),
+                    }
+                }
+                for x in alt_vars.unwrap_or_default() {
+                    if vars.contains(&x) {
+                        return Err(SpanError {
+                            span: x.span,
+                            err: LoweringError::DuplicatePatternVariable(x.name),
+                        });
+                    }
+                    vars.push(x);
+                }
+                return Ok(Pat::Or(pats));
+            }
+        };
+
+        let Some(ind) = self.global.constructor(&name.name) else {
+            return Err(SpanError {
+                span: name.span,
+                err: LoweringError::NotAConstructor(name.name),
+            });
+        };
+        let ind = self.global.inductive(ind).unwrap();
+        let arity = ind
+            .constructors
+            .iter()
+            .find(|(c, _)| *c == name.name)
+            .map(|(_, arity)| *arity)
+            .unwrap();
+        if args.len() != ind.params + arity {
+            return Err(SpanError {
+                span: pattern.span,
+                err: LoweringError::PatternArgCount(name.name, ind.params + arity, args.len()),
+            });
+        }
+        let mut args = args.into_iter();
+        let params = args
+            .by_ref()
+            .take(ind.params)
+            .map(|param| match param.variant {
+                PatternVariant::Wildcard => Ok(None),
+                PatternVariant::Ident(x) if self.global.constructor(&x.name).is_none() => {
+                    if vars.contains(&x) {
+                        return Err(SpanError {
+                            span: x.span,
+                            err: LoweringError::DuplicatePatternVariable(x.name),
+                        });
+                    }
+                    vars.push(x.clone());
+                    Ok(Some(x))
+                }
+                _ => Err(SpanError {
+                    span: param.span,
+                    err: LoweringError::ParameterPattern,
+                }),
+            })
+            .collect::<Result<_, _>>()?;
+        let args = args
+            .map(|arg| self.resolve(arg, vars))
+            .collect::<Result<_, _>>()?;
+        Ok(Pat::Constructor(pattern.span, name.name, params, args))
+    }
+
+    /// Returns the name of the scrutinee and the return clause for a match on `meta`.
+    ///
+    /// The first match gets the clauses given by the user, the others leave them to be inferred.
+    fn clauses(&mut self, meta: &Span, state: &mut State) -> (Ident, lowered::MatchArm) {
+        self.clauses.take().unwrap_or_else(|| {
+            let name = Ident {
+                span: meta.clone(),
+                name: "_".to_owned(),
+            };
+            let ret = lowered::MatchArm {
+                meta: self.span.clone(),
+                constructor: String::new(),
+                params: Vec::new(),
+                body: crate::lower::hole(self.span.clone(), state),
+            };
+            (name, ret)
+        })
+    }
+
+    /// Compiles the pattern matrix `rows` matching on `cols`,
+    /// in a context with `generated` more variables than the context of the match.
+    fn compile(
+        &mut self,
+        cols: Vec<lowered::Term>,
+        rows: Vec<Row>,
+        generated: usize,
+        state: &mut State,
+    ) -> Result<lowered::Term, Failure> {
+        let mut rows = expand_or(rows);
+        if rows.is_empty() {
+            return Err(Failure::Missing(vec![Missing::Any; cols.len()]));
+        }
+        let Some(i) = rows[0]
+            .pats
+            .iter()
+            .position(|pat| matches!(pat, Pat::Constructor(..)))
+        else {
+            let mut row = rows.swap_remove(0);
+            for (pat, col) in row.pats.iter().zip(&cols) {
+                if let Pat::Var(x) = pat {
+                    row.bindings.push((x.name.clone(), col.clone()));
+                }
+            }
+            self.used.insert((row.arm, row.alternative));
+            return Ok(self.body(row, generated));
+        };
+
+        let global = self.global;
+        let Pat::Constructor(_, first, _, _) = &rows[0].pats[i] else {
+            unreachable!()
+        };
+        let ind_name = global.constructor(first).unwrap().to_owned();
+        let ind = global.inductive(&ind_name).unwrap();
+
+        // The cases are in the order the constructors are first matched in, followed by the remaining constructors
+        let mut order = Vec::with_capacity(ind.constructors.len());
+        for row in &rows {
+            if let Pat::Constructor(span, name, _, _) = &row.pats[i] {
+                let Some(c) = ind.constructors.iter().position(|(c, _)| c == name) else {
+                    return Err(Failure::Error(SpanError {
+                        span: span.clone(),
+                        err: LoweringError::ConstructorMismatch(ind_name, name.clone()),
+                    }));
+                };
+                if !order.contains(&c) {
+                    order.push(c);
+                }
+            }
+        }
+        for c in 0..ind.constructors.len() {
+            if !order.contains(&c) {
+                order.push(c);
+            }
+        }
+
+        let (name, ret) = self.clauses(&cols[i].meta, state);
+        let mut arms = Vec::with_capacity(order.len());
+        for c in order {
+            let (constructor, arity) = &ind.constructors[c];
+            let bound = ind.params + arity;
+            let shifted: Vec<_> = cols.iter().map(|col| col.push(bound)).collect();
+            let wildcard = Ident {
+                span: self.span.clone(),
+                name: "_".to_owned(),
+            };
+            let mut params = vec![wildcard; bound];
+            let mut named = false;
+
+            let mut specialized = Vec::new();
+            for row in &rows {
+                let mut bindings: Vec<_> = row
+                    .bindings
+                    .iter()
+                    .map(|(x, t)| (x.clone(), t.push(bound)))
+                    .collect();
+                let mut pats = match &row.pats[i] {
+                    Pat::Constructor(_, name, param_vars, args) if name == constructor => {
+                        for (j, x) in param_vars.iter().enumerate() {
+                            if let Some(x) = x {
+                                bindings.push((x.name.clone(), var(&self.span, bound - 1 - j)));
+                            }
+                        }
+                        // The variables of the first row matching the constructor name its arguments
+                        if !named {
+                            named = true;
+                            let arg_vars = args.iter().map(|arg| match arg {
+                                Pat::Var(x) => Some(x.clone()),
+                                _ => None,
+                            });
+                            for (param, x) in params
+                                .iter_mut()
+                                .zip(param_vars.iter().cloned().chain(arg_vars))
+                            {
+                                if let Some(x) = x {
+                                    *param = x;
+                                }
+                            }
+                        }
+                        args.clone()
+                    }
+                    Pat::Constructor(..) => continue,
+                    Pat::Wildcard => vec![Pat::Wildcard; *arity],
+                    Pat::Var(x) => {
+                        bindings.push((x.name.clone(), shifted[i].clone()));
+                        vec![Pat::Wildcard; *arity]
+                    }
+                    Pat::Or(_) => unreachable!(),
+                };
+                pats.extend(
+                    row.pats
+                        .iter()
+                        .enumerate()
+                        .filter(|(j, _)| *j != i)
+                        .map(|(_, pat)| pat.clone()),
+                );
+                specialized.push(Row {
+                    pats,
+                    bindings,
+                    arm: row.arm,
+                    alternative: row.alternative,
+                });
+            }
+
+            let mut sub_cols: Vec<_> = (0..*arity)
+                .map(|j| var(&self.span, arity - 1 - j))
+                .collect();
+            sub_cols.extend(
+                shifted
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .map(|(_, col)| col),
+            );
+            // The cases of an indexed family which no row matches are left out,
+            // for elaboration to show that they are impossible
+            if specialized.is_empty() && ind.indices > 0 {
+                continue;
+            }
+            match self.compile(sub_cols, specialized, generated + bound, state) {
+                Ok(body) => arms.push(lowered::MatchArm {
+                    meta: self.span.clone(),
+                    constructor: constructor.clone(),
+                    params,
+                    body,
+                }),
+                Err(Failure::Missing(mut missing)) => {
+                    let args = missing.drain(..*arity).collect();
+                    missing.insert(
+                        i,
+                        Missing::Constructor(constructor.clone(), ind.params, args),
+                    );
+                    return Err(Failure::Missing(missing));
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(lowered::Term {
+            meta: self.span.clone(),
+            variant: Box::new(lowered::TermVariant::Match(
+                cols[i].clone(),
+                name,
+                ret,
+                arms,
+            )),
+        })
+    }
+
+    /// Returns the body of the arm selected by `row`, with its variables replaced by what they were bound to.
+    fn body(&self, row: Row, generated: usize) -> lowered::Term {
+        let (vars, body) = &self.arms[row.arm];
+        let values: Vec<_> = vars
+            .iter()
+            .map(|x| &row.bindings.iter().rev().find(|(y, _)| y == x).unwrap().1)
+            .collect();
+        let len = values.len();
+        body.subst(|meta, x, push| {
+            if x < push {
+                var(meta, x)
+            } else if x - push < len {
+                values[len - 1 - (x - push)].push(push)
+            } else {
+                var(meta, x - len + generated)
+            }
+        })
+    }
+}
@@ -4,69 +4,196 @@ use hane_kernel::{
     entry::Entry,
     global::{CommandOut, GEntryRef},
     stack::StackSlot,
-    term::TermVariant,
+    term::{grow, MatchArm, TermVariant},
     Stack,
 };
 
-use crate::Ident;
+use crate::{
+    env::{Env, Token},
+    lower::lowered,
+    Ident, Location, Span,
+};
 
 type Term<M> = hane_kernel::term::Term<M, Ident>;
 
-pub struct Print<T>(pub T);
+/// Prints the output of a command, with the arguments of constants printed according to `Env`.
+pub struct Print<'e, T>(pub &'e Env, pub T);
+
+/// Writes the `Arguments` line of the constant `name`, if it has any implicit arguments.
+fn write_arguments(f: &mut impl Write, env: &Env, name: &str) -> fmt::Result {
+    if let Some(args) = env.args(name) {
+        write!(f, "Arguments {name}")?;
+        for arg in args {
+            if arg.implicit {
+                write!(f, " {{{}}}", arg.name)?;
+            } else {
+                write!(f, " {}", arg.name)?;
+            }
+        }
+        writeln!(f)?;
+    }
+    Ok(())
+}
 
-impl<'a, M> Display for Print<CommandOut<'a, M, Ident>> {
+impl<'e, 'a, M: Clone> Display for Print<'e, CommandOut<'a, M, Ident>> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let env = self.0;
         let mut names = Stack::new();
-        match &self.0 {
+        match &self.1 {
             CommandOut::Entry(entry) => match *entry {
                 GEntryRef::Definition(name, ttype, val) => {
                     write!(f, "{name} = ")?;
-                    write_term(f, val, &mut names, 200)?;
+                    write_term(f, env, val, &mut names, 200)?;
                     write!(f, "\n\t : ")?;
-                    write_term(f, ttype, &mut names, 200)?;
-                    writeln!(f)
+                    write_term(f, env, ttype, &mut names, 200)?;
+                    writeln!(f)?;
+                    write_arguments(f, env, name)
                 }
                 GEntryRef::Axiom(name, ttype) => {
                     write!(f, "*** [ {name} : ")?;
-                    write_term(f, ttype, &mut names, 200)?;
-                    writeln!(f, " ]")
+                    write_term(f, env, ttype, &mut names, 200)?;
+                    writeln!(f, " ]")?;
+                    write_arguments(f, env, name)
                 }
                 GEntryRef::Inductive(_, params, bodies)
                 | GEntryRef::InductiveConstructor(_, _, params, bodies) => {
+                    // The types being declared are applied to all of their arguments in the constructors,
+                    // so they are printed as variables, whose implicit arguments are not hidden
+                    let declared: Vec<_> = bodies.iter().map(|body| &*body.name).collect();
+                    let mut names = names.slot();
+                    names.extend(bodies.iter().map(|body| unlocated(&body.name)));
+
                     let mut sep = "Inductive";
                     for body in bodies {
                         write!(f, "{sep} {}", body.name)?;
                         sep = "\n  with";
+                        let args = env.args(&body.name).unwrap_or_default();
                         let mut names = names.slot();
-                        for param in params {
-                            write!(f, " ({} : ", param.x.name)?;
-                            write_term(f, &param.ttype, &mut names, 200)?;
-                            write!(f, ")")?;
+                        for (i, param) in params.iter().enumerate() {
+                            let implicit = args.get(i).is_some_and(|arg| arg.implicit);
+                            let (open, close) = if implicit { ("{", "}") } else { ("(", ")") };
+                            write!(f, " {open}{} : ", param.x.name)?;
+                            write_term(f, env, &param.ttype, &mut names, 200)?;
+                            write!(f, "{close}")?;
                             names.push_onto(param.x.clone());
                         }
 
                         write!(f, " : ")?;
-                        write_term(f, &body.arity_type, &mut names, 200)?;
+                        write_term(f, env, &body.arity_type, &mut names, 200)?;
                         write!(f, " :=")?;
 
                         let mut sep = " ";
                         for constructor in &body.constructors {
                             write!(f, "\n  {sep} {} : ", constructor.name)?;
                             sep = "|";
-                            write_term(f, &constructor.arity_type, &mut names, 200)?;
+                            let ttype = declared_as_vars(
+                                &constructor.arity_type,
+                                &declared,
+                                params.len(),
+                                0,
+                            );
+                            write_term(f, env, &ttype, &mut names, 200)?;
+                        }
+                    }
+                    writeln!(f, ".")?;
+                    for body in bodies {
+                        write_arguments(f, env, &body.name)?;
+                        for constructor in &body.constructors {
+                            write_arguments(f, env, &constructor.name)?;
                         }
                     }
-                    writeln!(f, ".")
+                    Ok(())
                 }
             },
             CommandOut::Term(term) => {
-                write_term(f, term, &mut names, 200)?;
+                write_term(f, env, term, &mut names, 200)?;
+                writeln!(f)
+            }
+            CommandOut::Obligation(name, ttype, context) => {
+                // The type of an obligation binds its context, which is split off to be shown like the hypotheses of a goal
+                let mut goal = *ttype;
+                let mut entries = Vec::with_capacity(*context);
+                for _ in 0..*context {
+                    let entry = match &*goal.variant {
+                        TermVariant::Product(x, x_tp, body) => {
+                            goal = body;
+                            Entry::new(x.clone(), x_tp.clone())
+                        }
+                        TermVariant::Bind(x, x_tp, value, body) => {
+                            goal = body;
+                            Entry::with_value(x.clone(), value.clone(), x_tp.clone())
+                        }
+                        _ => unreachable!(),
+                    };
+                    entries.push(entry);
+                }
+                writeln!(f, "{name}")?;
+                let mut names = write_local(f, env, &entries.into_iter().collect(), &mut names)?;
+                writeln!(f, "============================")?;
+                write_term(f, env, goal, &mut names, 200)?;
                 writeln!(f)
             }
         }
     }
 }
 
+/// A name for printing, which is never reported and so is not located anywhere in the input.
+fn unlocated(name: &str) -> Ident {
+    let start = Location {
+        pos: 0,
+        line: 1,
+        col: 1,
+    };
+    Ident {
+        span: Span { start, end: start },
+        name: name.to_owned(),
+    }
+}
+
+/// Replaces the constants `declared` in `term`, which is under `depth` binders,
+/// with the variables bound outside of the `params` newest ones, the last of `declared` being the newest.
+fn declared_as_vars<M: Clone>(
+    term: &Term<M>,
+    declared: &[&str],
+    params: usize,
+    depth: usize,
+) -> Term<M> {
+    let go = |t: &Term<M>, extra| declared_as_vars(t, declared, params, depth + extra);
+    let variant = grow(|| match &*term.variant {
+        TermVariant::Const(name) => match declared.iter().position(|x| x == name) {
+            Some(k) => TermVariant::Var(depth + params + declared.len() - 1 - k),
+            None => TermVariant::Const(name.clone()),
+        },
+        TermVariant::Sort(_) | TermVariant::Var(_) => (*term.variant).clone(),
+        TermVariant::App(t1, t2) => TermVariant::App(go(t1, 0), go(t2, 0)),
+        TermVariant::Product(x, t1, t2) => TermVariant::Product(x.clone(), go(t1, 0), go(t2, 1)),
+        TermVariant::Abstract(x, t1, t2) => TermVariant::Abstract(x.clone(), go(t1, 0), go(t2, 1)),
+        TermVariant::Bind(x, t1, t2, t3) => {
+            TermVariant::Bind(x.clone(), go(t1, 0), go(t2, 0), go(t3, 1))
+        }
+        TermVariant::Match(t, x, ret, arms) => {
+            let arm = |arm: &MatchArm<M, Ident>, extra| MatchArm {
+                body: go(&arm.body, arm.params.len() + extra),
+                ..arm.clone()
+            };
+            TermVariant::Match(
+                go(t, 0),
+                x.clone(),
+                arm(ret, 1),
+                arms.iter().map(|a| arm(a, 0)).collect(),
+            )
+        }
+        TermVariant::Evar(id, subst) => {
+            TermVariant::Evar(*id, subst.iter().map(|t| go(t, 0)).collect())
+        }
+        TermVariant::Rewrite(reverse, eq, t) => TermVariant::Rewrite(*reverse, go(eq, 0), go(t, 0)),
+    });
+    Term {
+        meta: term.meta.clone(),
+        variant: Box::new(variant),
+    }
+}
+
 fn fresh(x: &Ident, names: &Stack<Ident>) -> Ident {
     if !names.contains(x) {
         return x.clone();
@@ -85,13 +212,138 @@ fn fresh(x: &Ident, names: &Stack<Ident>) -> Ident {
     unreachable!()
 }
 
-pub fn write_term<M>(
+/// Matches `term` against the body of a notation, which is under `push` binders of the body,
+/// filling in `captures` with the terms taking the place of the variables of the notation.
+///
+/// Holes in the body match anything, as they are where implicit arguments were inserted.
+fn capture<M: Clone>(
+    pattern: &lowered::Term,
+    term: &Term<M>,
+    push: usize,
+    captures: &mut [Option<Term<M>>],
+) -> bool {
+    use TermVariant as V;
+    match (&*pattern.variant, &*term.variant) {
+        (V::Evar(_, _), _) => true,
+        (V::Var(n), _) if *n >= push => {
+            // The captured term must not refer to the binders of the body
+            let Ok(arg) = term.try_subst(|meta, x, p| match x.checked_sub(p) {
+                Some(x) if x < push => Err(()),
+                Some(x) => Ok(Term {
+                    meta: meta.clone(),
+                    variant: Box::new(V::Var(x - push + p)),
+                }),
+                None => Ok(Term {
+                    meta: meta.clone(),
+                    variant: Box::new(V::Var(x)),
+                }),
+            }) else {
+                return false;
+            };
+            let i = captures.len() - 1 - (n - push);
+            match &captures[i] {
+                Some(captured) => *captured == arg,
+                None => {
+                    captures[i] = Some(arg);
+                    true
+                }
+            }
+        }
+        (V::Var(n), V::Var(m)) => n == m,
+        (V::Sort(l), V::Sort(r)) => l == r,
+        (V::Const(l), V::Const(r)) => l == r,
+        (V::App(lf, lv), V::App(rf, rv)) => {
+            capture(lf, rf, push, captures) && capture(lv, rv, push, captures)
+        }
+        (V::Product(_, l_tp, l), V::Product(_, r_tp, r))
+        | (V::Abstract(_, l_tp, l), V::Abstract(_, r_tp, r)) => {
+            capture(l_tp, r_tp, push, captures) && capture(l, r, push + 1, captures)
+        }
+        (V::Bind(_, l_tp, l_val, l), V::Bind(_, r_tp, r_val, r)) => {
+            capture(l_tp, r_tp, push, captures)
+                && capture(l_val, r_val, push, captures)
+                && capture(l, r, push + 1, captures)
+        }
+        (V::Match(l, _, l_ret, l_arms), V::Match(r, _, r_ret, r_arms)) => {
+            capture(l, r, push, captures)
+                && l_arms.len() == r_arms.len()
+                && std::iter::once((l_ret, r_ret, 1))
+                    .chain(l_arms.iter().zip(r_arms).map(|(l, r)| (l, r, 0)))
+                    .all(|(l, r, extra)| {
+                        l.constructor == r.constructor
+                            && l.params.len() == r.params.len()
+                            && capture(&l.body, &r.body, push + l.params.len() + extra, captures)
+                    })
+        }
+        _ => false,
+    }
+}
+
+/// Writes `term` with the notation of the highest priority whose body it matches, returning whether there was one.
+fn write_notation<M: Clone>(
     buf: &mut impl Write,
+    env: &Env,
+    term: &Term<M>,
+    names: &mut Stack<Ident>,
+    level: usize,
+) -> Result<bool, fmt::Error> {
+    for notation in env.notations() {
+        // A notation standing for one of its variables would be printed forever
+        if matches!(&*notation.body.variant, TermVariant::Var(_)) {
+            continue;
+        }
+        let vars = notation.levels();
+        let mut captures = vec![None; vars.len()];
+        if !capture(&notation.body, term, 0, &mut captures) {
+            continue;
+        }
+        let Some(captures) = captures.into_iter().collect::<Option<Vec<_>>>() else {
+            continue;
+        };
+        // Applications bind tighter than any notation, so their head and arguments always need parentheses
+        let parens = level < 10 || notation.level > level;
+        if parens {
+            write!(buf, "(")?;
+        }
+        let mut args = captures.iter().zip(vars);
+        let mut sep = "";
+        for token in &notation.tokens {
+            write!(buf, "{sep}")?;
+            sep = " ";
+            match token {
+                Token::Symbol(symbol) => write!(buf, "{symbol}")?,
+                Token::Var(_) => {
+                    // Products and arrows would take in the rest of the notation
+                    let (arg, level) = args.next().unwrap();
+                    write_term(buf, env, arg, names, level.min(199))?;
+                }
+            }
+        }
+        if parens {
+            write!(buf, ")")?;
+        }
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+pub fn write_term<M: Clone>(
+    buf: &mut impl Write,
+    env: &Env,
     term: &Term<M>,
     names: &mut Stack<Ident>,
     level: usize,
 ) -> fmt::Result {
-    match &*term.variant {
+    if let Some(n) = env
+        .number_notation()
+        .and_then(|notation| notation.number(term))
+    {
+        return write!(buf, "{n}");
+    }
+    if write_notation(buf, env, term, names, level)? {
+        return Ok(());
+    }
+    grow(|| match &*term.variant {
         TermVariant::Sort(sort) => write!(buf, "{sort}"),
         TermVariant::Var(n) => {
             if let Some(x) = names.get(*n) {
@@ -101,29 +353,86 @@ pub fn write_term<M>(
             }
         }
         TermVariant::Const(name) => write!(buf, "{name}"),
-        TermVariant::App(f, v) => {
+        TermVariant::App(_, _) => {
+            let mut f = term;
+            let mut args = Vec::new();
+            while let TermVariant::App(g, v) = &*f.variant {
+                args.push(v);
+                f = g;
+            }
+            args.reverse();
+            // An inserted coercion is hidden, leaving the term it converts in its place
+            if let TermVariant::Const(name) = &*f.variant {
+                if let Some(coercion) = env.coercion(name) {
+                    if args.len() >= coercion.arity {
+                        let coerced = args[coercion.arity - 1];
+                        let rest = &args[coercion.arity..];
+                        if rest.is_empty() {
+                            return write_term(buf, env, coerced, names, level);
+                        }
+                        if level < 10 {
+                            write!(buf, "(")?;
+                        }
+                        write_term(buf, env, coerced, names, 10)?;
+                        for v in rest {
+                            write!(buf, " ")?;
+                            write_term(buf, env, v, names, 9)?;
+                        }
+                        if level < 10 {
+                            write!(buf, ")")?;
+                        }
+                        return Ok(());
+                    }
+                }
+            }
+            // Implicit arguments of constants are hidden
+            let implicits = match &*f.variant {
+                TermVariant::Const(name) => env.args(name.as_str()),
+                _ => None,
+            }
+            .unwrap_or_default();
+            let args: Vec<_> = args
+                .into_iter()
+                .enumerate()
+                .filter(|(i, _)| !implicits.get(*i).is_some_and(|arg| arg.implicit))
+                .map(|(_, v)| v)
+                .collect();
+            if args.is_empty() {
+                return write_term(buf, env, f, names, level);
+            }
             if level < 10 {
                 write!(buf, "(")?;
             }
-            write_term(buf, f, names, 10)?;
-            write!(buf, " ")?;
-            write_term(buf, v, names, 9)?;
+            write_term(buf, env, f, names, 10)?;
+            for v in args {
+                write!(buf, " ")?;
+                write_term(buf, env, v, names, 9)?;
+            }
             if level < 10 {
                 write!(buf, ")")?;
             }
             Ok(())
         }
         TermVariant::Product(x, x_tp, t) => {
-            let x = fresh(x, names);
             if level < 200 {
                 write!(buf, "(")?;
             }
+            if let Some(t) = t.pop(0) {
+                write_term(buf, env, x_tp, names, 199)?;
+                write!(buf, " -> ")?;
+                write_term(buf, env, &t, names, 200)?;
+                if level < 200 {
+                    write!(buf, ")")?;
+                }
+                return Ok(());
+            }
+            let x = fresh(x, names);
             write!(buf, "forall {} : ", x.name)?;
-            write_term(buf, x_tp, names, 200)?;
+            write_term(buf, env, x_tp, names, 200)?;
             write!(buf, ", ")?;
             {
                 let mut names = names.push(x);
-                write_term(buf, t, &mut names, 200)?
+                write_term(buf, env, t, &mut names, 200)?
             }
             if level < 200 {
                 write!(buf, ")")?;
@@ -136,11 +445,11 @@ pub fn write_term<M>(
                 write!(buf, "(")?;
             }
             write!(buf, "fun {} : ", x.name)?;
-            write_term(buf, x_tp, names, 200)?;
+            write_term(buf, env, x_tp, names, 200)?;
             write!(buf, " => ")?;
             {
                 let mut names = names.push(x);
-                write_term(buf, t, &mut names, 200)?
+                write_term(buf, env, t, &mut names, 200)?
             }
             if level < 200 {
                 write!(buf, ")")?;
@@ -153,13 +462,13 @@ pub fn write_term<M>(
                 write!(buf, "(")?;
             }
             write!(buf, "let {} : ", x.name)?;
-            write_term(buf, x_tp, names, 200)?;
+            write_term(buf, env, x_tp, names, 200)?;
             write!(buf, " := ")?;
-            write_term(buf, x_val, names, 200)?;
+            write_term(buf, env, x_val, names, 200)?;
             write!(buf, " in ")?;
             {
                 let mut names = names.push(x);
-                write_term(buf, t, &mut names, 200)?
+                write_term(buf, env, t, &mut names, 200)?
             }
             if level < 200 {
                 write!(buf, ")")?;
@@ -168,7 +477,7 @@ pub fn write_term<M>(
         }
         TermVariant::Match(t, name, ret, arms) => {
             write!(buf, "match ")?;
-            write_term(buf, t, names, 200)?;
+            write_term(buf, env, t, names, 200)?;
             let mut name = fresh(name, names);
             write!(buf, " as {} in {}", name.name, ret.constructor)?;
             {
@@ -182,7 +491,7 @@ pub fn write_term<M>(
                 }
                 write!(buf, " return ")?;
                 let mut names = names.push(name);
-                write_term(buf, term, &mut names, 200)?;
+                write_term(buf, env, &ret.body, &mut names, 200)?;
                 name = names.pop().next().unwrap();
             }
             write!(buf, " with")?;
@@ -199,23 +508,58 @@ pub fn write_term<M>(
                     names.push_onto(x);
                 }
                 write!(buf, " => ")?;
-                let mut names = names.push(name);
-                write_term(buf, term, &mut names, 200)?;
-                name = names.pop().next().unwrap();
+                write_term(buf, env, &arm.body, &mut names, 200)?;
             }
             write!(buf, " end")
         }
-    }
+        TermVariant::Evar(id, subst) => {
+            write!(buf, "?{id}")?;
+            // The substitution is only shown when it is not the identity
+            let identity = subst.iter().enumerate().all(
+                |(i, t)| matches!(&*t.variant, TermVariant::Var(n) if *n == subst.len() - 1 - i),
+            );
+            if !identity {
+                write!(buf, "@{{")?;
+                let mut sep = "";
+                for t in subst {
+                    write!(buf, "{sep}")?;
+                    write_term(buf, env, t, names, 200)?;
+                    sep = "; ";
+                }
+                write!(buf, "}}")?;
+            }
+            Ok(())
+        }
+        TermVariant::Rewrite(reverse, eq, t) => {
+            if level < 200 {
+                write!(buf, "(")?;
+            }
+            write!(buf, "rewrite {}", if *reverse { "<- " } else { "" })?;
+            write_term(buf, env, eq, names, 200)?;
+            write!(buf, " in ")?;
+            write_term(buf, env, t, names, 200)?;
+            if level < 200 {
+                write!(buf, ")")?;
+            }
+            Ok(())
+        }
+    })
 }
 
-pub fn print_term<M>(term: &Term<M>, names: &mut Stack<Ident>, level: usize) -> String {
+pub fn print_term<M: Clone>(
+    env: &Env,
+    term: &Term<M>,
+    names: &mut Stack<Ident>,
+    level: usize,
+) -> String {
     let mut buf = String::new();
-    write_term(&mut buf, term, names, level).unwrap();
+    write_term(&mut buf, env, term, names, level).unwrap();
     buf
 }
 
-pub fn write_local<'a, M>(
+pub fn write_local<'a, M: Clone>(
     buf: &mut impl Write,
+    env: &Env,
     local: &Stack<Entry<M, Ident>>,
     names: &'a mut Stack<Ident>,
 ) -> Result<StackSlot<'a, Ident>, fmt::Error> {
@@ -223,13 +567,44 @@ pub fn write_local<'a, M>(
     for entry in local.iter().rev() {
         let x = fresh(&entry.x, &names);
         write!(buf, "{}: ", x.name)?;
-        write_term(buf, &entry.ttype, &mut names, 200)?;
+        write_term(buf, env, &entry.ttype, &mut names, 200)?;
         if let Some(value) = &entry.value {
             write!(buf, " := ")?;
-            write_term(buf, value, &mut names, 200)?;
+            write_term(buf, env, value, &mut names, 200)?;
         }
         writeln!(buf)?;
         names.push_onto(x);
     }
     Ok(names)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Location, Span};
+
+    #[test]
+    fn deep_applications() {
+        let loc = Location {
+            pos: 0,
+            line: 1,
+            col: 1,
+        };
+        let span = Span {
+            start: loc,
+            end: loc,
+        };
+        let constant = |name: &str| Term {
+            meta: span.clone(),
+            variant: Box::new(TermVariant::Const(name.to_owned())),
+        };
+        let t = (0..200_000).fold(constant("O"), |t, _| Term {
+            meta: span.clone(),
+            variant: Box::new(TermVariant::App(constant("S"), t)),
+        });
+
+        let printed = print_term(&Env::new(), &t, &mut Stack::new(), 200);
+        assert!(printed.starts_with("S (S (S "));
+        assert!(printed.ends_with(&format!("S O{}", ")".repeat(199_999))));
+    }
+}
@@ -0,0 +1,353 @@
+use hane_kernel::{Sort, Stack};
+
+use crate::{
+    env::{Arg, Env, Inductive},
+    lower::{binder_args, hole, lowered, product_args, LoweringError, State},
+    Expr, Ident, Record, Span, SpanError,
+};
+
+type Result<T> = std::result::Result<T, SpanError<LoweringError>>;
+
+fn term(meta: &Span, variant: lowered::TermVariant) -> lowered::Term {
+    lowered::Term {
+        meta: meta.clone(),
+        variant: Box::new(variant),
+    }
+}
+
+/// Applies the constant `f` to the variables `vars`, which are given from the first argument.
+fn applied(meta: &Span, f: &str, vars: impl Iterator<Item = usize>) -> lowered::Term {
+    vars.fold(
+        term(meta, lowered::TermVariant::Const(f.to_owned())),
+        |f, x| {
+            term(
+                meta,
+                lowered::TermVariant::App(f, term(meta, lowered::TermVariant::Var(x))),
+            )
+        },
+    )
+}
+
+/// Lowers the record declared by the command at `span` into its inductive type and its projections.
+/// Each projection matches on the record, and the types of later fields refer to earlier ones through their projections.
+///
+/// The parameters are implicit arguments of the constructor and the projections.
+/// The record is an implicit argument of the projections of a class, as it is found by instance resolution.
+pub fn lower(
+    span: Span,
+    record: Record,
+    class: bool,
+    global: &mut Env,
+    state: &mut State,
+) -> Result<Vec<lowered::Command>> {
+    let name = record.name;
+    let (constructor, constructor_span) = match record.constructor {
+        Some(constructor) => (constructor.name, constructor.span),
+        None => (format!("Build_{}", name.name), name.span.clone()),
+    };
+    let taken = |x: &str| global.contains(x) || x == name.name || x == constructor;
+    if global.contains(&name.name) {
+        return Err(SpanError {
+            span: name.span,
+            err: LoweringError::NameNotFree(name.name),
+        });
+    }
+    if global.contains(&constructor) || constructor == name.name {
+        return Err(SpanError {
+            span: constructor_span,
+            err: LoweringError::NameNotFree(constructor),
+        });
+    }
+    for (i, (field, _)) in record.fields.iter().enumerate() {
+        if taken(&field.name) || record.fields[..i].iter().any(|(x, _)| x == field) {
+            return Err(SpanError {
+                span: field.span.clone(),
+                err: LoweringError::NameNotFree(field.name.clone()),
+            });
+        }
+    }
+
+    let type_args: Vec<_> = binder_args(&record.params).collect();
+    let param_args: Vec<_> = type_args
+        .iter()
+        .map(|arg| Arg {
+            name: arg.name.clone(),
+            implicit: true,
+        })
+        .collect();
+    let mut stack = Stack::new();
+    let mut names = stack.slot();
+    let mut params = Vec::with_capacity(record.params.len());
+    for param in record.params {
+        let ident = param.ident.clone();
+        params.push(param.lower(params.last(), global, &mut names, state)?);
+        names.push_onto(ident);
+    }
+    let sort = match record.sort {
+        Some(sort) => sort.lower(global, &mut names, state)?,
+        None => term(&name.span, lowered::TermVariant::Sort(Sort::Type(0))),
+    };
+    // Each field is in the context of the parameters and the fields before it
+    let mut fields = Vec::with_capacity(record.fields.len());
+    let mut field_args = Vec::with_capacity(record.fields.len());
+    for (field, ttype) in record.fields {
+        field_args.push(product_args(&ttype));
+        let ttype = ttype.lower(global, &mut names, state)?;
+        names.push_onto(field.clone());
+        fields.push((field, ttype));
+    }
+
+    let k = params.len();
+    let n = fields.len();
+    let constructor_type = fields.iter().rev().fold(
+        applied(&span, &name.name, (0..k).map(|i| n + k - 1 - i)),
+        |body, (field, ttype)| {
+            term(
+                &span,
+                lowered::TermVariant::Product(field.clone(), ttype.clone(), body),
+            )
+        },
+    );
+    let inductive = lowered::Command {
+        meta: span.clone(),
+        variant: lowered::CommandVariant::Inductive(
+            params.clone(),
+            vec![lowered::IndBody {
+                name: name.name.clone(),
+                ttype: sort,
+                constructors: vec![lowered::IndConstructor {
+                    name: constructor.clone(),
+                    ttype: constructor_type,
+                }],
+            }],
+        ),
+    };
+
+    // The projections are in the context of the parameters and the record, which is the newest variable
+    let binder = Ident {
+        span: span.clone(),
+        name: "r".to_owned(),
+    };
+    let record_type = applied(&span, &name.name, (0..k).map(|i| k - 1 - i));
+    let projection = |m: usize| applied(&span, &fields[m].0.name, (0..=k).rev());
+    let arm_params: Vec<_> = params
+        .iter()
+        .map(|param| param.x.clone())
+        .chain(fields.iter().map(|(field, _)| field.clone()))
+        .collect();
+    let mut commands = vec![inductive];
+    for (j, (field, ttype)) in fields.iter().enumerate() {
+        let ttype = ttype.subst(|meta, x, push| {
+            if x < push {
+                return term(meta, lowered::TermVariant::Var(x));
+            }
+            match x - push {
+                x if x < j => projection(j - 1 - x).push(push),
+                x => term(meta, lowered::TermVariant::Var(x - j + 1 + push)),
+            }
+        });
+        let ret = lowered::MatchArm {
+            meta: span.clone(),
+            constructor: String::new(),
+            params: Vec::new(),
+            body: hole(span.clone(), state),
+        };
+        let arm = lowered::MatchArm {
+            meta: span.clone(),
+            constructor: constructor.clone(),
+            params: arm_params.clone(),
+            body: term(&span, lowered::TermVariant::Var(n - 1 - j)),
+        };
+        let scrutinee = term(&span, lowered::TermVariant::Var(0));
+        let value = term(
+            &span,
+            lowered::TermVariant::Match(scrutinee, binder.clone(), ret, vec![arm]),
+        );
+        let ttype = term(
+            &span,
+            lowered::TermVariant::Product(binder.clone(), record_type.clone(), ttype),
+        );
+        let value = term(
+            &span,
+            lowered::TermVariant::Abstract(binder.clone(), record_type.clone(), value),
+        );
+        let (ttype, value) = params
+            .iter()
+            .rev()
+            .fold((ttype, value), |(ttype, value), param| {
+                (
+                    term(
+                        &span,
+                        lowered::TermVariant::Product(param.x.clone(), param.ttype.clone(), ttype),
+                    ),
+                    term(
+                        &span,
+                        lowered::TermVariant::Abstract(param.x.clone(), param.ttype.clone(), value),
+                    ),
+                )
+            });
+        commands.push(lowered::Command {
+            meta: field.span.clone(),
+            variant: lowered::CommandVariant::Definition(field.name.clone(), ttype, value),
+        });
+    }
+
+    global.insert(name.name.clone());
+    global.insert(constructor.clone());
+    global.set_args(name.name.clone(), type_args);
+    let mut args = param_args.clone();
+    args.extend(fields.iter().map(|(field, _)| Arg {
+        name: field.name.clone(),
+        implicit: false,
+    }));
+    global.set_args(constructor.clone(), args);
+    for ((field, _), extra) in fields.iter().zip(field_args) {
+        global.insert(field.name.clone());
+        let mut args = param_args.clone();
+        args.push(Arg {
+            name: binder.name.clone(),
+            implicit: class,
+        });
+        args.extend(extra);
+        global.set_args(field.name.clone(), args);
+    }
+    global.add_inductive(
+        name.name.clone(),
+        Inductive {
+            params: k,
+            indices: 0,
+            constructors: vec![(constructor.clone(), n)],
+        },
+    );
+    global.add_record(
+        name.name.clone(),
+        crate::env::Record {
+            constructor,
+            fields: fields.into_iter().map(|(field, _)| field.name).collect(),
+        },
+    );
+    if class {
+        global.add_class(name.name);
+    }
+    Ok(commands)
+}
+
+/// Returns the values given to the fields of `record`, in the order they are declared.
+fn order_fields<T>(
+    name: &str,
+    record: &crate::env::Record,
+    mut values: Vec<(Ident, T)>,
+) -> Result<Vec<Option<T>>> {
+    for (i, (x, _)) in values.iter().enumerate() {
+        if !record.fields.contains(&x.name) {
+            return Err(SpanError {
+                span: x.span.clone(),
+                err: LoweringError::UnknownField(name.to_owned(), x.name.clone()),
+            });
+        }
+        if values[..i].iter().any(|(y, _)| x == y) {
+            return Err(SpanError {
+                span: x.span.clone(),
+                err: LoweringError::DuplicateField(x.name.clone()),
+            });
+        }
+    }
+    Ok(record
+        .fields
+        .iter()
+        .map(|field| {
+            let i = values.iter().position(|(x, _)| x.name == *field)?;
+            Some(values.swap_remove(i).1)
+        })
+        .collect())
+}
+
+/// Applies the constant `f` to holes for the `params` parameters of a record.
+fn with_params(span: &Span, f: &str, params: usize, state: &mut State) -> lowered::Term {
+    (0..params).fold(
+        term(span, lowered::TermVariant::Const(f.to_owned())),
+        |f, _| {
+            term(
+                span,
+                lowered::TermVariant::App(f, hole(span.clone(), state)),
+            )
+        },
+    )
+}
+
+/// Lowers the record `{| base with values |}` at `span`, which is a record of the type having the first field.
+pub fn construct(
+    span: Span,
+    base: Option<Expr>,
+    values: Vec<(Ident, Expr)>,
+    global: &Env,
+    names: &mut Stack<Ident>,
+    state: &mut State,
+) -> Result<lowered::Term> {
+    let first = &values[0].0;
+    let Some((name, record)) = global.record_of_field(&first.name) else {
+        return Err(SpanError {
+            span: first.span.clone(),
+            err: LoweringError::NotAField(first.name.clone()),
+        });
+    };
+    let params = global.inductive(name).unwrap().params;
+    let values = order_fields(name, record, values)?;
+    let mut value = with_params(&span, &record.constructor, params, state);
+    let Some(base) = base else {
+        for (field, v) in record.fields.iter().zip(values) {
+            let Some(v) = v else {
+                return Err(SpanError {
+                    span,
+                    err: LoweringError::MissingField(field.clone()),
+                });
+            };
+            let v = v.lower(global, names, state)?;
+            value = term(&span, lowered::TermVariant::App(value, v));
+        }
+        return Ok(value);
+    };
+    // The record being updated is bound, so that the values are under one more variable
+    let base = base.lower(global, names, state)?;
+    for (field, v) in record.fields.iter().zip(values) {
+        let v = match v {
+            Some(v) => v.lower(global, names, state)?.push(1),
+            None => {
+                let projection = with_params(&span, field, params, state);
+                let record = term(&span, lowered::TermVariant::Var(0));
+                term(&span, lowered::TermVariant::App(projection, record))
+            }
+        };
+        value = term(&span, lowered::TermVariant::App(value, v));
+    }
+    let x = Ident {
+        span: span.clone(),
+        name: name.to_owned(),
+    };
+    let ttype = hole(span.clone(), state);
+    Ok(term(
+        &span,
+        lowered::TermVariant::Bind(x, ttype, base, value),
+    ))
+}
+
+/// Lowers the projection `t.(field)` at `span`.
+pub fn project(
+    span: Span,
+    t: Expr,
+    field: Ident,
+    global: &Env,
+    names: &mut Stack<Ident>,
+    state: &mut State,
+) -> Result<lowered::Term> {
+    let Some((name, _)) = global.record_of_field(&field.name) else {
+        return Err(SpanError {
+            span: field.span,
+            err: LoweringError::NotAField(field.name),
+        });
+    };
+    let params = global.inductive(name).unwrap().params;
+    let projection = with_params(&span, &field.name, params, state);
+    let t = t.lower(global, names, state)?;
+    Ok(term(&span, lowered::TermVariant::App(projection, t)))
+}
@@ -0,0 +1,317 @@
+use crate::{
+    env::{self, Env},
+    lower::{lowered, LoweringError},
+    parser, Binder, Command, CommandVariant, Expr, ExprItem, ExprVariant, Ident, IndBody, MatchArm,
+    Pattern, PatternVariant, Span, SpanError,
+};
+
+type Result<T> = std::result::Result<T, SpanError<LoweringError>>;
+
+/// The library of every ring, followed by that of either semirings or rings.
+/// Each command is separated from the next by a blank line.
+const COMMON: &str = include_str!("ring/common.v");
+const SEMIRING: &str = include_str!("ring/semiring.v");
+const RING: &str = include_str!("ring/ring.v");
+
+/// The placeholders of the templates for the carrier, the operations and the axioms, along with the roles they are registered as.
+const ROLES: &[(&str, &str)] = &[
+    ("eq", "eq"),
+    ("Z", "ring_carrier"),
+    ("zero", "ring_0"),
+    ("one", "ring_1"),
+    ("add", "ring_add"),
+    ("mul", "ring_mul"),
+    ("add_0_l", "ring_add_0_l"),
+    ("add_comm", "ring_add_comm"),
+    ("add_assoc", "ring_add_assoc"),
+    ("mul_1_l", "ring_mul_1_l"),
+    ("mul_0_l", "ring_mul_0_l"),
+    ("mul_comm", "ring_mul_comm"),
+    ("mul_assoc", "ring_mul_assoc"),
+    ("distr_l", "ring_distr_l"),
+];
+
+/// The placeholders only a ring has, which are all registered if `ring_opp` is.
+const RING_ROLES: &[(&str, &str)] = &[("opp", "ring_opp"), ("opp_def", "ring_opp_def")];
+
+/// Replaces the placeholders of a template, where `$R` is the name of the library and `$R_x` is `name_x`.
+/// The constants of the ring are given explicitly as `@c`, except where only a name is allowed,
+/// as the inductive type of an `in` clause and the constructor of a pattern.
+fn instantiate(template: &str, name: &str, constants: &[(&str, String)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('$') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        let end = rest
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_' && c != '\'')
+            .unwrap_or(rest.len());
+        let placeholder = &rest[..end];
+        rest = &rest[end..];
+        if placeholder == "R" {
+            out.push_str(name);
+        } else if let Some(suffix) = placeholder.strip_prefix("R_") {
+            out.push_str(name);
+            out.push('_');
+            out.push_str(suffix);
+        } else {
+            let (_, constant) = constants.iter().find(|(x, _)| *x == placeholder).unwrap();
+            let bare = (placeholder == "eq" && out.ends_with("in "))
+                || (placeholder == "eq_refl" && out.ends_with("| "));
+            if !bare {
+                out.push('@');
+            }
+            out.push_str(constant);
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Lowers `Add Ring name`, which declares the library `ring` proves equalities with for the registered ring.
+/// The library is instantiated from templates, whose spans are replaced by the span of the command.
+pub fn lower(span: Span, name: Ident, global: &mut Env) -> Result<Vec<lowered::Command>> {
+    let invalid = |reason: String| SpanError {
+        span: span.clone(),
+        err: LoweringError::InvalidRing(reason),
+    };
+    let registered = |role: &str| {
+        global
+            .registered(role)
+            .map(str::to_owned)
+            .ok_or_else(|| invalid(format!("nothing is registered as `{role}`")))
+    };
+    let ring = global.registered("ring_opp").is_some();
+    let mut constants = Vec::new();
+    for &(placeholder, role) in ROLES.iter().chain(if ring { RING_ROLES } else { &[] }) {
+        constants.push((placeholder, registered(role)?));
+    }
+    // The proofs of reflexivity are the constructor of the equality
+    let eq_refl = match global.inductive(&constants[0].1) {
+        Some(env::Inductive {
+            params: 2,
+            indices: 1,
+            constructors,
+        }) if matches!(constructors[..], [(_, 0)]) => constructors[0].0.clone(),
+        _ => return Err(invalid(
+            "the equality must be an inductive type with a single constructor taking no arguments"
+                .to_owned(),
+        )),
+    };
+    constants.push(("eq_refl", eq_refl));
+
+    let templates: &[&str] = if ring {
+        &[COMMON, RING]
+    } else {
+        &[COMMON, SEMIRING]
+    };
+    // Each command is parsed on its own, as finding the lines and columns of the spans is quadratic in the length of the input
+    let mut commands = Vec::new();
+    for template in templates {
+        let library = instantiate(template, &name.name, &constants);
+        for chunk in library.split("\n\n") {
+            let parsed = parser::parse(chunk)
+                .unwrap_or_else(|_| panic!("the ring library does not parse:\n{chunk}"));
+            commands.extend(parsed);
+        }
+    }
+    for command in &mut commands {
+        respan_command(command, &span);
+    }
+    for command in &commands {
+        let names: Vec<&Ident> = match &command.variant {
+            CommandVariant::Definition(x, _, _, _) => vec![x],
+            CommandVariant::Inductive(bodies) => bodies
+                .iter()
+                .flat_map(|body| {
+                    std::iter::once(&body.name).chain(
+                        body.constructors
+                            .iter()
+                            .map(|constructor| &constructor.name),
+                    )
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        if let Some(x) = names.into_iter().find(|x| global.contains(&x.name)) {
+            return Err(SpanError {
+                span: name.span,
+                err: LoweringError::NameNotFree(x.name.clone()),
+            });
+        }
+    }
+
+    let mut lowered = Vec::new();
+    for command in commands {
+        lowered.extend(command.lower(global)?);
+    }
+    let [_, (_, carrier), (_, zero), (_, one), (_, add), (_, mul), ..] = &constants[..] else {
+        unreachable!()
+    };
+    global.set_ring(env::Ring {
+        carrier: carrier.clone(),
+        zero: zero.clone(),
+        one: one.clone(),
+        add: add.clone(),
+        mul: mul.clone(),
+        opp: global.registered("ring_opp").map(str::to_owned),
+        name: name.name,
+    });
+    Ok(lowered)
+}
+
+fn respan_command(command: &mut Command, span: &Span) {
+    command.span = span.clone();
+    match &mut command.variant {
+        CommandVariant::Definition(x, params, ttype, value) => {
+            respan_ident(x, span);
+            respan_binders(params, span);
+            if let Some(ttype) = ttype {
+                respan_expr(ttype, span);
+            }
+            respan_expr(value, span);
+        }
+        CommandVariant::Inductive(bodies) => {
+            for IndBody {
+                name,
+                params,
+                ttype,
+                constructors,
+            } in bodies
+            {
+                respan_ident(name, span);
+                respan_binders(params, span);
+                respan_expr(ttype, span);
+                for constructor in constructors {
+                    respan_ident(&mut constructor.name, span);
+                    respan_expr(&mut constructor.ttype, span);
+                }
+            }
+        }
+        _ => unreachable!("the ring library only has definitions and inductive types"),
+    }
+}
+
+fn respan_ident(x: &mut Ident, span: &Span) {
+    x.span = span.clone();
+}
+
+fn respan_binders(binders: &mut [Binder], span: &Span) {
+    for binder in binders {
+        respan_ident(&mut binder.ident, span);
+        if let Some(ttype) = &mut binder.ttype {
+            respan_expr(ttype, span);
+        }
+    }
+}
+
+fn respan_pattern(pattern: &mut Pattern, span: &Span) {
+    pattern.span = span.clone();
+    match &mut pattern.variant {
+        PatternVariant::Wildcard => {}
+        PatternVariant::Ident(x) => respan_ident(x, span),
+        PatternVariant::Constructor(x, patterns) => {
+            respan_ident(x, span);
+            for pattern in patterns {
+                respan_pattern(pattern, span);
+            }
+        }
+        PatternVariant::Or(patterns) => {
+            for pattern in patterns {
+                respan_pattern(pattern, span);
+            }
+        }
+    }
+}
+
+fn respan_expr(expr: &mut Expr, span: &Span) {
+    expr.span = span.clone();
+    match &mut *expr.variant {
+        ExprVariant::Sort(_)
+        | ExprVariant::Var(_)
+        | ExprVariant::Explicit(_)
+        | ExprVariant::Hole
+        | ExprVariant::Goal(_)
+        | ExprVariant::Number(_) => {}
+        ExprVariant::App(f, v) => {
+            respan_expr(f, span);
+            respan_expr(v, span);
+        }
+        ExprVariant::NamedApp(f, x, v) => {
+            respan_expr(f, span);
+            respan_ident(x, span);
+            respan_expr(v, span);
+        }
+        ExprVariant::Product(binders, body) | ExprVariant::Abstract(binders, body) => {
+            respan_binders(binders, span);
+            respan_expr(body, span);
+        }
+        ExprVariant::Bind(x, ttype, value, body) => {
+            respan_ident(x, span);
+            if let Some(ttype) = ttype {
+                respan_expr(ttype, span);
+            }
+            respan_expr(value, span);
+            respan_expr(body, span);
+        }
+        ExprVariant::Match(scrutinees, name, ind, ret, arms) => {
+            for scrutinee in scrutinees {
+                respan_expr(scrutinee, span);
+            }
+            if let Some(name) = name {
+                respan_ident(name, span);
+            }
+            if let Some(ind) = ind {
+                respan_ident(&mut ind.constructor, span);
+                for param in &mut ind.params {
+                    respan_ident(param, span);
+                }
+            }
+            if let Some(ret) = ret {
+                respan_expr(ret, span);
+            }
+            for MatchArm { alternatives, body } in arms {
+                for (alternative, patterns) in alternatives {
+                    *alternative = span.clone();
+                    for pattern in patterns {
+                        respan_pattern(pattern, span);
+                    }
+                }
+                respan_expr(body, span);
+            }
+        }
+        ExprVariant::Notation(items) => {
+            for item in items {
+                match item {
+                    ExprItem::Term(item_span, expr) => {
+                        *item_span = span.clone();
+                        respan_expr(expr, span);
+                    }
+                    ExprItem::Named(item_span, x, expr) => {
+                        *item_span = span.clone();
+                        respan_ident(x, span);
+                        respan_expr(expr, span);
+                    }
+                    ExprItem::Symbol(x) => respan_ident(x, span),
+                }
+            }
+        }
+        ExprVariant::Record(record, fields) => {
+            if let Some(record) = record {
+                respan_expr(record, span);
+            }
+            for (x, value) in fields {
+                respan_ident(x, span);
+                respan_expr(value, span);
+            }
+        }
+        ExprVariant::Project(record, x) => {
+            respan_expr(record, span);
+            respan_ident(x, span);
+        }
+        ExprVariant::Rewrite(_, eq, body) => {
+            respan_expr(eq, span);
+            respan_expr(body, span);
+        }
+    }
+}
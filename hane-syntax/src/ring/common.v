@@ -0,0 +1,294 @@
+(* The library `ring` proves equalities with, instantiated for a ring by `Add Ring`.
+   The kernel has no fixpoints, so normalization recurses as deep as the fuel numerals allow,
+   each normal form carrying the proof that it is equal to the term it normalizes. *)
+Definition $R_sym (x y : $Z) (e : $eq $Z x y) : $eq $Z y x :=
+    match e in $eq _ _ w return $eq $Z w x with
+    | $eq_refl _ _ => $eq_refl $Z x
+    end.
+
+Definition $R_trans (x y z : $Z) (e1 : $eq $Z x y) (e2 : $eq $Z y z) : $eq $Z x z :=
+    match e2 in $eq _ _ w return $eq $Z x w with
+    | $eq_refl _ _ => e1
+    end.
+
+Definition $R_cong (f : $Z -> $Z) (x y : $Z) (e : $eq $Z x y) : $eq $Z (f x) (f y) :=
+    match e in $eq _ _ w return $eq $Z (f x) (f w) with
+    | $eq_refl _ _ => $eq_refl $Z (f x)
+    end.
+
+Definition $R_cong2 (f : $Z -> $Z -> $Z) (x x' y y' : $Z) (e1 : $eq $Z x x') (e2 : $eq $Z y y') : $eq $Z (f x y) (f x' y') :=
+    $R_trans (f x y) (f x' y) (f x' y') ($R_cong (fun t : $Z => f t y) x x' e1) ($R_cong (fun t : $Z => f x' t) y y' e2).
+
+(* Consequences of the axioms of the ring *)
+Definition $R_add_0_r (x : $Z) : $eq $Z ($add x $zero) x :=
+    $R_trans ($add x $zero) ($add $zero x) x ($add_comm x $zero) ($add_0_l x).
+
+Definition $R_mul_1_r (x : $Z) : $eq $Z ($mul x $one) x :=
+    $R_trans ($mul x $one) ($mul $one x) x ($mul_comm x $one) ($mul_1_l x).
+
+Definition $R_mul_0_r (x : $Z) : $eq $Z ($mul x $zero) $zero :=
+    $R_trans ($mul x $zero) ($mul $zero x) $zero ($mul_comm x $zero) ($mul_0_l x).
+
+Definition $R_add_swap (x y z : $Z) : $eq $Z ($add x ($add y z)) ($add y ($add x z)) :=
+    $R_trans ($add x ($add y z)) ($add ($add y x) z) ($add y ($add x z))
+        ($R_trans ($add x ($add y z)) ($add ($add x y) z) ($add ($add y x) z)
+            ($add_assoc x y z) ($R_cong (fun t : $Z => $add t z) ($add x y) ($add y x) ($add_comm x y)))
+        ($R_sym ($add y ($add x z)) ($add ($add y x) z) ($add_assoc y x z)).
+
+Definition $R_mul_swap (x y z : $Z) : $eq $Z ($mul x ($mul y z)) ($mul y ($mul x z)) :=
+    $R_trans ($mul x ($mul y z)) ($mul ($mul y x) z) ($mul y ($mul x z))
+        ($R_trans ($mul x ($mul y z)) ($mul ($mul x y) z) ($mul ($mul y x) z)
+            ($mul_assoc x y z) ($R_cong (fun t : $Z => $mul t z) ($mul x y) ($mul y x) ($mul_comm x y)))
+        ($R_sym ($mul y ($mul x z)) ($mul ($mul y x) z) ($mul_assoc y x z)).
+
+Definition $R_distr_r (x y z : $Z) : $eq $Z ($mul x ($add y z)) ($add ($mul x y) ($mul x z)) :=
+    $R_trans ($mul x ($add y z)) ($add ($mul y x) ($mul z x)) ($add ($mul x y) ($mul x z))
+        ($R_trans ($mul x ($add y z)) ($mul ($add y z) x) ($add ($mul y x) ($mul z x)) ($mul_comm x ($add y z)) ($distr_l y z x))
+        ($R_cong2 $add ($mul y x) ($mul x y) ($mul z x) ($mul x z) ($mul_comm y x) ($mul_comm z x)).
+
+(* The index of an atom, the subterms which are not built from the operations of the ring *)
+Inductive $R_idx : Set := $R_idx_O : $R_idx | $R_idx_S : $R_idx -> $R_idx.
+
+Inductive $R_order : Set := $R_lt : $R_order | $R_eq : $R_order | $R_gt : $R_order.
+
+(* A Church numeral, which is doubled rather than incremented to keep the proofs small *)
+Definition $R_fuel : Type{0} := forall X : Set, X -> (X -> X) -> X.
+
+Definition $R_fuel_one : $R_fuel := fun (X : Set) (x : X) (f : X -> X) => f x.
+
+Definition $R_fuel_double (n : $R_fuel) : $R_fuel := fun (X : Set) (x : X) (f : X -> X) => n X (n X x f) f.
+
+(* A monomial `x1 * (x2 * .. 1)` whose atoms are sorted by their index *)
+Inductive $R_mon : forall x : $Z, Set :=
+    $R_mon_one : $R_mon $one
+  | $R_mon_mul : forall (i : $R_idx) (x t : $Z), $R_mon t -> $R_mon ($mul x t).
+
+(* A polynomial `m1 + (m2 + .. 0)` whose monomials are sorted, repeating a monomial instead of computing coefficients *)
+Inductive $R_poly : forall x : $Z, Set :=
+    $R_poly_zero : $R_poly $zero
+  | $R_poly_add : forall (x t : $Z), $R_mon x -> $R_poly t -> $R_poly ($add x t).
+
+(* The comparisons of indices and monomials, which need as much fuel as the longest of the two *)
+Definition $R_cmp_idx (F : $R_fuel) : $R_idx -> $R_idx -> $R_order :=
+    F ($R_idx -> $R_idx -> $R_order) (fun (i j : $R_idx) => $R_eq)
+        (fun (rec : $R_idx -> $R_idx -> $R_order) (i j : $R_idx) =>
+            match i in $R_idx return $R_order with
+            | $R_idx_O => match j in $R_idx return $R_order with
+                | $R_idx_O => $R_eq
+                | $R_idx_S _ => $R_lt
+                end
+            | $R_idx_S i1 => match j in $R_idx return $R_order with
+                | $R_idx_O => $R_gt
+                | $R_idx_S j1 => rec i1 j1
+                end
+            end).
+
+Definition $R_cmp_mon (ci : $R_idx -> $R_idx -> $R_order) (F : $R_fuel) : forall x y : $Z, $R_mon x -> $R_mon y -> $R_order :=
+    F (forall x y : $Z, $R_mon x -> $R_mon y -> $R_order) (fun (x y : $Z) (m : $R_mon x) (n : $R_mon y) => $R_eq)
+        (fun (rec : forall x y : $Z, $R_mon x -> $R_mon y -> $R_order) (x y : $Z) (m : $R_mon x) (n : $R_mon y) =>
+            match m in $R_mon _ return $R_order with
+            | $R_mon_one => match n in $R_mon _ return $R_order with
+                | $R_mon_one => $R_eq
+                | $R_mon_mul _ _ _ _ => $R_lt
+                end
+            | $R_mon_mul i _ t m1 => match n in $R_mon _ return $R_order with
+                | $R_mon_one => $R_gt
+                | $R_mon_mul j _ u n1 => match ci i j in $R_order return $R_order with
+                    | $R_lt => $R_lt
+                    | $R_eq => rec t u m1 n1
+                    | $R_gt => $R_gt
+                    end
+                end
+            end).
+
+(* The normal form of a monomial `v`, which fails when the fuel ran out *)
+Inductive $R_mnf (v : $Z) : Set :=
+    $R_mnf_mk : forall w : $Z, $R_mon w -> $eq $Z v w -> $R_mnf v
+  | $R_mnf_fail : $R_mnf v.
+
+(* The proofs of the steps of merging are lemmas, so that recursing does not copy them *)
+Definition $R_times_gt (a t b u w : $Z) (e : $eq $Z ($mul ($mul a t) u) w) : $eq $Z ($mul ($mul a t) ($mul b u)) ($mul b w) :=
+    $R_trans ($mul ($mul a t) ($mul b u)) ($mul b ($mul ($mul a t) u)) ($mul b w)
+        ($R_mul_swap ($mul a t) b u) ($R_cong ($mul b) ($mul ($mul a t) u) w e).
+
+Definition $R_times_le (a t b u w : $Z) (e : $eq $Z ($mul t ($mul b u)) w) : $eq $Z ($mul ($mul a t) ($mul b u)) ($mul a w) :=
+    $R_trans ($mul ($mul a t) ($mul b u)) ($mul a ($mul t ($mul b u))) ($mul a w)
+        ($R_sym ($mul a ($mul t ($mul b u))) ($mul ($mul a t) ($mul b u)) ($mul_assoc a t ($mul b u)))
+        ($R_cong ($mul a) ($mul t ($mul b u)) w e).
+
+Definition $R_merge_gt (a t b u w : $Z) (e : $eq $Z ($add ($add a t) u) w) : $eq $Z ($add ($add a t) ($add b u)) ($add b w) :=
+    $R_trans ($add ($add a t) ($add b u)) ($add b ($add ($add a t) u)) ($add b w)
+        ($R_add_swap ($add a t) b u) ($R_cong ($add b) ($add ($add a t) u) w e).
+
+Definition $R_merge_le (a t b u w : $Z) (e : $eq $Z ($add t ($add b u)) w) : $eq $Z ($add ($add a t) ($add b u)) ($add a w) :=
+    $R_trans ($add ($add a t) ($add b u)) ($add a ($add t ($add b u))) ($add a w)
+        ($R_sym ($add a ($add t ($add b u))) ($add ($add a t) ($add b u)) ($add_assoc a t ($add b u)))
+        ($R_cong ($add a) ($add t ($add b u)) w e).
+
+(* Multiplying two monomials merges their atoms, recursing once for each of them *)
+Definition $R_times (ci : $R_idx -> $R_idx -> $R_order) (F : $R_fuel) : forall x y : $Z, $R_mon x -> $R_mon y -> $R_mnf ($mul x y) :=
+    F (forall x y : $Z, $R_mon x -> $R_mon y -> $R_mnf ($mul x y)) (fun (x y : $Z) (m : $R_mon x) (n : $R_mon y) => $R_mnf_fail ($mul x y))
+        (fun (rec : forall x y : $Z, $R_mon x -> $R_mon y -> $R_mnf ($mul x y)) (x y : $Z) (m : $R_mon x) (n : $R_mon y) =>
+            match m in $R_mon x0 return $R_mnf ($mul x0 y) with
+            | $R_mon_one => $R_mnf_mk ($mul $one y) y n ($mul_1_l y)
+            | $R_mon_mul i a t m1 => match n in $R_mon y0 return $R_mnf ($mul ($mul a t) y0) with
+                | $R_mon_one => $R_mnf_mk ($mul ($mul a t) $one) ($mul a t) ($R_mon_mul i a t m1) ($R_mul_1_r ($mul a t))
+                | $R_mon_mul j b u n1 => match ci i j in $R_order return $R_mnf ($mul ($mul a t) ($mul b u)) with
+                    | $R_gt => match rec ($mul a t) u ($R_mon_mul i a t m1) n1 in $R_mnf _ return $R_mnf ($mul ($mul a t) ($mul b u)) with
+                        | $R_mnf_mk _ w k e => $R_mnf_mk ($mul ($mul a t) ($mul b u)) ($mul b w) ($R_mon_mul j b w k) ($R_times_gt a t b u w e)
+                        | $R_mnf_fail _ => $R_mnf_fail ($mul ($mul a t) ($mul b u))
+                        end
+                    | $R_lt | $R_eq => match rec t ($mul b u) m1 ($R_mon_mul j b u n1) in $R_mnf _ return $R_mnf ($mul ($mul a t) ($mul b u)) with
+                        | $R_mnf_mk _ w k e => $R_mnf_mk ($mul ($mul a t) ($mul b u)) ($mul a w) ($R_mon_mul i a w k) ($R_times_le a t b u w e)
+                        | $R_mnf_fail _ => $R_mnf_fail ($mul ($mul a t) ($mul b u))
+                        end
+                    end
+                end
+            end).
+
+(* The normal form of `v`, which fails when the fuel ran out *)
+Inductive $R_nf (v : $Z) : Set :=
+    $R_nf_mk : forall w : $Z, $R_poly w -> $eq $Z v w -> $R_nf v
+  | $R_nf_fail : $R_nf v.
+
+(* Adding two polynomials merges their monomials in the same way *)
+Definition $R_merge (cm : forall x y : $Z, $R_mon x -> $R_mon y -> $R_order) (F : $R_fuel) : forall x y : $Z, $R_poly x -> $R_poly y -> $R_nf ($add x y) :=
+    F (forall x y : $Z, $R_poly x -> $R_poly y -> $R_nf ($add x y)) (fun (x y : $Z) (p : $R_poly x) (q : $R_poly y) => $R_nf_fail ($add x y))
+        (fun (rec : forall x y : $Z, $R_poly x -> $R_poly y -> $R_nf ($add x y)) (x y : $Z) (p : $R_poly x) (q : $R_poly y) =>
+            match p in $R_poly x0 return $R_nf ($add x0 y) with
+            | $R_poly_zero => $R_nf_mk ($add $zero y) y q ($add_0_l y)
+            | $R_poly_add a t m p1 => match q in $R_poly y0 return $R_nf ($add ($add a t) y0) with
+                | $R_poly_zero => $R_nf_mk ($add ($add a t) $zero) ($add a t) ($R_poly_add a t m p1) ($R_add_0_r ($add a t))
+                | $R_poly_add b u n q1 => match cm a b m n in $R_order return $R_nf ($add ($add a t) ($add b u)) with
+                    | $R_gt => match rec ($add a t) u ($R_poly_add a t m p1) q1 in $R_nf _ return $R_nf ($add ($add a t) ($add b u)) with
+                        | $R_nf_mk _ w r e => $R_nf_mk ($add ($add a t) ($add b u)) ($add b w) ($R_poly_add b w n r) ($R_merge_gt a t b u w e)
+                        | $R_nf_fail _ => $R_nf_fail ($add ($add a t) ($add b u))
+                        end
+                    | $R_lt | $R_eq => match rec t ($add b u) p1 ($R_poly_add b u n q1) in $R_nf _ return $R_nf ($add ($add a t) ($add b u)) with
+                        | $R_nf_mk _ w r e => $R_nf_mk ($add ($add a t) ($add b u)) ($add a w) ($R_poly_add a w m r) ($R_merge_le a t b u w e)
+                        | $R_nf_fail _ => $R_nf_fail ($add ($add a t) ($add b u))
+                        end
+                    end
+                end
+            end).
+
+Definition $R_scale_zero (acc x : $Z) : $eq $Z ($add acc ($mul x $zero)) acc :=
+    $R_trans ($add acc ($mul x $zero)) ($add acc $zero) acc ($R_cong ($add acc) ($mul x $zero) $zero ($R_mul_0_r x)) ($R_add_0_r acc).
+
+(* `acc + x * (b + u) = w` from `x * b = z`, `(z + 0) + acc = acc1` and `acc1 + x * u = w` *)
+Definition $R_scale_step (acc x b u z acc1 w : $Z) (f : $eq $Z ($mul x b) z) (g : $eq $Z ($add ($add z $zero) acc) acc1)
+    (h : $eq $Z ($add acc1 ($mul x u)) w) : $eq $Z ($add acc ($mul x ($add b u))) w :=
+    $R_trans ($add acc ($mul x ($add b u))) ($add ($add acc ($mul x b)) ($mul x u)) w
+        ($R_trans ($add acc ($mul x ($add b u))) ($add acc ($add ($mul x b) ($mul x u))) ($add ($add acc ($mul x b)) ($mul x u))
+            ($R_cong ($add acc) ($mul x ($add b u)) ($add ($mul x b) ($mul x u)) ($R_distr_r x b u))
+            ($add_assoc acc ($mul x b) ($mul x u)))
+        ($R_trans ($add ($add acc ($mul x b)) ($mul x u)) ($add acc1 ($mul x u)) w
+            ($R_cong (fun h : $Z => $add h ($mul x u)) ($add acc ($mul x b)) acc1
+                ($R_trans ($add acc ($mul x b)) ($add ($add z $zero) acc) acc1
+                    ($R_trans ($add acc ($mul x b)) ($add ($mul x b) acc) ($add ($add z $zero) acc)
+                        ($add_comm acc ($mul x b))
+                        ($R_cong (fun h : $Z => $add h acc) ($mul x b) ($add z $zero)
+                            ($R_trans ($mul x b) z ($add z $zero) f ($R_sym ($add z $zero) z ($R_add_0_r z)))))
+                    g))
+            h).
+
+(* Adding the products of the monomial `m` with the monomials of a polynomial to `acc`, inserting them one at a time *)
+Definition $R_scale (ci : $R_idx -> $R_idx -> $R_order) (cm : forall x y : $Z, $R_mon x -> $R_mon y -> $R_order) (F : $R_fuel)
+    : forall acc x y : $Z, $R_poly acc -> $R_mon x -> $R_poly y -> $R_nf ($add acc ($mul x y)) :=
+    F (forall acc x y : $Z, $R_poly acc -> $R_mon x -> $R_poly y -> $R_nf ($add acc ($mul x y)))
+        (fun (acc x y : $Z) (A : $R_poly acc) (m : $R_mon x) (q : $R_poly y) => $R_nf_fail ($add acc ($mul x y)))
+        (fun (rec : forall acc x y : $Z, $R_poly acc -> $R_mon x -> $R_poly y -> $R_nf ($add acc ($mul x y)))
+            (acc x y : $Z) (A : $R_poly acc) (m : $R_mon x) (q : $R_poly y) =>
+            match q in $R_poly y0 return $R_nf ($add acc ($mul x y0)) with
+            | $R_poly_zero => $R_nf_mk ($add acc ($mul x $zero)) acc A ($R_scale_zero acc x)
+            | $R_poly_add b u n q1 =>
+                match $R_times ci F x b m n in $R_mnf _ return $R_nf ($add acc ($mul x ($add b u))) with
+                | $R_mnf_fail _ => $R_nf_fail ($add acc ($mul x ($add b u)))
+                | $R_mnf_mk _ z k f =>
+                    match $R_merge cm F ($add z $zero) acc ($R_poly_add z $zero k $R_poly_zero) A in $R_nf _ return $R_nf ($add acc ($mul x ($add b u))) with
+                    | $R_nf_fail _ => $R_nf_fail ($add acc ($mul x ($add b u)))
+                    | $R_nf_mk _ acc1 A1 g =>
+                        match rec acc1 x u A1 m q1 in $R_nf _ return $R_nf ($add acc ($mul x ($add b u))) with
+                        | $R_nf_fail _ => $R_nf_fail ($add acc ($mul x ($add b u)))
+                        | $R_nf_mk _ w r h => $R_nf_mk ($add acc ($mul x ($add b u))) w r ($R_scale_step acc x b u z acc1 w f g h)
+                        end
+                    end
+                end
+            end).
+
+Definition $R_product_zero (acc y : $Z) : $eq $Z ($add acc ($mul $zero y)) acc :=
+    $R_trans ($add acc ($mul $zero y)) ($add acc $zero) acc ($R_cong ($add acc) ($mul $zero y) $zero ($mul_0_l y)) ($R_add_0_r acc).
+
+(* `acc + (a + t) * y = w` from `acc + a * y = acc1` and `acc1 + t * y = w` *)
+Definition $R_product_step (acc a t y acc1 w : $Z) (g : $eq $Z ($add acc ($mul a y)) acc1) (h : $eq $Z ($add acc1 ($mul t y)) w)
+    : $eq $Z ($add acc ($mul ($add a t) y)) w :=
+    $R_trans ($add acc ($mul ($add a t) y)) ($add ($add acc ($mul a y)) ($mul t y)) w
+        ($R_trans ($add acc ($mul ($add a t) y)) ($add acc ($add ($mul a y) ($mul t y))) ($add ($add acc ($mul a y)) ($mul t y))
+            ($R_cong ($add acc) ($mul ($add a t) y) ($add ($mul a y) ($mul t y)) ($distr_l a t y))
+            ($add_assoc acc ($mul a y) ($mul t y)))
+        ($R_trans ($add ($add acc ($mul a y)) ($mul t y)) ($add acc1 ($mul t y)) w
+            ($R_cong (fun h : $Z => $add h ($mul t y)) ($add acc ($mul a y)) acc1 g) h).
+
+(* Multiplying two polynomials adds the products of each monomial of the first with the second to `acc` *)
+Definition $R_product (ci : $R_idx -> $R_idx -> $R_order) (cm : forall x y : $Z, $R_mon x -> $R_mon y -> $R_order) (F : $R_fuel)
+    : forall acc x y : $Z, $R_poly acc -> $R_poly x -> $R_poly y -> $R_nf ($add acc ($mul x y)) :=
+    F (forall acc x y : $Z, $R_poly acc -> $R_poly x -> $R_poly y -> $R_nf ($add acc ($mul x y)))
+        (fun (acc x y : $Z) (A : $R_poly acc) (p : $R_poly x) (q : $R_poly y) => $R_nf_fail ($add acc ($mul x y)))
+        (fun (rec : forall acc x y : $Z, $R_poly acc -> $R_poly x -> $R_poly y -> $R_nf ($add acc ($mul x y)))
+            (acc x y : $Z) (A : $R_poly acc) (p : $R_poly x) (q : $R_poly y) =>
+            match p in $R_poly x0 return $R_nf ($add acc ($mul x0 y)) with
+            | $R_poly_zero => $R_nf_mk ($add acc ($mul $zero y)) acc A ($R_product_zero acc y)
+            | $R_poly_add a t m p1 =>
+                match $R_scale ci cm F acc a y A m q in $R_nf _ return $R_nf ($add acc ($mul ($add a t) y)) with
+                | $R_nf_fail _ => $R_nf_fail ($add acc ($mul ($add a t) y))
+                | $R_nf_mk _ acc1 A1 g =>
+                    match rec acc1 t y A1 p1 q in $R_nf _ return $R_nf ($add acc ($mul ($add a t) y)) with
+                    | $R_nf_fail _ => $R_nf_fail ($add acc ($mul ($add a t) y))
+                    | $R_nf_mk _ w r h => $R_nf_mk ($add acc ($mul ($add a t) y)) w r ($R_product_step acc a t y acc1 w g h)
+                    end
+                end
+            end).
+
+Definition $R_padd (cm : forall x y : $Z, $R_mon x -> $R_mon y -> $R_order) (x y : $Z) (p : $R_poly x) (q : $R_poly y) (F : $R_fuel) : $R_nf ($add x y) :=
+    $R_merge cm F x y p q.
+
+Definition $R_pmul (ci : $R_idx -> $R_idx -> $R_order) (cm : forall x y : $Z, $R_mon x -> $R_mon y -> $R_order)
+    (x y : $Z) (p : $R_poly x) (q : $R_poly y) (F : $R_fuel) : $R_nf ($mul x y) :=
+    match $R_product ci cm F $zero x y $R_poly_zero p q in $R_nf _ return $R_nf ($mul x y) with
+    | $R_nf_mk _ w r e => $R_nf_mk ($mul x y) w r ($R_trans ($mul x y) ($add $zero ($mul x y)) w ($R_sym ($add $zero ($mul x y)) ($mul x y) ($add_0_l ($mul x y))) e)
+    | $R_nf_fail _ => $R_nf_fail ($mul x y)
+    end.
+
+Definition $R_nf_atom (i : $R_idx) (x : $Z) : $R_nf x :=
+    $R_nf_mk x ($add ($mul x $one) $zero) ($R_poly_add ($mul x $one) $zero ($R_mon_mul i x $one $R_mon_one) $R_poly_zero)
+        ($R_sym ($add ($mul x $one) $zero) x ($R_trans ($add ($mul x $one) $zero) ($mul x $one) x ($R_add_0_r ($mul x $one)) ($R_mul_1_r x))).
+
+Definition $R_nf_zero : $R_nf $zero := $R_nf_mk $zero $zero $R_poly_zero ($eq_refl $Z $zero).
+
+Definition $R_nf_one : $R_nf $one :=
+    $R_nf_mk $one ($add $one $zero) ($R_poly_add $one $zero $R_mon_one $R_poly_zero) ($R_sym ($add $one $zero) $one ($R_add_0_r $one)).
+
+(* Normalizing both sides of `v1 = v2` results in terms `w1` and `w2` such that `w1 = w2` proves `v1 = v2`.
+   They are the same terms exactly when the sides are equal, so `R_refl` proves `R_holds` by conversion. *)
+Inductive $R_final (v1 v2 : $Z) : Set :=
+    $R_final_mk : forall w1 w2 : $Z, ($eq $Z w1 w2 -> $eq $Z v1 v2) -> $R_final v1 v2.
+
+Definition $R_holds (v1 v2 : $Z) (f : $R_final v1 v2) : Prop :=
+    match f in $R_final _ _ return Prop with
+    | $R_final_mk _ _ w1 w2 k => $eq $Z w1 w2
+    end.
+
+Definition $R_same (v1 v2 : $Z) (f : $R_final v1 v2) : Prop :=
+    match f in $R_final _ _ return Prop with
+    | $R_final_mk _ _ w1 w2 k => $eq $Z w1 w1
+    end.
+
+Definition $R_refl (v1 v2 : $Z) (f : $R_final v1 v2) : $R_same v1 v2 f :=
+    match f as g in $R_final _ _ return $R_same v1 v2 g with
+    | $R_final_mk _ _ w1 w2 k => $eq_refl $Z w1
+    end.
+
+Definition $R (v1 v2 : $Z) (f : $R_final v1 v2) : $R_holds v1 v2 f -> $eq $Z v1 v2 :=
+    match f as g in $R_final _ _ return $R_holds v1 v2 g -> $eq $Z v1 v2 with
+    | $R_final_mk _ _ w1 w2 k => k
+    end.
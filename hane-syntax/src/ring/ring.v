@@ -0,0 +1,249 @@
+(* A term of the ring reified as a function of the operations which are run on it *)
+Inductive $R_alg (X : $Z -> Type{0}) : Type{0} :=
+    $R_alg_mk : (forall (i : $R_idx) (x : $Z), X x) -> X $zero -> X $one ->
+        (forall x y : $Z, X x -> X y -> X ($add x y)) -> (forall x y : $Z, X x -> X y -> X ($mul x y)) ->
+        (forall x : $Z, X x -> X ($opp x)) -> $R_alg X.
+
+Definition $R_atom (X : $Z -> Type{0}) (A : $R_alg X) : forall (i : $R_idx) (x : $Z), X x :=
+    match A in $R_alg _ return forall (i : $R_idx) (x : $Z), X x with
+    | $R_alg_mk _ f _ _ _ _ _ => f
+    end.
+
+Definition $R_zero (X : $Z -> Type{0}) (A : $R_alg X) : X $zero :=
+    match A in $R_alg _ return X $zero with
+    | $R_alg_mk _ _ f _ _ _ _ => f
+    end.
+
+Definition $R_one (X : $Z -> Type{0}) (A : $R_alg X) : X $one :=
+    match A in $R_alg _ return X $one with
+    | $R_alg_mk _ _ _ f _ _ _ => f
+    end.
+
+Definition $R_add (X : $Z -> Type{0}) (A : $R_alg X) : forall x y : $Z, X x -> X y -> X ($add x y) :=
+    match A in $R_alg _ return forall x y : $Z, X x -> X y -> X ($add x y) with
+    | $R_alg_mk _ _ _ _ f _ _ => f
+    end.
+
+Definition $R_mul (X : $Z -> Type{0}) (A : $R_alg X) : forall x y : $Z, X x -> X y -> X ($mul x y) :=
+    match A in $R_alg _ return forall x y : $Z, X x -> X y -> X ($mul x y) with
+    | $R_alg_mk _ _ _ _ _ f _ => f
+    end.
+
+Definition $R_opp (X : $Z -> Type{0}) (A : $R_alg X) : forall x : $Z, X x -> X ($opp x) :=
+    match A in $R_alg _ return forall x : $Z, X x -> X ($opp x) with
+    | $R_alg_mk _ _ _ _ _ _ f => f
+    end.
+
+Definition $R_expr (v : $Z) : Type{1} := forall X : $Z -> Type{0}, $R_alg X -> X v.
+
+(* A term `v` of a ring is written as the difference of the sums of monomials `p` and `n`, as `v + n = p` *)
+Inductive $R_rnf (v : $Z) : Set :=
+    $R_rnf_mk : forall p n : $Z, $R_poly p -> $R_poly n -> $eq $Z ($add v n) p -> $R_rnf v
+  | $R_rnf_fail : $R_rnf v.
+
+Definition $R_rnf_lift (v : $Z) (x : $R_nf v) : $R_rnf v :=
+    match x in $R_nf _ return $R_rnf v with
+    | $R_nf_mk _ w p e => $R_rnf_mk v w $zero p $R_poly_zero ($R_trans ($add v $zero) v w ($R_add_0_r v) e)
+    | $R_nf_fail _ => $R_rnf_fail v
+    end.
+
+Definition $R_swap4 (a b c d : $Z) : $eq $Z ($add ($add a b) ($add c d)) ($add ($add a c) ($add b d)) :=
+    $R_trans ($add ($add a b) ($add c d)) ($add a ($add b ($add c d))) ($add ($add a c) ($add b d))
+        ($R_sym ($add a ($add b ($add c d))) ($add ($add a b) ($add c d)) ($add_assoc a b ($add c d)))
+        ($R_trans ($add a ($add b ($add c d))) ($add a ($add c ($add b d))) ($add ($add a c) ($add b d))
+            ($R_cong (fun h : $Z => $add a h) ($add b ($add c d)) ($add c ($add b d)) ($R_add_swap b c d))
+            ($add_assoc a c ($add b d))).
+
+Definition $R_add_step (a b n1 n2 p1 p2 wn wp : $Z) (e1 : $eq $Z ($add a n1) p1) (e2 : $eq $Z ($add b n2) p2)
+    (en : $eq $Z ($add n1 n2) wn) (ep : $eq $Z ($add p1 p2) wp) : $eq $Z ($add ($add a b) wn) wp :=
+    $R_trans ($add ($add a b) wn) ($add ($add a b) ($add n1 n2)) wp
+        ($R_cong (fun h : $Z => $add ($add a b) h) wn ($add n1 n2) ($R_sym ($add n1 n2) wn en))
+        ($R_trans ($add ($add a b) ($add n1 n2)) ($add ($add a n1) ($add b n2)) wp
+            ($R_swap4 a b n1 n2)
+            ($R_trans ($add ($add a n1) ($add b n2)) ($add p1 p2) wp ($R_cong2 $add ($add a n1) p1 ($add b n2) p2 e1 e2) ep)).
+
+Definition $R_opp_step (a n p : $Z) (e : $eq $Z ($add a n) p) : $eq $Z ($add ($opp a) p) n :=
+    $R_trans ($add ($opp a) p) ($add ($opp a) ($add a n)) n
+        ($R_cong (fun h : $Z => $add ($opp a) h) p ($add a n) ($R_sym ($add a n) p e))
+        ($R_trans ($add ($opp a) ($add a n)) ($add ($add ($opp a) a) n) n
+            ($add_assoc ($opp a) a n)
+            ($R_trans ($add ($add ($opp a) a) n) ($add $zero n) n
+                ($R_cong (fun h : $Z => $add h n) ($add ($opp a) a) $zero
+                    ($R_trans ($add ($opp a) a) ($add a ($opp a)) $zero ($add_comm ($opp a) a) ($opp_def a)))
+                ($add_0_l n))).
+
+(* `a + ((b + d) + (c + d)) = ((a + b) + (c + d)) + d` *)
+Definition $R_ac (a b c d : $Z) : $eq $Z ($add a ($add ($add b d) ($add c d))) ($add ($add ($add a b) ($add c d)) d) :=
+    $R_trans ($add a ($add ($add b d) ($add c d))) ($add ($add a ($add b d)) ($add c d)) ($add ($add ($add a b) ($add c d)) d)
+        ($add_assoc a ($add b d) ($add c d))
+        ($R_trans ($add ($add a ($add b d)) ($add c d)) ($add ($add ($add a b) d) ($add c d)) ($add ($add ($add a b) ($add c d)) d)
+            ($R_cong (fun h : $Z => $add h ($add c d)) ($add a ($add b d)) ($add ($add a b) d) ($add_assoc a b d))
+            ($R_trans ($add ($add ($add a b) d) ($add c d)) ($add ($add a b) ($add d ($add c d))) ($add ($add ($add a b) ($add c d)) d)
+                ($R_sym ($add ($add a b) ($add d ($add c d))) ($add ($add ($add a b) d) ($add c d)) ($add_assoc ($add a b) d ($add c d)))
+                ($R_trans ($add ($add a b) ($add d ($add c d))) ($add ($add a b) ($add ($add c d) d)) ($add ($add ($add a b) ($add c d)) d)
+                    ($R_cong (fun h : $Z => $add ($add a b) h) ($add d ($add c d)) ($add ($add c d) d) ($add_comm d ($add c d)))
+                    ($add_assoc ($add a b) ($add c d) d)))).
+
+(* `a * b + ((a + n1) * n2 + n1 * (b + n2)) = (a + n1) * (b + n2) + n1 * n2` *)
+Definition $R_mul_core (a b n1 n2 : $Z) :
+    $eq $Z ($add ($mul a b) ($add ($mul ($add a n1) n2) ($mul n1 ($add b n2)))) ($add ($mul ($add a n1) ($add b n2)) ($mul n1 n2)) :=
+    $R_trans ($add ($mul a b) ($add ($mul ($add a n1) n2) ($mul n1 ($add b n2))))
+        ($add ($mul a b) ($add ($add ($mul a n2) ($mul n1 n2)) ($add ($mul n1 b) ($mul n1 n2))))
+        ($add ($mul ($add a n1) ($add b n2)) ($mul n1 n2))
+        ($R_cong2 (fun x y : $Z => $add ($mul a b) ($add x y))
+            ($mul ($add a n1) n2) ($add ($mul a n2) ($mul n1 n2)) ($mul n1 ($add b n2)) ($add ($mul n1 b) ($mul n1 n2))
+            ($distr_l a n1 n2) ($R_distr_r n1 b n2))
+        ($R_trans ($add ($mul a b) ($add ($add ($mul a n2) ($mul n1 n2)) ($add ($mul n1 b) ($mul n1 n2))))
+            ($add ($add ($add ($mul a b) ($mul a n2)) ($add ($mul n1 b) ($mul n1 n2))) ($mul n1 n2))
+            ($add ($mul ($add a n1) ($add b n2)) ($mul n1 n2))
+            ($R_ac ($mul a b) ($mul a n2) ($mul n1 b) ($mul n1 n2))
+            ($R_cong (fun h : $Z => $add h ($mul n1 n2))
+                ($add ($add ($mul a b) ($mul a n2)) ($add ($mul n1 b) ($mul n1 n2))) ($mul ($add a n1) ($add b n2))
+                ($R_sym ($mul ($add a n1) ($add b n2)) ($add ($add ($mul a b) ($mul a n2)) ($add ($mul n1 b) ($mul n1 n2)))
+                    ($R_trans ($mul ($add a n1) ($add b n2)) ($add ($mul a ($add b n2)) ($mul n1 ($add b n2)))
+                        ($add ($add ($mul a b) ($mul a n2)) ($add ($mul n1 b) ($mul n1 n2)))
+                        ($distr_l a n1 ($add b n2))
+                        ($R_cong2 $add ($mul a ($add b n2)) ($add ($mul a b) ($mul a n2)) ($mul n1 ($add b n2)) ($add ($mul n1 b) ($mul n1 n2))
+                            ($R_distr_r a b n2) ($R_distr_r n1 b n2)))))).
+
+Definition $R_mul_step (a b n1 n2 p1 p2 wpp wnn wpn wnp wp wn : $Z) (e1 : $eq $Z ($add a n1) p1) (e2 : $eq $Z ($add b n2) p2)
+    (epp : $eq $Z ($mul p1 p2) wpp) (enn : $eq $Z ($mul n1 n2) wnn) (epn : $eq $Z ($mul p1 n2) wpn) (enp : $eq $Z ($mul n1 p2) wnp)
+    (ep : $eq $Z ($add wpp wnn) wp) (en : $eq $Z ($add wpn wnp) wn) : $eq $Z ($add ($mul a b) wn) wp :=
+    $R_trans ($add ($mul a b) wn) ($add ($mul a b) ($add ($mul p1 n2) ($mul n1 p2))) wp
+        ($R_cong (fun h : $Z => $add ($mul a b) h) wn ($add ($mul p1 n2) ($mul n1 p2))
+            ($R_trans wn ($add wpn wnp) ($add ($mul p1 n2) ($mul n1 p2))
+                ($R_sym ($add wpn wnp) wn en)
+                ($R_cong2 $add wpn ($mul p1 n2) wnp ($mul n1 p2) ($R_sym ($mul p1 n2) wpn epn) ($R_sym ($mul n1 p2) wnp enp))))
+        ($R_trans ($add ($mul a b) ($add ($mul p1 n2) ($mul n1 p2))) ($add ($mul a b) ($add ($mul ($add a n1) n2) ($mul n1 ($add b n2)))) wp
+            ($R_cong2 (fun x y : $Z => $add ($mul a b) ($add ($mul x n2) ($mul n1 y))) p1 ($add a n1) p2 ($add b n2)
+                ($R_sym ($add a n1) p1 e1) ($R_sym ($add b n2) p2 e2))
+            ($R_trans ($add ($mul a b) ($add ($mul ($add a n1) n2) ($mul n1 ($add b n2)))) ($add ($mul ($add a n1) ($add b n2)) ($mul n1 n2)) wp
+                ($R_mul_core a b n1 n2)
+                ($R_trans ($add ($mul ($add a n1) ($add b n2)) ($mul n1 n2)) ($add ($mul p1 p2) ($mul n1 n2)) wp
+                    ($R_cong2 (fun x y : $Z => $add ($mul x y) ($mul n1 n2)) ($add a n1) p1 ($add b n2) p2 e1 e2)
+                    ($R_trans ($add ($mul p1 p2) ($mul n1 n2)) ($add wpp wnn) wp ($R_cong2 $add ($mul p1 p2) wpp ($mul n1 n2) wnn epp enn) ep)))).
+
+Definition $R_cancel (x y c : $Z) (e : $eq $Z ($add x c) ($add y c)) : $eq $Z x y :=
+    $R_trans x ($add ($add x c) ($opp c)) y
+        ($R_trans x ($add x ($add c ($opp c))) ($add ($add x c) ($opp c))
+            ($R_trans x ($add x $zero) ($add x ($add c ($opp c)))
+                ($R_sym ($add x $zero) x ($R_add_0_r x))
+                ($R_cong (fun h : $Z => $add x h) $zero ($add c ($opp c)) ($R_sym ($add c ($opp c)) $zero ($opp_def c))))
+            ($add_assoc x c ($opp c)))
+        ($R_trans ($add ($add x c) ($opp c)) ($add ($add y c) ($opp c)) y
+            ($R_cong (fun h : $Z => $add h ($opp c)) ($add x c) ($add y c) e)
+            ($R_trans ($add ($add y c) ($opp c)) ($add y ($add c ($opp c))) y
+                ($R_sym ($add y ($add c ($opp c))) ($add ($add y c) ($opp c)) ($add_assoc y c ($opp c)))
+                ($R_trans ($add y ($add c ($opp c))) ($add y $zero) y
+                    ($R_cong (fun h : $Z => $add y h) ($add c ($opp c)) $zero ($opp_def c))
+                    ($R_add_0_r y)))).
+
+(* `v1 = v2` from `v1 + n1 = p1`, `v2 + n2 = p2` and `p1 + n2 = p2 + n1` *)
+Definition $R_finish_step (v1 v2 n1 n2 p1 p2 w1 w2 : $Z) (e1 : $eq $Z ($add v1 n1) p1) (e2 : $eq $Z ($add v2 n2) p2)
+    (f1 : $eq $Z ($add p1 n2) w1) (f2 : $eq $Z ($add p2 n1) w2) (h : $eq $Z w1 w2) : $eq $Z v1 v2 :=
+    $R_cancel v1 v2 ($add n1 n2)
+        ($R_trans ($add v1 ($add n1 n2)) ($add ($add v1 n1) n2) ($add v2 ($add n1 n2))
+            ($add_assoc v1 n1 n2)
+            ($R_trans ($add ($add v1 n1) n2) w1 ($add v2 ($add n1 n2))
+                ($R_trans ($add ($add v1 n1) n2) ($add p1 n2) w1 ($R_cong (fun h : $Z => $add h n2) ($add v1 n1) p1 e1) f1)
+                ($R_trans w1 w2 ($add v2 ($add n1 n2)) h
+                    ($R_trans w2 ($add ($add v2 n2) n1) ($add v2 ($add n1 n2))
+                        ($R_trans w2 ($add p2 n1) ($add ($add v2 n2) n1)
+                            ($R_sym ($add p2 n1) w2 f2)
+                            ($R_cong (fun h : $Z => $add h n1) p2 ($add v2 n2) ($R_sym ($add v2 n2) p2 e2)))
+                        ($R_trans ($add ($add v2 n2) n1) ($add v2 ($add n2 n1)) ($add v2 ($add n1 n2))
+                            ($R_sym ($add v2 ($add n2 n1)) ($add ($add v2 n2) n1) ($add_assoc v2 n2 n1))
+                            ($R_cong (fun h : $Z => $add v2 h) ($add n2 n1) ($add n1 n2) ($add_comm n2 n1))))))).
+
+Definition $R_rnf_add (cm : forall x y : $Z, $R_mon x -> $R_mon y -> $R_order) (a b : $Z) (x : $R_rnf a) (y : $R_rnf b) (F : $R_fuel) : $R_rnf ($add a b) :=
+    match x in $R_rnf _ return $R_rnf ($add a b) with
+    | $R_rnf_fail _ => $R_rnf_fail ($add a b)
+    | $R_rnf_mk _ p1 n1 P1 N1 e1 =>
+        match y in $R_rnf _ return $R_rnf ($add a b) with
+        | $R_rnf_fail _ => $R_rnf_fail ($add a b)
+        | $R_rnf_mk _ p2 n2 P2 N2 e2 =>
+            match $R_padd cm p1 p2 P1 P2 F in $R_nf _ return $R_rnf ($add a b) with
+            | $R_nf_fail _ => $R_rnf_fail ($add a b)
+            | $R_nf_mk _ wp WP ep =>
+                match $R_padd cm n1 n2 N1 N2 F in $R_nf _ return $R_rnf ($add a b) with
+                | $R_nf_fail _ => $R_rnf_fail ($add a b)
+                | $R_nf_mk _ wn WN en => $R_rnf_mk ($add a b) wp wn WP WN ($R_add_step a b n1 n2 p1 p2 wn wp e1 e2 en ep)
+                end
+            end
+        end
+    end.
+
+Definition $R_rnf_opp (a : $Z) (x : $R_rnf a) : $R_rnf ($opp a) :=
+    match x in $R_rnf _ return $R_rnf ($opp a) with
+    | $R_rnf_fail _ => $R_rnf_fail ($opp a)
+    | $R_rnf_mk _ p n P N e => $R_rnf_mk ($opp a) n p N P ($R_opp_step a n p e)
+    end.
+
+Definition $R_rnf_mul (ci : $R_idx -> $R_idx -> $R_order) (cm : forall x y : $Z, $R_mon x -> $R_mon y -> $R_order)
+    (a b : $Z) (x : $R_rnf a) (y : $R_rnf b) (F : $R_fuel) : $R_rnf ($mul a b) :=
+    match x in $R_rnf _ return $R_rnf ($mul a b) with
+    | $R_rnf_fail _ => $R_rnf_fail ($mul a b)
+    | $R_rnf_mk _ p1 n1 P1 N1 e1 =>
+        match y in $R_rnf _ return $R_rnf ($mul a b) with
+        | $R_rnf_fail _ => $R_rnf_fail ($mul a b)
+        | $R_rnf_mk _ p2 n2 P2 N2 e2 =>
+            match $R_pmul ci cm p1 p2 P1 P2 F in $R_nf _ return $R_rnf ($mul a b) with
+            | $R_nf_fail _ => $R_rnf_fail ($mul a b)
+            | $R_nf_mk _ wpp WPP epp =>
+                match $R_pmul ci cm n1 n2 N1 N2 F in $R_nf _ return $R_rnf ($mul a b) with
+                | $R_nf_fail _ => $R_rnf_fail ($mul a b)
+                | $R_nf_mk _ wnn WNN enn =>
+                    match $R_pmul ci cm p1 n2 P1 N2 F in $R_nf _ return $R_rnf ($mul a b) with
+                    | $R_nf_fail _ => $R_rnf_fail ($mul a b)
+                    | $R_nf_mk _ wpn WPN epn =>
+                        match $R_pmul ci cm n1 p2 N1 P2 F in $R_nf _ return $R_rnf ($mul a b) with
+                        | $R_nf_fail _ => $R_rnf_fail ($mul a b)
+                        | $R_nf_mk _ wnp WNP enp =>
+                            match $R_padd cm wpp wnn WPP WNN F in $R_nf _ return $R_rnf ($mul a b) with
+                            | $R_nf_fail _ => $R_rnf_fail ($mul a b)
+                            | $R_nf_mk _ wp WP ep =>
+                                match $R_padd cm wpn wnp WPN WNP F in $R_nf _ return $R_rnf ($mul a b) with
+                                | $R_nf_fail _ => $R_rnf_fail ($mul a b)
+                                | $R_nf_mk _ wn WN en => $R_rnf_mk ($mul a b) wp wn WP WN
+                                    ($R_mul_step a b n1 n2 p1 p2 wpp wnn wpn wnp wp wn e1 e2 epp enn epn enp ep en)
+                                end
+                            end
+                        end
+                    end
+                end
+            end
+        end
+    end.
+
+Definition $R_eval (ci : $R_idx -> $R_idx -> $R_order) (cm : forall x y : $Z, $R_mon x -> $R_mon y -> $R_order)
+    : $R_alg (fun w : $Z => $R_fuel -> $R_rnf w) :=
+    $R_alg_mk (fun w : $Z => $R_fuel -> $R_rnf w)
+        (fun (i : $R_idx) (x : $Z) (F : $R_fuel) => $R_rnf_lift x ($R_nf_atom i x))
+        (fun F : $R_fuel => $R_rnf_lift $zero $R_nf_zero)
+        (fun F : $R_fuel => $R_rnf_lift $one $R_nf_one)
+        (fun (a b : $Z) (x : $R_fuel -> $R_rnf a) (y : $R_fuel -> $R_rnf b) (F : $R_fuel) => $R_rnf_add cm a b (x F) (y F) F)
+        (fun (a b : $Z) (x : $R_fuel -> $R_rnf a) (y : $R_fuel -> $R_rnf b) (F : $R_fuel) => $R_rnf_mul ci cm a b (x F) (y F) F)
+        (fun (a : $Z) (x : $R_fuel -> $R_rnf a) (F : $R_fuel) => $R_rnf_opp a (x F)).
+
+Definition $R_finish (cm : forall x y : $Z, $R_mon x -> $R_mon y -> $R_order) (v1 v2 : $Z) (x : $R_rnf v1) (y : $R_rnf v2) (F : $R_fuel) : $R_final v1 v2 :=
+    match x in $R_rnf _ return $R_final v1 v2 with
+    | $R_rnf_fail _ => $R_final_mk v1 v2 v1 v2 (fun h : $eq $Z v1 v2 => h)
+    | $R_rnf_mk _ p1 n1 P1 N1 e1 =>
+        match y in $R_rnf _ return $R_final v1 v2 with
+        | $R_rnf_fail _ => $R_final_mk v1 v2 v1 v2 (fun h : $eq $Z v1 v2 => h)
+        | $R_rnf_mk _ p2 n2 P2 N2 e2 =>
+            match $R_padd cm p1 n2 P1 N2 F in $R_nf _ return $R_final v1 v2 with
+            | $R_nf_fail _ => $R_final_mk v1 v2 v1 v2 (fun h : $eq $Z v1 v2 => h)
+            | $R_nf_mk _ w1 W1 f1 =>
+                match $R_padd cm p2 n1 P2 N1 F in $R_nf _ return $R_final v1 v2 with
+                | $R_nf_fail _ => $R_final_mk v1 v2 v1 v2 (fun h : $eq $Z v1 v2 => h)
+                | $R_nf_mk _ w2 W2 f2 => $R_final_mk v1 v2 w1 w2 ($R_finish_step v1 v2 n1 n2 p1 p2 w1 w2 e1 e2 f1 f2)
+                end
+            end
+        end
+    end.
+
+Definition $R_run (v1 v2 : $Z) (e1 : $R_expr v1) (e2 : $R_expr v2) (Fi Fm F : $R_fuel) : $R_final v1 v2 :=
+    let ci : $R_idx -> $R_idx -> $R_order := $R_cmp_idx Fi in
+    let cm : forall x y : $Z, $R_mon x -> $R_mon y -> $R_order := $R_cmp_mon ci Fm in
+    $R_finish cm v1 v2 (e1 (fun w : $Z => $R_fuel -> $R_rnf w) ($R_eval ci cm) F) (e2 (fun w : $Z => $R_fuel -> $R_rnf w) ($R_eval ci cm) F) F.
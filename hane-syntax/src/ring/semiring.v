@@ -0,0 +1,85 @@
+Definition $R_nf_add (cm : forall x y : $Z, $R_mon x -> $R_mon y -> $R_order) (a b : $Z) (x : $R_nf a) (y : $R_nf b) (F : $R_fuel) : $R_nf ($add a b) :=
+    match x in $R_nf _ return $R_nf ($add a b) with
+    | $R_nf_fail _ => $R_nf_fail ($add a b)
+    | $R_nf_mk _ w1 p e1 =>
+        match y in $R_nf _ return $R_nf ($add a b) with
+        | $R_nf_fail _ => $R_nf_fail ($add a b)
+        | $R_nf_mk _ w2 q e2 =>
+            match $R_padd cm w1 w2 p q F in $R_nf _ return $R_nf ($add a b) with
+            | $R_nf_mk _ w r e => $R_nf_mk ($add a b) w r ($R_trans ($add a b) ($add w1 w2) w ($R_cong2 $add a w1 b w2 e1 e2) e)
+            | $R_nf_fail _ => $R_nf_fail ($add a b)
+            end
+        end
+    end.
+
+Definition $R_nf_mul (ci : $R_idx -> $R_idx -> $R_order) (cm : forall x y : $Z, $R_mon x -> $R_mon y -> $R_order)
+    (a b : $Z) (x : $R_nf a) (y : $R_nf b) (F : $R_fuel) : $R_nf ($mul a b) :=
+    match x in $R_nf _ return $R_nf ($mul a b) with
+    | $R_nf_fail _ => $R_nf_fail ($mul a b)
+    | $R_nf_mk _ w1 p e1 =>
+        match y in $R_nf _ return $R_nf ($mul a b) with
+        | $R_nf_fail _ => $R_nf_fail ($mul a b)
+        | $R_nf_mk _ w2 q e2 =>
+            match $R_pmul ci cm w1 w2 p q F in $R_nf _ return $R_nf ($mul a b) with
+            | $R_nf_mk _ w r e => $R_nf_mk ($mul a b) w r ($R_trans ($mul a b) ($mul w1 w2) w ($R_cong2 $mul a w1 b w2 e1 e2) e)
+            | $R_nf_fail _ => $R_nf_fail ($mul a b)
+            end
+        end
+    end.
+
+(* A term of the semiring reified as a function of the operations which are run on it *)
+Inductive $R_alg (X : $Z -> Type{0}) : Type{0} :=
+    $R_alg_mk : (forall (i : $R_idx) (x : $Z), X x) -> X $zero -> X $one ->
+        (forall x y : $Z, X x -> X y -> X ($add x y)) -> (forall x y : $Z, X x -> X y -> X ($mul x y)) -> $R_alg X.
+
+Definition $R_atom (X : $Z -> Type{0}) (A : $R_alg X) : forall (i : $R_idx) (x : $Z), X x :=
+    match A in $R_alg _ return forall (i : $R_idx) (x : $Z), X x with
+    | $R_alg_mk _ f _ _ _ _ => f
+    end.
+
+Definition $R_zero (X : $Z -> Type{0}) (A : $R_alg X) : X $zero :=
+    match A in $R_alg _ return X $zero with
+    | $R_alg_mk _ _ f _ _ _ => f
+    end.
+
+Definition $R_one (X : $Z -> Type{0}) (A : $R_alg X) : X $one :=
+    match A in $R_alg _ return X $one with
+    | $R_alg_mk _ _ _ f _ _ => f
+    end.
+
+Definition $R_add (X : $Z -> Type{0}) (A : $R_alg X) : forall x y : $Z, X x -> X y -> X ($add x y) :=
+    match A in $R_alg _ return forall x y : $Z, X x -> X y -> X ($add x y) with
+    | $R_alg_mk _ _ _ _ f _ => f
+    end.
+
+Definition $R_mul (X : $Z -> Type{0}) (A : $R_alg X) : forall x y : $Z, X x -> X y -> X ($mul x y) :=
+    match A in $R_alg _ return forall x y : $Z, X x -> X y -> X ($mul x y) with
+    | $R_alg_mk _ _ _ _ _ f => f
+    end.
+
+Definition $R_expr (v : $Z) : Type{1} := forall X : $Z -> Type{0}, $R_alg X -> X v.
+
+Definition $R_eval (ci : $R_idx -> $R_idx -> $R_order) (cm : forall x y : $Z, $R_mon x -> $R_mon y -> $R_order)
+    : $R_alg (fun w : $Z => $R_fuel -> $R_nf w) :=
+    $R_alg_mk (fun w : $Z => $R_fuel -> $R_nf w)
+        (fun (i : $R_idx) (x : $Z) (F : $R_fuel) => $R_nf_atom i x)
+        (fun F : $R_fuel => $R_nf_zero)
+        (fun F : $R_fuel => $R_nf_one)
+        (fun (a b : $Z) (x : $R_fuel -> $R_nf a) (y : $R_fuel -> $R_nf b) (F : $R_fuel) => $R_nf_add cm a b (x F) (y F) F)
+        (fun (a b : $Z) (x : $R_fuel -> $R_nf a) (y : $R_fuel -> $R_nf b) (F : $R_fuel) => $R_nf_mul ci cm a b (x F) (y F) F).
+
+Definition $R_finish (v1 v2 : $Z) (x : $R_nf v1) (y : $R_nf v2) : $R_final v1 v2 :=
+    match x in $R_nf _ return $R_final v1 v2 with
+    | $R_nf_mk _ w1 p e1 =>
+        match y in $R_nf _ return $R_final v1 v2 with
+        | $R_nf_mk _ w2 q e2 => $R_final_mk v1 v2 w1 w2
+            (fun h : $eq $Z w1 w2 => $R_trans v1 w1 v2 e1 ($R_trans w1 w2 v2 h ($R_sym v2 w2 e2)))
+        | $R_nf_fail _ => $R_final_mk v1 v2 v1 v2 (fun h : $eq $Z v1 v2 => h)
+        end
+    | $R_nf_fail _ => $R_final_mk v1 v2 v1 v2 (fun h : $eq $Z v1 v2 => h)
+    end.
+
+Definition $R_run (v1 v2 : $Z) (e1 : $R_expr v1) (e2 : $R_expr v2) (Fi Fm F : $R_fuel) : $R_final v1 v2 :=
+    let ci : $R_idx -> $R_idx -> $R_order := $R_cmp_idx Fi in
+    let cm : forall x y : $Z, $R_mon x -> $R_mon y -> $R_order := $R_cmp_mon ci Fm in
+    $R_finish v1 v2 (e1 (fun w : $Z => $R_fuel -> $R_nf w) ($R_eval ci cm) F) (e2 (fun w : $Z => $R_fuel -> $R_nf w) ($R_eval ci cm) F).
@@ -1,9 +1,12 @@
+use hane_check::check;
+use hane_kernel::export::{read, write};
 use hane_kernel::global::Global;
+use hane_syntax::elab::elaborate;
+use hane_syntax::env::Env;
 use hane_syntax::eval::EvalError;
 use hane_syntax::parser::parse;
 use hane_syntax::print::Print;
 use hane_syntax::SpanError;
-use std::collections::HashSet;
 use std::fmt::Write;
 use std::fs::read_to_string;
 
@@ -73,11 +76,39 @@ fn main() {
             }
         };
 
-        let mut global = HashSet::new();
+        let mut env = Env::new();
         let lower = commands
             .into_iter()
-            .map(|command| command.lower(&mut global))
-            .collect::<Result<Vec<_>, _>>();
+            .map(|command| command.lower(&mut env))
+            .collect::<Result<Vec<_>, _>>()
+            .map(|commands| commands.into_iter().flatten().collect::<Vec<_>>());
+
+        let warn_path = {
+            let mut path = path.to_path_buf();
+            assert!(path.pop());
+            path.push(format!("{name}.warn"));
+            path
+        };
+        let warnings = env
+            .take_warnings()
+            .into_iter()
+            .map(|warning| warning.print(Some(path.to_string_lossy().as_ref()), &content))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let expected_warnings = if warn_path.exists() {
+            read_to_string(&warn_path).unwrap()
+        } else {
+            String::new()
+        };
+        if warnings != expected_warnings {
+            eprintln!("{name}: Warnings do not match expected warnings");
+            eprintln!("expected:");
+            eprintln!("```\n{expected_warnings}\n```");
+            eprintln!("actual:");
+            eprintln!("```\n{warnings}\n```");
+            failed += 1;
+            continue;
+        }
 
         let lower_err_path = {
             let mut path = path.to_path_buf();
@@ -155,10 +186,15 @@ fn main() {
 
         let mut global = Global::new();
         let mut out_buf = String::new();
+        #[allow(clippy::result_large_err)]
         let result = commands.into_iter().try_for_each(|command| {
-            command.eval(&mut global, |out| {
-                write!(out_buf, "{}", Print(out)).unwrap()
-            })
+            elaborate(command, &global, &env)?
+                .into_iter()
+                .try_for_each(|command| {
+                    command.eval(&mut global, |out| {
+                        write!(out_buf, "{}", Print(&env, out)).unwrap()
+                    })
+                })
         });
 
         let result_err_path = {
@@ -179,7 +215,7 @@ fn main() {
                 Err((span, err)) => {
                     let err = SpanError {
                         span,
-                        err: EvalError(err),
+                        err: EvalError(err, &env),
                     }
                     .print(Some(path.to_string_lossy().as_ref()), &content);
                     if err != result_err {
@@ -210,7 +246,7 @@ fn main() {
         if let Err((span, err)) = result {
             let err = SpanError {
                 span,
-                err: EvalError(err),
+                err: EvalError(err, &env),
             }
             .print(Some(path.to_string_lossy().as_ref()), &content);
             eprintln!("{name}: Failed with error:");
@@ -230,6 +266,47 @@ fn main() {
             failed += 1;
             continue;
         }
+
+        let stdout_path = {
+            let mut path = path.to_path_buf();
+            assert!(path.pop());
+            path.push(format!("{name}.stdout"));
+            path
+        };
+        if stdout_path.exists() {
+            let stdout = read_to_string(&stdout_path).unwrap();
+            if out_buf != stdout {
+                eprintln!("{name}: Printed output does not match expected output");
+                eprintln!("expected:");
+                eprintln!("```\n{stdout}\n```");
+                eprintln!("actual:");
+                eprintln!("```\n{out_buf}\n```");
+                failed += 1;
+                continue;
+            }
+        }
+
+        // The checker is given the environment as `hane-export` would write it
+        let written = write(&global, |x| &x.name);
+        let global = match read(&written) {
+            Ok(global) if write(&global, |x| x) == written => global,
+            Ok(_) => {
+                eprintln!("{name}: The written environment changed when read back");
+                failed += 1;
+                continue;
+            }
+            Err(err) => {
+                eprintln!("{name}: The written environment could not be read: {err}");
+                failed += 1;
+                continue;
+            }
+        };
+        let rejections = check(&global);
+        if !rejections.is_empty() {
+            eprintln!("{name}: The checker rejected {} entries", rejections.len());
+            failed += 1;
+            continue;
+        }
     }
 
     if failed != 0 {
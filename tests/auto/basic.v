@@ -0,0 +1,38 @@
+Inductive True : Prop := I : True.
+Inductive and (A : Prop) (B : Prop) : Prop := conj : A -> B -> and A B.
+Inductive or (A : Prop) (B : Prop) : Prop :=
+    | or_introl : A -> or A B
+    | or_intror : B -> or A B.
+Inductive nat : Set := O : nat | S : nat -> nat.
+Inductive even : nat -> Prop :=
+    | even_O : even O
+    | even_SS : forall n : nat, even n -> even (S (S n)).
+
+Hint Constructors True and or.
+Hint Constructors even : arith.
+
+Theorem tautology (A B : Prop) : A -> B -> and (or B True) A.
+Proof.
+    auto.
+Qed.
+Print tautology.
+
+Theorem four : even (S (S (S (S O)))).
+Proof.
+    auto with arith.
+Qed.
+Print four.
+
+Axiom P : nat -> Prop.
+Axiom Q : nat -> Prop.
+Axiom P_Q : forall n : nat, P n -> Q (S n).
+Hint Resolve P_Q.
+
+Theorem from_hypothesis (n : nat) (H : P n) : Q (S n).
+Proof.
+    auto.
+Qed.
+Print from_hypothesis.
+
+Definition under_products : forall n : nat, P n -> and (Q (S n)) True := by auto.
+Print under_products.
@@ -0,0 +1,10 @@
+Inductive nat : Set := O : nat | S : nat -> nat.
+Inductive even : nat -> Prop :=
+    | even_O : even O
+    | even_SS : forall n : nat, even n -> even (S (S n)).
+Hint Constructors even.
+
+Theorem deep : even (S (S (S (S (S (S O)))))).
+Proof.
+    auto 2.
+Qed.
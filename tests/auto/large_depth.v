@@ -0,0 +1,6 @@
+Inductive True : Prop := I : True.
+
+Theorem trivial : True.
+Proof.
+    auto 99999999999999999999999.
+Qed.
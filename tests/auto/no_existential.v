@@ -0,0 +1,12 @@
+Inductive nat : Set := O : nat | S : nat -> nat.
+Inductive le (n : nat) : nat -> Prop :=
+    | le_n : le n n
+    | le_S : forall m : nat, le n m -> le n (S m).
+
+Axiom le_trans : forall n m p : nat, le n m -> le m p -> le n p.
+Hint Resolve le_trans : order.
+
+Theorem chain_auto (a b c : nat) (H1 : le a b) (H2 : le b c) : le a c.
+Proof.
+    auto with order.
+Qed.
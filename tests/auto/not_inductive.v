@@ -0,0 +1,2 @@
+Axiom A : Prop.
+Hint Constructors A.
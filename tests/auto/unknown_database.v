@@ -0,0 +1,6 @@
+Inductive True : Prop := I : True.
+
+Theorem trivial : True.
+Proof.
+    auto with nowhere.
+Qed.
@@ -0,0 +1,50 @@
+Inductive bool : Set := true : bool | false : bool.
+Inductive nat : Set := O : nat | S : nat -> nat.
+Inductive prod (A B : Set) : Set := pair : A -> B -> prod A B.
+Arguments pair {A} {B} a b.
+
+Definition andb (a b : bool) : bool :=
+    match a with
+    | true => b
+    | false => false
+    end.
+
+Class Eqb (A : Set) : Set := { eqb : A -> A -> bool }.
+
+Instance eqb_bool : Eqb bool := {
+    eqb := fun a b =>
+        match a, b with
+        | true, true | false, false => true
+        | _, _ => false
+        end
+}.
+
+(* Tried after `eqb_bool`, as its priority is higher *)
+Instance eqb_bool_false : Eqb bool | 1 := Build_Eqb (fun _ _ => false).
+
+Instance eqb_nat_zero : Eqb nat := {
+    eqb := fun n m =>
+        match n, m with
+        | O, O => true
+        | _, _ => false
+        end
+}.
+
+Instance eqb_prod {A B : Set} [Eqb A] [eq_b : Eqb B] : Eqb (prod A B) := {
+    eqb := fun p q =>
+        match p, q with
+        | pair _ _ a b, pair _ _ c d => andb (eqb a c) (eqb b d)
+        end
+}.
+
+Definition same := eqb (pair true (pair O false)) (pair true (pair O false)).
+Definition different := eqb (pair true O) (pair false O).
+
+Definition both {A : Set} [Eqb A] (a b c : A) : bool := andb (eqb a b) (eqb b c).
+Definition all_true := both true true true.
+
+Compute same.
+Compute different.
+Compute all_true.
+Check @eqb.
+Check @eqb_prod.
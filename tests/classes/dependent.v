@@ -0,0 +1,21 @@
+Inductive bool : Set := true : bool | false : bool.
+Inductive True : Prop := I : True.
+
+Class Pointed (A : Set) := {
+    point : A;
+    is_point : A -> Prop;
+    point_ok : is_point point
+}.
+
+Instance pointed_bool : Pointed bool := {
+    is_point := fun _ => True;
+    point := true;
+    point_ok := I
+}.
+
+Definition the_point : bool := point.
+Definition proof : is_point (A := bool) point := point_ok.
+
+Compute the_point.
+Check @point_ok.
+Print point.
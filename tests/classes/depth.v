@@ -0,0 +1,8 @@
+Inductive nat : Set := O : nat | S : nat -> nat.
+
+Class Default (A : Set) := { default : A }.
+
+(* Applies to its own premise forever *)
+Instance default_loop {A : Set} [Default A] : Default A := { default := default }.
+
+Definition bad : nat := default.
@@ -0,0 +1,5 @@
+Inductive bool : Set := true : bool | false : bool.
+
+Class Eqb (A : Set) : Set := { eqb : A -> A -> bool; neqb : A -> A -> bool }.
+
+Instance eqb_bool : Eqb bool := { eqb := fun _ _ => true }.
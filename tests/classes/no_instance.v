@@ -0,0 +1,14 @@
+Inductive bool : Set := true : bool | false : bool.
+Inductive nat : Set := O : nat | S : nat -> nat.
+Inductive prod (A B : Set) : Set := pair : A -> B -> prod A B.
+Arguments pair {A} {B} a b.
+
+Class Eqb (A : Set) : Set := { eqb : A -> A -> bool }.
+
+Instance eqb_bool : Eqb bool := { eqb := fun _ _ => true }.
+
+Instance eqb_prod {A B : Set} [Eqb A] [Eqb B] : Eqb (prod A B) := {
+    eqb := fun _ _ => true
+}.
+
+Definition bad := eqb (pair true O) (pair true O).
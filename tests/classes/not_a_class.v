@@ -0,0 +1,3 @@
+Inductive bool : Set := true : bool | false : bool.
+
+Instance bool_instance : bool := true.
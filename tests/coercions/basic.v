@@ -0,0 +1,49 @@
+Inductive bool : Set := true : bool | false : bool.
+Inductive nat : Set := O : nat | S : nat -> nat.
+Number Notation nat O S.
+Inductive True : Prop := I : True.
+Inductive False : Prop := .
+
+(* To sorts *)
+Definition is_true (b : bool) : Prop :=
+    match b with
+    | true => True
+    | false => False
+    end.
+Coercion is_true : bool >-> Sortclass.
+
+Definition trivial : true := I.
+Definition implies (b : bool) (p : b) : b := p.
+
+(* Between types, along a path *)
+Definition nat_of_bool (b : bool) : nat :=
+    match b with
+    | true => 1
+    | false => 0
+    end.
+Coercion nat_of_bool : bool >-> nat.
+
+Inductive int : Set := of_nat : nat -> int | neg_succ : nat -> int.
+Coercion of_nat : nat >-> int.
+
+Definition one : nat := true.
+Definition zero : int := false.
+Definition two : int := 2.
+
+(* To functions *)
+Inductive fn : Set := mk_fn : (nat -> nat) -> fn.
+Definition apply (f : fn) : nat -> nat :=
+    match f with
+    | mk_fn g => g
+    end.
+Coercion apply : fn >-> Funclass.
+
+Definition succ : fn := mk_fn S.
+Definition three : nat := succ 2.
+
+Check trivial.
+Check implies.
+Print zero.
+Print three.
+Compute three.
+Compute zero.
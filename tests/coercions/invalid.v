@@ -0,0 +1,4 @@
+Inductive bool : Set := true : bool | false : bool.
+Definition is_true (b : bool) : Prop := forall P : Prop, P.
+
+Coercion is_true : Sortclass >-> bool.
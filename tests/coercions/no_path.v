@@ -0,0 +1,6 @@
+Inductive bool : Set := true : bool | false : bool.
+Inductive nat : Set := O : nat | S : nat -> nat.
+Inductive int : Set := of_nat : nat -> int.
+Coercion of_nat : nat >-> int.
+
+Definition bad : int := true.
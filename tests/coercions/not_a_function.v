@@ -0,0 +1,3 @@
+Inductive nat : Set := O : nat | S : nat -> nat.
+
+Coercion S : nat >-> Funclass.
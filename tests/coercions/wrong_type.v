@@ -0,0 +1,5 @@
+Inductive bool : Set := true : bool | false : bool.
+Inductive nat : Set := O : nat | S : nat -> nat.
+Definition f (n : nat) : bool := true.
+
+Coercion f : bool >-> nat.
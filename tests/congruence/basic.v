@@ -0,0 +1,51 @@
+Inductive False : Prop :=.
+Inductive eq (T : Set) (x : T) : forall y : T, Prop :=
+    eq_refl : eq T x x.
+Inductive nat : Set :=
+    | O : nat
+    | S : forall n : nat, nat.
+
+Register eq as eq.
+Register False as False.
+
+Theorem apply_eq (f : nat -> nat) (a b c : nat) : eq nat (f a) b -> eq nat a c -> eq nat (f c) b.
+Proof.
+    intros H1 H2.
+    congruence.
+Qed.
+Print apply_eq.
+
+Theorem two_args (g : nat -> nat -> nat) (a b c d : nat) :
+    eq nat a b -> eq nat c d -> eq nat (g a c) (g b d).
+Proof.
+    intros.
+    congruence.
+Qed.
+
+Theorem fixed_point (f : nat -> nat) (a : nat) : eq nat (f (f (f a))) a -> eq nat (f (f a)) a -> eq nat (f a) a.
+Proof.
+    intros H1 H2.
+    congruence.
+Qed.
+
+Theorem injection (n m : nat) : eq nat (S n) (S m) -> eq nat n m.
+Proof.
+    intro H.
+    congruence.
+Qed.
+Print injection.
+
+Theorem disjoint (n : nat) (P : Prop) : eq nat (S n) O -> P.
+Proof.
+    intro H.
+    congruence.
+Qed.
+Print disjoint.
+
+Theorem disequality (f : nat -> nat) (a b : nat) : eq nat a b -> (eq nat (f a) (f b) -> False) -> eq nat O (S O).
+Proof.
+    intros H1 H2.
+    congruence.
+Qed.
+
+Definition by_congruence (a b : nat) (H : eq nat a b) : eq nat b a := by congruence.
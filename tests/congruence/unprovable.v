@@ -0,0 +1,9 @@
+Inductive eq (T : Set) (x : T) : forall y : T, Prop :=
+    eq_refl : eq T x x.
+Inductive nat : Set :=
+    | O : nat
+    | S : forall n : nat, nat.
+
+Register eq as eq.
+
+Definition not_congruent (f : nat -> nat) (a b : nat) (H : eq nat (f a) (f b)) : eq nat a b := by congruence.
@@ -0,0 +1,4 @@
+Inductive eq (T : Set) (x : T) : forall y : T, Prop :=
+    eq_refl : eq T x x.
+
+Definition refl (T : Set) (x : T) : eq T x x := by congruence.
@@ -0,0 +1,7 @@
+Inductive eq (T : Set) (x : T) : forall y : T, Prop :=
+    eq_refl : eq T x x.
+
+Definition eq_trans (T : Set) (x : T) (y : T) (z : T) (e1 : eq T x y) (e2 : eq T y z) : eq T x z :=
+    match e1 with
+    | eq_refl _ _ => e2
+    end.
@@ -0,0 +1,42 @@
+Inductive False : Prop :=.
+
+Inductive nat : Set := O : nat | S : forall n : nat, nat.
+
+Inductive eq (T : Set) (x : T) : forall y : T, Prop :=
+    eq_refl : eq T x x.
+
+Inductive even : forall n : nat, Prop :=
+    even_O : even O
+  | even_SS : forall (n : nat) (H : even n), even (S (S n)).
+
+Inductive vec (A : Set) : forall n : nat, Set :=
+    vnil : vec A O
+  | vcons : forall (n : nat) (x : A) (v : vec A n), vec A (S n).
+
+Definition O_not_S (n : nat) (e : eq nat O (S n)) : False :=
+    match e with end.
+
+Definition S_inj (n : nat) (m : nat) (e : eq nat (S n) (S m)) : eq nat n m :=
+    match e with
+    | eq_refl _ _ => eq_refl nat n
+    end.
+
+Definition not_even_one (H : even (S O)) : False :=
+    match H with end.
+
+Definition even_inv (n : nat) (H : even (S (S n))) : even n :=
+    match H with
+    | even_SS m H1 => H1
+    end.
+
+Definition head (A : Set) (n : nat) (v : vec A (S n)) : A :=
+    match v with
+    | vcons _ _ x _ => x
+    end.
+
+Definition tail (A : Set) (n : nat) (v : vec A (S n)) : vec A n :=
+    match v with
+    | vcons _ _ _ t => t
+    end.
+
+Compute head nat O (vcons nat O (S O) (vnil nat)).
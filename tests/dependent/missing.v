@@ -0,0 +1,15 @@
+Inductive nat : Set := O : nat | S : forall n : nat, nat.
+
+Inductive vec (A : Set) : forall n : nat, Set :=
+    vnil : vec A O
+  | vcons : forall (n : nat) (x : A) (v : vec A n), vec A (S n).
+
+Definition head (n : nat) (v : vec nat (S n)) : nat :=
+    match v with
+    | vcons _ _ x _ => x
+    end.
+
+Definition bad (n : nat) (v : vec nat n) : nat :=
+    match v with
+    | vcons _ _ x _ => x
+    end.
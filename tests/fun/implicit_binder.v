@@ -0,0 +1 @@
+Definition id : forall A : Set, A -> A := fun {A : Set} (x : A) => x.
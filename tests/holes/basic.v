@@ -0,0 +1,17 @@
+Inductive nat : Set := O : nat | S : forall n : nat, nat.
+
+Inductive eq (T : Set) (x : T) : forall y : T, Prop :=
+    eq_refl : eq T x x.
+
+Definition two : nat := S (S O).
+
+Definition refl_two : eq _ two (S (S O)) := eq_refl _ two.
+
+Definition id : forall A : Set, forall x : A, A := fun A : Set => fun x : A => x.
+
+Definition three : nat := id _ (S two).
+
+Definition refl_succ : forall n : nat, eq nat (S n) (S n) := fun n : _ => eq_refl _ (S n).
+
+Check eq_refl _ O.
+Check fun n : nat => id _ (id _ n).
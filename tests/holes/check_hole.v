@@ -0,0 +1 @@
+Check _.
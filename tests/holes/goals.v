@@ -0,0 +1,18 @@
+Inductive nat : Set := O : nat | S : forall n : nat, nat.
+
+Inductive eq (T : Set) (x : T) : forall y : T, Prop :=
+    eq_refl : eq T x x.
+
+Axiom plus : nat -> nat -> nat.
+
+Definition plus_n_O : forall n : nat, eq nat (plus n O) n := fun n : nat => ?base.
+
+Definition pred_twice (n : nat) : nat :=
+    let m := match n with O => O | S k => k end in
+    match m with O => ?zero | S k => k end.
+
+Definition solved : eq nat O O := eq_refl ?T O.
+
+Check plus_n_O.
+
+Show Holes.
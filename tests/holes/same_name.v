@@ -0,0 +1,18 @@
+Inductive nat : Set := O : nat | S : forall n : nat, nat.
+
+Inductive prod (A : Set) (B : Set) : Set := pair : forall a : A, forall b : B, prod A B.
+
+(* Each definition has its own holes, so they can share a name *)
+Definition one : nat := S ?n.
+Definition two : nat := S (S ?n).
+
+(* The occurrences of a hole in the same definition are the same hole *)
+Definition p : prod nat nat := pair nat nat ?a (S ?a).
+
+Check S ?n.
+Check S ?n.
+
+Print two.
+Print p.
+
+Show Holes.
@@ -0,0 +1,6 @@
+(* `Show` is only a keyword at the start of a command *)
+Inductive Show : Set := Hidden : Show | Shown : Show.
+
+Definition Show_all (s : Show) : Show := ?Show.
+
+Show Holes.
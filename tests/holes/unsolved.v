@@ -0,0 +1,5 @@
+Inductive nat : Set := O : nat | S : forall n : nat, nat.
+
+Definition id : forall A : Set, forall x : A, A := fun A : Set => fun x : A => x.
+
+Definition f : forall n : nat, nat := fun n : nat => id nat (id _ _).
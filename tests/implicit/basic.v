@@ -0,0 +1,31 @@
+Inductive nat : Set := O : nat | S : forall n : nat, nat.
+
+Inductive eq {T : Set} (x : T) : forall y : T, Prop :=
+    eq_refl : eq T x x.
+
+Definition id {A : Set} (x : A) : A := x.
+
+Definition two : nat := id (S (id (S O))).
+
+Definition refl_two : eq two (S (S O)) := eq_refl two.
+
+Definition eq_sym {T : Set} {x : T} {y : T} (e : eq x y) : eq y x :=
+    match e as e in eq _ _ z return eq z x with
+    | eq_refl _ _ => eq_refl x
+    end.
+
+Definition sym_two : eq (S (S O)) two := eq_sym refl_two.
+
+Definition explicit : nat := @id nat O.
+Definition named : nat := id (A := nat) O.
+
+Axiom const : forall {A : Set} {B : Set} (a : A) (b : B), A.
+Arguments const {A} B a b.
+Definition three : nat := const nat two O.
+
+Check @eq_sym.
+Check sym_two.
+Check @eq_sym nat.
+Print id.
+Print eq.
+Print const.
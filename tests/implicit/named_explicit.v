@@ -0,0 +1,3 @@
+Inductive nat : Set := O : nat.
+Definition id {A : Set} (x : A) : A := x.
+Definition bad : nat := @id (A := nat) O.
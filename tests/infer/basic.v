@@ -0,0 +1,24 @@
+Inductive nat : Set := O : nat | S : forall n : nat, nat.
+
+Definition two := S (S O).
+
+Definition add_two (n : nat) := S (S n).
+
+Definition twice : forall f : forall n : nat, nat, forall n : nat, nat := fun f n => f (f n).
+
+Definition four := twice (fun n => S n) two.
+
+Definition five := let x := S two in S (S x).
+
+Definition six : nat := let x := two in let y : nat := S x in S (S (S y)).
+
+Definition apply {A : Set} (f : forall x : A, A) x := f x.
+
+Definition seven := apply S six.
+
+Check let x := O in S x.
+Check fun (n : nat) m => add_two (S m).
+Print two.
+Print add_two.
+Print apply.
+Compute seven.
@@ -0,0 +1,7 @@
+Definition f : Type{0} -> Type{0} := fun x => x.
+Definition h : forall A : Type{0}, Type{0} := fun A => A.
+Definition g := (fun x => x) Type{0}.
+
+Print f.
+Print h.
+Check g.
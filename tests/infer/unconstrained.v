@@ -0,0 +1 @@
+Definition f := fun x => x.
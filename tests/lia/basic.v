@@ -0,0 +1,94 @@
+Inductive False : Prop :=.
+Inductive or (P : Prop) (Q : Prop) : Prop :=
+    | or_introl : forall p : P, or P Q
+    | or_intror : forall q : Q, or P Q.
+Inductive eq (T : Set) (x : T) : forall y : T, Prop :=
+    eq_refl : eq T x x.
+Inductive nat : Set := O : nat | S : nat -> nat.
+Inductive le (n : nat) : nat -> Prop :=
+    | le_n : le n n
+    | le_S : forall m : nat, le n m -> le n (S m).
+Definition lt (n m : nat) : Prop := le (S n) m.
+
+Axiom plus : nat -> nat -> nat.
+Axiom plus_O_n : forall n : nat, eq nat (plus O n) n.
+Axiom plus_Sn_m : forall n m : nat, eq nat (plus (S n) m) (S (plus n m)).
+Axiom plus_comm : forall n m : nat, eq nat (plus n m) (plus m n).
+Axiom plus_assoc : forall n m p : nat, eq nat (plus n (plus m p)) (plus (plus n m) p).
+Axiom plus_le_compat : forall n m p q : nat, le n m -> le p q -> le (plus n p) (plus m q).
+Axiom le_0_n : forall n : nat, le O n.
+Axiom le_S_n : forall n m : nat, le (S n) (S m) -> le n m.
+Axiom le_Sn_n : forall n : nat, le (S n) n -> False.
+Axiom le_trans : forall n m p : nat, le n m -> le m p -> le n p.
+Axiom le_or_lt : forall n m : nat, or (le n m) (lt m n).
+Axiom le_antisym : forall n m : nat, le n m -> le m n -> eq nat n m.
+
+Register False as False.
+Register or as or.
+Register eq as eq.
+Register nat as nat.
+Register plus as plus.
+Register le as le.
+Register lt as lt.
+Register plus_O_n as plus_O_n.
+Register plus_Sn_m as plus_Sn_m.
+Register plus_comm as plus_comm.
+Register plus_assoc as plus_assoc.
+Register plus_le_compat as plus_le_compat.
+Register le_0_n as le_0_n.
+Register le_S_n as le_S_n.
+Register le_Sn_n as le_Sn_n.
+Register le_trans as le_trans.
+Register le_or_lt as le_or_lt.
+Register le_antisym as le_antisym.
+
+Theorem lt_irrefl (n : nat) : lt n n -> False.
+Proof.
+    intro H.
+    lia.
+Qed.
+Print lt_irrefl.
+
+Theorem lt_le (n m : nat) : lt n m -> le n m.
+Proof.
+    intro H.
+    lia.
+Qed.
+
+Theorem le_plus_r (n m : nat) : le n (plus m n).
+Proof.
+    lia.
+Qed.
+
+Theorem two_bounds (x y z : nat) : le x y -> lt y z -> lt x z.
+Proof.
+    intros H1 H2.
+    lia.
+Qed.
+
+Theorem combination (x y : nat) : le (plus x x) y -> le y (S O) -> eq nat x O.
+Proof.
+    intros H1 H2.
+    lia.
+Qed.
+
+Theorem squeeze (n : nat) : le (S (S n)) (S (S (S O))) -> le (S (S (S O))) (plus n (S (S O))) -> eq nat n (S O).
+Proof.
+    intros H1 H2.
+    lia.
+Qed.
+
+Theorem from_equality (x y : nat) (P : Prop) : eq nat (plus x (S O)) y -> le y x -> P.
+Proof.
+    intros H1 H2.
+    lia.
+Qed.
+
+(* No rational solution is excluded, the bounds have to be rounded to the integers *)
+Theorem double_odd (x : nat) : eq nat (plus x x) (S O) -> False.
+Proof.
+    intro H.
+    lia.
+Qed.
+
+Definition by_lia (a b : nat) (H : lt a b) : le (S (S a)) (plus b (S O)) := by lia.
@@ -0,0 +1,17 @@
+Inductive False : Prop :=.
+Inductive eq (T : Set) (x : T) : forall y : T, Prop :=
+    eq_refl : eq T x x.
+Inductive nat : Set := O : nat | S : nat -> nat.
+Inductive le (n : nat) : nat -> Prop :=
+    | le_n : le n n
+    | le_S : forall m : nat, le n m -> le n (S m).
+
+Axiom plus : nat -> nat -> nat.
+
+Register False as False.
+Register eq as eq.
+Register nat as nat.
+Register plus as plus.
+Register le as le.
+
+Definition absurd (n : nat) (H : le (S n) n) : False := by lia.
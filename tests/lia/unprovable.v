@@ -0,0 +1,27 @@
+Inductive False : Prop :=.
+Inductive or (P : Prop) (Q : Prop) : Prop :=
+    | or_introl : forall p : P, or P Q
+    | or_intror : forall q : Q, or P Q.
+Inductive eq (T : Set) (x : T) : forall y : T, Prop :=
+    eq_refl : eq T x x.
+Inductive nat : Set := O : nat | S : nat -> nat.
+Inductive le (n : nat) : nat -> Prop :=
+    | le_n : le n n
+    | le_S : forall m : nat, le n m -> le n (S m).
+Definition lt (n m : nat) : Prop := le (S n) m.
+
+Axiom plus : nat -> nat -> nat.
+Axiom le_0_n : forall n : nat, le O n.
+Axiom le_or_lt : forall n m : nat, or (le n m) (lt m n).
+
+Register False as False.
+Register or as or.
+Register eq as eq.
+Register nat as nat.
+Register plus as plus.
+Register le as le.
+Register lt as lt.
+Register le_0_n as le_0_n.
+Register le_or_lt as le_or_lt.
+
+Definition le_plus (n m : nat) : le (plus n m) n := by lia.
@@ -0,0 +1,48 @@
+Inductive nat : Set := O : nat | S : forall n : nat, nat.
+
+Inductive bool : Set := true : bool | false : bool.
+
+Inductive prod (A : Set) (B : Set) : Set := pair : forall a : A, forall b : B, prod A B.
+
+Inductive eq (T : Set) (x : T) : forall y : T, Prop :=
+    eq_refl : eq T x x.
+
+Definition fst (A : Set) (B : Set) (p : prod A B) : A :=
+    match p with
+    | pair _ _ x _ => x
+    end.
+
+Definition pred (n : nat) : nat :=
+    match n with
+    | O => O
+    | S m => m
+    end.
+
+Definition is_zero (n : nat) :=
+    match n with
+    | O => true
+    | S _ => false
+    end.
+
+Definition eq_sym (T : Set) (x : T) (y : T) (e : eq T x y) : eq T y x :=
+    match e with
+    | eq_refl _ _ => eq_refl T x
+    end.
+
+Definition eq_trans (T : Set) (x : T) (y : T) (z : T) (e1 : eq T x y) (e2 : eq T y z) : eq T x z :=
+    match e2 in eq _ _ w with
+    | eq_refl _ _ => e1
+    end.
+
+Definition refl_match (n : nat) :=
+    match n as m return eq nat m m with
+    | O => eq_refl nat O
+    | S k => eq_refl nat (S k)
+    end.
+
+Print fst.
+Print is_zero.
+Print eq_sym.
+Print eq_trans.
+Compute is_zero (S O).
+Check refl_match.
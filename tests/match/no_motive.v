@@ -0,0 +1,3 @@
+Inductive nat : Set := O : nat | S : forall n : nat, nat.
+
+Check fun n : nat => match n with end.
@@ -0,0 +1,3 @@
+Definition f (A : Set) (x : A) : A :=
+    match x with
+    end.
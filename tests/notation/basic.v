@@ -0,0 +1,27 @@
+Inductive and (A : Prop) (B : Prop) : Prop := conj : forall a : A, forall b : B, and A B.
+Inductive or (A : Prop) (B : Prop) : Prop :=
+    or_introl : forall a : A, or A B
+  | or_intror : forall b : B, or A B.
+Inductive False : Prop := .
+Definition not (A : Prop) : Prop := forall a : A, False.
+
+Notation "~ x" := (not x) (at level 75, right associativity).
+Notation "A /\ B" := (and A B) (at level 80, right associativity).
+Notation "A \/ B" := (or A B) (at level 85, right associativity).
+
+Definition and_comm (A : Prop) (B : Prop) (H : A /\ B) : B /\ A :=
+    match H with
+    | conj _ _ a b => conj B A b a
+    end.
+
+Definition or_and (A : Prop) (B : Prop) (C : Prop) (H : A /\ B \/ C) : (A \/ C) /\ (B \/ C) :=
+    match H with
+    | or_introl _ _ (conj _ _ a b) => conj (A \/ C) (B \/ C) (or_introl A C a) (or_introl B C b)
+    | or_intror _ _ c => conj (A \/ C) (B \/ C) (or_intror A C c) (or_intror B C c)
+    end.
+
+Definition not_not (A : Prop) (a : A) : ~ ~ A := fun n => n a.
+
+Check fun (A : Prop) (B : Prop) (C : Prop) => forall h : A /\ (B /\ C), (A /\ B) /\ C.
+Print or_and.
+Print not_not.
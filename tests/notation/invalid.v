@@ -0,0 +1,3 @@
+Axiom and : forall A : Prop, forall B : Prop, Prop.
+
+Notation "A B" := (and A B).
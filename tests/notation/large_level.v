@@ -0,0 +1,5 @@
+Inductive bool : Set := true : bool | false : bool.
+
+Definition andb (a : bool) (b : bool) : bool := match a with true => b | false => false end.
+
+Notation "a && b" := (andb a b) (at level 99999999999999999999999).
@@ -0,0 +1,5 @@
+Axiom and : forall A : Prop, forall B : Prop, Prop.
+
+Notation "A /\ B" := (and A B) (at level 80, right associativity).
+
+Check fun (A : Prop) (B : Prop) => A /\.
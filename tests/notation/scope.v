@@ -0,0 +1,19 @@
+Inductive nat : Set := O : nat | S : forall n : nat, nat.
+Inductive eq (A : Set) (x : A) : forall y : A, Prop := eq_refl : eq A x x.
+
+Axiom plus : forall n : nat, forall m : nat, nat.
+Axiom mult : forall n : nat, forall m : nat, nat.
+
+Notation "x = y" := (eq _ x y) (at level 70, no associativity).
+Notation "x + y" := (plus x y) (at level 50, left associativity) : nat_scope.
+Notation "x * y" := (mult x y) (at level 40, left associativity) : nat_scope.
+Open Scope nat_scope.
+
+Definition distr (a : nat) (b : nat) (c : nat) := a * (b + c) = a * b + a * c.
+Definition assoc (a : nat) (b : nat) (c : nat) := a + b + c = a + (b + c).
+Definition succ (a : nat) (b : nat) := S a * b + S (a + b).
+
+Print distr.
+Print assoc.
+Print succ.
+Check fun (a : nat) => eq_refl nat (a + a).
@@ -0,0 +1,8 @@
+(* `Open` and `Scope` are only keywords at the start of a command *)
+Inductive Scope : Set := Open : Scope | Closed : Scope.
+Definition flip (s : Scope) : Scope := match s with Open => Closed | Closed => Open end.
+
+Notation "! s" := (flip s) (at level 35) : Scope.
+Open Scope Scope.
+
+Check ! Open.
@@ -0,0 +1,6 @@
+Inductive nat : Set := O : nat | S : forall n : nat, nat.
+Axiom plus : forall n : nat, forall m : nat, nat.
+
+Notation "x + y" := (plus x y) (at level 50, left associativity) : nat_scope.
+
+Definition two := S O + S O.
@@ -0,0 +1,18 @@
+Inductive positive : Set :=
+    xH : positive
+  | xO : positive -> positive
+  | xI : positive -> positive.
+Inductive N : Set := N0 : N | Npos : positive -> N.
+
+Number Notation positive xH xO xI : positive_scope.
+Open Scope positive_scope.
+
+Definition six := xO 3.
+Definition double (p : positive) := xO p.
+Definition large := 1000000.
+
+Print six.
+Print large.
+Compute double 21.
+Check Npos 1.
+Compute xI (xO xH).
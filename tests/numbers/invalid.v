@@ -0,0 +1,3 @@
+Inductive nat : Set := O : nat | S : nat -> nat.
+
+Number Notation nat S O.
@@ -0,0 +1,8 @@
+(* `Number` is only a keyword at the start of a command *)
+Inductive Number : Set := Zero : Number | Succ : Number -> Number.
+
+Number Notation Number Zero Succ : Number_scope.
+Open Scope Number_scope.
+
+Definition Number_two : Number := 2.
+Print Number_two.
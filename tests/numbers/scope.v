@@ -0,0 +1,5 @@
+Inductive nat : Set := O : nat | S : nat -> nat.
+
+Number Notation nat O S : nat_scope.
+
+Definition two := 2.
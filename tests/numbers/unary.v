@@ -0,0 +1,14 @@
+Inductive nat : Set := O : nat | S : nat -> nat.
+Inductive eq (A : Set) (x : A) : A -> Prop := eq_refl : eq A x x.
+
+Number Notation nat O S.
+
+Definition pred (n : nat) : nat := match n with O => 0 | S m => m end.
+Definition three := S 2.
+Definition ten := 10.
+Definition pred_ten : eq nat (pred ten) 9 := eq_refl nat 9.
+
+Print three.
+Print pred_ten.
+Compute pred 12.
+Check S (S (pred 0)).
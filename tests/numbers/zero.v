@@ -0,0 +1,8 @@
+Inductive positive : Set :=
+    xH : positive
+  | xO : positive -> positive
+  | xI : positive -> positive.
+
+Number Notation positive xH xO xI.
+
+Definition zero := 0.
@@ -0,0 +1,7 @@
+Inductive bool : Set := true : bool | false : bool.
+
+Definition f (a : bool) (b : bool) : bool :=
+    match a, b with
+    | true => true
+    | _, _ => false
+    end.
@@ -0,0 +1,26 @@
+Inductive nat : Set := O : nat | S : forall n : nat, nat.
+
+Inductive bool : Set := true : bool | false : bool.
+
+Definition and (a : bool) (b : bool) : bool :=
+    match a, b with
+    | true, true => true
+    | _, _ => false
+    end.
+
+Definition eqb (a : bool) (b : bool) : bool :=
+    match a, b with
+    | true, true | false, false => true
+    | _, _ => false
+    end.
+
+Definition le (n : nat) (m : nat) : bool :=
+    match n, m with
+    | O, _ => true
+    | S _, O => false
+    | S n1, S m1 => true
+    end.
+
+Compute and true false.
+Compute eqb false false.
+Compute le (S O) O.
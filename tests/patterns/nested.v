@@ -0,0 +1,30 @@
+Inductive nat : Set := O : nat | S : forall n : nat, nat.
+
+Inductive bool : Set := true : bool | false : bool.
+
+Inductive list (A : Set) : Set :=
+    nil : list A
+  | cons : forall x : A, forall xs : list A, list A.
+
+Definition pred2 (n : nat) : nat :=
+    match n with
+    | S (S m) => m
+    | _ => O
+    end.
+
+Definition even (n : nat) : bool :=
+    match n with
+    | O => true
+    | S O => false
+    | S (S m) => true
+    end.
+
+Definition second (l : list nat) : nat :=
+    match l with
+    | cons _ _ (cons _ y _) => y
+    | _ => O
+    end.
+
+Compute pred2 (S (S (S O))).
+Compute pred2 (S O).
+Compute second (cons nat O (cons nat (S O) (nil nat))).
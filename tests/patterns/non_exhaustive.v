@@ -0,0 +1,7 @@
+Inductive nat : Set := O : nat | S : forall n : nat, nat.
+
+Definition pred2 (n : nat) : nat :=
+    match n with
+    | O => O
+    | S O => O
+    end.
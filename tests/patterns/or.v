@@ -0,0 +1,18 @@
+Inductive color : Set := red : color | green : color | blue : color.
+
+Inductive bool : Set := true : bool | false : bool.
+
+Definition is_red (c : color) : bool :=
+    match c with
+    | red => true
+    | green | blue => false
+    end.
+
+Definition warm (c : color) : bool :=
+    match c with
+    | (red | green) => true
+    | blue => false
+    end.
+
+Compute is_red blue.
+Compute warm green.
@@ -0,0 +1,14 @@
+Inductive bool : Set := true : bool | false : bool.
+
+Definition not (b : bool) : bool :=
+    match b with
+    | true => false
+    | false => true
+    | _ => false
+    end.
+
+Definition id (b : bool) : bool :=
+    match b with
+    | x => x
+    | true => true
+    end.
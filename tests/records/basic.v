@@ -0,0 +1,23 @@
+Inductive bool : Set := true : bool | false : bool.
+Inductive eq (A : Set) (x : A) : A -> Prop := eq_refl : eq A x x.
+
+Record prod (A : Set) (B : Set) : Set := pair { fst : A; snd : B }.
+
+Record sigma_bool : Set := {
+    value : bool;
+    proof : eq bool value true
+}.
+
+Definition p : prod bool bool := {| fst := true; snd := false |}.
+Definition q : prod bool bool := {| snd := true; fst := false |}.
+Definition swapped := {| p with fst := p.(snd); snd := p.(fst) |}.
+Definition first := {| q with snd := true |}.(fst).
+Definition witness : sigma_bool := {| value := true; proof := eq_refl bool true |}.
+Definition witness_proof : eq bool witness.(value) true := witness.(proof).
+
+Compute swapped.
+Compute first.
+Compute witness.(value).
+Check @pair.
+Check @proof.
+Print snd.
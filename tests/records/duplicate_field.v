@@ -0,0 +1,5 @@
+Inductive bool : Set := true : bool | false : bool.
+
+Record prod (A : Set) (B : Set) : Set := pair { fst : A; snd : B }.
+
+Definition p : prod bool bool := {| fst := true; snd := false; fst := false |}.
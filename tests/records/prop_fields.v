@@ -0,0 +1,3 @@
+Inductive bool : Set := true : bool | false : bool.
+Record R : Prop := { v : bool; w : bool }.
+Print R.
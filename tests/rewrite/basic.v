@@ -0,0 +1,27 @@
+Inductive eq (T : Set) (x : T) : forall y : T, Prop :=
+    eq_refl : eq T x x.
+Inductive nat : Set := O : nat | S : nat -> nat.
+
+Axiom plus : nat -> nat -> nat.
+Axiom plus_O_n : forall m : nat, eq nat (plus O m) m.
+
+Definition eq_elem (T : Set) (x : T) (R : forall x : T, Prop) (r : R x) (y : T) (e : eq T x y) : R y :=
+    rewrite <- e in r.
+
+Definition eq_elem_r (T : Set) (x : T) (y : T) (R : forall x : T, Prop) (r : R y) (e : eq T x y) : R x :=
+    rewrite e in r.
+
+Definition eq_sym (T : Set) (x : T) (y : T) (e : eq T x y) : eq T y x :=
+    rewrite e in eq_refl T y.
+
+Definition f_equal (S : Set) (T : Set) (f : forall x : S, T) (x : S) (y : S) (e : eq S x y) : eq T (f x) (f y) :=
+    rewrite <- e in eq_refl T (f x).
+
+Definition plus_O_O : eq nat (plus O (plus O O)) O :=
+    rewrite plus_O_n O in plus_O_n O.
+
+Definition S_plus_O_n (m : nat) : eq nat (S (plus O m)) (S m) :=
+    rewrite (plus_O_n m) in rewrite <- (plus_O_n m) in eq_refl nat (S (plus O m)).
+
+Print eq_sym.
+Print f_equal.
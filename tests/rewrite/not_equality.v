@@ -0,0 +1,4 @@
+Inductive nat : Set := O : nat | S : nat -> nat.
+
+Definition bad (n : nat) : nat :=
+    rewrite n in n.
@@ -0,0 +1,6 @@
+Inductive eq (T : Set) (x : T) : forall y : T, Prop :=
+    eq_refl : eq T x x.
+Inductive nat : Set := O : nat | S : nat -> nat.
+
+Definition unchanged (x : nat) (y : nat) (e : eq nat x y) : eq nat O O :=
+    rewrite e in eq_refl nat O.
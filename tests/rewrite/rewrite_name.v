@@ -0,0 +1,13 @@
+(* `rewrite` is only a keyword when it is followed by an equality and `in` *)
+Inductive eq (T : Set) (x : T) : forall y : T, Prop :=
+    eq_refl : eq T x x.
+Inductive nat : Set := O : nat | S : nat -> nat.
+
+Definition rewrite (n : nat) : nat := S n.
+Definition two := rewrite (rewrite O).
+
+Definition eq_two (n : nat) (e : eq nat n two) : eq nat two n :=
+    rewrite e in eq_refl nat two.
+
+Print two.
+Print eq_two.
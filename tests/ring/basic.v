@@ -0,0 +1,72 @@
+Inductive eq (T : Set) (x : T) : forall y : T, Prop :=
+    eq_refl : eq T x x.
+
+Axiom Z : Set.
+Axiom zero : Z.
+Axiom one : Z.
+Axiom add : Z -> Z -> Z.
+Axiom mul : Z -> Z -> Z.
+Axiom opp : Z -> Z.
+Axiom add_0_l : forall x : Z, eq Z (add zero x) x.
+Axiom add_comm : forall x y : Z, eq Z (add x y) (add y x).
+Axiom add_assoc : forall x y z : Z, eq Z (add x (add y z)) (add (add x y) z).
+Axiom mul_1_l : forall x : Z, eq Z (mul one x) x.
+Axiom mul_0_l : forall x : Z, eq Z (mul zero x) zero.
+Axiom mul_comm : forall x y : Z, eq Z (mul x y) (mul y x).
+Axiom mul_assoc : forall x y z : Z, eq Z (mul x (mul y z)) (mul (mul x y) z).
+Axiom distr_l : forall x y z : Z, eq Z (mul (add x y) z) (add (mul x z) (mul y z)).
+Axiom opp_def : forall x : Z, eq Z (add x (opp x)) zero.
+
+Register eq as eq.
+Register Z as ring_carrier.
+Register zero as ring_0.
+Register one as ring_1.
+Register add as ring_add.
+Register mul as ring_mul.
+Register opp as ring_opp.
+Register add_0_l as ring_add_0_l.
+Register add_comm as ring_add_comm.
+Register add_assoc as ring_add_assoc.
+Register mul_1_l as ring_mul_1_l.
+Register mul_0_l as ring_mul_0_l.
+Register mul_comm as ring_mul_comm.
+Register mul_assoc as ring_mul_assoc.
+Register distr_l as ring_distr_l.
+Register opp_def as ring_opp_def.
+Add Ring Zr.
+
+Theorem add_swap (a b c : Z) : eq Z (add a (add b c)) (add c (add b a)).
+Proof.
+    ring.
+Qed.
+
+Theorem square (a b : Z) :
+    eq Z (mul (add a b) (add a b)) (add (mul a a) (add (mul (add one one) (mul a b)) (mul b b))).
+Proof.
+    ring.
+Qed.
+
+Theorem difference_of_squares (a b : Z) :
+    eq Z (mul (add a (opp b)) (add a b)) (add (mul a a) (opp (mul b b))).
+Proof.
+    ring.
+Qed.
+
+Theorem cancel (a b : Z) : eq Z (add (add a b) (opp a)) b.
+Proof.
+    ring.
+Qed.
+
+Theorem opp_mul (a b : Z) : eq Z (mul (opp a) (opp b)) (mul b a).
+Proof.
+    ring.
+Qed.
+
+Theorem atoms (f : Z -> Z) (a b c : Z) :
+    eq Z (mul (add (f a) (opp c)) (add b (f a))) (add (add (mul b (f a)) (mul (f a) (f a))) (opp (add (mul c b) (mul (f a) c)))).
+Proof.
+    ring.
+Qed.
+
+Definition by_ring (a : Z) : eq Z (mul a (add one zero)) (add zero a) := by ring.
+Print by_ring.
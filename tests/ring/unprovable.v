@@ -0,0 +1,37 @@
+Inductive eq (T : Set) (x : T) : forall y : T, Prop :=
+    eq_refl : eq T x x.
+Inductive nat : Set := O : nat | S : nat -> nat.
+
+Axiom plus : nat -> nat -> nat.
+Axiom mult : nat -> nat -> nat.
+Axiom plus_O_n : forall n : nat, eq nat (plus O n) n.
+Axiom plus_comm : forall n m : nat, eq nat (plus n m) (plus m n).
+Axiom plus_assoc : forall n m p : nat, eq nat (plus n (plus m p)) (plus (plus n m) p).
+Axiom mult_1_n : forall n : nat, eq nat (mult (S O) n) n.
+Axiom mult_O_n : forall n : nat, eq nat (mult O n) O.
+Axiom mult_comm : forall n m : nat, eq nat (mult n m) (mult m n).
+Axiom mult_assoc : forall n m p : nat, eq nat (mult n (mult m p)) (mult (mult n m) p).
+Axiom mult_plus_distr_r : forall n m p : nat, eq nat (mult (plus n m) p) (plus (mult n p) (mult m p)).
+
+Definition one : nat := S O.
+
+Register eq as eq.
+Register nat as ring_carrier.
+Register O as ring_0.
+Register one as ring_1.
+Register plus as ring_add.
+Register mult as ring_mul.
+Register plus_O_n as ring_add_0_l.
+Register plus_comm as ring_add_comm.
+Register plus_assoc as ring_add_assoc.
+Register mult_1_n as ring_mul_1_l.
+Register mult_O_n as ring_mul_0_l.
+Register mult_comm as ring_mul_comm.
+Register mult_assoc as ring_mul_assoc.
+Register mult_plus_distr_r as ring_distr_l.
+Add Ring N.
+
+Theorem unequal (n m : nat) : eq nat (mult n n) (mult n m).
+Proof.
+    ring.
+Qed.
@@ -0,0 +1,35 @@
+Inductive eq (T : Set) (x : T) : forall y : T, Prop :=
+    eq_refl : eq T x x.
+
+Axiom Z : Set.
+Axiom zero : Z.
+Axiom one : Z.
+Axiom add : Z -> Z -> Z.
+Axiom mul : Z -> Z -> Z.
+Axiom opp : Z -> Z.
+Axiom add_0_l : forall x : Z, eq Z (add zero x) x.
+Axiom add_comm : forall x y : Z, eq Z (add x y) (add y x).
+Axiom add_assoc : forall x y z : Z, eq Z (add x (add y z)) (add (add x y) z).
+Axiom mul_1_l : forall x : Z, eq Z (mul one x) x.
+Axiom mul_0_l : forall x : Z, eq Z (mul zero x) zero.
+Axiom mul_comm : forall x y : Z, eq Z (mul x y) (mul y x).
+Axiom mul_assoc : forall x y z : Z, eq Z (mul x (mul y z)) (mul (mul x y) z).
+Axiom distr_l : forall x y z : Z, eq Z (mul (add x y) z) (add (mul x z) (mul y z)).
+
+Register eq as eq.
+Register Z as ring_carrier.
+Register zero as ring_0.
+Register one as ring_1.
+Register add as ring_add.
+Register mul as ring_mul.
+Register opp as ring_opp.
+Register add_0_l as ring_add_0_l.
+Register add_comm as ring_add_comm.
+Register add_assoc as ring_add_assoc.
+Register mul_1_l as ring_mul_1_l.
+Register mul_0_l as ring_mul_0_l.
+Register mul_comm as ring_mul_comm.
+Register mul_assoc as ring_mul_assoc.
+Register distr_l as ring_distr_l.
+Add Ring Zr.
+
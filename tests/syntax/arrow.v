@@ -0,0 +1,15 @@
+Inductive nat : Set := O : nat | S : nat -> nat.
+Inductive and (A B : Prop) : Prop := conj : A -> B -> and A B.
+
+Notation "A /\ B" := (and A B) (at level 80, right associativity).
+
+Definition compose {A B C : Set} (g : B -> C) (f : A -> B) : A -> C := fun x => g (f x).
+Definition twice (f : nat -> nat) := compose f f.
+Definition proj1 (A B : Prop) (H : A /\ B) : A := match H with conj _ _ a _ => a end.
+Definition curry (A B C : Prop) (f : A /\ B -> C) : A -> B -> C := fun a b => f (conj A B a b).
+
+Print compose.
+Print twice.
+Print curry.
+Check fun (A : Prop) (f : (A -> A) -> A) => f.
+Check forall (A B : Prop) (a : A), A /\ B -> B.
@@ -0,0 +1,5 @@
+Inductive eq (A : Set) (x : A) : A -> Prop := eq_refl : eq A x x.
+
+Definition shadow (A : Set) := fun (A : Set) (x y : A) => eq A x y.
+
+Print shadow.
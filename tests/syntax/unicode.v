@@ -0,0 +1,10 @@
+Inductive nat : Set := O : nat | S : nat → nat.
+
+Definition id' := λ (A : Set) (x : A) => x.
+Definition n' : nat := id' nat (S O).
+Definition π : ∀ A B : Set, A → B → A := λ A B a b => a.
+Definition f'' (n n' : nat) := π nat nat n' n.
+
+Print π.
+Print f''.
+Check ∀ {x' : nat}, nat.
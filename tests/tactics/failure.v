@@ -0,0 +1,6 @@
+Inductive True : Prop := I : True.
+
+Theorem not_a_product : True.
+Proof.
+    intro x.
+Qed.
@@ -0,0 +1,55 @@
+Inductive eq (T : Set) (x : T) : forall y : T, Prop :=
+    eq_refl : eq T x x.
+Inductive nat : Set := O : nat | S : nat -> nat.
+
+(* Induction principles are not generated, so the eliminator is assumed rather than defined *)
+Axiom nat_ind : forall P : nat -> Prop, P O -> (forall n : nat, P n -> P (S n)) -> forall n : nat, P n.
+Axiom plus : nat -> nat -> nat.
+Axiom plus_O_n : forall m : nat, eq nat (plus O m) m.
+Axiom plus_S_n : forall n m : nat, eq nat (plus (S n) m) (S (plus n m)).
+
+Theorem eq_S : forall n m : nat, eq nat n m -> eq nat (S n) (S m).
+Proof.
+    intros n m e.
+    destruct e.
+    reflexivity.
+Qed.
+
+Theorem eq_trans (T : Set) (x : T) (y : T) (z : T) : eq T x y -> eq T y z -> eq T x z.
+Proof.
+    intros e1 e2.
+    destruct e2.
+    exact e1.
+Qed.
+
+Theorem plus_n_O : forall n : nat, eq nat (plus n O) n.
+Proof.
+    intro n.
+    induction n.
+    apply plus_O_n.
+    intros n IH.
+    apply eq_trans.
+    exact (S (plus n O)).
+    apply plus_S_n.
+    apply eq_S.
+    exact IH.
+Qed.
+
+(* The hypothesis on `n` is generalized, so the induction hypothesis quantifies over it *)
+Theorem plus_n_O_refl : forall n : nat, eq nat n n -> eq nat (plus n O) n.
+Proof.
+    intros n e.
+    induction n.
+    intro e.
+    apply plus_O_n.
+    intros n IH e.
+    apply eq_trans.
+    exact (S (plus n O)).
+    apply plus_S_n.
+    apply eq_S.
+    apply IH.
+    reflexivity.
+Qed.
+
+Check plus_n_O.
+Check plus_n_O_refl.
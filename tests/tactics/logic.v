@@ -0,0 +1,67 @@
+Inductive False : Prop :=.
+Inductive True : Prop := I : True.
+Inductive and (P : Prop) (Q : Prop) : Prop :=
+    conj : forall (p : P) (q : Q), and P Q.
+Inductive or (P : Prop) (Q : Prop) : Prop :=
+    | or_introl : forall p : P, or P Q
+    | or_intror : forall q : Q, or P Q.
+Inductive eq (T : Set) (x : T) : forall y : T, Prop :=
+    eq_refl : eq T x x.
+
+Definition not (P : Prop) : Prop := P -> False.
+
+Theorem id (P : Prop) : P -> P.
+Proof.
+    intro p.
+    exact p.
+Qed.
+
+Lemma and_comm : forall P Q : Prop, and P Q -> and Q P.
+Proof.
+    intros P Q H.
+    destruct H as [p q].
+    split.
+    assumption.
+    assumption.
+Qed.
+
+Theorem or_comm : forall P Q : Prop, or P Q -> or Q P.
+Proof.
+    intros.
+    destruct H.
+    apply or_intror.
+    exact p.
+    apply or_introl.
+    exact q.
+Qed.
+
+Theorem modus_ponens (P : Prop) (Q : Prop) : P -> (P -> Q) -> Q.
+Proof.
+    intros p f.
+    apply f.
+    exact p.
+Qed.
+
+Theorem not_false : not False.
+Proof.
+    intro f.
+    destruct f.
+Qed.
+
+Theorem eq_sym (T : Set) (x : T) (y : T) : eq T x y -> eq T y x.
+Proof.
+    intro e.
+    symmetry.
+    exact e.
+Qed.
+
+Theorem refl_true : eq True I I.
+Proof.
+    reflexivity.
+Qed.
+
+Check id.
+Check and_comm.
+Print or_comm.
+Print not_false.
+Print eq_sym.
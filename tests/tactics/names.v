@@ -0,0 +1,14 @@
+Inductive eq (T : Set) (x : T) : forall y : T, Prop :=
+    eq_refl : eq T x x.
+Inductive nat : Set := O : nat | S : nat -> nat.
+Definition pred (n : nat) : nat := match n with O => O | S m => m end.
+
+(* The argument of `S` is anonymous, so it is named after its type, and `n` is taken *)
+Theorem pred_S : forall n : nat, eq nat (pred (S n)) n.
+Proof.
+    intro n.
+    destruct n.
+    reflexivity.
+    exact (eq_refl nat (S n0)).
+Qed.
+Print pred_S.
@@ -0,0 +1,7 @@
+Inductive True : Prop := I : True.
+
+Theorem trivial : True.
+Proof.
+    exact I.
+    exact I.
+Qed.
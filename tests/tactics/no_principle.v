@@ -0,0 +1,9 @@
+Inductive eq (T : Set) (x : T) : forall y : T, Prop :=
+    eq_refl : eq T x x.
+Inductive nat : Set := O : nat | S : nat -> nat.
+
+Theorem refl : forall n : nat, eq nat n n.
+Proof.
+    intro n.
+    induction n.
+Qed.
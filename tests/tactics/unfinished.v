@@ -0,0 +1,8 @@
+Inductive and (P : Prop) (Q : Prop) : Prop :=
+    conj : forall (p : P) (q : Q), and P Q.
+
+Theorem pair (P : Prop) (Q : Prop) (p : P) : Q -> and P Q.
+Proof.
+    intro q.
+    split.
+Qed.
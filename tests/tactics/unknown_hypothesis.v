@@ -0,0 +1,5 @@
+Theorem id (P : Prop) : P -> P.
+Proof.
+    intro p.
+    exact q.
+Qed.
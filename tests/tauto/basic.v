@@ -0,0 +1,43 @@
+Inductive False : Prop :=.
+Inductive True : Prop := I : True.
+Inductive and (P : Prop) (Q : Prop) : Prop :=
+    conj : forall (p : P) (q : Q), and P Q.
+Inductive or (P : Prop) (Q : Prop) : Prop :=
+    | or_introl : forall p : P, or P Q
+    | or_intror : forall q : Q, or P Q.
+
+Definition not (P : Prop) : Prop := P -> False.
+
+Register True as True.
+Register False as False.
+Register and as and.
+Register or as or.
+
+Definition and_comm (P Q : Prop) : and P Q -> and Q P := by tauto.
+Print and_comm.
+
+Definition or_comm (P Q : Prop) : or P Q -> or Q P := by tauto.
+Print or_comm.
+
+Definition curry (P Q R : Prop) : (and P Q -> R) -> P -> Q -> R := by tauto.
+
+Definition distrib (P Q R : Prop) : and P (or Q R) -> or (and P Q) (and P R) := by tauto.
+
+Definition contra (P Q : Prop) : (P -> Q) -> not Q -> not P := by tauto.
+
+Definition nnem (P : Prop) : not (not (or P (not P))) := by tauto.
+Print nnem.
+
+Definition explosion (P : Prop) : and P (not P) -> forall Q : Prop, Q -> True := by tauto.
+
+Theorem peirce_weak (P Q : Prop) : ((((P -> Q) -> P) -> P) -> Q) -> Q.
+Proof.
+    tauto.
+Qed.
+
+Theorem with_hypotheses : forall P Q : Prop, P -> or Q (not Q) -> or (and P Q) (not Q).
+Proof.
+    intros P Q p H.
+    tauto.
+Qed.
+Print with_hypotheses.
@@ -0,0 +1,13 @@
+(* `by` is only a keyword after the `:=` of a definition *)
+Inductive True : Prop := I : True.
+Inductive and (P : Prop) (Q : Prop) : Prop :=
+    conj : forall (p : P) (q : Q), and P Q.
+
+Register True as True.
+Register and as and.
+
+Definition by (P : Prop) : Prop := and P True.
+Definition by_True : by True := by tauto.
+Definition by_again : by True := conj True True I I.
+
+Print by_True.
@@ -0,0 +1,3 @@
+Inductive False : Prop :=.
+
+Register False as Bottom.
@@ -0,0 +1,9 @@
+Inductive False : Prop :=.
+Inductive or (P : Prop) (Q : Prop) : Prop :=
+    | or_introl : forall p : P, or P Q
+    | or_intror : forall q : Q, or P Q.
+
+Register False as False.
+Register or as or.
+
+Definition excluded_middle (P : Prop) : or P (P -> False) := by tauto.
@@ -0,0 +1,13 @@
+Inductive empty : Prop :=.
+Inductive both (A B : Prop) : Prop :=
+    pair : A -> B -> both A B.
+Inductive either (A B : Prop) : Prop :=
+    | left : A -> either A B
+    | right : B -> either A B.
+
+Register empty as False.
+Register both as and.
+Register either as or.
+
+Definition swap (A B : Prop) : either (both A B) empty -> both B A := by tauto.
+Print swap.